@@ -0,0 +1,100 @@
+use channels_lite::channels::{channel_author, channel_subscriber, Network};
+use channels_lite::transport::{mock::MockTransport, ChannelTransport};
+use channels_lite::utils::payload::{
+    json::{Payload, PayloadBuilder},
+    PacketPayload,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+
+const PAYLOAD_SIZES: &[usize] = &[64, 512, 2048];
+
+#[derive(Serialize, Deserialize)]
+struct Reading {
+    data: String,
+}
+
+fn reading(size: usize) -> Reading {
+    Reading {
+        data: "x".repeat(size),
+    }
+}
+
+fn payload(size: usize) -> Payload {
+    PayloadBuilder::new()
+        .public(&reading(size))
+        .unwrap()
+        .masked(&reading(size))
+        .unwrap()
+        .build()
+}
+
+fn throughput(size: usize) -> Throughput {
+    Throughput::Bytes(size.try_into().unwrap())
+}
+
+fn payload_codec(c: &mut Criterion) {
+    let mut group = c.benchmark_group("payload");
+    for size in PAYLOAD_SIZES.iter().copied() {
+        group.throughput(throughput(size));
+        group.bench_with_input(BenchmarkId::new("encode", size), &size, |b, size| {
+            b.iter(|| payload(*size))
+        });
+        let encoded = payload(size);
+        group.bench_with_input(BenchmarkId::new("decode", size), &encoded, |b, encoded| {
+            b.iter(|| Payload::unwrap_bytes(&encoded.public_data().0).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn connected_pair() -> (
+    channel_author::Channel<ChannelTransport>,
+    channel_subscriber::Channel<ChannelTransport>,
+) {
+    let tangle = MockTransport::new();
+    let mut author = channel_author::Channel::new_with_transport(
+        Network::Devnet,
+        ChannelTransport::Mock(tangle.clone()),
+        None,
+    )
+    .unwrap();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+    let mut subscriber = channel_subscriber::Channel::new_with_transport(
+        Network::Devnet,
+        ChannelTransport::Mock(tangle),
+        channel_address,
+        announcement_tag,
+        None,
+    )
+    .unwrap();
+    let subscription_tag = subscriber.connect().unwrap();
+    let keyload_tag = author.add_subscriber(subscription_tag).unwrap();
+    subscriber.update_keyload(keyload_tag).unwrap();
+    (author, subscriber)
+}
+
+fn send_receive(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mock_transport");
+    for size in PAYLOAD_SIZES.iter().copied() {
+        group.throughput(throughput(size));
+
+        let (mut author, _) = connected_pair();
+        group.bench_with_input(BenchmarkId::new("send", size), &size, |b, size| {
+            b.iter(|| author.write_signed(payload(*size)).unwrap())
+        });
+
+        let (mut author, mut subscriber) = connected_pair();
+        group.bench_with_input(BenchmarkId::new("send_receive", size), &size, |b, size| {
+            b.iter(|| {
+                let tag = author.write_signed(payload(*size)).unwrap();
+                subscriber.read_signed(tag).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, payload_codec, send_receive);
+criterion_main!(benches);
@@ -2,10 +2,7 @@ use channels_lite::channels::{channel_author, channel_subscriber, Network};
 use channels_lite::utils::payload::json::PayloadBuilder;
 use failure::Fallible;
 use serde::{Deserialize, Serialize};
-use std::{
-    thread,
-    time::{Duration, SystemTime, UNIX_EPOCH},
-};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 ///
 /// Some example of sensor Data
@@ -32,82 +29,109 @@ impl SensorData {
 async fn main() -> Fallible<()> {
     let seed_author = None;
     let seed_subscriber = None;
-    let delay_time: u64 = 40;
+    let attachment_timeout = Duration::from_secs(120);
+    //The author and the subscriber must talk to the same network
+    let network = Network::Devnet;
 
     //Create Channel Instance for author
-    let mut channel_author = channel_author::Channel::new(Network::Main, seed_author);
+    let mut channel_author = channel_author::Channel::new(network, seed_author).unwrap();
 
     //Open Channel
-    let (channel_address, announcement_tag) = channel_author.open().unwrap();
+    let (channel_address, announcement_tag) = channel_author.open_async().await.unwrap();
     println!("Author: Announced channel: {} ", channel_address);
 
-    //Give messages some time to propagate
-    println!("Waiting for propagation... ({}s)", delay_time);
-    thread::sleep(Duration::from_secs(delay_time));
+    //Wait for the announcement to reach the node
+    println!("Waiting for attachment...");
+    channel_author
+        .wait_for_attachment_async(&announcement_tag, attachment_timeout)
+        .await
+        .unwrap();
 
     //Create Channel Instance for subscriber
     let mut channel_subscriber = channel_subscriber::Channel::new(
-        Network::Devnet,
+        network,
         channel_address,
         announcement_tag,
         seed_subscriber,
-    );
+    )
+    .unwrap();
 
     //Connect to channel
-    let subscription_tag = channel_subscriber.connect().unwrap();
+    let subscription_tag = channel_subscriber.connect_async().await.unwrap();
     println!("Subscriber: Connected to channel: {}", subscription_tag);
 
-    //Give messages some time to propagate
-    println!("Waiting for propagation... ({}s)", delay_time);
-    thread::sleep(Duration::from_secs(delay_time));
+    //Wait for the subscription to reach the node
+    println!("Waiting for attachment...");
+    channel_author
+        .wait_for_attachment_async(&subscription_tag, attachment_timeout)
+        .await
+        .unwrap();
 
     //Add subscriber
-    let keyload_tag = channel_author.add_subscriber(subscription_tag).unwrap();
+    let keyload_tag = channel_author
+        .add_subscriber_async(subscription_tag)
+        .await
+        .unwrap();
     println!("Author: keyload_tag ID: {} ", keyload_tag);
 
-    //Give messages some time to propagate
-    println!("Waiting for propagation... ({}s)", delay_time);
-    thread::sleep(Duration::from_secs(delay_time));
+    //Wait for the keyload to reach the node
+    println!("Waiting for attachment...");
+    channel_author
+        .wait_for_attachment_async(&keyload_tag, attachment_timeout)
+        .await
+        .unwrap();
 
-    channel_subscriber.update_keyload(keyload_tag).unwrap();
+    channel_subscriber
+        .update_keyload_async(keyload_tag)
+        .await
+        .unwrap();
     println!("Subscriber: Updated keyload");
 
     //Write signed public message
     let s0 = channel_author
-        .write_signed(PayloadBuilder::new().public(&SensorData::new(1.0))?.build())
+        .write_signed_async(PayloadBuilder::new().public(&SensorData::new(1.0))?.build())
+        .await
         .unwrap();
     println!("Author: Sent signed public message: {}", s0);
 
     //Write signed masked message
     let s1 = channel_author
-        .write_signed(
+        .write_signed_async(
             PayloadBuilder::new()
                 .masked(&SensorData::new(19.0))?
                 .build(),
         )
+        .await
         .unwrap();
     println!("Author: Sent signed masked message: {}", s1);
 
     //Write tagged message
     let s2 = channel_author
-        .write_tagged(
+        .write_tagged_async(
             PayloadBuilder::new()
                 .public(&SensorData::new(17.0))?
                 .masked(&SensorData::new(19.0))?
                 .build(),
         )
+        .await
         .unwrap();
     println!("Author: Sent tagged message: {}", s2);
 
-    //Give messages some time to propagate
-    println!("Waiting for propagation... ({}s)", delay_time * 2);
-    thread::sleep(Duration::from_secs(delay_time * 2));
+    //Wait for the messages to reach the node
+    println!("Waiting for attachment...");
+    for tag in [&s0, &s1, &s2].iter() {
+        channel_author
+            .wait_for_attachment_async(tag, attachment_timeout)
+            .await
+            .unwrap();
+    }
 
-    let tags = channel_subscriber.get_next_message();
+    let tags = channel_subscriber.get_next_message_async().await;
 
     //Read all signed messages
     let list_signed_public: Vec<(Option<String>, Option<String>)> = channel_subscriber
-        .read_signed(tags[1].clone().unwrap())
+        .read_signed_async(tags[1].clone().unwrap())
+        .await
         .unwrap();
     println!("Subscriber: Reading signed public messages");
     for msg in list_signed_public.iter() {
@@ -119,7 +143,8 @@ async fn main() -> Fallible<()> {
     }
 
     let list_signed_masked: Vec<(Option<String>, Option<String>)> = channel_subscriber
-        .read_signed(tags[2].clone().unwrap())
+        .read_signed_async(tags[2].clone().unwrap())
+        .await
         .unwrap();
     println!("Subscriber: Reading signed masked messages");
     for msg in list_signed_masked.iter() {
@@ -132,7 +157,8 @@ async fn main() -> Fallible<()> {
 
     //Read all tagged messages
     let list_tagged: Vec<(Option<String>, Option<String>)> = channel_subscriber
-        .read_tagged(tags[3].clone().unwrap())
+        .read_tagged_async(tags[3].clone().unwrap())
+        .await
         .unwrap();
     println!("Subscriber: Reading tagged messages");
     for msg in list_tagged.iter() {
@@ -148,9 +174,11 @@ async fn main() -> Fallible<()> {
     let unsubscribe_tag = channel_subscriber.disconnect().unwrap();
     println!("Subscriber: Disconnected from channel");
 
-    //Give messages some time to propagate
-    println!("Waiting for propagation... ({}s)", delay_time);
-    thread::sleep(Duration::from_secs(delay_time));
+    //Wait for the unsubscription to reach the node
+    channel_author
+        .wait_for_attachment_async(&unsubscribe_tag, attachment_timeout)
+        .await
+        .unwrap();
 
     channel_author.remove_subscriber(unsubscribe_tag).unwrap();
     println!("Author: Removed subscriber");
@@ -0,0 +1,51 @@
+//!
+//! Channel operations traced with spans
+//!
+//! Run with `cargo run --example tracing --features tracing,test-transport`
+//!
+use channels_lite::channels::{channel_author, channel_subscriber, Network};
+use channels_lite::transport::{mock::MockTransport, ChannelTransport};
+use channels_lite::utils::payload::json::PayloadBuilder;
+use serde::Serialize;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+#[derive(Serialize)]
+struct SensorData {
+    presure: f32,
+}
+
+fn main() -> anyhow::Result<()> {
+    // Print each span when it closes, with its fields and timings
+    tracing_subscriber::fmt()
+        .with_span_events(FmtSpan::CLOSE)
+        .with_max_level(tracing::Level::DEBUG)
+        .init();
+
+    let tangle = MockTransport::new();
+    let mut author = channel_author::Channel::new_with_transport(
+        Network::Devnet,
+        ChannelTransport::Mock(tangle.clone()),
+        None,
+    )?;
+    let (channel_address, announcement_tag) = author.open()?;
+
+    let mut subscriber = channel_subscriber::Channel::new_with_transport(
+        Network::Devnet,
+        ChannelTransport::Mock(tangle),
+        channel_address,
+        announcement_tag,
+        None,
+    )?;
+    let subscription_tag = subscriber.connect()?;
+    let keyload_tag = author.add_subscriber(subscription_tag)?;
+    subscriber.update_keyload(keyload_tag)?;
+
+    author.write_signed(
+        PayloadBuilder::new()
+            .public(&SensorData { presure: 1.0 })?
+            .build(),
+    )?;
+    subscriber.get_next_message();
+
+    Ok(())
+}
@@ -0,0 +1,341 @@
+//!
+//! Command line client of the channels
+//!
+//! Every command prints JSON on stdout, `subscriber fetch` one message per line. The
+//! author and subscriber states are sealed with the password in a `FileStore`
+//!
+//! Run with `cargo run --features cli --bin channels-lite -- --help`
+//!
+use anyhow::{bail, Context, Result};
+use channels_lite::channels::{
+    channel_author, channel_subscriber, config::NetworkConfig, store::FileStore, Network,
+};
+use channels_lite::error::ChannelError;
+use channels_lite::transport::{tangle::TangleTransport, ChannelTransport};
+use channels_lite::utils::payload::json::PayloadBuilder;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
+use std::{fs, process, str::FromStr, thread, time::Duration};
+
+///
+/// Settings shared by the commands
+///
+pub(crate) struct Options {
+    network: Network,
+    node_url: Option<String>,
+    seed: Option<String>,
+    store: FileStore,
+    id: Option<String>,
+    password: String,
+}
+
+impl Options {
+    ///
+    /// Options of the command line, fails without a password
+    ///
+    pub(crate) fn from_matches(matches: &ArgMatches) -> Result<Self> {
+        let password = match matches.value_of("password") {
+            Some(password) if !password.is_empty() => password.to_string(),
+            _ => bail!("A password is required, set --password or CHANNELS_LITE_PASSWORD"),
+        };
+        Ok(Options {
+            network: Network::from_str(matches.value_of("network").unwrap_or("devnet"))?,
+            node_url: matches.value_of("node-url").map(str::to_string),
+            seed: matches.value_of("seed").map(str::to_string),
+            store: FileStore::new(matches.value_of("state-dir").unwrap_or(".channels-lite"))?,
+            id: matches.value_of("id").map(str::to_string),
+            password: password,
+        })
+    }
+
+    ///
+    /// Transport to the node url if given, to the node of the network otherwise
+    ///
+    fn transport(&self) -> Result<ChannelTransport> {
+        let url = match &self.node_url {
+            Some(url) => url.as_str(),
+            None => self.network.as_string(),
+        };
+        let transport =
+            TangleTransport::from_url(url, &NetworkConfig::default(), self.network.send_options())?;
+        Ok(ChannelTransport::Tangle(transport))
+    }
+
+    fn id<'a>(&'a self, default: &'a str) -> &'a str {
+        self.id.as_deref().unwrap_or(default)
+    }
+}
+
+pub(crate) fn app() -> App<'static, 'static> {
+    App::new("channels-lite")
+        .about("Author and subscriber operations on IOTA Streams channels")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("network")
+                .long("network")
+                .env("CHANNELS_LITE_NETWORK")
+                .default_value("devnet")
+                .help("Network: mainnet, devnet, comnet, local or custom:<mwm>:<url>")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("node-url")
+                .long("node-url")
+                .env("CHANNELS_LITE_NODE_URL")
+                .takes_value(true)
+                .help("Node url, overriding the node of the network")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .env("CHANNELS_LITE_SEED")
+                .takes_value(true)
+                .hide_env_values(true)
+                .help("Seed of a new author or subscriber, random if not set")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("state-dir")
+                .long("state-dir")
+                .env("CHANNELS_LITE_STATE_DIR")
+                .default_value(".channels-lite")
+                .help("Directory of the sealed states")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("id")
+                .long("id")
+                .env("CHANNELS_LITE_ID")
+                .takes_value(true)
+                .help("Id of the state, `author` or `subscriber` by default")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("password")
+                .long("password")
+                .env("CHANNELS_LITE_PASSWORD")
+                .takes_value(true)
+                .empty_values(false)
+                .hide_env_values(true)
+                .help("Password sealing the state, required")
+                .global(true),
+        )
+        .subcommand(
+            SubCommand::with_name("author")
+                .about("Channel author")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(SubCommand::with_name("open").about("Open a new channel"))
+                .subcommand(
+                    SubCommand::with_name("add-subscriber")
+                        .about("Grant access to a subscriber, prints the keyload tag")
+                        .arg(
+                            Arg::with_name("tag")
+                                .required(true)
+                                .help("Subscription tag"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("send")
+                        .about("Write a signed packet, prints its tag")
+                        .arg(
+                            Arg::with_name("file")
+                                .long("file")
+                                .takes_value(true)
+                                .required(true)
+                                .help("JSON file of the payload"),
+                        )
+                        .arg(
+                            Arg::with_name("masked")
+                                .long("masked")
+                                .help("Send the payload as masked data instead of public data"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("subscriber")
+                .about("Channel subscriber")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("connect")
+                        .about("Subscribe to a channel, prints the subscription tag")
+                        .arg(
+                            Arg::with_name("invite")
+                                .required(true)
+                                .help("Channel address and announcement tag, as `address:tag`"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("fetch")
+                        .about("Print the new messages, one JSON object per line")
+                        .arg(
+                            Arg::with_name("follow")
+                                .long("follow")
+                                .help("Keep polling until the channel is closed"),
+                        )
+                        .arg(
+                            Arg::with_name("interval")
+                                .long("interval")
+                                .default_value("10")
+                                .help("Seconds between two polls with --follow"),
+                        ),
+                ),
+        )
+}
+
+fn main() {
+    let matches = app().get_matches();
+    if let Err(e) = run(&matches) {
+        // The channel errors carry their stable code, for the scripts branching on it
+        let mut error = match e.downcast_ref::<ChannelError>() {
+            Some(e) => e.as_json(),
+            None => json!({}),
+        };
+        error["error"] = json!(format!("{:#}", e));
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    let options = Options::from_matches(matches)?;
+    match matches.subcommand() {
+        ("author", Some(author)) => match author.subcommand() {
+            ("open", Some(_)) => author_open(&options),
+            ("add-subscriber", Some(args)) => {
+                author_add_subscriber(&options, args.value_of("tag").unwrap())
+            }
+            ("send", Some(args)) => author_send(
+                &options,
+                args.value_of("file").unwrap(),
+                args.is_present("masked"),
+            ),
+            _ => unreachable!(),
+        },
+        ("subscriber", Some(subscriber)) => match subscriber.subcommand() {
+            ("connect", Some(args)) => {
+                subscriber_connect(&options, args.value_of("invite").unwrap())
+            }
+            ("fetch", Some(args)) => {
+                let interval = args
+                    .value_of("interval")
+                    .unwrap()
+                    .parse()
+                    .context("--interval must be a number of seconds")?;
+                subscriber_fetch(
+                    &options,
+                    args.is_present("follow"),
+                    Duration::from_secs(interval),
+                )
+            }
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
+
+fn author_open(options: &Options) -> Result<()> {
+    let mut author = channel_author::Channel::new_with_transport(
+        options.network,
+        options.transport()?,
+        options.seed.clone(),
+    )?;
+    let (channel_address, announcement_tag) = author.open()?;
+    author.persist_to(&options.store, options.id("author"), &options.password)?;
+    print(json!({
+        "channel_address": channel_address,
+        "announcement_tag": announcement_tag,
+        "invite": format!("{}:{}", channel_address, announcement_tag),
+    }))
+}
+
+fn author_add_subscriber(options: &Options, subscription_tag: &str) -> Result<()> {
+    let mut author = restore_author(options)?;
+    let keyload_tag = author.add_subscriber(subscription_tag.to_string())?;
+    author.persist_to(&options.store, options.id("author"), &options.password)?;
+    print(json!({ "keyload_tag": keyload_tag }))
+}
+
+fn author_send(options: &Options, file: &str, masked: bool) -> Result<()> {
+    let data: Value = serde_json::from_slice(
+        &fs::read(file).with_context(|| format!("Failed to read {}", file))?,
+    )
+    .with_context(|| format!("{} is not valid JSON", file))?;
+    let mut builder = PayloadBuilder::new();
+    if masked {
+        builder.masked(&data)?;
+    } else {
+        builder.public(&data)?;
+    }
+
+    let mut author = restore_author(options)?;
+    let tag = author.write_signed(builder.build())?;
+    author.persist_to(&options.store, options.id("author"), &options.password)?;
+    print(json!({ "tag": tag }))
+}
+
+fn subscriber_connect(options: &Options, invite: &str) -> Result<()> {
+    let (channel_address, announcement_tag) = parse_invite(invite)?;
+    let mut subscriber = channel_subscriber::Channel::new_with_transport(
+        options.network,
+        options.transport()?,
+        channel_address,
+        announcement_tag,
+        options.seed.clone(),
+    )?;
+    let subscription_tag = subscriber.connect()?;
+    subscriber.persist_to(&options.store, options.id("subscriber"), &options.password)?;
+    print(json!({ "subscription_tag": subscription_tag }))
+}
+
+fn subscriber_fetch(options: &Options, follow: bool, interval: Duration) -> Result<()> {
+    let mut subscriber = channel_subscriber::Channel::restore_from_with_transport(
+        &options.store,
+        options.id("subscriber"),
+        &options.password,
+        options.network,
+        options.transport()?,
+    )?;
+    loop {
+        for msg in subscriber.poll_new()? {
+            print(serde_json::to_value(&msg)?)?;
+        }
+        subscriber.persist_to(&options.store, options.id("subscriber"), &options.password)?;
+        if !follow || subscriber.is_closed() {
+            return Ok(());
+        }
+        thread::sleep(interval);
+    }
+}
+
+fn restore_author(options: &Options) -> Result<channel_author::TangleChannel> {
+    Ok(channel_author::Channel::restore_from_with_transport(
+        &options.store,
+        options.id("author"),
+        &options.password,
+        options.network,
+        options.transport()?,
+    )?)
+}
+
+///
+/// Split an invite `channel_address:announcement_tag`
+///
+fn parse_invite(invite: &str) -> Result<(String, String)> {
+    let mut parts = invite.trim().splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some(address), Some(tag)) if !address.is_empty() && !tag.is_empty() => {
+            Ok((address.to_string(), tag.to_string()))
+        }
+        _ => Err(ChannelError::InvalidAddress {
+            address: invite.to_string(),
+            reason: "expected `channel_address:announcement_tag`".to_string(),
+        }
+        .into()),
+    }
+}
+
+fn print(value: Value) -> Result<()> {
+    println!("{}", serde_json::to_string(&value)?);
+    Ok(())
+}
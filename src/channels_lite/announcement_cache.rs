@@ -0,0 +1,77 @@
+//!
+//! Announcement cache
+//!
+use core::cell::RefCell;
+use iota_streams::app_channels::api::tangle::{Address, Message};
+use iota_streams::core::prelude::Rc;
+use std::{collections::HashMap, fmt};
+
+///
+/// Announcements already fetched, keyed by channel address and announcement tag
+///
+/// Clones share the same entries, so the subscribers of a channel built with the same
+/// cache fetch the announcement from the node once
+///
+#[derive(Clone, Default)]
+pub struct AnnouncementCache {
+    entries: Rc<RefCell<HashMap<(String, String), Message>>>,
+}
+
+impl AnnouncementCache {
+    ///
+    /// Create an empty cache
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Number of announcements cached
+    ///
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    ///
+    /// Whether no announcement is cached
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    ///
+    /// Whether the announcement of a channel is cached
+    ///
+    pub fn contains(&self, channel_address: &str, announcement_tag: &str) -> bool {
+        self.entries
+            .borrow()
+            .contains_key(&(channel_address.to_string(), announcement_tag.to_string()))
+    }
+
+    ///
+    /// Drop every cached announcement
+    ///
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+
+    pub(crate) fn get(&self, link: &Address) -> Option<Message> {
+        self.entries.borrow().get(&key(link)).cloned()
+    }
+
+    pub(crate) fn insert(&self, link: &Address, msg: Message) {
+        self.entries.borrow_mut().insert(key(link), msg);
+    }
+}
+
+impl fmt::Debug for AnnouncementCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnnouncementCache")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+fn key(link: &Address) -> (String, String) {
+    (link.appinst.to_string(), link.msgid.to_string())
+}
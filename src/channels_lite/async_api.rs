@@ -0,0 +1,256 @@
+//!
+//! Async channel API
+//!
+//! Async versions of the public methods of the author and subscriber. They run the
+//! same code as the blocking methods through `tokio::task::block_in_place`, so the
+//! executor keeps running the other tasks while a channel operation waits on the
+//! node. Requires the threaded tokio runtime, `block_in_place` panics on the basic
+//! scheduler.
+//!
+//! The sends wait for the rate limit with a tokio timer before running, not in
+//! `block_in_place`.
+//!
+use super::{
+    channel_author, channel_subscriber,
+    message::ChannelMessage,
+    multi_subscriber::{MultiSubscriber, SourcedMessage},
+};
+use crate::error::{ChannelError, Result};
+use crate::utils::payload::PacketPayload;
+use iota_streams::app::transport::Transport;
+use iota_streams::app_channels::api::tangle::{Address, Message};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::task::block_in_place;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+///
+/// Wait without blocking the executor until the rate limit allows a send
+///
+/// A single wait: with a clock other than the system clock, e.g. a `MockClock`, the
+/// send itself waits for the rest on that clock
+///
+async fn rate_limit_ready(ready: std::result::Result<(), Duration>) {
+    if let Err(wait) = ready {
+        tokio::time::delay_for(wait).await;
+    }
+}
+
+impl<T> channel_author::Channel<T>
+where
+    T: Transport<Address, Message>,
+    T::SendOptions: Copy + Default,
+    T::RecvOptions: Copy + Default,
+{
+    ///
+    /// Open a channel
+    ///
+    pub async fn open_async(&mut self) -> Result<(String, String)> {
+        rate_limit_ready(self.send_ready()).await;
+        block_in_place(|| self.open())
+    }
+
+    ///
+    /// Add subscriber
+    ///
+    pub async fn add_subscriber_async(&mut self, subscribe_tag: String) -> Result<String> {
+        rate_limit_ready(self.send_ready()).await;
+        block_in_place(|| self.add_subscriber(subscribe_tag))
+    }
+
+    ///
+    /// Write signed packet
+    ///
+    pub async fn write_signed_async<P>(&mut self, payload: P) -> Result<String>
+    where
+        P: PacketPayload,
+    {
+        rate_limit_ready(self.send_ready()).await;
+        block_in_place(|| self.write_signed(payload))
+    }
+
+    ///
+    /// Write tagged packet
+    ///
+    pub async fn write_tagged_async<P>(&mut self, payload: P) -> Result<String>
+    where
+        P: PacketPayload,
+    {
+        rate_limit_ready(self.send_ready()).await;
+        block_in_place(|| self.write_tagged(payload))
+    }
+
+    ///
+    /// Collect the acknowledgements published by subscribers for a message
+    ///
+    pub async fn collect_acks_async(&mut self, tag: String) -> Result<Vec<String>> {
+        block_in_place(|| self.collect_acks(tag))
+    }
+
+    ///
+    /// Wait until the node serves the message
+    ///
+    /// Same as `wait_for_attachment`, the waits between two checks don't block the
+    /// executor
+    ///
+    pub async fn wait_for_attachment_async(&self, tag: &str, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match block_in_place(|| self.wait_for_attachment(tag, Duration::from_secs(0))) {
+                Err(ChannelError::Timeout { .. }) => (),
+                res => return res,
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(ChannelError::Timeout {
+                    node: self.node().to_string(),
+                    after: timeout,
+                });
+            }
+            tokio::time::delay_for(POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+
+    ///
+    /// Close the channel
+    ///
+    pub async fn close_async(&mut self) -> Result<String> {
+        rate_limit_ready(self.send_ready()).await;
+        block_in_place(|| self.close())
+    }
+}
+
+impl<T> channel_subscriber::Channel<T>
+where
+    T: Transport<Address, Message>,
+    T::SendOptions: Copy + Default,
+    T::RecvOptions: Copy + Default,
+{
+    ///
+    /// Connect
+    ///
+    pub async fn connect_async(&mut self) -> Result<String> {
+        rate_limit_ready(self.send_ready()).await;
+        block_in_place(|| self.connect())
+    }
+
+    ///
+    /// Update keyload
+    ///
+    pub async fn update_keyload_async(&mut self, keyload_tag: String) -> Result<()> {
+        block_in_place(|| self.update_keyload(keyload_tag))
+    }
+
+    ///
+    /// Read signed packet
+    ///
+    pub async fn read_signed_async(
+        &mut self,
+        signed_packet_tag: String,
+    ) -> Result<Vec<(Option<String>, Option<String>)>> {
+        block_in_place(|| self.read_signed(signed_packet_tag))
+    }
+
+    ///
+    /// Read tagged packet
+    ///
+    pub async fn read_tagged_async(
+        &mut self,
+        tagged_packet_tag: String,
+    ) -> Result<Vec<(Option<String>, Option<String>)>> {
+        block_in_place(|| self.read_tagged(tagged_packet_tag))
+    }
+
+    ///
+    /// Read a message and all the messages linked beneath it
+    ///
+    pub async fn read_subtree_async(&mut self, anchor_tag: String) -> Result<Vec<ChannelMessage>> {
+        block_in_place(|| self.read_subtree(anchor_tag))
+    }
+
+    ///
+    /// Acknowledge a message
+    ///
+    pub async fn acknowledge_async(&mut self, tag: String) -> Result<String> {
+        rate_limit_ready(self.send_ready()).await;
+        block_in_place(|| self.acknowledge(tag))
+    }
+
+    ///
+    /// Generates the next message in the channels
+    ///
+    pub async fn get_next_message_async(&mut self) -> Vec<Option<String>> {
+        block_in_place(|| self.get_next_message())
+    }
+
+    ///
+    /// New messages since the previous call
+    ///
+    pub async fn poll_new_async(&mut self) -> Result<Vec<ChannelMessage>> {
+        block_in_place(|| self.poll_new())
+    }
+
+    ///
+    /// Next message in the channel, waiting up to `timeout` for it to appear
+    ///
+    /// Same as `next_message_blocking`, the waits between two fetches don't block the
+    /// executor
+    ///
+    pub async fn next_message_async(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<ChannelMessage>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let msg = block_in_place(|| self.next_message_blocking(Duration::from_secs(0)))?;
+            if msg.is_some() || self.is_closed() {
+                return Ok(msg);
+            }
+            let now = Instant::now();
+            if now >= deadline || self.cancel_token().is_cancelled() {
+                return Ok(None);
+            }
+            tokio::time::delay_for(POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+
+    ///
+    /// Hand the messages of the channel to `handler` one at a time, until the end of
+    /// channel
+    ///
+    /// The next message is fetched once the future returned by the handler completed: a
+    /// handler slower than the channel holds the walk back instead of letting the
+    /// messages pile up. Returns after the end of channel marker was handled, when the
+    /// `cancel_token` is cancelled, or with the first error of the handler
+    ///
+    pub async fn consume<F, Fut>(&mut self, mut handler: F) -> Result<()>
+    where
+        F: FnMut(ChannelMessage) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        loop {
+            match block_in_place(|| self.next_message_blocking(Duration::from_secs(0)))? {
+                Some(msg) => handler(msg).await?,
+                None if self.is_closed() || self.cancel_token().is_cancelled() => return Ok(()),
+                None => tokio::time::delay_for(POLL_INTERVAL).await,
+            }
+        }
+    }
+}
+
+impl MultiSubscriber {
+    ///
+    /// Connect to every channel
+    ///
+    pub async fn connect_all_async(&mut self) -> Result<Vec<(String, String)>> {
+        block_in_place(|| self.connect_all())
+    }
+
+    ///
+    /// Fetch the new messages of every channel
+    ///
+    pub async fn fetch_all_async(&mut self) -> Vec<SourcedMessage> {
+        block_in_place(|| self.fetch_all())
+    }
+}
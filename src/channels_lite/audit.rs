@@ -0,0 +1,139 @@
+//!
+//! Audit reports
+//!
+//! The author records every message it publishes. The report lists them in order with
+//! a digest of their payload, along with the keyload epochs and the members they grant
+//! access to. A subscriber cross-checks a report against the tangle with
+//! `verify_audit_report`
+//!
+use super::message::MessageKind;
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+
+///
+/// Version of the audit report format
+///
+pub const AUDIT_REPORT_VERSION: u32 = 1;
+
+///
+/// Message published by the author
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AuditRecord {
+    /// Message tag
+    ///
+    pub tag: String,
+    /// Message kind
+    ///
+    pub kind: MessageKind,
+    /// Tag of the message it is linked to, none for the announcement
+    ///
+    pub parent: Option<String>,
+    /// Time the message was published, in seconds since the unix epoch
+    ///
+    pub timestamp: u64,
+    /// Digest of the public and masked payloads, see `payload_digest`
+    ///
+    pub digest: String,
+}
+
+///
+/// Members granted access by a keyload, until the next one
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct KeyloadEpoch {
+    /// Keyload tag
+    ///
+    pub tag: String,
+    /// Time the keyload was published, in seconds since the unix epoch
+    ///
+    pub timestamp: u64,
+    /// Subscribe tags of the subscribers included, their public keys are only known to
+    /// Streams
+    ///
+    pub subscriptions: Vec<String>,
+    /// Hex identifiers of the pre-shared keys included
+    ///
+    pub psk_ids: Vec<String>,
+}
+
+///
+/// Messages published by the author and who could read them
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AuditReport {
+    /// Report format version
+    ///
+    pub version: u32,
+    /// Channel address
+    ///
+    pub channel_address: String,
+    /// Announcement tag
+    ///
+    pub announcement_tag: String,
+    /// Whether the author closed the channel
+    ///
+    pub closed: bool,
+    /// Messages, in publication order
+    ///
+    pub messages: Vec<AuditRecord>,
+    /// Keyload epochs, in publication order
+    ///
+    pub keyloads: Vec<KeyloadEpoch>,
+}
+
+impl AuditReport {
+    ///
+    /// Parse a report written by `export_audit_report`
+    ///
+    pub fn from_reader(reader: impl Read) -> Result<Self> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+///
+/// Outcome of the verification of an audit report, tags by result
+///
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AuditVerification {
+    /// Found with the recorded kind and digest
+    ///
+    pub verified: Vec<String>,
+    /// Not found on the tangle
+    ///
+    pub missing: Vec<String>,
+    /// Found with another kind or digest
+    ///
+    pub mismatched: Vec<String>,
+    /// Found but not readable by the subscriber, e.g. not part of the keyload
+    ///
+    pub unreadable: Vec<String>,
+}
+
+impl AuditVerification {
+    ///
+    /// Whether every message of the report was found and matches
+    ///
+    pub fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.mismatched.is_empty() && self.unreadable.is_empty()
+    }
+}
+
+///
+/// Hex SHA-256 of the public and masked payloads as published, length-prefixed so the
+/// boundary between them is part of the digest
+///
+pub fn payload_digest(public: &[u8], masked: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(&(public.len() as u64).to_be_bytes());
+    hasher.update(public);
+    hasher.update(&(masked.len() as u64).to_be_bytes());
+    hasher.update(masked);
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
@@ -0,0 +1,102 @@
+//!
+//! Author bookkeeping state
+//!
+use super::audit::{AuditRecord, KeyloadEpoch};
+use super::policy::EpochCounters;
+use crate::error::{ChannelError, Result};
+use crate::utils::idempotency::IdempotentWrite;
+use base64::{decode_config, encode_config, URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+
+///
+/// Version of the author state token format
+///
+pub const AUTHOR_STATE_VERSION: u32 = 1;
+
+///
+/// Bookkeeping of an author, exported as an opaque token to survive restarts
+///
+/// The keys are not part of it, they derive from the seed
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AuthorState {
+    /// Token format version
+    ///
+    pub version: u32,
+    /// Channel address
+    ///
+    pub channel_address: String,
+    /// Announcement tag
+    ///
+    pub announcement_tag: String,
+    /// Tag of the last keyload
+    ///
+    pub last_keyload_tag: String,
+    /// Tag of the last packet written
+    ///
+    pub previous_msg_tag: String,
+    /// Subscribe tags processed by `add_subscriber`, in order
+    ///
+    pub processed_subscriptions: Vec<String>,
+    /// Messages published, for the audit report
+    ///
+    #[serde(default)]
+    pub history: Vec<AuditRecord>,
+    /// Keyload epochs, for the audit report
+    ///
+    #[serde(default)]
+    pub keyload_epochs: Vec<KeyloadEpoch>,
+    /// Hex identifiers of the pre-shared keys added
+    ///
+    #[serde(default)]
+    pub psk_ids: Vec<String>,
+    /// Whether the packets are numbered
+    ///
+    #[serde(default)]
+    pub sequence_numbers: bool,
+    /// Whether the JSON data is written in canonical form
+    ///
+    #[serde(default)]
+    pub canonical_json: bool,
+    /// Sequence number of the next packet
+    ///
+    #[serde(default)]
+    pub next_seq: u64,
+    /// Writes remembered by `write_signed_idempotent`, least recently used first
+    ///
+    #[serde(default)]
+    pub idempotent_writes: Vec<IdempotentWrite>,
+    /// Counters of the current keyload epoch, for the channel policy
+    ///
+    #[serde(default)]
+    pub epoch: EpochCounters,
+    /// Generation of the author, raised by each snapshot
+    ///
+    #[serde(default)]
+    pub generation: u64,
+}
+
+impl AuthorState {
+    ///
+    /// Encode as an opaque token
+    ///
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        encode_config(&json, URL_SAFE_NO_PAD)
+    }
+
+    ///
+    /// Decode a token
+    ///
+    pub fn decode(token: &str) -> Result<Self> {
+        let json = decode_config(token.trim(), URL_SAFE_NO_PAD)?;
+        let state: AuthorState = serde_json::from_slice(&json)?;
+        if state.version != AUTHOR_STATE_VERSION {
+            return Err(ChannelError::PayloadEncoding(format!(
+                "Unsupported author state version {}, expected {}",
+                state.version, AUTHOR_STATE_VERSION
+            )));
+        }
+        Ok(state)
+    }
+}
@@ -0,0 +1,191 @@
+//!
+//! Builder settings shared by the author and the subscriber
+//!
+//! Both builders hold the node settings in a `config: ChannelConfig` and the transports
+//! replacing the node client in `transport` and `read_transport`, the setters of these
+//! are written once here and expanded in each builder.
+//!
+
+///
+/// Setters of the node, client and transport settings of a channel builder
+///
+macro_rules! node_settings {
+    () => {
+        ///
+        /// Check the node compatibility and health before building the channel
+        ///
+        pub fn verify_node(&mut self, verify: bool) -> &mut Self {
+            self.config.verify_node = verify;
+            self
+        }
+
+        ///
+        /// Maximum milestone lag tolerated by the node health check
+        ///
+        pub fn max_milestone_lag(&mut self, max_lag: u32) -> &mut Self {
+            self.config.max_milestone_lag = max_lag;
+            self
+        }
+
+        ///
+        /// Check the node milestone lag before each send, refusing to send through a
+        /// desynced node
+        ///
+        pub fn verify_sync(&mut self, verify: bool) -> &mut Self {
+            self.config.verify_sync = verify;
+            self
+        }
+
+        ///
+        /// Time the result of the sync check is reused
+        ///
+        pub fn sync_check_ttl(&mut self, ttl: std::time::Duration) -> &mut Self {
+            self.config.sync_check_ttl = ttl;
+            self
+        }
+
+        ///
+        /// HTTP settings of the node client
+        ///
+        pub fn network(
+            &mut self,
+            network: $crate::channels_lite::config::NetworkConfig,
+        ) -> &mut Self {
+            self.config.network = network;
+            self
+        }
+
+        ///
+        /// Proxy, root certificates and user agent of the node client
+        ///
+        /// Invalid settings fail the build
+        ///
+        pub fn http_client(
+            &mut self,
+            http: $crate::channels_lite::config::HttpClientConfig,
+        ) -> &mut Self {
+            self.config.network.http = http;
+            self
+        }
+
+        ///
+        /// Read every message from the additional nodes and require `min_agreement` of
+        /// them, primary node included, to return the same content
+        ///
+        pub fn quorum(&mut self, quorum: $crate::channels_lite::config::QuorumConfig) -> &mut Self {
+            self.config.quorum = Some(quorum);
+            self
+        }
+
+        ///
+        /// Detect the send options from the node info, falling back to the network
+        /// defaults
+        ///
+        pub fn auto_tune(&mut self, auto_tune: bool) -> &mut Self {
+            self.config.auto_tune = auto_tune;
+            self
+        }
+
+        ///
+        /// Retry configuration applied to the node communication
+        ///
+        pub fn retry(&mut self, retry: $crate::transport::retry::RetryConfig) -> &mut Self {
+            self.config.retry = retry;
+            self
+        }
+
+        ///
+        /// Proof of work mode, the network default if not set
+        ///
+        pub fn pow_mode(&mut self, mode: $crate::channels_lite::config::PowMode) -> &mut Self {
+            self.config.pow_mode = Some(mode);
+            self
+        }
+
+        ///
+        /// Threads of the local proof of work, `min(4, num_cpus)` by default
+        ///
+        pub fn pow_threads(&mut self, threads: usize) -> &mut Self {
+            self.config.pow_threads = threads;
+            self
+        }
+
+        ///
+        /// Node or PoW service attaching the messages when the proof of work is remote
+        ///
+        pub fn pow_node(&mut self, url: &str) -> &mut Self {
+            self.config.pow_node = Some(url.to_string());
+            self
+        }
+
+        ///
+        /// Node the messages are sent through, e.g. a low-latency node, the network node
+        /// if not set
+        ///
+        pub fn write_node(&mut self, url: &str) -> &mut Self {
+            self.config.write_node = Some(url.to_string());
+            self
+        }
+
+        ///
+        /// Node the messages are fetched from, e.g. a permanode holding the history, the
+        /// write node if not set
+        ///
+        pub fn read_node(&mut self, url: &str) -> &mut Self {
+            self.config.read_node = Some(url.to_string());
+            self
+        }
+
+        ///
+        /// Rate limit of the outgoing messages
+        ///
+        pub fn rate_limit(&mut self, limit: $crate::transport::rate_limit::RateLimit) -> &mut Self {
+            self.config.rate_limit = Some(limit);
+            self
+        }
+
+        ///
+        /// Time source of the sleeps, timeouts and timestamps, the system clock by
+        /// default
+        ///
+        pub fn clock(
+            &mut self,
+            clock: ::iota_streams::core::prelude::Rc<dyn $crate::utils::clock::Clock>,
+        ) -> &mut Self {
+            self.config.clock = clock;
+            self
+        }
+
+        ///
+        /// Transport used instead of the node client, e.g. replaying recorded fixtures or
+        /// going through a proxy
+        ///
+        /// The node settings still apply to `check_node` and `verify_node`
+        ///
+        pub fn transport<T>(&mut self, transport: T) -> &mut Self
+        where
+            T: $crate::transport::Transport<$crate::transport::Address, $crate::transport::Message>
+                + 'static,
+            T::SendOptions: Default,
+            T::RecvOptions: Default,
+        {
+            self.transport = Some($crate::transport::custom::CustomTransport::new(transport));
+            self
+        }
+
+        ///
+        /// Transport the messages are fetched from instead of the read node, see
+        /// `transport`
+        ///
+        pub fn read_transport<T>(&mut self, transport: T) -> &mut Self
+        where
+            T: $crate::transport::Transport<$crate::transport::Address, $crate::transport::Message>
+                + 'static,
+            T::SendOptions: Default,
+            T::RecvOptions: Default,
+        {
+            self.read_transport = Some($crate::transport::custom::CustomTransport::new(transport));
+            self
+        }
+    };
+}
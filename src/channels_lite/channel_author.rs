@@ -1,195 +1,1928 @@
 //!
 //! Channel author
 //!
-use super::Network;
-use crate::utils::{payload::PacketPayload, random_seed};
-use anyhow::{bail, Result};
+use super::{
+    audit::{payload_digest, AuditRecord, AuditReport, KeyloadEpoch, AUDIT_REPORT_VERSION},
+    author_state::{AuthorState, AUTHOR_STATE_VERSION},
+    channel_subscriber,
+    config::{redact_credentials, ChannelConfig, NetworkConfig},
+    message::{ChannelMessage, MessageKind, SentMessage},
+    node::{NodeHealth, NodeInfo, SendOptionsSource, TunedSendOptions},
+    policy::{ChannelPolicy, EpochCounters, PolicyStats},
+    reader::ChannelReader,
+    shutdown::{AttachedStore, ShutdownReport},
+    snapshot::{ChannelSnapshot, SnapshotContent},
+    span::OpSpan,
+    store::{load_sealed, save_sealed, StateStore, StoredAuthor},
+    summary::{ChannelRole, ChannelSummary},
+    Network,
+};
+use crate::error::{ChannelError, Result};
+use crate::transport::{
+    custom::CustomTransport,
+    retry::RetryingTransport,
+    stats::TransportStats,
+    tangle::{packet_fragments, SentRecord},
+    ChannelTransport,
+};
+use crate::utils::{
+    ack::{
+        new_request_id, Ack, AckOutcome, AckReceived, AckRequestPayload, PendingAck,
+        ACK_REQUEST_CAPACITY, ACK_RETENTION, DEFAULT_ACK_TIMEOUT,
+    },
+    clock::Clock,
+    content_type::TypedPayload,
+    digest::{payload_content_digest, CanonicalPayload},
+    end_of_channel::EndOfChannel,
+    expiry::{ExpiringPayload, Expiry},
+    file_transfer::{FileChunk, FileManifest, FILE_CHUNK_SIZE},
+    idempotency::{IdempotencyCache, IdempotentWrite, DEFAULT_IDEMPOTENCY_WINDOW},
+    payload::{
+        json::{Payload, PayloadBuilder},
+        PacketPayload,
+    },
+    protocol::ProtocolMarker,
+    psk::{psk_id, Psk},
+    random_seed,
+    secret::Secret,
+    sequence::{SequenceNumber, SequencedPayload},
+};
+use base64::{encode_config, URL_SAFE_NO_PAD};
 use core::cell::RefCell;
 use iota::client as iota_client;
-use iota_streams::app::transport::tangle::{
-    client::{RecvOptions, SendTrytesOptions},
-    PAYLOAD_BYTES,
-};
+use iota_streams::app::transport::tangle::{client::RecvOptions, PAYLOAD_BYTES};
 use iota_streams::app_channels::{
-    api::tangle::{Address, Author},
+    api::tangle::{Address, Author, Message},
     message,
 };
 use iota_streams::{
     app::transport::Transport,
     core::prelude::{Rc, String},
+    ddml::types::Bytes,
 };
-use std::string::ToString;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt, fs,
+    io::Write,
+    path::Path,
+    string::ToString,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const ATTACHMENT_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 ///
 /// Channel
 ///
-pub struct Channel {
-    author: Author<&'static iota_client::Client>,
-    send_opt: SendTrytesOptions,
+pub struct Channel<T = ChannelTransport> {
+    author: Author<RetryingTransport<T>>,
+    transport: Rc<RefCell<RetryingTransport<T>>>,
+    config: ChannelConfig,
+    node_url: String,
+    send_opt: TunedSendOptions,
     channel_address: String,
     announcement_id: String,
     last_keyload_tag: String,
     previous_msg_tag: String,
+    closed: bool,
+    auto_keyload: bool,
+    protocol_marker: bool,
+    idempotency: IdempotencyCache,
+    sequence_numbers: bool,
+    canonical_json: bool,
+    next_seq: u64,
+    generation: u64,
+    acks: HashMap<String, Vec<String>>,
+    ack_timeout: Duration,
+    ack_requests: HashMap<String, AckRequest>,
+    ack_order: VecDeque<String>,
+    ack_inbox: Vec<(Ack, String)>,
+    policy: ChannelPolicy,
+    epoch: EpochCounters,
+    processed_subscriptions: Vec<String>,
+    pending_subscriptions: Vec<String>,
+    history: Vec<AuditRecord>,
+    keyload_epochs: Vec<KeyloadEpoch>,
+    psk_ids: Vec<String>,
+    read_back: usize,
+    marker_checked: bool,
+    store: Option<AttachedStore>,
+    shut_down: bool,
+    seed: Secret<String>,
+}
+
+///
+/// Acknowledgement request of `write_signed_with_ack`
+///
+struct AckRequest {
+    pending: PendingAck,
+    acknowledged: bool,
+    timed_out: bool,
 }
 
-impl Channel {
+///
+/// Channel on the tangle
+///
+pub type TangleChannel = Channel<ChannelTransport>;
+
+impl Channel<ChannelTransport> {
     ///
     /// Initialize the Channel
     ///
-    pub fn new(node: Network, seed_option: Option<String>) -> Channel {
+    /// Fails if the transport of the node can't be built, e.g. for a malformed url
+    ///
+    pub fn new(node: Network, seed_option: Option<String>) -> Result<Channel> {
+        let config = ChannelConfig::new(node);
+        let transport = ChannelTransport::Tangle(config.tangle_transport()?);
+        Self::with_tangle_config(config, transport, seed_option)
+    }
+
+    ///
+    /// Restore an author persisted with `persist_to`
+    ///
+    pub fn restore_from<S>(store: &S, id: &str, password: &str, node: Network) -> Result<Channel>
+    where
+        S: StateStore + ?Sized,
+    {
+        let stored: StoredAuthor = load_sealed(store, id, password)?;
+        let config = ChannelConfig::new(node);
+        let transport = ChannelTransport::Tangle(config.tangle_transport()?);
+        let mut channel =
+            Self::with_tangle_config(config, transport, Some(stored.seed.into_inner()))?;
+        channel.restore_state(&stored.state)?;
+        Ok(channel)
+    }
+
+    ///
+    /// Restore an author from a snapshot taken on another machine, see `snapshot`
+    ///
+    /// The author must not go on writing on the machine the snapshot was taken from
+    ///
+    pub fn from_snapshot(node: Network, snapshot: &[u8], password: &str) -> Result<Channel> {
+        let snapshot = ChannelSnapshot::from_bytes(snapshot)?;
+        let content = snapshot.open(password)?;
+        let config = ChannelConfig::new(node);
+        let transport = ChannelTransport::Tangle(config.tangle_transport()?);
+        let mut channel =
+            Self::with_tangle_config(config, transport, Some(content.seed.expose().clone()))?;
+        channel.restore_snapshot(content)?;
+        Ok(channel)
+    }
+
+    fn with_tangle_config(
+        config: ChannelConfig,
+        mut transport: ChannelTransport,
+        seed_option: Option<String>,
+    ) -> Result<Channel> {
+        let send_opt = config.tune_send_options(&mut transport);
+        Self::with_config(config, transport, seed_option, send_opt)
+    }
+
+    ///
+    /// Check the health of the configured node
+    ///
+    pub fn check_node(&self) -> Result<NodeHealth> {
+        self.transport
+            .borrow()
+            .check_node(&self.node_url, self.config.max_milestone_lag)
+    }
+
+    ///
+    /// Check the node is synced enough to publish, returns its milestone lag
+    ///
+    /// Always queries the node, unlike the cached check run before the sends
+    ///
+    pub fn check_sync(&self) -> Result<u32> {
+        self.transport
+            .borrow()
+            .check_sync(&self.node_url, self.config.max_milestone_lag)
+    }
+
+    ///
+    /// Check the configured node runs a supported software and version
+    ///
+    /// The returned info carries the network id, to check the node belongs to the
+    /// intended network
+    ///
+    pub fn check_node_compatibility(&self) -> Result<NodeInfo> {
+        self.transport
+            .borrow()
+            .check_node_compatibility(&self.node_url)
+    }
+
+    ///
+    /// Last messages sent, at most `tangle::SENT_LOG_CAPACITY`, with where their proof
+    /// of work was computed
+    ///
+    pub fn sent_log(&self) -> Vec<SentRecord> {
+        self.transport.borrow().inner().sent_log().to_vec()
+    }
+
+    ///
+    /// Switch to another node, keeping the channel state
+    ///
+    /// The node url is validated and the node health-checked before the switch
+    ///
+    pub fn set_node(&mut self, url: &str) -> Result<()> {
+        let transport = self
+            .config
+            .tangle_transport_at(url, self.send_opt.options)?;
+        self.transport
+            .borrow_mut()
+            .switch_node(transport, self.config.max_milestone_lag)?;
+        self.node_url = url.to_string();
+        Ok(())
+    }
+}
+
+impl<T> Channel<T>
+where
+    T: Transport<Address, Message>,
+    T::SendOptions: Copy + Default,
+    T::RecvOptions: Copy + Default,
+{
+    ///
+    /// Initialize the Channel on a given transport
+    ///
+    /// The node is only used for the send options and the reports
+    ///
+    pub fn new_with_transport(
+        node: Network,
+        transport: T,
+        seed_option: Option<String>,
+    ) -> Result<Self> {
+        let config = ChannelConfig::new(node);
+        let send_opt = TunedSendOptions {
+            options: config.node.send_options(),
+            source: SendOptionsSource::Static,
+        };
+        Self::with_config(config, transport, seed_option, send_opt)
+    }
+
+    ///
+    /// Restore an author persisted with `persist_to` on a given transport
+    ///
+    pub fn restore_from_with_transport<S>(
+        store: &S,
+        id: &str,
+        password: &str,
+        node: Network,
+        transport: T,
+    ) -> Result<Self>
+    where
+        S: StateStore + ?Sized,
+    {
+        let stored: StoredAuthor = load_sealed(store, id, password)?;
+        let mut channel =
+            Self::new_with_transport(node, transport, Some(stored.seed.into_inner()))?;
+        channel.restore_state(&stored.state)?;
+        Ok(channel)
+    }
+
+    ///
+    /// Restore an author from a snapshot on a given transport, see `from_snapshot`
+    ///
+    pub fn from_snapshot_with_transport(
+        node: Network,
+        transport: T,
+        snapshot: &[u8],
+        password: &str,
+    ) -> Result<Self> {
+        let snapshot = ChannelSnapshot::from_bytes(snapshot)?;
+        let content = snapshot.open(password)?;
+        let mut channel =
+            Self::new_with_transport(node, transport, Some(content.seed.expose().clone()))?;
+        channel.restore_snapshot(content)?;
+        Ok(channel)
+    }
+
+    ///
+    /// Capture everything needed to go on publishing from another machine, sealed with
+    /// the password
+    ///
+    /// Bundles the seed, the bookkeeping of `export_state` and the policy. The author
+    /// restored with `from_snapshot` runs one generation after this one: the original
+    /// author must stop writing, and a snapshot must be restored once, see the
+    /// `snapshot` module for what the subscribers detect otherwise
+    ///
+    pub fn snapshot(&self, password: &str) -> Result<ChannelSnapshot> {
+        if self.announcement_id.is_empty() {
+            return Err(ChannelError::NotOpened);
+        }
+        let content = SnapshotContent {
+            seed: self.seed.clone(),
+            state: self.export_state(),
+            policy: self.policy.clone(),
+            generation: self.generation + 1,
+        };
+        ChannelSnapshot::seal(
+            &self.channel_address,
+            self.config.clock.system_time(),
+            &content,
+            password,
+        )
+    }
+
+    ///
+    /// Generation of the author, raised on the author restored from each snapshot
+    ///
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn restore_snapshot(&mut self, content: SnapshotContent) -> Result<()> {
+        self.restore_state(&content.state)?;
+        self.set_policy(content.policy);
+        self.generation = content.generation;
+        Ok(())
+    }
+
+    ///
+    /// Save the seed and the state of the author, sealed with the password
+    ///
+    /// A failed save leaves the previously stored state in place
+    ///
+    pub fn persist_to<S>(&self, store: &S, id: &str, password: &str) -> Result<()>
+    where
+        S: StateStore + ?Sized,
+    {
+        let stored = StoredAuthor {
+            seed: self.seed.clone(),
+            state: self.export_state(),
+        };
+        save_sealed(store, id, password, &stored)
+    }
+
+    ///
+    /// Store the state is persisted to by `shutdown`
+    ///
+    pub fn attach_store(&mut self, store: Rc<dyn StateStore>, id: &str, password: &str) {
+        self.store = Some(AttachedStore::new(store, id, password));
+    }
+
+    ///
+    /// Persist the state to the attached store, if any, and release the channel
+    ///
+    /// Every message is sent by the time the write methods return, nothing is left to
+    /// flush. The report carries the final `export_state` token and the number of
+    /// acknowledgements still awaited. A failed persist is returned and the channel
+    /// dropped with a warning
+    ///
+    pub fn shutdown(mut self) -> Result<ShutdownReport> {
+        let persisted_as = match &self.store {
+            Some(attached) => {
+                self.persist_to(
+                    attached.store.as_ref(),
+                    &attached.id,
+                    attached.password.expose(),
+                )?;
+                Some(attached.id.clone())
+            }
+            None => None,
+        };
+        self.shut_down = true;
+        Ok(ShutdownReport {
+            persisted_as: persisted_as,
+            pending_messages: 0,
+            final_state: self.export_state(),
+            pending_acks: self.pending_acks().len(),
+        })
+    }
+
+    fn with_config(
+        config: ChannelConfig,
+        transport: T,
+        seed_option: Option<String>,
+        send_opt: TunedSendOptions,
+    ) -> Result<Self> {
         let seed = match seed_option {
             Some(seed) => seed,
             None => random_seed::new(),
         };
-        iota_client::Client::add_node(node.as_string()).unwrap();
-        let author = Author::new(
-            &seed,
-            "utf-8",
-            PAYLOAD_BYTES,
-            false,
-            Rc::new(RefCell::new(iota_client::Client::get())),
-        );
+        let transport = Rc::new(RefCell::new(config.wrap_transport(transport)?));
+        let author = Author::new(&seed, "utf-8", PAYLOAD_BYTES, false, transport.clone());
 
-        let channel_address = author.channel_address().unwrap().to_string();
+        let channel_address = author
+            .channel_address()
+            .ok_or_else(|| ChannelError::State("The author has no channel address".to_string()))?
+            .to_string();
 
-        Self {
+        Ok(Self {
             author: author,
-            send_opt: node.send_options(),
+            transport: transport,
+            send_opt: send_opt,
+            node_url: config.write_node_url().to_string(),
+            config: config,
             channel_address: channel_address,
             announcement_id: String::default(),
             last_keyload_tag: String::default(),
             previous_msg_tag: String::default(),
+            closed: false,
+            auto_keyload: false,
+            protocol_marker: true,
+            idempotency: IdempotencyCache::new(),
+            sequence_numbers: false,
+            canonical_json: false,
+            next_seq: 0,
+            generation: 0,
+            acks: HashMap::new(),
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            ack_requests: HashMap::new(),
+            ack_order: VecDeque::new(),
+            ack_inbox: Vec::new(),
+            policy: ChannelPolicy::default(),
+            epoch: EpochCounters::default(),
+            processed_subscriptions: Vec::new(),
+            pending_subscriptions: Vec::new(),
+            history: Vec::new(),
+            keyload_epochs: Vec::new(),
+            psk_ids: Vec::new(),
+            read_back: 0,
+            marker_checked: false,
+            store: None,
+            shut_down: false,
+            seed: Secret::new(seed),
+        })
+    }
+
+    ///
+    /// Url of the node the channel talks to
+    ///
+    pub fn node(&self) -> &str {
+        &self.node_url
+    }
+
+    ///
+    /// Replace the underlying transport, keeping the channel state
+    ///
+    pub fn set_transport(&mut self, transport: T) {
+        *self.transport.borrow_mut().inner_mut() = transport;
+    }
+
+    ///
+    /// Fetch the messages through another transport, e.g. a permanode, the underlying
+    /// transport is then used for the sends only. `None` to fetch through it again
+    ///
+    pub fn set_read_transport(&mut self, transport: Option<T>) {
+        self.transport.borrow_mut().set_reader(transport);
+    }
+
+    ///
+    /// Url of the node the messages are fetched from, `None` if it is the node the
+    /// channel sends through or a read transport was set instead
+    ///
+    pub fn read_node(&self) -> Option<&str> {
+        self.config.read_node.as_deref()
+    }
+
+    ///
+    /// Replace the time source of the sleeps, timeouts and timestamps
+    ///
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.transport.borrow_mut().set_clock(clock.clone());
+        self.config.clock = clock;
+    }
+
+    ///
+    /// Check the rate limit allows a send right away, returns the time to wait otherwise
+    ///
+    pub(crate) fn send_ready(&self) -> std::result::Result<(), Duration> {
+        self.transport.borrow_mut().send_ready()
+    }
+
+    ///
+    /// HTTP settings the node client was built with
+    ///
+    pub fn network_config(&self) -> &NetworkConfig {
+        &self.config.network
+    }
+
+    ///
+    /// Send options used for outgoing messages, with their origin
+    ///
+    pub fn send_options(&self) -> &TunedSendOptions {
+        &self.send_opt
+    }
+
+    ///
+    /// Statistics of the transport operations made so far
+    ///
+    pub fn transport_stats(&self) -> TransportStats {
+        self.transport.borrow().stats().clone()
+    }
+
+    ///
+    /// Role, address, messages sent, last message written and node of the channel
+    ///
+    pub fn summary(&self) -> ChannelSummary {
+        let stats = self.transport.borrow().stats().send.clone();
+        ChannelSummary::new(
+            ChannelRole::Author,
+            &self.channel_address,
+            stats.count - stats.errors,
+            self.history.last().map(|record| record.tag.clone()),
+            &self.node_url,
+        )
+    }
+
+    ///
+    /// Reset the transport statistics
+    ///
+    pub fn reset_transport_stats(&mut self) {
+        self.transport.borrow_mut().reset_stats();
+    }
+
+    ///
+    /// Attachment time of a message as reported by the node, unlike the payload
+    /// timestamps it doesn't depend on the clock of the writing device
+    ///
+    /// Fails if the message is not found or the node doesn't report its attachment time
+    ///
+    pub fn attachment_time(&self, tag: &str) -> Result<SystemTime> {
+        let link = self.link(tag)?;
+        self.transport.borrow_mut().attachment_time(&link)
+    }
+
+    ///
+    /// Whether the node serves the message
+    ///
+    /// Attached doesn't mean confirmed: this Streams version doesn't expose the inclusion
+    /// state of the bundles, a message counts as attached once the node returns it, or
+    /// once enough nodes agree on it with quorum reads. The read node is asked when one is
+    /// set
+    ///
+    pub fn is_attached(&self, tag: &str) -> Result<bool> {
+        let link = self.link(tag)?;
+        let msgs = self
+            .transport
+            .borrow_mut()
+            .recv_messages(&link)
+            .map_err(ChannelError::from)?;
+        Ok(!msgs.is_empty())
+    }
+
+    ///
+    /// Wait until the node serves the message, see `is_attached`
+    ///
+    /// Polls the node every second, the transport errors worth retrying count as not
+    /// attached yet. Fails with `ChannelError::Timeout` if the message is still not
+    /// attached after `timeout`
+    ///
+    pub fn wait_for_attachment(&self, tag: &str, timeout: Duration) -> Result<()> {
+        let clock = self.config.clock.clone();
+        let deadline = clock.now() + timeout;
+        loop {
+            match self.is_attached(tag) {
+                Ok(true) => return Ok(()),
+                Ok(false) => (),
+                Err(e) if e.is_retryable() => log::debug!(
+                    target: "channels_lite::author",
+                    "Message {} not attached yet: {}",
+                    tag,
+                    e
+                ),
+                Err(e) => return Err(e),
+            }
+            let now = clock.now();
+            if now >= deadline || !clock.can_sleep() {
+                return Err(ChannelError::Timeout {
+                    node: self.node_url.clone(),
+                    after: timeout,
+                });
+            }
+            clock.sleep(ATTACHMENT_POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    ///
+    /// Link to a message of the channel
+    ///
+    fn link(&self, tag: &str) -> Result<Address> {
+        Address::from_str(&self.channel_address, tag).map_err(|()| ChannelError::InvalidAddress {
+            address: format!("{}:{}", self.channel_address, tag),
+            reason: "not a valid message tag".to_string(),
+        })
+    }
+
+    ///
+    /// Check packets can be written, the channel must be open, shared with a keyload and
+    /// not closed
+    ///
+    /// With the automatic keyload enabled, a keyload is published when there is none yet
+    ///
+    fn ensure_writable(&mut self) -> Result<()> {
+        if self.announcement_id.is_empty() {
+            return Err(ChannelError::NotOpened);
         }
+        if self.closed {
+            return Err(ChannelError::State(format!(
+                "Channel {} is closed",
+                self.channel_address
+            )));
+        }
+        if self.last_keyload_tag.is_empty() {
+            if !self.auto_keyload {
+                return Err(ChannelError::NoKeyload(self.channel_address.clone()));
+            }
+            self.warm_up()?;
+            let announce_link = self.link(&self.announcement_id)?;
+            let keyload = self.author.send_keyload_for_everyone(&announce_link)?;
+            self.last_keyload_tag = keyload.0.msgid.to_string();
+            self.record_keyload();
+        }
+        Ok(())
     }
 
     ///
     /// Open a channel
     ///
     pub fn open(&mut self) -> Result<(String, String)> {
+        let span = OpSpan::new("open", &self.channel_address, 0);
+        let res = span.in_scope(|| self.send_announce());
+        span.record(&res, |(_, tag)| tag);
+        res
+    }
+
+    fn send_announce(&mut self) -> Result<(String, String)> {
         let announcement_message = self.author.send_announce()?;
 
         self.announcement_id = announcement_message.msgid.to_string();
+        self.record(
+            self.announcement_id.clone(),
+            MessageKind::Announce,
+            None,
+            payload_digest(&[], &[]),
+        );
+        if self.protocol_marker {
+            self.send_protocol_marker(&announcement_message)?;
+        }
 
         Ok((self.channel_address.clone(), self.announcement_id.clone()))
     }
 
+    ///
+    /// Send the announcement again, for the nodes that pruned it
+    ///
+    /// The announcement derives from the author key only, the message sent is the same
+    /// and so is the returned tag: the subscribers already connected are unaffected and
+    /// the new ones keep joining with the tag they were given. Long-lived channels call
+    /// this periodically when their nodes don't keep the history
+    ///
+    pub fn rebroadcast_announcement(&mut self) -> Result<String> {
+        if self.announcement_id.is_empty() {
+            return Err(ChannelError::NotOpened);
+        }
+        let span = OpSpan::new("rebroadcast_announcement", &self.channel_address, 0);
+        let res = span.in_scope(|| {
+            let announcement_message = self.author.send_announce()?;
+            let tag = announcement_message.msgid.to_string();
+            if tag != self.announcement_id {
+                return Err(ChannelError::State(format!(
+                    "Announcement sent again as {}, not {}",
+                    tag, self.announcement_id
+                )));
+            }
+            Ok(tag)
+        });
+        span.record(&res, |tag| tag);
+        res
+    }
+
+    ///
+    /// Publish the protocol version marker, a signed packet linked to the announcement
+    ///
+    /// The packets written afterwards stay linked to the keyloads, the marker is only
+    /// read by the subscribers connecting
+    ///
+    fn send_protocol_marker(&mut self, announce_link: &Address) -> Result<()> {
+        let payload = PayloadBuilder::new()
+            .public(&ProtocolMarker::new())?
+            .build();
+        let (public, masked) = (payload.public_data(), payload.masked_data());
+        let (marker_link, _) = self
+            .author
+            .send_signed_packet(announce_link, public, masked)?;
+        self.record(
+            marker_link.msgid.to_string(),
+            MessageKind::SignedPacket,
+            Some(self.announcement_id.clone()),
+            payload_digest(&public.0, &masked.0),
+        );
+        Ok(())
+    }
+
+    ///
+    /// Address the message `index` positions ahead will be published at, 0 being the
+    /// next message
+    ///
+    /// Message addresses derive from the announcement, the author key and the sequence
+    /// number in single branch mode, whatever keyload or packet a message is linked to,
+    /// and from the previous message of the author in multi branch mode. The prediction
+    /// assumes the author publishes the next messages, messages published meanwhile by
+    /// subscribers move the sequence forward
+    ///
+    pub fn predict_message_address(&self, index: u64) -> Result<String> {
+        if self.announcement_id.is_empty() {
+            return Err(ChannelError::NotOpened);
+        }
+        let announce_link = self.link(&self.announcement_id)?;
+        let branching = self.author.is_multi_branching();
+        let pk = *self.author.get_pk();
+
+        let (mut link, mut seq_no) = self
+            .author
+            .gen_next_msg_ids(branching)
+            .into_iter()
+            .find(|(key, _)| *key == pk)
+            .map(|(_, cursor)| (cursor.link, cursor.seq_no))
+            .ok_or_else(|| {
+                ChannelError::State("Sequence state of the author not found".to_string())
+            })?;
+        for _ in 0..index {
+            seq_no += 1;
+            let previous = if branching { &link } else { &announce_link };
+            link = self.author.gen_msg_id(previous, pk, seq_no);
+        }
+        Ok(link.msgid.to_string())
+    }
+
     ///
     /// Add subscriber
     ///
     pub fn add_subscriber(&mut self, subscribe_tag: String) -> Result<String> {
-        let subscribe_link = match Address::from_str(&self.channel_address, &subscribe_tag) {
-            Ok(subscribe_link) => subscribe_link,
-            Err(()) => bail!(
-                "Failed to create Address from {}:{}",
-                &self.channel_address,
-                &subscribe_tag
-            ),
-        };
+        let span = OpSpan::new("add_subscriber", &self.channel_address, 0);
+        let res = span.in_scope(|| self.send_keyload(subscribe_tag));
+        span.record(&res, |tag| tag);
+        res
+    }
+
+    fn send_keyload(&mut self, subscribe_tag: String) -> Result<String> {
+        if self.announcement_id.is_empty() {
+            return Err(ChannelError::NotOpened);
+        }
+        let subscribe_link = self.link(&subscribe_tag)?;
+        self.warm_up()?;
 
         let message_list = self.author.receive_subscribe(&subscribe_link)?;
 
-        let announce_link =
-            Address::from_str(&self.channel_address, &self.announcement_id).unwrap();
+        let announce_link = self.link(&self.announcement_id)?;
 
         self.last_keyload_tag = {
             let keyload = self.author.send_keyload_for_everyone(&announce_link)?;
             keyload.0.msgid.to_string()
         };
+        if !self.processed_subscriptions.contains(&subscribe_tag) {
+            self.processed_subscriptions.push(subscribe_tag);
+        }
+        self.record_keyload();
 
         Ok(self.last_keyload_tag.clone())
     }
 
     ///
-    /// Write signed packet
+    /// Subscribe tags processed by `add_subscriber`, in order
     ///
-    pub fn write_signed<T>(&mut self, payload: T) -> Result<String>
-    where
-        T: PacketPayload,
-    {
-        let signed_packet_link = {
-            if self.previous_msg_tag == String::default() {
-                let keyload_link =
-                    Address::from_str(&self.channel_address, &self.last_keyload_tag).unwrap();
-                let msg = self.author.send_signed_packet(
-                    &keyload_link,
-                    &payload.public_data(),
-                    &payload.masked_data(),
-                )?;
-                let ret_link = msg.0;
-                ret_link.clone()
-            } else {
-                let msg = self.author.send_signed_packet(
-                    &Address::from_str(&self.channel_address, &self.previous_msg_tag).unwrap(),
-                    &payload.public_data(),
-                    &payload.masked_data(),
-                )?;
-                let ret_link = msg.0;
-                ret_link.clone()
-            }
-        };
-
-        self.previous_msg_tag = signed_packet_link.msgid.to_string().clone();
-
-        Ok(signed_packet_link.msgid.to_string())
+    pub fn processed_subscriptions(&self) -> Vec<String> {
+        self.processed_subscriptions.clone()
     }
 
     ///
-    /// Write tagged packet
+    /// Opaque token capturing the bookkeeping of the author
     ///
-    pub fn write_tagged<T>(&mut self, payload: T) -> Result<String>
-    where
-        T: PacketPayload,
-    {
-        let _keyload_link =
-            Address::from_str(&self.channel_address, &self.last_keyload_tag).unwrap();
-        let tagged_packet_link = {
-            if self.previous_msg_tag == String::default() {
-                let keyload_link =
-                    Address::from_str(&self.channel_address, &self.last_keyload_tag).unwrap();
-                let msg = self.author.send_tagged_packet(
-                    &keyload_link,
-                    &payload.public_data(),
-                    &payload.masked_data(),
-                )?;
-                let ret_link = msg.0;
-                ret_link.clone()
-            } else {
-                let previous_msg_link =
-                    Address::from_str(&self.channel_address, &self.previous_msg_tag).unwrap();
-                let msg = self.author.send_tagged_packet(
-                    &previous_msg_link,
-                    &payload.public_data(),
-                    &payload.masked_data(),
-                )?;
-                let ret_link = msg.0;
-                ret_link.clone()
-            }
-        };
-
-        Ok(tagged_packet_link.msgid.to_string())
+    /// Restore it with `restore_state` on an author created with the same seed
+    ///
+    pub fn export_state(&self) -> String {
+        AuthorState {
+            version: AUTHOR_STATE_VERSION,
+            channel_address: self.channel_address.clone(),
+            announcement_tag: self.announcement_id.clone(),
+            last_keyload_tag: self.last_keyload_tag.clone(),
+            previous_msg_tag: self.previous_msg_tag.clone(),
+            processed_subscriptions: self.processed_subscriptions.clone(),
+            history: self.history.clone(),
+            keyload_epochs: self.keyload_epochs.clone(),
+            psk_ids: self.psk_ids.clone(),
+            sequence_numbers: self.sequence_numbers,
+            canonical_json: self.canonical_json,
+            next_seq: self.next_seq,
+            generation: self.generation,
+            idempotent_writes: self.idempotency.writes(),
+            epoch: self.epoch.clone(),
+        }
+        .encode()
     }
-    /*
+
     ///
-    /// Remove subscriber
+    /// Restore the bookkeeping captured by `export_state`
     ///
+    /// The author must be created with the same seed. The processed subscribe messages
+    /// are fetched and processed again before the next keyload, so the subscribers keep
+    /// their access in it, nothing is fetched by the restore itself. See `warm_up`
     ///
-    pub fn remove_subscriber(&mut self, unsubscribe_tag: String) -> Result<()> {
-        let unsubscribe_link = Address::from_str(&self.channel_address, &unsubscribe_tag).unwrap();
+    pub fn restore_state(&mut self, token: &str) -> Result<()> {
+        let state = AuthorState::decode(token)?;
+        if state.channel_address != self.channel_address {
+            return Err(ChannelError::State(format!(
+                "Author state belongs to channel {}, not {}, was the author created with the same seed?",
+                state.channel_address, self.channel_address
+            )));
+        }
 
-        let message_list = iota_client::Client::get()
-            .recv_messages_with_options(&unsubscribe_link, RecvOptions::default())?;
-        for tx in message_list.iter() {
-            let header = tx.parse_header()?;
-            if header.check_content_type(message::UNSUBSCRIBE) {
-                match self.author.unsubscribe(header.clone()) {
-                    Ok(_) => {
-                        break;
-                    }
-                    Err(e) => println!("Unsubscribe Packet Error: {}", e),
-                }
-            }
+        for subscribe_tag in state.processed_subscriptions.iter() {
+            self.link(subscribe_tag)?;
         }
+
+        self.pending_subscriptions = state.processed_subscriptions.clone();
+        self.announcement_id = state.announcement_tag;
+        self.last_keyload_tag = state.last_keyload_tag;
+        self.previous_msg_tag = state.previous_msg_tag;
+        self.processed_subscriptions = state.processed_subscriptions;
+        self.history = state.history;
+        self.keyload_epochs = state.keyload_epochs;
+        self.psk_ids = state.psk_ids;
+        self.sequence_numbers = state.sequence_numbers;
+        self.canonical_json = state.canonical_json;
+        self.next_seq = state.next_seq;
+        self.generation = state.generation;
+        self.idempotency.restore(state.idempotent_writes);
+        self.epoch = state.epoch;
         Ok(())
     }
-    */
+
+    ///
+    /// Process again the subscribe messages of a restored state
+    ///
+    /// Called before each keyload and before reading the acknowledgements, call it right
+    /// after the restore to fetch them up front instead. A subscribe message failing leaves it and the next ones to the
+    /// next call
+    ///
+    pub fn warm_up(&mut self) -> Result<()> {
+        while let Some(subscribe_tag) = self.pending_subscriptions.first() {
+            let subscribe_link = self.link(subscribe_tag)?;
+            self.author.receive_subscribe(&subscribe_link)?;
+            self.pending_subscriptions.remove(0);
+        }
+        Ok(())
+    }
+
+    ///
+    /// Whether the subscribe messages of the restored state were all processed again
+    ///
+    pub fn is_warm(&self) -> bool {
+        self.pending_subscriptions.is_empty()
+    }
+
+    ///
+    /// Grant access to the holders of a pre-shared key
+    ///
+    /// Publishes a keyload for the subscribers and the pre-shared keys added so far,
+    /// returns its tag. Readers provisioned with the key decrypt the messages anchored
+    /// after it without subscribing
+    ///
+    pub fn add_psk(&mut self, psk: Psk) -> Result<String> {
+        if self.announcement_id.is_empty() {
+            return Err(ChannelError::NotOpened);
+        }
+        self.warm_up()?;
+        let id = psk_id(&psk);
+        let id_hex = id.to_hex();
+        self.author.store_psk(id.into_streams(), psk.into_streams());
+
+        let announce_link = self.link(&self.announcement_id)?;
+        let keyload = self.author.send_keyload_for_everyone(&announce_link)?;
+        self.last_keyload_tag = keyload.0.msgid.to_string();
+        if !self.psk_ids.contains(&id_hex) {
+            self.psk_ids.push(id_hex);
+        }
+        self.record_keyload();
+
+        Ok(self.last_keyload_tag.clone())
+    }
+
+    ///
+    /// Write signed packet
+    ///
+    pub fn write_signed<P>(&mut self, payload: P) -> Result<String>
+    where
+        P: PacketPayload,
+    {
+        let span = OpSpan::new(
+            "write_signed",
+            &self.channel_address,
+            payload_size(&payload),
+        );
+        let res = span.in_scope(|| self.send_signed(payload));
+        span.record(&res, |tag| tag);
+        res
+    }
+
+    fn send_signed<P>(&mut self, payload: P) -> Result<String>
+    where
+        P: PacketPayload,
+    {
+        self.ensure_writable()?;
+        let parent = self.packet_parent();
+        let (public, masked) = self.packet_data(&payload)?;
+        self.check_fragments(&public, &masked)?;
+        self.check_policy(&public, &masked)?;
+        let signed_packet_link = {
+            if self.previous_msg_tag == String::default() {
+                let keyload_link = self.link(&self.last_keyload_tag)?;
+                let msg = self
+                    .author
+                    .send_signed_packet(&keyload_link, &public, &masked)?;
+                let ret_link = msg.0;
+                ret_link.clone()
+            } else {
+                let msg = self.author.send_signed_packet(
+                    &self.link(&self.previous_msg_tag)?,
+                    &public,
+                    &masked,
+                )?;
+                let ret_link = msg.0;
+                ret_link.clone()
+            }
+        };
+
+        self.previous_msg_tag = signed_packet_link.msgid.to_string().clone();
+        self.record(
+            self.previous_msg_tag.clone(),
+            MessageKind::SignedPacket,
+            Some(parent),
+            payload_digest(&public.0, &masked.0),
+        );
+        self.epoch.count(public.0.len() + masked.0.len());
+        if self.sequence_numbers {
+            self.next_seq += 1;
+        }
+
+        Ok(signed_packet_link.msgid.to_string())
+    }
+
+    ///
+    /// Write signed packet once per idempotency key
+    ///
+    /// Opt-in guard against the same publish being triggered twice: a write under a key
+    /// used within the idempotency window returns the tag of the first write without
+    /// sending anything. The key must be reused with the same payload, another payload
+    /// fails with `ChannelError::State`. `write_signed` keeps sending identical payloads
+    /// as new packets.
+    ///
+    /// The keys remembered are part of `export_state`, a restart doesn't forget them
+    ///
+    pub fn write_signed_idempotent<P>(
+        &mut self,
+        payload: P,
+        idempotency_key: &str,
+    ) -> Result<String>
+    where
+        P: PacketPayload,
+    {
+        let digest = payload_digest(&payload.public_data().0, &payload.masked_data().0);
+        let now = self.config.clock.system_time();
+        if let Some(write) = self.idempotency.lookup(idempotency_key, now) {
+            if write.digest != digest {
+                return Err(ChannelError::State(format!(
+                    "Idempotency key {} was used for another payload, written as {}",
+                    idempotency_key, write.tag
+                )));
+            }
+            return Ok(write.tag);
+        }
+
+        let tag = self.write_signed(payload)?;
+        self.idempotency.insert(
+            IdempotentWrite {
+                key: idempotency_key.to_string(),
+                digest: digest,
+                tag: tag.clone(),
+                written_at: now,
+            },
+            now,
+        );
+        Ok(tag)
+    }
+
+    ///
+    /// Write signed packet expiring after `ttl`
+    ///
+    /// The public data, which must be JSON, is wrapped in an `Expiring` envelope with the
+    /// current time and the ttl. The subscriber unwraps it when walking the channel and
+    /// flags the message once expired, `read_signed` returns the envelope as is
+    ///
+    pub fn write_signed_ttl<P>(&mut self, payload: P, ttl: Duration) -> Result<String>
+    where
+        P: PacketPayload,
+    {
+        let expiry = Expiry::new(self.config.clock.system_time(), ttl)?;
+        self.write_signed(ExpiringPayload::wrap(&payload, expiry)?)
+    }
+
+    ///
+    /// Write signed packet without waiting for the rate limit
+    ///
+    /// Fails with `ChannelError::RateLimited` when the rate limit doesn't allow a send right now
+    ///
+    pub fn try_write_signed<P>(&mut self, payload: P) -> Result<String>
+    where
+        P: PacketPayload,
+    {
+        if let Err(retry_after) = self.send_ready() {
+            return Err(ChannelError::RateLimited {
+                retry_after: retry_after,
+            });
+        }
+        self.write_signed(payload)
+    }
+
+    ///
+    /// Write tagged packet
+    ///
+    pub fn write_tagged<P>(&mut self, payload: P) -> Result<String>
+    where
+        P: PacketPayload,
+    {
+        let span = OpSpan::new(
+            "write_tagged",
+            &self.channel_address,
+            payload_size(&payload),
+        );
+        let res = span.in_scope(|| self.send_tagged(payload));
+        span.record(&res, |tag| tag);
+        res
+    }
+
+    ///
+    /// Write signed packet with a content type
+    ///
+    /// The public data, which must be JSON, is wrapped in a `Typed` envelope. Subscribers
+    /// find the content type in `ChannelMessage::content_type` and decode the message
+    /// with the `Decoders` registered for it
+    ///
+    pub fn write_signed_typed<P>(&mut self, content_type: &str, payload: P) -> Result<String>
+    where
+        P: PacketPayload,
+    {
+        self.write_signed(TypedPayload::wrap(&payload, content_type)?)
+    }
+
+    ///
+    /// Write tagged packet with a content type, see `write_signed_typed`
+    ///
+    pub fn write_tagged_typed<P>(&mut self, content_type: &str, payload: P) -> Result<String>
+    where
+        P: PacketPayload,
+    {
+        self.write_tagged(TypedPayload::wrap(&payload, content_type)?)
+    }
+
+    ///
+    /// Write signed packet, returns its tag with the digest of the application data
+    ///
+    /// The digest covers the public and masked data as given, before the envelopes and
+    /// the framing, the JSON data in canonical form. The subscribers compute the same
+    /// over the data unwrapped, see `ChannelMessage::verify_digest`, and so does anyone
+    /// holding an off-chain copy, see `digest::content_digest`
+    ///
+    pub fn write_signed_with_digest<P>(&mut self, payload: P) -> Result<SentMessage>
+    where
+        P: PacketPayload,
+    {
+        let digest = payload_content_digest(&payload.public_data().0, &payload.masked_data().0);
+        let tag = self.write_signed(payload)?;
+        Ok(SentMessage {
+            tag: tag,
+            digest: digest,
+        })
+    }
+
+    ///
+    /// Write tagged packet, returns its tag with the digest of the application data, see
+    /// `write_signed_with_digest`
+    ///
+    pub fn write_tagged_with_digest<P>(&mut self, payload: P) -> Result<SentMessage>
+    where
+        P: PacketPayload,
+    {
+        let digest = payload_content_digest(&payload.public_data().0, &payload.masked_data().0);
+        let tag = self.write_tagged(payload)?;
+        Ok(SentMessage {
+            tag: tag,
+            digest: digest,
+        })
+    }
+
+    fn send_tagged<P>(&mut self, payload: P) -> Result<String>
+    where
+        P: PacketPayload,
+    {
+        self.ensure_writable()?;
+        let parent = self.packet_parent();
+        let (public, masked) = self.packet_data(&payload)?;
+        self.check_fragments(&public, &masked)?;
+        self.check_policy(&public, &masked)?;
+        let tagged_packet_link = {
+            if self.previous_msg_tag == String::default() {
+                let keyload_link = self.link(&self.last_keyload_tag)?;
+                let msg = self
+                    .author
+                    .send_tagged_packet(&keyload_link, &public, &masked)?;
+                let ret_link = msg.0;
+                ret_link.clone()
+            } else {
+                let previous_msg_link = self.link(&self.previous_msg_tag)?;
+                let msg = self
+                    .author
+                    .send_tagged_packet(&previous_msg_link, &public, &masked)?;
+                let ret_link = msg.0;
+                ret_link.clone()
+            }
+        };
+        self.record(
+            tagged_packet_link.msgid.to_string(),
+            MessageKind::TaggedPacket,
+            Some(parent),
+            payload_digest(&public.0, &masked.0),
+        );
+        self.epoch.count(public.0.len() + masked.0.len());
+        if self.sequence_numbers {
+            self.next_seq += 1;
+        }
+
+        Ok(tagged_packet_link.msgid.to_string())
+    }
+
+    ///
+    /// Size in bytes of the public and masked data the next packet would carry, without
+    /// sending it
+    ///
+    /// Runs the same encoding as `write_signed` and `write_tagged`, sequence number
+    /// included, and fails the way they would. `write_signed_ttl` adds its envelope on
+    /// top, measure `ExpiringPayload::wrap` for it
+    ///
+    pub fn encoded_size<P>(&self, payload: &P) -> Result<usize>
+    where
+        P: PacketPayload,
+    {
+        let (public, masked) = self.packet_data(payload)?;
+        Ok(public.0.len() + masked.0.len())
+    }
+
+    ///
+    /// Republish the messages of another channel
+    ///
+    /// Reads the new messages of the source subscriber, as `poll_new` does, and writes
+    /// their data in this channel, the signed packets as signed packets and the tagged
+    /// packets as tagged packets. Returns the new tags, in order.
+    ///
+    /// The masked data, decrypted by the source subscriber, is encrypted again: only the
+    /// subscribers granted by the keyloads of this channel read it. The messages written
+    /// with a ttl keep their expiry time. The expired messages, the messages that can't
+    /// be decoded and the end of channel marker are skipped. The messages are read and
+    /// written one at a time: when a write fails, the message is left to the next read of
+    /// the source, the ones mirrored before it stay mirrored
+    ///
+    pub fn mirror_from<S>(
+        &mut self,
+        source: &mut channel_subscriber::Channel<S>,
+    ) -> Result<Vec<String>>
+    where
+        S: Transport<Address, Message>,
+        S::SendOptions: Copy + Default,
+        S::RecvOptions: Copy + Default,
+    {
+        self.ensure_writable()?;
+        let now = self.config.clock.system_time();
+
+        let source_author = source.author_key();
+        let mut tags = Vec::new();
+        while let Some(tag) =
+            source.consume_next(|msg| self.mirror_message(msg, now, source_author.as_deref()))?
+        {
+            tags.extend(tag);
+        }
+        Ok(tags)
+    }
+
+    ///
+    /// Write the data of a message read from another channel, `None` if it is skipped
+    ///
+    fn mirror_message(
+        &mut self,
+        msg: &ChannelMessage,
+        now: SystemTime,
+        source_author: Option<&str>,
+    ) -> Result<Option<String>> {
+        if msg.kind != MessageKind::SignedPacket && msg.kind != MessageKind::TaggedPacket {
+            return Ok(None);
+        }
+        if msg.is_end_of_channel(source_author) || msg.is_expired(now) {
+            return Ok(None);
+        }
+        let payload = match &msg.decode_error {
+            Some(e) => Err(ChannelError::PayloadEncoding(e.clone())),
+            None => mirror_payload(msg),
+        };
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(e) => {
+                log::warn!(
+                    target: "channels_lite::author",
+                    "Message {} not mirrored: {}",
+                    msg.tag, e
+                );
+                return Ok(None);
+            }
+        };
+
+        let tag = match (msg.kind, msg.expires_at) {
+            (MessageKind::SignedPacket, Some(expires_at)) => {
+                let ttl = expires_at.duration_since(now).unwrap_or_default();
+                self.write_signed_ttl(payload, ttl)?
+            }
+            (MessageKind::SignedPacket, None) => self.write_signed(payload)?,
+            _ => self.write_tagged(payload)?,
+        };
+        Ok(Some(tag))
+    }
+
+    ///
+    /// Write a file
+    ///
+    /// The file is split into signed chunk messages followed by a manifest listing the
+    /// chunk tags. Returns the manifest tag followed by the chunk tags.
+    ///
+    pub fn write_file(&mut self, path: &Path) -> Result<Vec<String>> {
+        let data = fs::read(path)?;
+
+        let mut chunk_tags: Vec<String> = Vec::new();
+        for (index, chunk) in data.chunks(FILE_CHUNK_SIZE).enumerate() {
+            let file_chunk = FileChunk {
+                index: index,
+                data: encode_config(chunk, URL_SAFE_NO_PAD),
+            };
+            let payload = PayloadBuilder::new().public(&file_chunk)?.build();
+            chunk_tags.push(self.write_signed(payload)?);
+        }
+
+        let manifest = FileManifest {
+            file_name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            total_size: data.len() as u64,
+            chunk_count: chunk_tags.len(),
+            chunk_tags: chunk_tags.clone(),
+        };
+        let payload = PayloadBuilder::new().public(&manifest)?.build();
+        let manifest_tag = self.write_signed(payload)?;
+
+        let mut tags = vec![manifest_tag];
+        tags.extend(chunk_tags);
+        Ok(tags)
+    }
+    ///
+    /// Messages published by the author and who could read them
+    ///
+    /// Covers the messages published since the channel was opened, the history is part of
+    /// the exported state
+    ///
+    pub fn audit_report(&self) -> AuditReport {
+        AuditReport {
+            version: AUDIT_REPORT_VERSION,
+            channel_address: self.channel_address.clone(),
+            announcement_tag: self.announcement_id.clone(),
+            closed: self.closed,
+            messages: self.history.clone(),
+            keyloads: self.keyload_epochs.clone(),
+        }
+    }
+
+    ///
+    /// Write the audit report as JSON
+    ///
+    /// The output only depends on the channel history, the same history always gives the
+    /// same document
+    ///
+    pub fn export_audit_report(&self, writer: impl Write) -> Result<()> {
+        serde_json::to_writer_pretty(writer, &self.audit_report())?;
+        Ok(())
+    }
+
+    ///
+    /// Public and masked data of a packet, the public data numbered when the sequence
+    /// numbers are enabled
+    ///
+    fn packet_data<P>(&self, payload: &P) -> Result<(Bytes, Bytes)>
+    where
+        P: PacketPayload,
+    {
+        if self.canonical_json {
+            return self.numbered_data(&CanonicalPayload::wrap(payload)?);
+        }
+        self.numbered_data(payload)
+    }
+
+    fn numbered_data<P>(&self, payload: &P) -> Result<(Bytes, Bytes)>
+    where
+        P: PacketPayload,
+    {
+        if !self.sequence_numbers {
+            return Ok((payload.public_data().clone(), payload.masked_data().clone()));
+        }
+        let seq = SequenceNumber {
+            sender: self.channel_address.clone(),
+            n: self.next_seq,
+            generation: self.generation,
+        };
+        let numbered = SequencedPayload::wrap(payload, seq)?;
+        Ok((
+            numbered.public_data().clone(),
+            numbered.masked_data().clone(),
+        ))
+    }
+
+    ///
+    /// Refuse the packet that could span more transactions than `max_fragments`
+    ///
+    fn check_fragments(&self, public: &Bytes, masked: &Bytes) -> Result<()> {
+        match self.config.max_fragments {
+            Some(max) => {
+                let fragments = packet_fragments(public.0.len(), masked.0.len());
+                if fragments > max {
+                    return Err(ChannelError::TooManyFragments {
+                        tag: self.packet_parent(),
+                        fragments: fragments,
+                        max: max,
+                    });
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    ///
+    /// Check the packet about to be written against the policy, in the epoch of the
+    /// last keyload
+    ///
+    fn check_policy(&mut self, public: &Bytes, masked: &Bytes) -> Result<()> {
+        self.epoch.enter(&self.last_keyload_tag);
+        let size = public.0.len() + masked.0.len();
+        let reason = match (
+            self.policy.max_messages_per_epoch,
+            self.policy.max_payload_bytes,
+        ) {
+            (Some(max), _) if self.epoch.messages >= max => {
+                format!("at most {} messages per keyload epoch", max)
+            }
+            (_, Some(max)) if size > max => {
+                format!("{} bytes of payload, at most {} allowed", size, max)
+            }
+            _ => return Ok(()),
+        };
+        Err(ChannelError::PolicyViolation {
+            reason: reason,
+            keyload_tag: self.epoch.keyload_tag.clone(),
+            messages: self.epoch.messages,
+            payload_bytes: self.epoch.payload_bytes,
+        })
+    }
+
+    ///
+    /// Tag the next packet is linked to
+    ///
+    fn packet_parent(&self) -> String {
+        if self.previous_msg_tag.is_empty() {
+            self.last_keyload_tag.clone()
+        } else {
+            self.previous_msg_tag.clone()
+        }
+    }
+
+    fn record(&mut self, tag: String, kind: MessageKind, parent: Option<String>, digest: String) {
+        let timestamp = self
+            .config
+            .clock
+            .system_time()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        self.history.push(AuditRecord {
+            tag: tag,
+            kind: kind,
+            parent: parent,
+            timestamp: timestamp,
+            digest: digest,
+        });
+    }
+
+    fn record_keyload(&mut self) {
+        self.record(
+            self.last_keyload_tag.clone(),
+            MessageKind::Keyload,
+            Some(self.announcement_id.clone()),
+            payload_digest(&[], &[]),
+        );
+        let timestamp = self.history.last().map_or(0, |record| record.timestamp);
+        self.keyload_epochs.push(KeyloadEpoch {
+            tag: self.last_keyload_tag.clone(),
+            timestamp: timestamp,
+            subscriptions: self.processed_subscriptions.clone(),
+            psk_ids: self.psk_ids.clone(),
+        });
+    }
+
+    ///
+    /// Collect the acknowledgements published by subscribers for a message
+    ///
+    /// Returns the tags of the acknowledgement messages found so far
+    ///
+    pub fn collect_acks(&mut self, tag: String) -> Result<Vec<String>> {
+        self.warm_up()?;
+        self.fetch_acks();
+        Ok(self.acks.get(&tag).cloned().unwrap_or_default())
+    }
+
+    ///
+    /// Write signed packet and wait for its acknowledgement
+    ///
+    /// The public data, which must be JSON, is wrapped in an `AckRequested` envelope with
+    /// a new request id. The subscriber reading it replies with `acknowledge`, and
+    /// `poll_acks` matches the reply to the request. The request times out after the
+    /// acknowledgement timeout, five minutes by default
+    ///
+    pub fn write_signed_with_ack<P>(&mut self, payload: P) -> Result<PendingAck>
+    where
+        P: PacketPayload,
+    {
+        let request_id = new_request_id();
+        let tag = self.write_signed(AckRequestPayload::wrap(&payload, &request_id)?)?;
+        let requested_at = self.config.clock.system_time();
+        let pending = PendingAck {
+            request_id: request_id.clone(),
+            tag: tag,
+            requested_at: requested_at,
+            deadline: requested_at + self.ack_timeout,
+        };
+        if self.ack_order.len() == ACK_REQUEST_CAPACITY {
+            if let Some(oldest) = self.ack_order.pop_front() {
+                self.ack_requests.remove(&oldest);
+            }
+        }
+        self.ack_order.push_back(request_id.clone());
+        self.ack_requests.insert(
+            request_id,
+            AckRequest {
+                pending: pending.clone(),
+                acknowledged: false,
+                timed_out: false,
+            },
+        );
+        Ok(pending)
+    }
+
+    ///
+    /// Fetch the acknowledgements published since the last poll and match them to the
+    /// requests of `write_signed_with_ack`
+    ///
+    /// Reports every acknowledgement carrying a request id, `Acknowledged` or `Late`
+    /// for the requests of this author and `UnknownRequest` for the others, then the
+    /// requests whose deadline passed without acknowledgement, once each. A request
+    /// stays known after its first acknowledgement, the acknowledgements of several
+    /// subscribers are all reported, until `ACK_RETENTION` after its deadline. At most
+    /// `ACK_REQUEST_CAPACITY` requests are remembered, the oldest are forgotten first
+    ///
+    pub fn poll_acks(&mut self) -> Result<Vec<AckReceived>> {
+        self.warm_up()?;
+        self.fetch_acks();
+        let now = self.config.clock.system_time();
+
+        let mut received = Vec::new();
+        for (ack, ack_tag) in std::mem::take(&mut self.ack_inbox) {
+            let request_id = match ack.request {
+                Some(request_id) => request_id,
+                None => continue,
+            };
+            let at = self.transport.borrow().attached_at(&ack_tag).unwrap_or(now);
+            let (tag, requested_at, outcome) = match self.ack_requests.get_mut(&request_id) {
+                Some(request) if request.pending.tag == ack.ack => {
+                    request.acknowledged = true;
+                    let outcome = if request.timed_out || at > request.pending.deadline {
+                        AckOutcome::Late
+                    } else {
+                        AckOutcome::Acknowledged
+                    };
+                    (ack.ack, Some(request.pending.requested_at), outcome)
+                }
+                _ => (ack.ack, None, AckOutcome::UnknownRequest),
+            };
+            received.push(AckReceived {
+                request_id: request_id,
+                tag: tag,
+                ack_tag: Some(ack_tag),
+                requested_at: requested_at,
+                at: at,
+                outcome: outcome,
+            });
+        }
+
+        for request in self.ack_requests.values_mut() {
+            if request.acknowledged || request.timed_out || now <= request.pending.deadline {
+                continue;
+            }
+            request.timed_out = true;
+            received.push(AckReceived {
+                request_id: request.pending.request_id.clone(),
+                tag: request.pending.tag.clone(),
+                ack_tag: None,
+                requested_at: Some(request.pending.requested_at),
+                at: request.pending.deadline,
+                outcome: AckOutcome::TimedOut,
+            });
+        }
+
+        // The timeouts are reported above before the requests are forgotten
+        self.ack_requests
+            .retain(|_, request| now <= request.pending.deadline + ACK_RETENTION);
+        let ack_requests = &self.ack_requests;
+        self.ack_order.retain(|id| ack_requests.contains_key(id));
+        Ok(received)
+    }
+
+    ///
+    /// Acknowledgement requests not acknowledged yet, timed out ones included until
+    /// `ACK_RETENTION` after their deadline
+    ///
+    pub fn pending_acks(&self) -> Vec<PendingAck> {
+        let mut pending: Vec<PendingAck> = self
+            .ack_requests
+            .values()
+            .filter(|request| !request.acknowledged)
+            .map(|request| request.pending.clone())
+            .collect();
+        pending.sort_by_key(|pending| pending.requested_at);
+        pending
+    }
+
+    ///
+    /// Limits enforced on every packet written from now on, see `ChannelPolicy`
+    ///
+    /// A packet over a limit fails with `ChannelError::PolicyViolation` before being
+    /// sent. The counters of the current epoch are kept when the policy changes
+    ///
+    pub fn set_policy(&mut self, policy: ChannelPolicy) {
+        self.policy = policy;
+    }
+
+    ///
+    /// Policy enforced and counters of the current keyload epoch
+    ///
+    pub fn stats(&self) -> PolicyStats {
+        let mut epoch = self.epoch.clone();
+        epoch.enter(&self.last_keyload_tag);
+        PolicyStats {
+            policy: self.policy.clone(),
+            epoch: epoch,
+        }
+    }
+
+    ///
+    /// Time `poll_acks` waits for an acknowledgement before reporting a timeout, for the
+    /// messages written from now on
+    ///
+    pub fn set_ack_timeout(&mut self, timeout: Duration) {
+        self.ack_timeout = timeout;
+    }
+
+    ///
+    /// Read the acknowledgements published since the last fetch
+    ///
+    fn fetch_acks(&mut self) {
+        for msg in self.author.fetch_next_msgs() {
+            let msg = ChannelMessage::from_unwrapped(&msg);
+            if msg.kind != MessageKind::SignedPacket {
+                continue;
+            }
+            if let Some(ack) = msg.public.as_ref().and_then(|public| Ack::parse(public)) {
+                self.acks
+                    .entry(ack.ack.clone())
+                    .or_default()
+                    .push(msg.tag.clone());
+                if ack.request.is_some() {
+                    self.ack_inbox.push((ack, msg.tag));
+                }
+            }
+        }
+    }
+
+    ///
+    /// Close the channel
+    ///
+    /// Publishes the end of channel marker as a signed packet and rejects any further write
+    ///
+    pub fn close(&mut self) -> Result<String> {
+        let payload = PayloadBuilder::new().public(&EndOfChannel::new())?.build();
+        let close_tag = self.write_signed(payload)?;
+        self.closed = true;
+        Ok(close_tag)
+    }
+
+    ///
+    /// Number the packets, for the `SequenceGuard` of the subscribers
+    ///
+    /// Disabled by default. The public data, which must be JSON, is wrapped in a
+    /// `Sequenced` envelope, numbered from 0 with the channel address as sender id. The
+    /// numbering carries on when disabled and enabled again, and is part of the
+    /// exported state
+    ///
+    pub fn set_sequence_numbers(&mut self, enabled: bool) {
+        self.sequence_numbers = enabled;
+    }
+
+    ///
+    /// Write the JSON data in canonical form, members sorted and no whitespace
+    ///
+    /// Disabled by default, the data is written as serialized by the caller. The digests
+    /// are computed over the canonical form either way, enable it so that the data on the
+    /// tangle is byte for byte the one digested. Part of the exported state
+    ///
+    pub fn set_canonical_json(&mut self, enabled: bool) {
+        self.canonical_json = enabled;
+    }
+
+    ///
+    /// Publish a keyload on the first write when none was published yet
+    ///
+    /// Disabled by default, the writes fail with `ChannelError::NoKeyload` instead. The
+    /// automatic keyload includes the subscribers and pre-shared keys added so far, none
+    /// on a fresh channel: the public data is readable by anyone, the masked data by no one
+    /// until the next keyload
+    ///
+    pub fn set_auto_keyload(&mut self, auto_keyload: bool) {
+        self.auto_keyload = auto_keyload;
+    }
+
+    ///
+    /// Time an idempotency key of `write_signed_idempotent` is remembered, ten minutes
+    /// by default
+    ///
+    pub fn set_idempotency_window(&mut self, window: Duration) {
+        self.idempotency.set_window(window);
+    }
+
+    ///
+    /// Publish the protocol version marker after the announcement when opening the
+    /// channel
+    ///
+    /// Enabled by default, the subscribers read the version on `connect`. Without the
+    /// marker the channel reads as one opened by an older version of the crate
+    ///
+    pub fn set_protocol_marker(&mut self, protocol_marker: bool) {
+        self.protocol_marker = protocol_marker;
+    }
+
+    ///
+    /// Whether the channel has been closed
+    ///
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+}
+
+///
+/// Public state and bookkeeping only, the seed and node credentials are redacted and
+/// the Streams state is left out
+///
+impl<T> fmt::Debug for Channel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel")
+            .field("node_url", &redact_credentials(&self.node_url))
+            .field("channel_address", &self.channel_address)
+            .field("announcement_tag", &self.announcement_id)
+            .field("last_keyload_tag", &self.last_keyload_tag)
+            .field("previous_msg_tag", &self.previous_msg_tag)
+            .field("closed", &self.closed)
+            .field("auto_keyload", &self.auto_keyload)
+            .field("protocol_marker", &self.protocol_marker)
+            .field("generation", &self.generation)
+            .field(
+                "next_seq",
+                &if self.sequence_numbers {
+                    Some(self.next_seq)
+                } else {
+                    None
+                },
+            )
+            .field("messages", &self.history.len())
+            .field("keyloads", &self.keyload_epochs.len())
+            .field("subscriptions", &self.processed_subscriptions.len())
+            .field("pending_subscriptions", &self.pending_subscriptions.len())
+            .field("psk_ids", &self.psk_ids)
+            .field("transport_stats", self.transport.borrow().stats())
+            .field("shut_down", &self.shut_down)
+            .field("seed", &self.seed)
+            .finish()
+    }
+}
+
+///
+/// One-line summary, see `ChannelSummary`
+///
+impl<T> fmt::Display for Channel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.summary().fmt(f)
+    }
+}
+
+impl<T> Drop for Channel<T> {
+    fn drop(&mut self) {
+        if let (false, Some(attached)) = (self.shut_down, &self.store) {
+            log::warn!(
+                target: "channels_lite::author",
+                "Author of channel {} dropped without shutdown, its state was not persisted to {:?}",
+                self.channel_address,
+                attached.id
+            );
+        }
+    }
+}
+
+///
+/// Reads back the packets published by this author
+///
+impl<T> ChannelReader for Channel<T>
+where
+    T: Transport<Address, Message>,
+    T::SendOptions: Copy + Default,
+    T::RecvOptions: Copy + Default,
+{
+    fn channel_address(&self) -> &str {
+        &self.channel_address
+    }
+
+    fn fetch_all(&mut self) -> Result<Vec<ChannelMessage>> {
+        let mut msgs = Vec::new();
+        while self.read_back < self.history.len() {
+            let record = self.history[self.read_back].clone();
+            if record.kind == MessageKind::SignedPacket || record.kind == MessageKind::TaggedPacket
+            {
+                let msg = self.read(&record.tag)?;
+                // Only the first packet linked to the announcement can be the marker
+                let mut marker = false;
+                if !self.marker_checked && record.parent.as_ref() == Some(&self.announcement_id) {
+                    self.marker_checked = true;
+                    marker = msg.protocol_version().is_some();
+                }
+                if !marker {
+                    msgs.push(msg);
+                }
+            }
+            self.read_back += 1;
+        }
+        Ok(msgs)
+    }
+
+    fn read(&mut self, tag: &str) -> Result<ChannelMessage> {
+        let link = self.link(tag)?;
+        let unwrapped = self.author.receive_msg(&link, None)?;
+        Ok(ChannelMessage::from_unwrapped(&unwrapped))
+    }
+}
+
+///
+/// Payload of a mirrored message, the public and masked data are JSON
+///
+fn mirror_payload(msg: &ChannelMessage) -> Result<Payload> {
+    let mut builder = PayloadBuilder::new();
+    if let Some(public) = &msg.public {
+        builder.public(&serde_json::from_str::<serde_json::Value>(public)?)?;
+    }
+    if let Some(masked) = &msg.masked {
+        builder.masked(&serde_json::from_str::<serde_json::Value>(masked)?)?;
+    }
+    Ok(builder.build())
+}
+
+fn payload_size<P: PacketPayload>(payload: &P) -> usize {
+    payload.public_data().0.len() + payload.masked_data().0.len()
+}
+
+///
+/// Channel Builder
+///
+#[derive(Debug)]
+pub struct ChannelBuilder {
+    config: ChannelConfig,
+    seed: Option<Secret<String>>,
+    auto_keyload: bool,
+    protocol_marker: bool,
+    idempotency_window: Duration,
+    policy: ChannelPolicy,
+    transport: Option<CustomTransport>,
+    read_transport: Option<CustomTransport>,
+}
+
+impl ChannelBuilder {
+    ///
+    /// Create Instance
+    ///
+    pub fn new(node: Network) -> Self {
+        ChannelBuilder {
+            config: ChannelConfig::new(node),
+            seed: None,
+            auto_keyload: false,
+            protocol_marker: true,
+            idempotency_window: DEFAULT_IDEMPOTENCY_WINDOW,
+            policy: ChannelPolicy::default(),
+            transport: None,
+            read_transport: None,
+        }
+    }
+
+    ///
+    /// Author seed, a random one is generated if not set
+    ///
+    pub fn seed(&mut self, seed: String) -> &mut Self {
+        self.seed = Some(Secret::new(seed));
+        self
+    }
+
+    ///
+    /// Publish a keyload on the first write when none was published yet, see
+    /// `Channel::set_auto_keyload`
+    ///
+    pub fn auto_keyload(&mut self, auto_keyload: bool) -> &mut Self {
+        self.auto_keyload = auto_keyload;
+        self
+    }
+
+    ///
+    /// Publish the protocol version marker when opening the channel, see
+    /// `Channel::set_protocol_marker`
+    ///
+    pub fn protocol_marker(&mut self, protocol_marker: bool) -> &mut Self {
+        self.protocol_marker = protocol_marker;
+        self
+    }
+
+    ///
+    /// Time an idempotency key is remembered, see `Channel::set_idempotency_window`
+    ///
+    pub fn idempotency_window(&mut self, window: Duration) -> &mut Self {
+        self.idempotency_window = window;
+        self
+    }
+
+    ///
+    /// Limits enforced on the packets written, see `Channel::set_policy`
+    ///
+    pub fn policy(&mut self, policy: ChannelPolicy) -> &mut Self {
+        self.policy = policy;
+        self
+    }
+
+    node_settings!();
+
+    ///
+    /// Maximum transactions per message, for the nodes limiting the bundle size
+    ///
+    /// A packet that could span more fails with `ChannelError::TooManyFragments` before
+    /// Streams wraps it, the tag being the one it would link to: the channel stays
+    /// writable and readable. The size is estimated with `PACKET_OVERHEAD_BYTES` on top
+    /// of the data. Send large data with `write_file`, which chunks it
+    ///
+    pub fn max_fragments(&mut self, max: usize) -> &mut Self {
+        self.config.max_fragments = Some(max);
+        self
+    }
+
+    ///
+    /// Build
+    ///
+    pub fn build(&self) -> Result<Channel> {
+        let transport = match &self.transport {
+            Some(custom) => ChannelTransport::Custom(custom.clone()),
+            None => ChannelTransport::Tangle(self.config.tangle_transport()?),
+        };
+        let read_transport = match &self.read_transport {
+            Some(custom) => Some(ChannelTransport::Custom(custom.clone())),
+            None => self.config.read_transport()?.map(ChannelTransport::Tangle),
+        };
+        let mut channel = Channel::with_tangle_config(
+            self.config.clone(),
+            transport,
+            self.seed.clone().map(Secret::into_inner),
+        )?;
+        channel.set_read_transport(read_transport);
+        channel.set_auto_keyload(self.auto_keyload);
+        channel.set_protocol_marker(self.protocol_marker);
+        channel.set_idempotency_window(self.idempotency_window);
+        channel.set_policy(self.policy.clone());
+        if self.config.verify_node {
+            channel.check_node_compatibility()?;
+            channel.check_node()?;
+        }
+        Ok(channel)
+    }
 }
@@ -1,197 +1,2711 @@
 //!
 //! Channel Subscriber
 //!
-use super::Network;
-use crate::utils::{payload::json::Payload, random_seed};
+use super::{
+    announcement_cache::AnnouncementCache,
+    audit::{payload_digest, AuditReport, AuditVerification},
+    config::{redact_credentials, ChannelConfig, NetworkConfig},
+    cursor::{Cursor, CURSOR_VERSION},
+    decoders::{Decoded, Decoders},
+    message::{ChannelMessage, MessageKind, MessageSource},
+    node::{NodeHealth, NodeInfo, SendOptionsSource, TunedSendOptions},
+    provenance::ProvenanceRecord,
+    reader::ChannelReader,
+    shutdown::{AttachedStore, ShutdownReport},
+    span::OpSpan,
+    store::{load_sealed, save_sealed, StateStore, StoredSubscriber},
+    summary::{ChannelRole, ChannelSummary},
+    Network,
+};
+use crate::error::{ChannelError, Result};
+use crate::transport::{
+    custom::CustomTransport, retry::RetryingTransport, stats::TransportStats, ChannelTransport,
+};
+#[cfg(feature = "json-schema")]
+use crate::utils::schema::JsonSchema;
+use crate::utils::{
+    ack::{Ack, ACK_REQUEST_CAPACITY},
+    cancel::CancelToken,
+    clock::Clock,
+    dedup::Deduplicator,
+    file_transfer::{FileChunk, FileManifest},
+    multipart::{MultiPartPayload, Parts},
+    payload::{
+        json::{Payload, PayloadBuilder},
+        Bytes, PacketPayload,
+    },
+    protocol::{ProtocolMarker, PROTOCOL_VERSION},
+    psk::{psk_id, Psk},
+    random_seed,
+    secret::{redact_trytes, Secret},
+    sequence::{SequenceEvent, SequenceGuard},
+};
+use base64::{decode_config, URL_SAFE_NO_PAD};
 use core::cell::RefCell;
 use iota::client as iota_client;
-use iota_streams::app::transport::tangle::{
-    client::{RecvOptions, SendTrytesOptions},
-    PAYLOAD_BYTES,
-};
+use iota_streams::app::transport::tangle::{client::RecvOptions, PAYLOAD_BYTES};
 use iota_streams::app::transport::Transport;
 use iota_streams::app_channels::{
     api::{
-        tangle::{Address, Subscriber},
+        tangle::{Address, Message, MessageContent, Subscriber, UnwrappedMessage},
         SequencingState,
     },
     message,
 };
 
+use instant::Instant;
 use iota_streams::core::prelude::{Rc, String};
+use iota_streams::ddml::{
+    command::Absorb,
+    types::{NBytes, U32},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    io::Write,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+const LOG_TARGET: &str = "channels_lite::subscriber";
+
+///
+/// Number of messages read remembered for `read_subtree`, the oldest are forgotten first
+///
+pub const READ_HISTORY_CAPACITY: usize = 10_000;
+
+///
+/// Age after which the channel is walked again by `backlog_estimate`
+///
+pub const BACKLOG_STALENESS: Duration = Duration::from_secs(10);
+
+///
+/// Gap tolerance policy for the message walk
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GapPolicy {
+    /// Fetch attempts before the next message is considered missing
+    ///
+    pub max_attempts: usize,
+    /// Delay between two fetch attempts
+    ///
+    pub retry_delay: Duration,
+    /// Re-anchor the walk on the last processed keyload once the attempts are exhausted,
+    /// when the keyload was processed with `update_keyload` or `read_from_keyload` and the
+    /// walk found nothing since: a message before it is missing
+    ///
+    pub skip_to_keyload: bool,
+}
+
+impl Default for GapPolicy {
+    fn default() -> Self {
+        GapPolicy {
+            max_attempts: 1,
+            retry_delay: Duration::from_secs(0),
+            skip_to_keyload: false,
+        }
+    }
+}
+
+///
+/// What `fetch_window` does with the messages the node reported no attachment time for
+///
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingTimestampPolicy {
+    /// Returned along with the messages of the window
+    ///
+    Include,
+    /// Skipped like the messages outside the window
+    ///
+    Exclude,
+}
 
-use anyhow::Result;
+///
+/// Gap skipped during the message walk
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Gap {
+    /// Last message tag read before the gap, if any
+    ///
+    pub after_tag: Option<String>,
+    /// First message tag found after re-anchoring on the keyload
+    ///
+    pub resumed_at: String,
+}
+
+///
+/// Keyload found in the channel
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct KeyloadInfo {
+    /// Keyload tag
+    ///
+    pub tag: String,
+    /// Whether this subscriber was authorized by the keyload
+    ///
+    pub authorized: bool,
+}
+
+///
+/// Channel metadata, enough to follow the channel again
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChannelInfo {
+    /// Channel address
+    ///
+    pub channel_address: String,
+    /// Announcement tag
+    ///
+    pub announcement_tag: String,
+    /// Keyloads seen so far, in order
+    ///
+    pub keyloads: Vec<KeyloadInfo>,
+}
 
 ///
 /// Channel subscriber
 ///
-pub struct Channel {
-    pub subscriber: Subscriber<&'static iota_client::Client>,
+pub struct Channel<T = ChannelTransport> {
+    subscriber: Subscriber<RetryingTransport<T>>,
+    transport: Rc<RefCell<RetryingTransport<T>>>,
     is_connected: bool,
-    send_opt: SendTrytesOptions,
+    config: ChannelConfig,
+    node_url: String,
+    send_opt: TunedSendOptions,
     announcement_link: Address,
     subscription_link: Address,
     channel_address: String,
+    last_keyload_link: Option<Address>,
+    keyload_ahead: bool,
+    gap_policy: GapPolicy,
+    gaps: Vec<Gap>,
+    is_closed: bool,
+    pending: VecDeque<ChannelMessage>,
+    keyloads: Vec<KeyloadInfo>,
+    backlog_checked_at: Option<Instant>,
+    read_position: usize,
+    last_read_tag: Option<String>,
+    read_tags: VecDeque<String>,
+    read_only: bool,
+    announcement_cache: Option<AnnouncementCache>,
+    drop_expired: bool,
+    skip_own: bool,
+    sequence_guard: Option<SequenceGuard>,
+    sequence_events: Vec<SequenceEvent>,
+    sequence_handler: Option<Box<dyn FnMut(&SequenceEvent)>>,
+    dedup: Option<Deduplicator>,
+    protocol_version: Option<u32>,
+    marker_tag: Option<String>,
+    strict_protocol: bool,
+    strict_access: bool,
+    multi_branching: Option<bool>,
+    ack_requests: HashMap<String, String>,
+    ack_order: VecDeque<String>,
+    latest: Option<ChannelMessage>,
+    cancel: CancelToken,
+    store: Option<AttachedStore>,
+    shut_down: bool,
+    seed: Secret<String>,
 }
 
-impl Channel {
+///
+/// Channel subscriber on the tangle
+///
+pub type TangleChannel = Channel<ChannelTransport>;
+
+impl Channel<ChannelTransport> {
     ///
     /// Initialize the subscriber
     ///
+    /// Fails if the transport of the node can't be built, e.g. for a malformed url
+    ///
     pub fn new(
         node: Network,
         channel_address: String,
         announcement_tag: String,
         seed_option: Option<String>,
-    ) -> Channel {
+    ) -> Result<Channel> {
+        let config = ChannelConfig::new(node);
+        let transport = ChannelTransport::Tangle(config.tangle_transport()?);
+        Self::with_tangle_config(
+            config,
+            transport,
+            channel_address,
+            announcement_tag,
+            seed_option,
+        )
+    }
+
+    ///
+    /// Restore a subscriber persisted with `persist_to`
+    ///
+    /// The channel is walked again up to the persisted read position
+    ///
+    pub fn restore_from<S>(store: &S, id: &str, password: &str, node: Network) -> Result<Channel>
+    where
+        S: StateStore + ?Sized,
+    {
+        let stored: StoredSubscriber = load_sealed(store, id, password)?;
+        let config = ChannelConfig::new(node);
+        let transport = ChannelTransport::Tangle(config.tangle_transport()?);
+        let mut channel = Self::with_tangle_config(
+            config,
+            transport,
+            stored.channel_address.clone(),
+            stored.announcement_tag.clone(),
+            Some(stored.seed.expose().clone()),
+        )?;
+        channel.restore_stored(stored)?;
+        Ok(channel)
+    }
+
+    fn with_tangle_config(
+        config: ChannelConfig,
+        mut transport: ChannelTransport,
+        channel_address: String,
+        announcement_tag: String,
+        seed_option: Option<String>,
+    ) -> Result<Channel> {
+        let send_opt = config.tune_send_options(&mut transport);
+        Self::with_config(
+            config,
+            transport,
+            channel_address,
+            announcement_tag,
+            seed_option,
+            send_opt,
+        )
+    }
+
+    ///
+    /// Check the health of the configured node
+    ///
+    pub fn check_node(&self) -> Result<NodeHealth> {
+        self.transport
+            .borrow()
+            .check_node(&self.node_url, self.config.max_milestone_lag)
+    }
+
+    ///
+    /// Check the node is synced enough to publish, returns its milestone lag
+    ///
+    /// Always queries the node, unlike the cached check run before the sends
+    ///
+    pub fn check_sync(&self) -> Result<u32> {
+        self.transport
+            .borrow()
+            .check_sync(&self.node_url, self.config.max_milestone_lag)
+    }
+
+    ///
+    /// Check the configured node runs a supported software and version
+    ///
+    /// The returned info carries the network id, to check the node belongs to the
+    /// intended network
+    ///
+    pub fn check_node_compatibility(&self) -> Result<NodeInfo> {
+        self.transport
+            .borrow()
+            .check_node_compatibility(&self.node_url)
+    }
+
+    ///
+    /// Switch to another node, keeping the channel state
+    ///
+    /// The node url is validated and the node health-checked before the switch
+    ///
+    pub fn set_node(&mut self, url: &str) -> Result<()> {
+        let transport = self
+            .config
+            .tangle_transport_at(url, self.send_opt.options)?;
+        self.transport
+            .borrow_mut()
+            .switch_node(transport, self.config.max_milestone_lag)?;
+        self.node_url = url.to_string();
+        Ok(())
+    }
+
+    ///
+    /// Initialize a read-only subscriber holding a pre-shared key
+    ///
+    /// No seed is needed, a random one is generated, and no subscribe message is sent,
+    /// see `set_psk`
+    ///
+    pub fn new_read_only(
+        node: Network,
+        channel_address: String,
+        announcement_tag: String,
+        psk: Psk,
+    ) -> Result<Channel> {
+        let seed = read_only_seed();
+        let mut channel = Self::new(node, channel_address, announcement_tag, Some(seed))?;
+        channel.set_psk(psk);
+        Ok(channel)
+    }
+}
+
+impl<T> Channel<T>
+where
+    T: Transport<Address, Message>,
+    T::SendOptions: Copy + Default,
+    T::RecvOptions: Copy + Default,
+{
+    ///
+    /// Initialize a read-only subscriber holding a pre-shared key on a given transport
+    ///
+    pub fn new_read_only_with_transport(
+        node: Network,
+        transport: T,
+        channel_address: String,
+        announcement_tag: String,
+        psk: Psk,
+    ) -> Result<Self> {
+        let seed = read_only_seed();
+        let mut channel = Self::new_with_transport(
+            node,
+            transport,
+            channel_address,
+            announcement_tag,
+            Some(seed),
+        )?;
+        channel.set_psk(psk);
+        Ok(channel)
+    }
+
+    ///
+    /// Restore a subscriber persisted with `persist_to` on a given transport
+    ///
+    pub fn restore_from_with_transport<S>(
+        store: &S,
+        id: &str,
+        password: &str,
+        node: Network,
+        transport: T,
+    ) -> Result<Self>
+    where
+        S: StateStore + ?Sized,
+    {
+        let stored: StoredSubscriber = load_sealed(store, id, password)?;
+        let mut channel = Self::new_with_transport(
+            node,
+            transport,
+            stored.channel_address.clone(),
+            stored.announcement_tag.clone(),
+            Some(stored.seed.expose().clone()),
+        )?;
+        channel.restore_stored(stored)?;
+        Ok(channel)
+    }
+
+    ///
+    /// Save the seed, the subscription and the read position, sealed with the password
+    ///
+    /// A failed save leaves the previously stored state in place. The read-only
+    /// subscribers are not persisted, the pre-shared key stays with the application
+    ///
+    pub fn persist_to<S>(&self, store: &S, id: &str, password: &str) -> Result<()>
+    where
+        S: StateStore + ?Sized,
+    {
+        self.ensure_connected()?;
+        if self.read_only {
+            return Err(ChannelError::State(
+                "Read-only subscribers are not persisted, recreate them from the pre-shared key"
+                    .to_string(),
+            ));
+        }
+        let stored = StoredSubscriber {
+            seed: self.seed.clone(),
+            channel_address: self.channel_address.clone(),
+            announcement_tag: self.announcement_link.msgid.to_string(),
+            subscription_tag: Some(self.subscription_link.msgid.to_string()),
+            last_keyload_tag: self
+                .last_keyload_link
+                .as_ref()
+                .map(|link| link.msgid.to_string()),
+            cursor: self.cursor(),
+        };
+        save_sealed(store, id, password, &stored)
+    }
+
+    ///
+    /// Store the state is persisted to by `shutdown`
+    ///
+    pub fn attach_store(&mut self, store: Rc<dyn StateStore>, id: &str, password: &str) {
+        self.store = Some(AttachedStore::new(store, id, password));
+    }
+
+    ///
+    /// Token cancelling the waits of `next_message_blocking`, e.g. from a signal handler
+    ///
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    ///
+    /// Cancel the waits, persist the state to the attached store, if any, and release
+    /// the channel
+    ///
+    /// The messages fetched but not handed out yet are counted in the report, they are
+    /// fetched again by the restored subscriber. The report carries the final `cursor`
+    /// token. A failed persist is returned and the channel dropped with a warning
+    ///
+    pub fn shutdown(mut self) -> Result<ShutdownReport> {
+        self.cancel.cancel();
+        let persisted_as = match &self.store {
+            Some(attached) => {
+                self.persist_to(
+                    attached.store.as_ref(),
+                    &attached.id,
+                    attached.password.expose(),
+                )?;
+                Some(attached.id.clone())
+            }
+            None => None,
+        };
+        self.shut_down = true;
+        Ok(ShutdownReport {
+            persisted_as: persisted_as,
+            pending_messages: self.pending.len(),
+            final_state: self.cursor(),
+            pending_acks: 0,
+        })
+    }
+
+    fn restore_stored(&mut self, stored: StoredSubscriber) -> Result<()> {
+        let announcement_link = self.announcement_link.clone();
+        self.receive_announcement(&announcement_link)?;
+        self.marker_tag = self.marker_link().map(|link| link.msgid.to_string());
+        if let Some(subscription_tag) = &stored.subscription_tag {
+            self.subscription_link = self.link(subscription_tag)?;
+        }
+        self.is_connected = true;
+        if let Some(keyload_tag) = stored.last_keyload_tag {
+            self.update_keyload(keyload_tag)?;
+        }
+        self.resume_from_cursor(&stored.cursor)
+    }
+
+    ///
+    /// Initialize the subscriber on a given transport
+    ///
+    /// The node is only used for the send options and the reports
+    ///
+    pub fn new_with_transport(
+        node: Network,
+        transport: T,
+        channel_address: String,
+        announcement_tag: String,
+        seed_option: Option<String>,
+    ) -> Result<Self> {
+        let config = ChannelConfig::new(node);
+        let send_opt = TunedSendOptions {
+            options: config.node.send_options(),
+            source: SendOptionsSource::Static,
+        };
+        Self::with_config(
+            config,
+            transport,
+            channel_address,
+            announcement_tag,
+            seed_option,
+            send_opt,
+        )
+    }
+
+    fn with_config(
+        config: ChannelConfig,
+        transport: T,
+        channel_address: String,
+        announcement_tag: String,
+        seed_option: Option<String>,
+        send_opt: TunedSendOptions,
+    ) -> Result<Self> {
         let seed = match seed_option {
             Some(seed) => seed,
             None => random_seed::new(),
         };
-        iota_client::Client::add_node(node.as_string()).unwrap();
-        let subscriber = Subscriber::new(
-            &seed,
-            "utf-8",
-            PAYLOAD_BYTES,
-            Rc::new(RefCell::new(iota_client::Client::get())),
-        );
+        let transport = Rc::new(RefCell::new(config.wrap_transport(transport)?));
+        let subscriber = Subscriber::new(&seed, "utf-8", PAYLOAD_BYTES, transport.clone());
+        let announcement_link =
+            Address::from_str(&channel_address, &announcement_tag).map_err(|()| {
+                ChannelError::InvalidAddress {
+                    address: format!("{}:{}", channel_address, announcement_tag),
+                    reason: "not a valid channel address and announcement tag".to_string(),
+                }
+            })?;
 
-        Self {
+        Ok(Self {
             subscriber: subscriber,
+            transport: transport,
             is_connected: false,
-            send_opt: node.send_options(),
-            announcement_link: Address::from_str(&channel_address, &announcement_tag).unwrap(),
+            send_opt: send_opt,
+            node_url: config.write_node_url().to_string(),
+            config: config,
+            announcement_link: announcement_link,
             subscription_link: Address::default(),
             channel_address: channel_address,
-        }
+            last_keyload_link: None,
+            keyload_ahead: false,
+            gap_policy: GapPolicy::default(),
+            gaps: Vec::new(),
+            is_closed: false,
+            pending: VecDeque::new(),
+            backlog_checked_at: None,
+            read_position: 0,
+            last_read_tag: None,
+            read_tags: VecDeque::new(),
+            read_only: false,
+            announcement_cache: None,
+            drop_expired: false,
+            skip_own: false,
+            sequence_guard: None,
+            sequence_events: Vec::new(),
+            sequence_handler: None,
+            dedup: Some(Deduplicator::new()),
+            protocol_version: None,
+            marker_tag: None,
+            strict_protocol: false,
+            strict_access: false,
+            multi_branching: None,
+            ack_requests: HashMap::new(),
+            ack_order: VecDeque::new(),
+            latest: None,
+            keyloads: Vec::new(),
+            cancel: CancelToken::new(),
+            store: None,
+            shut_down: false,
+            seed: Secret::new(seed),
+        })
     }
 
     ///
-    /// Connect
+    /// Url of the node the channel talks to
     ///
-    pub fn connect(&mut self) -> Result<String> {
-        self.subscriber
-            .receive_announcement(&self.announcement_link)?;
+    pub fn node(&self) -> &str {
+        &self.node_url
+    }
 
-        let subscribe_link = {
-            let msg = self.subscriber.send_subscribe(&self.announcement_link)?;
-            msg
-        };
+    ///
+    /// Replace the underlying transport, keeping the channel state
+    ///
+    pub fn set_transport(&mut self, transport: T) {
+        *self.transport.borrow_mut().inner_mut() = transport;
+    }
 
-        self.subscription_link = subscribe_link;
-        self.is_connected = true;
-        Ok(self.subscription_link.msgid.to_string())
+    ///
+    /// Fetch the messages through another transport, e.g. a permanode, the underlying
+    /// transport is then used for the sends only. `None` to fetch through it again
+    ///
+    pub fn set_read_transport(&mut self, transport: Option<T>) {
+        self.transport.borrow_mut().set_reader(transport);
     }
 
-    /*
     ///
-    /// Disconnect
+    /// Url of the node the messages are fetched from, `None` if it is the node the
+    /// channel sends through or a read transport was set instead
     ///
-    pub fn disconnect(&mut self) -> Result<String> {
-        let unsubscribe_link = {
-            let msg = self.subscriber.unsubscribe(&self.subscription_link)?;
-            iota_client::Client::get().send_message_with_options(&msg, self.send_opt)?;
-            msg.link.msgid
-        };
-        Ok(unsubscribe_link.to_string())
-    }*/
+    pub fn read_node(&self) -> Option<&str> {
+        self.config.read_node.as_deref()
+    }
 
     ///
-    /// Read signed packet
+    /// Replace the time source of the sleeps, timeouts and timestamps
     ///
-    pub fn read_signed(
-        &mut self,
-        signed_packet_tag: String,
-    ) -> Result<Vec<(Option<String>, Option<String>)>> {
-        let mut response: Vec<(Option<String>, Option<String>)> = Vec::new();
-        let link = Address::from_str(&self.channel_address, &signed_packet_tag).unwrap();
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.transport.borrow_mut().set_clock(clock.clone());
+        self.config.clock = clock;
+    }
 
-        if self.is_connected {
-            match self.subscriber.receive_signed_packet(&link.clone()) {
-                Ok((_signer, unwrapped_public, unwrapped_masked)) => {
-                    response.push((
-                        Payload::unwrap_data(&String::from_utf8(unwrapped_public.0).unwrap())
-                            .unwrap(),
-                        Payload::unwrap_data(&String::from_utf8(unwrapped_masked.0).unwrap())
-                            .unwrap(),
-                    ));
-                }
-                Err(e) => println!("Signed Packet Error: {}", e),
-            }
-        } else {
-            println!("Channel not connected");
-        }
+    ///
+    /// Check the rate limit allows a send right away, returns the time to wait otherwise
+    ///
+    #[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+    pub(crate) fn send_ready(&self) -> std::result::Result<(), Duration> {
+        self.transport.borrow_mut().send_ready()
+    }
 
-        Ok(response)
+    ///
+    /// HTTP settings the node client was built with
+    ///
+    pub fn network_config(&self) -> &NetworkConfig {
+        &self.config.network
     }
 
     ///
-    /// Read tagged packet
+    /// Send options used for outgoing messages, with their origin
     ///
-    pub fn read_tagged(
-        &mut self,
-        tagged_packet_tag: String,
-    ) -> Result<Vec<(Option<String>, Option<String>)>> {
-        let mut response: Vec<(Option<String>, Option<String>)> = Vec::new();
+    pub fn send_options(&self) -> &TunedSendOptions {
+        &self.send_opt
+    }
 
-        if self.is_connected {
-            let link = Address::from_str(&self.channel_address, &tagged_packet_tag).unwrap();
-
-            match self.subscriber.receive_tagged_packet(&link.clone()) {
-                Ok((unwrapped_public, unwrapped_masked)) => {
-                    response.push((
-                        Payload::unwrap_data(&String::from_utf8(unwrapped_public.0).unwrap())
-                            .unwrap(),
-                        Payload::unwrap_data(&String::from_utf8(unwrapped_masked.0).unwrap())
-                            .unwrap(),
-                    ));
-                }
-                Err(e) => println!("Tagged Packet Error: {}", e),
-            }
-        } else {
-            println!("Channel not connected");
-        }
+    ///
+    /// Statistics of the transport operations made so far
+    ///
+    pub fn transport_stats(&self) -> TransportStats {
+        self.transport.borrow().stats().clone()
+    }
 
-        Ok(response)
+    ///
+    /// Role, address, messages sent, last message read and node of the channel
+    ///
+    pub fn summary(&self) -> ChannelSummary {
+        let stats = self.transport.borrow().stats().send.clone();
+        ChannelSummary::new(
+            ChannelRole::Subscriber,
+            &self.channel_address,
+            stats.count - stats.errors,
+            self.last_read_tag.clone(),
+            &self.node_url,
+        )
     }
 
     ///
-    /// Update keyload
+    /// Reset the transport statistics
     ///
-    pub fn update_keyload(&mut self, keyload_tag: String) -> Result<()> {
-        let keyload_link = Address::from_str(&self.channel_address, &keyload_tag).unwrap();
+    pub fn reset_transport_stats(&mut self) {
+        self.transport.borrow_mut().reset_stats();
+    }
+
+    ///
+    /// Attachment time of a message as reported by the node, unlike the payload
+    /// timestamps it doesn't depend on the clock of the writing device
+    ///
+    /// Fails if the message is not found or the node doesn't report its attachment time
+    ///
+    pub fn attachment_time(&self, tag: &str) -> Result<SystemTime> {
+        let link = self.link(tag)?;
+        self.transport.borrow_mut().attachment_time(&link)
+    }
 
+    ///
+    /// Link to a message of the channel
+    ///
+    fn link(&self, tag: &str) -> Result<Address> {
+        Address::from_str(&self.channel_address, tag).map_err(|()| ChannelError::InvalidAddress {
+            address: format!("{}:{}", self.channel_address, tag),
+            reason: "not a valid message tag".to_string(),
+        })
+    }
+
+    fn ensure_connected(&self) -> Result<()> {
         if self.is_connected {
-            self.subscriber.receive_keyload(&keyload_link.clone());
+            Ok(())
         } else {
-            println!("Channel not connected");
+            Err(ChannelError::NotConnected)
         }
+    }
 
-        Ok(())
+    ///
+    /// Classify an error raised by Streams while reading a message
+    ///
+    /// The errors of the transport are kept, a message missing from the transport is
+    /// `MessageNotFound`, anything else is a protocol error
+    ///
+    fn read_error(&self, link: &Address, e: anyhow::Error) -> ChannelError {
+        let e = match e.downcast::<ChannelError>() {
+            Ok(e) => return e,
+            Err(e) => e,
+        };
+        let found = self
+            .transport
+            .borrow_mut()
+            .recv_messages(link)
+            .map_or(true, |msgs| !msgs.is_empty());
+        if found {
+            ChannelError::Protocol(redact_trytes(&format!("{:#}", e)))
+        } else {
+            ChannelError::MessageNotFound(link.msgid.to_string())
+        }
     }
 
     ///
-    /// Generates the next message in the channels
+    /// Classify an error raised by Streams while reading a packet
     ///
-    pub fn get_next_message(&mut self) -> Vec<Option<String>> {
-        let mut exists = true;
+    /// Same as `read_error`, a packet that can't be unwrapped while no keyload granted
+    /// access to the subscriber is `AccessDenied`
+    ///
+    fn packet_error(&self, link: &Address, e: anyhow::Error) -> ChannelError {
+        match self.read_error(link, e) {
+            ChannelError::Protocol(_) if !self.keyloads.iter().any(|k| k.authorized) => {
+                ChannelError::AccessDenied(link.msgid.to_string())
+            }
+            e => e,
+        }
+    }
 
-        let mut tags: Vec<Option<String>> = vec![];
+    ///
+    /// Use a pre-shared key to read the channel, making the subscriber read-only
+    ///
+    /// A read-only subscriber never publishes: `connect` only reads the announcement and
+    /// returns its tag, and the masked data is readable in the messages anchored after a
+    /// keyload including the key
+    ///
+    pub fn set_psk(&mut self, psk: Psk) {
+        self.subscriber
+            .store_psk(psk_id(&psk).into_streams(), psk.into_streams());
+        self.read_only = true;
+    }
 
-        while exists {
-            let msgs = self.subscriber.fetch_next_msgs();
-            exists = false;
+    ///
+    /// Whether the subscriber reads the channel with a pre-shared key only
+    ///
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
 
-            for msg in msgs {
-                println!("Message exists at {}... ", &msg.link.msgid);
-                tags.push(Some(msg.link.msgid.to_string()));
-                exists = true;
-            }
+    ///
+    /// Share an announcement cache with the other subscribers of the channel
+    ///
+    pub fn set_announcement_cache(&mut self, cache: AnnouncementCache) {
+        self.announcement_cache = Some(cache);
+    }
 
-            if !exists {
-                println!("No more messages in sequence.");
-            }
-        }
-        tags
+    ///
+    /// Connect
+    ///
+    /// Returns the subscription tag, or the announcement tag for a read-only subscriber
+    ///
+    /// The subscribe message is fetched back to check it landed, and sent again up to
+    /// `RetryConfig::max_send_attempts` times otherwise, with the retry backoff between
+    /// the check and the next attempt. Fails with `ChannelError::MessageNotFound` if the
+    /// last one is still not found, the subscriber stays disconnected
+    ///
+    pub fn connect(&mut self) -> Result<String> {
+        let span = OpSpan::new("connect", &self.channel_address, 0);
+        let res = span.in_scope(|| self.send_subscribe());
+        span.record(&res, |tag| tag);
+        res
+    }
+
+    fn send_subscribe(&mut self) -> Result<String> {
+        let announcement_link = self.announcement_link.clone();
+        self.receive_announcement(&announcement_link)?;
+        self.read_protocol_marker()?;
+
+        if self.read_only {
+            self.is_connected = true;
+            return Ok(announcement_link.msgid.to_string());
+        }
+
+        let retry = self.transport.borrow().config().clone();
+        let mut attempt = 1;
+        let subscribe_link = loop {
+            let link = self.subscriber.send_subscribe(&self.announcement_link)?;
+            if self.subscribe_landed(&link)? {
+                break link;
+            }
+            if !self.config.clock.can_sleep() {
+                // No waiting for the node, the subscribe message is taken as sent
+                break link;
+            }
+            self.config.clock.sleep(retry.delay(attempt));
+            if self.subscribe_landed(&link)? {
+                break link;
+            }
+            if attempt >= retry.max_send_attempts {
+                return Err(ChannelError::MessageNotFound(link.msgid.to_string()));
+            }
+            log::warn!(
+                target: LOG_TARGET,
+                "Subscribe message {} not found on the node, sending it again",
+                link.msgid
+            );
+            attempt += 1;
+        };
+
+        self.subscription_link = subscribe_link;
+        self.is_connected = true;
+        Ok(self.subscription_link.msgid.to_string())
+    }
+
+    ///
+    /// Whether the node serves the subscribe message, the errors worth retrying count as
+    /// not found
+    ///
+    fn subscribe_landed(&self, link: &Address) -> Result<bool> {
+        match self.transport.borrow_mut().recv_messages(link) {
+            Ok(msgs) => Ok(!msgs.is_empty()),
+            Err(e) => {
+                let e = ChannelError::transport(e);
+                if e.is_retryable() {
+                    Ok(false)
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    ///
+    /// Unwrap the announcement, from the cache when it was already fetched
+    ///
+    /// Fails with `ChannelError::AnnouncementMismatch` when the message found at the tag is
+    /// not an announcement of the channel
+    ///
+    fn receive_announcement(&mut self, link: &Address) -> Result<()> {
+        let cached = self
+            .announcement_cache
+            .as_ref()
+            .and_then(|cache| cache.get(link));
+        let msg = match cached {
+            Some(msg) => msg,
+            None => {
+                let fetched = self.transport.borrow_mut().recv_message(link);
+                let msg = fetched.map_err(|e| self.read_error(link, e))?;
+                self.check_announcement(link, &msg)?;
+                msg
+            }
+        };
+        self.subscriber
+            .handle_message(msg.clone(), None)
+            .map_err(|e| match self.read_error(link, e) {
+                ChannelError::Protocol(reason) => self.announcement_mismatch(link, reason),
+                e => e,
+            })?;
+        if let Some(cache) = &self.announcement_cache {
+            cache.insert(link, msg);
+        }
+        let multi_branching = self.subscriber.is_multi_branching();
+        log::debug!(
+            target: LOG_TARGET,
+            "Channel {} is {}",
+            self.channel_address,
+            if multi_branching {
+                "multi-branching"
+            } else {
+                "single-branching"
+            }
+        );
+        self.multi_branching = Some(multi_branching);
+        Ok(())
+    }
+
+    ///
+    /// Check the message fetched at the announcement tag is an announcement of the channel
+    ///
+    fn check_announcement(&self, link: &Address, msg: &Message) -> Result<()> {
+        let preparsed = msg
+            .binary
+            .parse_header()
+            .map_err(|e| self.read_error(link, e))?;
+        if !preparsed.check_content_type(message::ANNOUNCE) {
+            return Err(
+                self.announcement_mismatch(link, "the message is not an announcement".to_string())
+            );
+        }
+        if preparsed.header.link.appinst != link.appinst {
+            return Err(self.announcement_mismatch(
+                link,
+                format!(
+                    "the announcement belongs to channel {}",
+                    preparsed.header.link.appinst
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    ///
+    /// Read the protocol version marker published after the announcement, if any
+    ///
+    /// The channels opened by older versions of the crate have no marker, the version
+    /// stays `None` and the channel is read as before
+    ///
+    fn read_protocol_marker(&mut self) -> Result<()> {
+        let link = match self.marker_link() {
+            Some(link) => link,
+            None => return Ok(()),
+        };
+        self.marker_tag = Some(link.msgid.to_string());
+        // A missing message is reported as an error by some transports
+        let msgs = match self.transport.borrow_mut().recv_messages(&link) {
+            Ok(msgs) => msgs,
+            Err(e) => match ChannelError::transport(e) {
+                ChannelError::MessageNotFound(_) => Vec::new(),
+                e => return Err(e),
+            },
+        };
+        let signed = match msgs.first() {
+            Some(msg) => msg.binary.parse_header().map_or(false, |preparsed| {
+                preparsed.check_content_type(message::SIGNED_PACKET)
+            }),
+            None => false,
+        };
+        if !signed {
+            return Ok(());
+        }
+
+        let version = match self.subscriber.receive_signed_packet(&link) {
+            Ok((_, public, _)) => Payload::unwrap_bytes(&public.0)
+                .ok()
+                .flatten()
+                .and_then(|public| ProtocolMarker::parse(&public)),
+            Err(_) => None,
+        };
+        if let Some(version) = version {
+            self.check_protocol_version(version)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Address following the announcement, where the author publishes the protocol marker
+    ///
+    /// Only valid right after the announcement is received, before the walk moves the
+    /// cursors
+    ///
+    fn marker_link(&self) -> Option<Address> {
+        let branching = self.subscriber.is_multi_branching();
+        self.subscriber
+            .gen_next_msg_ids(branching)
+            .into_iter()
+            .next()
+            .map(|(_, cursor)| cursor.link)
+    }
+
+    ///
+    /// Record the protocol version of the channel, warn or fail if it is newer than the
+    /// one of this crate
+    ///
+    fn check_protocol_version(&mut self, version: u32) -> Result<()> {
+        self.protocol_version = Some(version);
+        if version <= PROTOCOL_VERSION {
+            return Ok(());
+        }
+        if self.strict_protocol {
+            return Err(ChannelError::UnsupportedProtocol {
+                version: version,
+                supported: PROTOCOL_VERSION,
+            });
+        }
+        log::warn!(
+            target: LOG_TARGET,
+            "Channel {} uses protocol version {}, this version reads up to {}",
+            self.channel_address,
+            version,
+            PROTOCOL_VERSION
+        );
+        Ok(())
+    }
+
+    fn announcement_mismatch(&self, link: &Address, reason: String) -> ChannelError {
+        ChannelError::AnnouncementMismatch {
+            channel_address: self.channel_address.clone(),
+            announcement_tag: link.msgid.to_string(),
+            reason: reason,
+        }
+    }
+
+    /*
+    ///
+    /// Disconnect
+    ///
+    pub fn disconnect(&mut self) -> Result<String> {
+        let unsubscribe_link = {
+            let msg = self.subscriber.unsubscribe(&self.subscription_link)?;
+            iota_client::Client::get().send_message_with_options(&msg, self.send_opt)?;
+            msg.link.msgid
+        };
+        Ok(unsubscribe_link.to_string())
+    }*/
+
+    ///
+    /// Read signed packet
+    ///
+    /// A subscriber no keyload granted access to reads the public data only, the masked
+    /// data is `None`, unless `set_strict_access` is enabled
+    ///
+    pub fn read_signed(
+        &mut self,
+        signed_packet_tag: String,
+    ) -> Result<Vec<(Option<String>, Option<String>)>> {
+        self.ensure_connected()?;
+        let link = self.link(&signed_packet_tag)?;
+
+        let (_signer, unwrapped_public, unwrapped_masked) =
+            match self.subscriber.receive_signed_packet(&link) {
+                Ok(unwrapped) => unwrapped,
+                Err(e) => match self.packet_error(&link, e) {
+                    ChannelError::AccessDenied(_) if !self.strict_access => {
+                        let public = self.receive_public_only(&link)?;
+                        return Ok(vec![(
+                            decode_data(&public.0, "public data", &signed_packet_tag)?,
+                            None,
+                        )]);
+                    }
+                    e => return Err(e),
+                },
+            };
+        Ok(vec![(
+            decode_data(&unwrapped_public.0, "public data", &signed_packet_tag)?,
+            decode_data(&unwrapped_masked.0, "masked data", &signed_packet_tag)?,
+        )])
+    }
+
+    ///
+    /// Read signed packet, validating the public data against a JSON schema
+    ///
+    /// Fails with `ChannelError::SchemaViolation` when the public data doesn't conform to the
+    /// schema, missing public data is validated as `null`
+    ///
+    #[cfg(feature = "json-schema")]
+    pub fn read_signed_validated(
+        &mut self,
+        signed_packet_tag: String,
+        schema: &JsonSchema,
+    ) -> Result<Vec<(serde_json::Value, Option<String>)>> {
+        let mut response = Vec::new();
+        for (public, masked) in self.read_signed(signed_packet_tag.clone())? {
+            let public: serde_json::Value = match public {
+                Some(public) => serde_json::from_str(&public)?,
+                None => serde_json::Value::Null,
+            };
+            let errors = schema.violations(&public);
+            if !errors.is_empty() {
+                return Err(ChannelError::SchemaViolation {
+                    tag: signed_packet_tag,
+                    errors: errors,
+                });
+            }
+            response.push((public, masked));
+        }
+        Ok(response)
+    }
+
+    ///
+    /// Read tagged packet
+    ///
+    pub fn read_tagged(
+        &mut self,
+        tagged_packet_tag: String,
+    ) -> Result<Vec<(Option<String>, Option<String>)>> {
+        let (unwrapped_public, unwrapped_masked) = self.receive_tagged(&tagged_packet_tag)?;
+        Ok(vec![(
+            decode_data(&unwrapped_public.0, "public data", &tagged_packet_tag)?,
+            decode_data(&unwrapped_masked.0, "masked data", &tagged_packet_tag)?,
+        )])
+    }
+
+    ///
+    /// Read the public data of a tagged packet only
+    ///
+    /// The masked data isn't decoded, a masked part written in another encoding doesn't
+    /// fail the read. The packet itself still needs a keyload granting access
+    ///
+    pub fn read_tagged_public(&mut self, tagged_packet_tag: String) -> Result<Option<String>> {
+        let (unwrapped_public, _) = self.receive_tagged(&tagged_packet_tag)?;
+        decode_data(&unwrapped_public.0, "public data", &tagged_packet_tag)
+    }
+
+    ///
+    /// Read the masked data of a tagged packet only, the public data isn't decoded
+    ///
+    pub fn read_tagged_masked(&mut self, tagged_packet_tag: String) -> Result<Option<String>> {
+        let (_, unwrapped_masked) = self.receive_tagged(&tagged_packet_tag)?;
+        decode_data(&unwrapped_masked.0, "masked data", &tagged_packet_tag)
+    }
+
+    fn receive_tagged(&mut self, tagged_packet_tag: &str) -> Result<(Bytes, Bytes)> {
+        self.ensure_connected()?;
+        let link = self.link(tagged_packet_tag)?;
+
+        self.subscriber
+            .receive_tagged_packet(&link)
+            .map_err(|e| self.packet_error(&link, e))
+    }
+
+    ///
+    /// Read the named parts of a signed packet written with a `MultiPartPayload`
+    ///
+    pub fn read_signed_parts(&mut self, signed_packet_tag: String) -> Result<Vec<(Parts, Parts)>> {
+        self.ensure_connected()?;
+        let link = self.link(&signed_packet_tag)?;
+
+        let (_signer, public, masked) = self
+            .subscriber
+            .receive_signed_packet(&link)
+            .map_err(|e| self.packet_error(&link, e))?;
+        Ok(vec![(
+            decode_parts(&public.0, "public parts", &signed_packet_tag)?,
+            decode_parts(&masked.0, "masked parts", &signed_packet_tag)?,
+        )])
+    }
+
+    ///
+    /// Read the named parts of a tagged packet written with a `MultiPartPayload`
+    ///
+    pub fn read_tagged_parts(&mut self, tagged_packet_tag: String) -> Result<Vec<(Parts, Parts)>> {
+        self.ensure_connected()?;
+        let link = self.link(&tagged_packet_tag)?;
+
+        let (public, masked) = self
+            .subscriber
+            .receive_tagged_packet(&link)
+            .map_err(|e| self.packet_error(&link, e))?;
+        Ok(vec![(
+            decode_parts(&public.0, "public parts", &tagged_packet_tag)?,
+            decode_parts(&masked.0, "masked parts", &tagged_packet_tag)?,
+        )])
+    }
+
+    ///
+    /// Read a message keeping its raw bytes
+    ///
+    /// Same as the other reads, with the exact bytes fetched from the tangle kept in
+    /// `ChannelMessage::raw`, e.g. to relay or archive the original messages. The message
+    /// is unwrapped from the bytes fetched, only a keyload is received again, for whether
+    /// it grants access
+    ///
+    pub fn read_with_raw(&mut self, tag: String) -> Result<Vec<ChannelMessage>> {
+        self.ensure_connected()?;
+        let link = self.link(&tag)?;
+
+        let raw_msgs = self
+            .transport
+            .borrow_mut()
+            .recv_messages(&link)
+            .map_err(ChannelError::from)?;
+        let mut msg = match raw_msgs.first() {
+            Some(raw) => self.unwrap_fetched(&link, raw)?,
+            None => return Ok(Vec::new()),
+        };
+        msg.source = self.source_of(&msg.tag);
+        msg.attached_at = self.transport.borrow().attached_at(&msg.tag);
+        if msg.kind == MessageKind::Keyload {
+            let authorized = self.keyload_access(&link);
+            self.record_keyload(msg.tag.clone(), authorized);
+        }
+
+        Ok(raw_msgs
+            .iter()
+            .map(|raw| ChannelMessage {
+                raw: Some(raw.binary.body.bytes.clone()),
+                ..msg.clone()
+            })
+            .collect())
+    }
+
+    ///
+    /// Cross-check an audit report exported by the author against the tangle
+    ///
+    /// Every message of the report must be found, with the recorded kind, and the packets
+    /// with the recorded payload digest. The packets are unwrapped again, so the masked
+    /// ones can only be checked by a subscriber granted access to them
+    ///
+    pub fn verify_audit_report(&mut self, report: &AuditReport) -> Result<AuditVerification> {
+        self.ensure_connected()?;
+        if report.channel_address != self.channel_address {
+            return Err(ChannelError::State(format!(
+                "Audit report of channel {}, not {}",
+                report.channel_address, self.channel_address
+            )));
+        }
+
+        let mut verification = AuditVerification::default();
+        for record in report.messages.iter() {
+            let link = self.link(&record.tag)?;
+            let found = self
+                .transport
+                .borrow_mut()
+                .recv_messages(&link)
+                .map_err(ChannelError::from)?;
+            if found.is_empty() {
+                verification.missing.push(record.tag.clone());
+                continue;
+            }
+            if record.kind != MessageKind::SignedPacket && record.kind != MessageKind::TaggedPacket
+            {
+                let header = found[0]
+                    .binary
+                    .parse_header()
+                    .map_err(|e| self.read_error(&link, e))?;
+                let content_type = match record.kind {
+                    MessageKind::Announce => message::ANNOUNCE,
+                    MessageKind::Keyload => message::KEYLOAD,
+                    _ => message::SUBSCRIBE,
+                };
+                if header.check_content_type(content_type) {
+                    verification.verified.push(record.tag.clone());
+                } else {
+                    verification.mismatched.push(record.tag.clone());
+                }
+                continue;
+            }
+
+            let unwrapped = match self.subscriber.receive_msg(&link, None) {
+                Ok(unwrapped) => unwrapped,
+                Err(e) => match self.packet_error(&link, e) {
+                    ChannelError::MessageNotFound(_) => {
+                        verification.missing.push(record.tag.clone());
+                        continue;
+                    }
+                    ChannelError::AccessDenied(_) | ChannelError::Protocol(_) => {
+                        verification.unreadable.push(record.tag.clone());
+                        continue;
+                    }
+                    e => return Err(e),
+                },
+            };
+            let (kind, digest) = match &unwrapped.body {
+                MessageContent::SignedPacket {
+                    public_payload,
+                    masked_payload,
+                    ..
+                } => (
+                    MessageKind::SignedPacket,
+                    payload_digest(&public_payload.0, &masked_payload.0),
+                ),
+                MessageContent::TaggedPacket {
+                    public_payload,
+                    masked_payload,
+                } => (
+                    MessageKind::TaggedPacket,
+                    payload_digest(&public_payload.0, &masked_payload.0),
+                ),
+                _ => (MessageKind::Other, String::new()),
+            };
+            if kind == record.kind && digest == record.digest {
+                verification.verified.push(record.tag.clone());
+            } else {
+                verification.mismatched.push(record.tag.clone());
+            }
+        }
+        Ok(verification)
+    }
+
+    ///
+    /// Read a file
+    ///
+    /// Reads the manifest written by the author and reassembles the file from its chunks
+    ///
+    pub fn read_file(&mut self, manifest_tag: String, out: &mut impl Write) -> Result<()> {
+        let manifest: FileManifest = match self.read_signed(manifest_tag.clone())?.pop() {
+            Some((Some(public), _)) => serde_json::from_str(&public)?,
+            _ => return Err(ChannelError::MessageNotFound(manifest_tag)),
+        };
+
+        if manifest.chunk_tags.len() != manifest.chunk_count {
+            return Err(ChannelError::PayloadEncoding(format!(
+                "File manifest {} lists {} chunk tags but expects {} chunks",
+                manifest_tag,
+                manifest.chunk_tags.len(),
+                manifest.chunk_count
+            )));
+        }
+
+        let mut written: u64 = 0;
+        for (index, chunk_tag) in manifest.chunk_tags.iter().enumerate() {
+            let chunk: FileChunk = match self.read_signed(chunk_tag.clone())?.pop() {
+                Some((Some(public), _)) => serde_json::from_str(&public)?,
+                _ => return Err(ChannelError::MessageNotFound(chunk_tag.clone())),
+            };
+            if chunk.index != index {
+                return Err(ChannelError::PayloadEncoding(format!(
+                    "Missing chunk {} of {}: found chunk {} at {}",
+                    index, manifest.chunk_count, chunk.index, chunk_tag
+                )));
+            }
+            let data = decode_config(&chunk.data, URL_SAFE_NO_PAD)?;
+            out.write_all(&data)?;
+            written += data.len() as u64;
+        }
+
+        if written != manifest.total_size {
+            return Err(ChannelError::PayloadEncoding(format!(
+                "File size mismatch: expected {} bytes, got {}",
+                manifest.total_size, written
+            )));
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Acknowledge a message
+    ///
+    /// Publishes a signed packet linked to the message, referencing its tag and, for
+    /// the messages written with `write_signed_with_ack`, the acknowledgement request.
+    /// Requires the subscriber to have write access to the channel.
+    ///
+    pub fn acknowledge(&mut self, tag: String) -> Result<String> {
+        self.ensure_connected()?;
+        if self.read_only {
+            return Err(ChannelError::State(
+                "Read-only subscriber can't publish acknowledgements".to_string(),
+            ));
+        }
+
+        let link = self.link(&tag)?;
+        // A request forgotten since the read is read back from the message
+        let request = match self.ack_requests.remove(&tag) {
+            Some(request) => {
+                self.ack_order.retain(|pending| pending != &tag);
+                Some(request)
+            }
+            None => {
+                let unwrapped = self
+                    .subscriber
+                    .receive_msg(&link, None)
+                    .map_err(|e| self.packet_error(&link, e))?;
+                ChannelMessage::from_unwrapped(&unwrapped).ack_request
+            }
+        };
+        let payload = PayloadBuilder::new()
+            .public(&Ack {
+                ack: tag,
+                request: request,
+            })?
+            .build();
+
+        let (ack_link, _) = self.subscriber.send_signed_packet(
+            &link,
+            &payload.public_data(),
+            &payload.masked_data(),
+        )?;
+
+        Ok(ack_link.msgid.to_string())
+    }
+
+    ///
+    /// Remember the acknowledgement request of a delivered message, forgetting the
+    /// oldest beyond `ACK_REQUEST_CAPACITY`
+    ///
+    fn remember_ack_request(&mut self, tag: &str, request: &str) {
+        if self.ack_requests.contains_key(tag) {
+            return;
+        }
+        if self.ack_order.len() == ACK_REQUEST_CAPACITY {
+            if let Some(oldest) = self.ack_order.pop_front() {
+                self.ack_requests.remove(&oldest);
+            }
+        }
+        self.ack_order.push_back(tag.to_string());
+        self.ack_requests
+            .insert(tag.to_string(), request.to_string());
+    }
+
+    ///
+    /// Update keyload
+    ///
+    pub fn update_keyload(&mut self, keyload_tag: String) -> Result<()> {
+        self.ensure_connected()?;
+        let keyload_link = self.link(&keyload_tag)?;
+
+        let authorized = self
+            .subscriber
+            .receive_keyload(&keyload_link)
+            .map_err(|e| self.read_error(&keyload_link, e))?;
+        self.record_keyload(keyload_tag, authorized);
+        self.last_keyload_link = Some(keyload_link);
+        self.keyload_ahead = true;
+
+        Ok(())
+    }
+
+    ///
+    /// Process a keyload and read the messages published after it
+    ///
+    /// Meant for the subscribers granted access late: the keyload moves the walk
+    /// forward, the messages published before it are not walked over. The walk is shared
+    /// with `poll_new`, the messages already fetched are returned first. Fails with
+    /// `AccessDenied` if the keyload doesn't grant access to this subscriber, the walk
+    /// is left untouched then
+    ///
+    pub fn read_from_keyload(&mut self, keyload_tag: String) -> Result<Vec<ChannelMessage>> {
+        self.ensure_connected()?;
+        let keyload_link = self.link(&keyload_tag)?;
+
+        let authorized = self
+            .subscriber
+            .receive_keyload(&keyload_link)
+            .map_err(|e| self.read_error(&keyload_link, e))?;
+        self.record_keyload(keyload_tag.clone(), authorized);
+        if !authorized {
+            return Err(ChannelError::AccessDenied(keyload_tag));
+        }
+        self.last_keyload_link = Some(keyload_link);
+        self.keyload_ahead = true;
+
+        self.poll_new()
+    }
+
+    ///
+    /// Next message in the channel, waiting up to `timeout` for it to appear
+    ///
+    /// Returns `None` if nothing arrives in time, the end of channel was reached or the
+    /// wait was cancelled with the `cancel_token`
+    ///
+    pub fn next_message_blocking(&mut self, timeout: Duration) -> Result<Option<ChannelMessage>> {
+        self.ensure_connected()?;
+
+        let clock = self.config.clock.clone();
+        let deadline = clock.now() + timeout;
+        loop {
+            if let Some(msg) = self.pending.pop_front() {
+                self.deliver(&msg);
+                if self.is_end_of_channel(&msg) {
+                    self.is_closed = true;
+                }
+                return Ok(Some(msg));
+            }
+            if self.is_closed || self.cancel.is_cancelled() {
+                return Ok(None);
+            }
+
+            self.fetch_new();
+
+            if self.pending.is_empty() {
+                let now = clock.now();
+                if now >= deadline || !clock.can_sleep() {
+                    return Ok(None);
+                }
+                clock.sleep(POLL_INTERVAL.min(deadline - now));
+            }
+        }
+    }
+
+    ///
+    /// Hand the next message of the walk to `f`, `None` at the end of the walk
+    ///
+    /// The message is only consumed when `f` succeeds, the next read returns it again
+    /// otherwise
+    ///
+    pub(crate) fn consume_next<F, R>(&mut self, f: F) -> Result<Option<R>>
+    where
+        F: FnOnce(&ChannelMessage) -> Result<R>,
+    {
+        self.ensure_connected()?;
+        if self.is_closed {
+            return Ok(None);
+        }
+
+        while self.pending.is_empty() && self.fetch_new() > 0 {}
+        let result = match self.pending.front() {
+            Some(msg) => f(msg)?,
+            None => return Ok(None),
+        };
+        if let Some(msg) = self.pending.pop_front() {
+            self.deliver(&msg);
+            if self.is_end_of_channel(&msg) {
+                self.is_closed = true;
+            }
+        }
+        Ok(Some(result))
+    }
+
+    ///
+    /// New messages since the previous call
+    ///
+    /// Walks the channel from the last message returned and returns the messages found,
+    /// in order. Each message is returned once: the walk is shared with
+    /// `get_next_message`, `next_message_blocking` and the exports, so the messages they
+    /// returned are skipped. Nothing is returned after the end of channel marker
+    ///
+    pub fn poll_new(&mut self) -> Result<Vec<ChannelMessage>> {
+        self.ensure_connected()?;
+        if self.is_closed {
+            return Ok(Vec::new());
+        }
+
+        let mut msgs = Vec::new();
+        for msg in self.fetch_all() {
+            let end_of_channel = self.is_end_of_channel(&msg);
+            msgs.push(msg);
+            if end_of_channel {
+                self.is_closed = true;
+                break;
+            }
+        }
+        Ok(msgs)
+    }
+
+    ///
+    /// New messages since the previous call attached between `from` and `to`, both
+    /// included
+    ///
+    /// Same walk as `poll_new`: every message is processed, the keyloads outside the
+    /// window included. The messages outside the window are consumed by the walk, they
+    /// are neither unwrapped nor marked read, and no later call returns them; an end of
+    /// channel marker outside the window doesn't close the walk. The attachment time is
+    /// the one reported by the node, `missing` decides for the messages without one.
+    /// Fails with `ChannelError::State` if `from` is after `to`
+    ///
+    pub fn fetch_window(
+        &mut self,
+        from: SystemTime,
+        to: SystemTime,
+        missing: MissingTimestampPolicy,
+    ) -> Result<Vec<ChannelMessage>> {
+        if from > to {
+            return Err(ChannelError::State(format!(
+                "Empty fetch window, {:?} is after {:?}",
+                from, to
+            )));
+        }
+        self.ensure_connected()?;
+        if self.is_closed {
+            return Ok(Vec::new());
+        }
+
+        let in_window = |attached_at: Option<SystemTime>| match attached_at {
+            Some(attached_at) => from <= attached_at && attached_at <= to,
+            None => missing == MissingTimestampPolicy::Include,
+        };
+        while self.fetch_new_within(&in_window) > 0 {}
+        let mut msgs = Vec::new();
+        while let Some(msg) = self.pending.pop_front() {
+            if !in_window(msg.attached_at) {
+                log::debug!(target: LOG_TARGET, "Skipping message {} outside the window", msg.tag);
+                continue;
+            }
+            self.deliver(&msg);
+            let end_of_channel = self.is_end_of_channel(&msg);
+            msgs.push(msg);
+            if end_of_channel {
+                self.is_closed = true;
+                break;
+            }
+        }
+        Ok(msgs)
+    }
+
+    ///
+    /// New messages since the previous call, decoded by content type
+    ///
+    /// Same walk as `poll_new`. The messages without a decoder for their content type are
+    /// returned `Unknown` with their raw public data, a decoder failing doesn't fail the
+    /// others
+    ///
+    pub fn fetch_all_decoded<T>(&mut self, decoders: &Decoders<T>) -> Result<Vec<Decoded<T>>> {
+        Ok(self
+            .poll_new()?
+            .iter()
+            .map(|msg| decoders.decode(msg))
+            .collect())
+    }
+
+    ///
+    /// Most recent packet of the channel
+    ///
+    /// Walks to the end of the channel one batch at a time, only the newest signed or
+    /// tagged packet is kept, the end of channel marker aside. The walk is shared with
+    /// `poll_new`: the messages passed over are not returned again. Without new
+    /// messages, returns the latest packet this subscriber walked over, `None` if the
+    /// channel has no packet yet
+    ///
+    pub fn read_latest(&mut self) -> Result<Option<ChannelMessage>> {
+        self.ensure_connected()?;
+
+        loop {
+            while let Some(msg) = self.pending.pop_front() {
+                self.deliver(&msg);
+                if self.is_end_of_channel(&msg) {
+                    self.is_closed = true;
+                }
+            }
+            if self.is_closed || self.fetch_new() == 0 {
+                break;
+            }
+        }
+        Ok(self.latest.clone())
+    }
+
+    ///
+    /// Export the channel history as NDJSON
+    ///
+    /// Walks the channel and writes one JSON object per message with its tag, type,
+    /// the attachment time reported by the node in seconds since the epoch, `null` if
+    /// the node didn't report it, and the unwrapped public and masked data.
+    /// Returns the number of lines written.
+    ///
+    pub fn export_ndjson(&mut self, out: &mut impl Write) -> Result<usize> {
+        self.ensure_connected()?;
+
+        let mut count = 0;
+        for msg in self.fetch_all() {
+            let timestamp = msg
+                .attached_at
+                .and_then(|attached_at| attached_at.duration_since(UNIX_EPOCH).ok())
+                .map(|since_epoch| since_epoch.as_secs());
+            let line = serde_json::json!({
+                "tag": msg.tag,
+                "type": msg.kind.as_str(),
+                "timestamp": timestamp,
+                "public": msg.public,
+                "masked": msg.masked,
+            });
+            serde_json::to_writer(&mut *out, &line)?;
+            out.write_all(b"\n")?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    ///
+    /// Export the provenance of the new messages, one record per message in channel
+    /// order
+    ///
+    /// Walks the channel as `fetch_all` does, the messages exported are not returned by
+    /// the other read methods
+    ///
+    pub fn export_provenance(&mut self) -> Result<Vec<ProvenanceRecord>> {
+        self.ensure_connected()?;
+
+        let exported_at = self.config.clock.system_time();
+        Ok(self
+            .fetch_all()
+            .into_iter()
+            .map(|msg| ProvenanceRecord::new(msg, exported_at))
+            .collect())
+    }
+
+    pub(crate) fn fetch_all(&mut self) -> Vec<ChannelMessage> {
+        while self.fetch_new() > 0 {}
+        let msgs: Vec<ChannelMessage> = self.pending.drain(..).collect();
+        for msg in msgs.iter() {
+            self.deliver(msg);
+        }
+        msgs
+    }
+
+    ///
+    /// Fetch the next messages into the pending queue, returns how many were found
+    ///
+    fn fetch_new(&mut self) -> usize {
+        self.fetch_new_within(|_| true)
+    }
+
+    ///
+    /// Fetch the next messages into the pending queue, passing over the ones whose
+    /// attachment time is outside the window, returns how many were found
+    ///
+    fn fetch_new_within<F>(&mut self, in_window: F) -> usize
+    where
+        F: Fn(Option<SystemTime>) -> bool,
+    {
+        let fetched = self.fetch_next_msgs();
+        let count = fetched.len();
+        let mut keyloads = Vec::new();
+        for msg in fetched {
+            let link = msg.link.clone();
+            if let MessageContent::Keyload = msg.body {
+                keyloads.push(link.clone());
+            }
+            let attached_at = self.transport.borrow().attached_at(&link.msgid.to_string());
+            if !in_window(attached_at) {
+                log::debug!(target: LOG_TARGET, "Skipping message {} outside the window", link.msgid);
+                continue;
+            }
+            let own = self.skip_own && self.is_own(&msg);
+            let mut msg = ChannelMessage::from_unwrapped(&msg);
+            msg.source = self.source_of(&msg.tag);
+            msg.attached_at = attached_at;
+            msg.expired = msg.is_expired(self.config.clock.system_time());
+            if own {
+                log::debug!(target: LOG_TARGET, "Skipping own message {}", msg.tag);
+                self.mark_read(&msg.tag);
+                continue;
+            }
+            if msg.expired && self.drop_expired {
+                log::debug!(target: LOG_TARGET, "Dropping expired message {}", msg.tag);
+                self.deliver(&msg);
+                continue;
+            }
+            self.pending.push_back(msg);
+        }
+        // In walk order, the session key of the last keyload is the one kept
+        for link in keyloads {
+            let authorized = self.keyload_access(&link);
+            self.record_keyload(link.msgid.to_string(), authorized);
+        }
+        count
+    }
+
+    ///
+    /// Whether a keyload already processed grants access to this subscriber
+    ///
+    /// Neither the walk nor `receive_msg` report it, the keyload is received again for
+    /// its result. A keyload that can't be fetched again is recorded as not granting
+    /// access, a later read of it sets it right
+    ///
+    fn keyload_access(&mut self, link: &Address) -> bool {
+        match self.subscriber.receive_keyload(link) {
+            Ok(authorized) => authorized,
+            Err(e) => {
+                log::warn!(
+                    target: LOG_TARGET,
+                    "Could not read keyload {} again: {}",
+                    link.msgid,
+                    e
+                );
+                false
+            }
+        }
+    }
+
+    ///
+    /// Fetch the next messages of the walk
+    ///
+    fn fetch_next_msgs(&mut self) -> Vec<UnwrappedMessage> {
+        let span = OpSpan::new("fetch", &self.channel_address, 0);
+        // Only the explicit lookups fall back to the archive, not the polls of the walk
+        self.transport.borrow_mut().set_archive_lookups(false);
+        let mut fetched = span.in_scope(|| self.subscriber.fetch_next_msgs());
+        self.transport.borrow_mut().set_archive_lookups(true);
+        if let Some(last) = fetched.last() {
+            span.record_tag(&last.link.msgid.to_string());
+        }
+        span.record_payload_size(fetched.iter().map(unwrapped_size).sum());
+        if !fetched.is_empty() {
+            // The walk moved on from the last keyload processed
+            self.keyload_ahead = false;
+        }
+        let mut marker = None;
+        let marker_tag = self.marker_tag.clone();
+        fetched.retain(|msg| {
+            if marker_tag != Some(msg.link.msgid.to_string()) {
+                return true;
+            }
+            match ChannelMessage::from_unwrapped(msg).protocol_version() {
+                Some(version) => {
+                    marker = Some(version);
+                    false
+                }
+                None => true,
+            }
+        });
+        if self.protocol_version.is_none() {
+            self.protocol_version = marker;
+        }
+        if let Some(dedup) = &mut self.dedup {
+            fetched.retain(|msg| {
+                let msgid = msg.link.msgid.to_string();
+                let first_seen = dedup.first_seen(&msgid);
+                if !first_seen {
+                    log::debug!(target: LOG_TARGET, "Dropping repeated message {}", msgid);
+                }
+                first_seen
+            });
+        }
+        fetched
+    }
+
+    ///
+    /// Whether the message is signed with the key of this subscriber
+    ///
+    fn is_own(&self, msg: &UnwrappedMessage) -> bool {
+        match &msg.body {
+            MessageContent::SignedPacket { pk, .. } => pk == self.subscriber.get_pk(),
+            _ => false,
+        }
+    }
+
+    fn source_of(&self, tag: &str) -> MessageSource {
+        if self.transport.borrow().served_by_archive(tag) {
+            MessageSource::Archive
+        } else {
+            MessageSource::Primary
+        }
+    }
+
+    fn record_keyload(&mut self, tag: String, authorized: bool) {
+        match self.keyloads.iter_mut().find(|keyload| keyload.tag == tag) {
+            Some(keyload) => keyload.authorized |= authorized,
+            None => self.keyloads.push(KeyloadInfo {
+                tag: tag,
+                authorized: authorized,
+            }),
+        }
+    }
+
+    ///
+    /// Keyloads seen so far in the channel, in order
+    ///
+    /// Walks the channel for new messages first, they remain available to the other
+    /// read methods
+    ///
+    pub fn keyload_history(&mut self) -> Result<Vec<KeyloadInfo>> {
+        self.ensure_connected()?;
+
+        while self.fetch_new() > 0 {}
+        Ok(self.keyloads.clone())
+    }
+
+    ///
+    /// Channel address, announcement tag and keyloads seen so far
+    ///
+    pub fn info(&self) -> ChannelInfo {
+        ChannelInfo {
+            channel_address: self.channel_address.clone(),
+            announcement_tag: self.announcement_link.msgid.to_string(),
+            keyloads: self.keyloads.clone(),
+        }
+    }
+
+    ///
+    /// Estimate of the messages left to read
+    ///
+    /// Walks the channel from the current position, the messages found remain available
+    /// to the other read methods. The walk is skipped when the last one is more recent
+    /// than `BACKLOG_STALENESS`, so messages published meanwhile are not counted yet
+    ///
+    pub fn backlog_estimate(&mut self) -> Result<usize> {
+        self.ensure_connected()?;
+
+        let now = self.config.clock.now();
+        let fresh = self.backlog_checked_at.map_or(false, |checked_at| {
+            now.duration_since(checked_at) < BACKLOG_STALENESS
+        });
+        if !fresh {
+            while self.fetch_new() > 0 {}
+            self.backlog_checked_at = Some(self.config.clock.now());
+        }
+        Ok(self.pending.len())
+    }
+
+    ///
+    /// Opaque token capturing the read position
+    ///
+    /// Lighter than a full state export, meant to hand the read progress over to another
+    /// subscriber instance, e.g. after a worker restart
+    ///
+    pub fn cursor(&self) -> String {
+        Cursor {
+            version: CURSOR_VERSION,
+            channel_address: self.channel_address.clone(),
+            position: self.read_position,
+            last_tag: self.last_read_tag.clone(),
+            sequence_guard: self.sequence_guard.clone(),
+        }
+        .encode()
+    }
+
+    ///
+    /// Move the read position to the one captured by a cursor token
+    ///
+    /// The channel is walked up to the position, the messages before it are not returned
+    /// by the read methods. The position can't be moved backwards
+    ///
+    pub fn resume_from_cursor(&mut self, token: &str) -> Result<()> {
+        self.ensure_connected()?;
+
+        let cursor = Cursor::decode(token)?;
+        if cursor.channel_address != self.channel_address {
+            return Err(ChannelError::State(format!(
+                "Cursor belongs to channel {}, not {}",
+                cursor.channel_address, self.channel_address
+            )));
+        }
+        if cursor.position < self.read_position {
+            return Err(ChannelError::State(format!(
+                "Cursor position {} is behind the current position {}",
+                cursor.position, self.read_position
+            )));
+        }
+
+        while self.read_position < cursor.position {
+            if self.pending.is_empty() && self.fetch_new() == 0 {
+                return Err(ChannelError::State(format!(
+                    "Cursor position {} not reached, the channel ends at {}",
+                    cursor.position, self.read_position
+                )));
+            }
+            if let Some(msg) = self.pending.pop_front() {
+                self.mark_read(&msg.tag);
+                if self.is_end_of_channel(&msg) {
+                    self.is_closed = true;
+                }
+            }
+        }
+
+        if self.last_read_tag != cursor.last_tag {
+            return Err(ChannelError::State(format!(
+                "Cursor expects {:?} at position {}, found {:?}",
+                cursor.last_tag, cursor.position, self.last_read_tag
+            )));
+        }
+        if cursor.sequence_guard.is_some() {
+            self.sequence_guard = cursor.sequence_guard;
+        }
+        Ok(())
+    }
+
+    fn mark_read(&mut self, tag: &str) {
+        self.read_position += 1;
+        self.last_read_tag = Some(tag.to_string());
+        if self.read_tags.len() == READ_HISTORY_CAPACITY {
+            self.read_tags.pop_front();
+        }
+        self.read_tags.push_back(tag.to_string());
+    }
+
+    ///
+    /// Mark a message read and check its sequence number
+    ///
+    fn deliver(&mut self, msg: &ChannelMessage) {
+        self.mark_read(&msg.tag);
+        let packet = msg.kind == MessageKind::SignedPacket || msg.kind == MessageKind::TaggedPacket;
+        if packet && !self.is_end_of_channel(msg) && !(msg.expired && self.drop_expired) {
+            self.latest = Some(msg.clone());
+        }
+        if let Some(request) = &msg.ack_request {
+            self.remember_ack_request(&msg.tag, request);
+        }
+        let sender = self.sequence_sender(msg);
+        let event = match (&mut self.sequence_guard, &msg.seq, sender) {
+            (Some(guard), Some(seq), Some(sender)) => guard.check_from(&sender, seq, &msg.tag),
+            _ => None,
+        };
+        if let Some(event) = event {
+            log::warn!(target: LOG_TARGET, "Sequence irregularity: {:?}", event);
+            if let Some(handler) = &mut self.sequence_handler {
+                handler(&event);
+            }
+            self.sequence_events.push(event);
+        }
+    }
+
+    ///
+    /// Sender the sequence number of a message is checked under
+    ///
+    /// The signed packets are tracked under their signer, the channel address standing
+    /// for the author, whatever sender the envelope claims. The tagged packets carry no
+    /// signature, any member of the keyload may write one: they are tracked under the
+    /// sender of the envelope
+    ///
+    fn sequence_sender(&self, msg: &ChannelMessage) -> Option<String> {
+        let seq = msg.seq.as_ref()?;
+        match (&msg.signer, &msg.kind) {
+            (Some(signer), _) if self.author_key().as_ref() == Some(signer) => {
+                Some(self.channel_address.clone())
+            }
+            (Some(signer), _) => Some(signer.clone()),
+            (None, MessageKind::TaggedPacket) => Some(seq.sender.clone()),
+            (None, _) => None,
+        }
+    }
+
+    ///
+    /// Whether the message is the end of channel marker signed by the author
+    ///
+    fn is_end_of_channel(&self, msg: &ChannelMessage) -> bool {
+        msg.is_end_of_channel(self.author_key().as_deref())
+    }
+
+    ///
+    /// Public key of the author as lowercase hex, once the announcement is received
+    ///
+    pub(crate) fn author_key(&self) -> Option<String> {
+        self.subscriber
+            .author_public_key()
+            .map(|pk| pk.as_bytes().iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    ///
+    /// Read a message and all the messages linked beneath it
+    ///
+    /// Walks the channel for new messages first, they remain available to the other
+    /// read methods. The messages are returned in channel order, the anchor first, and
+    /// include the messages of every branch anchored under it, e.g. all the packets
+    /// published after a keyload, among the pending messages and the last
+    /// `READ_HISTORY_CAPACITY` read. The links are the ones recorded when the walk
+    /// fetched the messages, only the messages of the subtree are fetched again
+    ///
+    pub fn read_subtree(&mut self, anchor_tag: String) -> Result<Vec<ChannelMessage>> {
+        self.ensure_connected()?;
+        let anchor = self.link(&anchor_tag)?;
+
+        while self.fetch_new() > 0 {}
+
+        let mut msgs = vec![self.read_message(&anchor)?];
+        let mut subtree: HashSet<String> = HashSet::new();
+        subtree.insert(anchor_tag);
+
+        let tags: Vec<String> = self
+            .read_tags
+            .iter()
+            .cloned()
+            .chain(self.pending.iter().map(|msg| msg.tag.clone()))
+            .collect();
+        for tag in tags {
+            if subtree.contains(&tag) {
+                continue;
+            }
+            let link = self.link(&tag)?;
+            if subtree.contains(&self.linked_tag(&link)?) {
+                msgs.push(self.read_message(&link)?);
+                subtree.insert(tag);
+            }
+        }
+        Ok(msgs)
+    }
+
+    ///
+    /// Unwrap a single message
+    ///
+    fn read_message(&mut self, link: &Address) -> Result<ChannelMessage> {
+        let mut msg = self.unwrap_message(link)?;
+        msg.source = self.source_of(&msg.tag);
+        msg.attached_at = self.transport.borrow().attached_at(&msg.tag);
+        msg.expired = msg.is_expired(self.config.clock.system_time());
+        Ok(msg)
+    }
+
+    ///
+    /// Unwrap a message, or read the public data of a signed packet without access to it
+    /// unless `strict_access` is set
+    ///
+    fn unwrap_message(&mut self, link: &Address) -> Result<ChannelMessage> {
+        let unwrapped = self.subscriber.receive_msg(link, None);
+        self.unwrapped_message(link, unwrapped, None)
+    }
+
+    ///
+    /// Unwrap a message already fetched, see `unwrap_message`
+    ///
+    fn unwrap_fetched(&mut self, link: &Address, raw: &Message) -> Result<ChannelMessage> {
+        let unwrapped = self.subscriber.handle_message(raw.clone(), None);
+        self.unwrapped_message(link, unwrapped, Some(raw))
+    }
+
+    ///
+    /// Message unwrapped by the subscriber, its public data only when the subscriber has
+    /// no access to it, read from `raw` when given
+    ///
+    fn unwrapped_message(
+        &self,
+        link: &Address,
+        unwrapped: anyhow::Result<UnwrappedMessage>,
+        raw: Option<&Message>,
+    ) -> Result<ChannelMessage> {
+        match unwrapped {
+            Ok(unwrapped) => Ok(ChannelMessage::from_unwrapped(&unwrapped)),
+            Err(e) => match self.packet_error(link, e) {
+                ChannelError::AccessDenied(_) if !self.strict_access => {
+                    let public = match raw {
+                        Some(raw) => self.public_only(link, raw)?,
+                        None => self.receive_public_only(link)?,
+                    };
+                    Ok(ChannelMessage::public_only(
+                        link.msgid.to_string(),
+                        &public.0,
+                    ))
+                }
+                e => Err(e),
+            },
+        }
+    }
+
+    ///
+    /// Public payload of a signed packet, read from the raw message without unwrapping it
+    ///
+    /// The keyload the packet is linked to wasn't processed, so the payload is read as
+    /// absorbed after the signer key: the masked payload and the signature are left
+    /// unchecked. Any other message fails with `ChannelError::AccessDenied`
+    ///
+    fn receive_public_only(&self, link: &Address) -> Result<Bytes> {
+        let raw_msgs = self
+            .transport
+            .borrow_mut()
+            .recv_messages(link)
+            .map_err(ChannelError::from)?;
+        let raw = raw_msgs
+            .first()
+            .ok_or_else(|| ChannelError::MessageNotFound(link.msgid.to_string()))?;
+        self.public_only(link, raw)
+    }
+
+    ///
+    /// Public payload of a signed packet already fetched, see `receive_public_only`
+    ///
+    fn public_only(&self, link: &Address, raw: &Message) -> Result<Bytes> {
+        let preparsed = raw
+            .binary
+            .parse_header()
+            .map_err(|e| self.read_error(link, e))?;
+        if !preparsed.check_content_type(message::SIGNED_PACKET) {
+            return Err(ChannelError::AccessDenied(link.msgid.to_string()));
+        }
+
+        let mut ctx = preparsed.ctx;
+        let mut signer = NBytes::<U32>::default();
+        let mut public = Bytes::default();
+        ctx.absorb(&mut signer)
+            .and_then(|ctx| ctx.absorb(&mut public))
+            .map_err(|e| self.read_error(link, e))?;
+        log::debug!(
+            target: LOG_TARGET,
+            "Read the public data of {} without access to the packet",
+            link.msgid
+        );
+        Ok(public)
+    }
+
+    ///
+    /// Tag of the message a message is linked to, as recorded when the message was
+    /// fetched, fetching it again otherwise
+    ///
+    fn linked_tag(&self, link: &Address) -> Result<String> {
+        let msgid = link.msgid.to_string();
+        if let Some(linked) = self.transport.borrow().linked_msgid(&msgid) {
+            return Ok(linked);
+        }
+        let raw_msgs = self
+            .transport
+            .borrow_mut()
+            .recv_messages(link)
+            .map_err(ChannelError::from)?;
+        let raw = raw_msgs
+            .first()
+            .ok_or_else(|| ChannelError::MessageNotFound(link.msgid.to_string()))?;
+        let preparsed = raw.binary.parse_header()?;
+        Ok(preparsed.header.link.msgid.to_string())
+    }
+
+    ///
+    /// Skip the expired messages while walking the channel instead of flagging them
+    ///
+    pub fn set_drop_expired(&mut self, drop_expired: bool) {
+        self.drop_expired = drop_expired;
+    }
+
+    ///
+    /// Skip the packets signed by this subscriber while walking the channel, e.g. its
+    /// acknowledgements, so that a subscriber writing to the channel doesn't read its
+    /// own output back
+    ///
+    /// Disabled by default. The skipped packets count in the read position, the tagged
+    /// packets carry no signature and are never skipped
+    ///
+    pub fn set_skip_own(&mut self, skip_own: bool) {
+        self.skip_own = skip_own;
+    }
+
+    ///
+    /// Check the sequence numbers of the delivered messages
+    ///
+    /// Disabled by default, the channel must be written with sequence numbers. The
+    /// replayed and missing numbers are reported in `sequence_events` and to the
+    /// sequence handler, the messages are delivered all the same. The guard is part of
+    /// the cursor, disabling it drops its state
+    ///
+    pub fn set_sequence_guard(&mut self, enabled: bool) {
+        match (enabled, &self.sequence_guard) {
+            (true, None) => self.sequence_guard = Some(SequenceGuard::new()),
+            (false, _) => self.sequence_guard = None,
+            _ => (),
+        }
+    }
+
+    ///
+    /// Sequence guard, if enabled
+    ///
+    pub fn sequence_guard(&self) -> Option<&SequenceGuard> {
+        self.sequence_guard.as_ref()
+    }
+
+    ///
+    /// Duplicates and gaps found so far by the sequence guard
+    ///
+    pub fn sequence_events(&self) -> &[SequenceEvent] {
+        &self.sequence_events
+    }
+
+    ///
+    /// Call `handler` for each duplicate or gap found by the sequence guard
+    ///
+    pub fn on_sequence_event<F>(&mut self, handler: F)
+    where
+        F: FnMut(&SequenceEvent) + 'static,
+    {
+        self.sequence_handler = Some(Box::new(handler));
+    }
+
+    ///
+    /// Drop the messages fetched again by the message walk, enabled by default
+    ///
+    /// The last `dedup::DEFAULT_DEDUP_CAPACITY` message ids of the session are remembered, a
+    /// message is never handed out twice within them. Disabling it forgets them
+    ///
+    pub fn set_deduplicate(&mut self, enabled: bool) {
+        match (enabled, &self.dedup) {
+            (true, None) => self.dedup = Some(Deduplicator::new()),
+            (false, _) => self.dedup = None,
+            _ => (),
+        }
+    }
+
+    ///
+    /// Number of repeated messages dropped by the deduplication
+    ///
+    pub fn duplicates_dropped(&self) -> usize {
+        self.dedup.as_ref().map_or(0, Deduplicator::dropped)
+    }
+
+    ///
+    /// Whether the channel is multi-branching, as flagged by its announcement, `None`
+    /// before `connect`
+    ///
+    /// The walk follows the mode of the announcement. The channels opened by this crate
+    /// are single-branching, a multi-branching channel of another Streams application
+    /// is walked through its sequence messages
+    ///
+    pub fn is_multi_branching(&self) -> Option<bool> {
+        self.multi_branching
+    }
+
+    ///
+    /// Protocol version announced by the author, `None` for the channels opened by older
+    /// versions of the crate or before `connect`
+    ///
+    pub fn protocol_version(&self) -> Option<u32> {
+        self.protocol_version
+    }
+
+    ///
+    /// Fail `connect` with `ChannelError::UnsupportedProtocol` when the channel uses a
+    /// newer protocol version than this crate, instead of logging a warning
+    ///
+    pub fn set_strict_protocol(&mut self, strict: bool) {
+        self.strict_protocol = strict;
+    }
+
+    ///
+    /// Fail the reads of the signed packets no keyload granted access to with
+    /// `ChannelError::AccessDenied`
+    ///
+    /// Disabled by default, `read_signed` and `read` return their public data instead,
+    /// with no masked data and `AccessLevel::PublicOnly`
+    ///
+    pub fn set_strict_access(&mut self, strict: bool) {
+        self.strict_access = strict;
+    }
+
+    ///
+    /// Set the gap tolerance policy used by `get_next_message`
+    ///
+    pub fn set_gap_policy(&mut self, policy: GapPolicy) {
+        self.gap_policy = policy;
+    }
+
+    ///
+    /// Gaps skipped so far by the message walk
+    ///
+    pub fn gaps(&self) -> &[Gap] {
+        &self.gaps
+    }
+
+    ///
+    /// Whether the end of channel marker has been found by the message walk
+    ///
+    pub fn is_closed(&self) -> bool {
+        self.is_closed
+    }
+
+    ///
+    /// Generates the next message in the channels
+    ///
+    /// When the next message can't be found, the fetch is retried according to the gap
+    /// policy. The walk is optionally re-anchored on the last processed keyload when it
+    /// found nothing since the keyload, the end of the channel is not taken for a gap
+    ///
+    pub fn get_next_message(&mut self) -> Vec<Option<String>> {
+        let mut tags: Vec<Option<String>> = vec![];
+        if self.is_closed {
+            return tags;
+        }
+
+        while let Some(msg) = self.pending.pop_front() {
+            log::debug!(target: LOG_TARGET, "Message exists at {}", &msg.tag);
+            tags.push(Some(msg.tag.clone()));
+            self.deliver(&msg);
+            if self.is_end_of_channel(&msg) {
+                log::debug!(target: LOG_TARGET, "End of channel reached");
+                self.is_closed = true;
+                return tags;
+            }
+        }
+
+        let mut attempts = 0;
+
+        loop {
+            let msgs = self.fetch_next_msgs();
+
+            if msgs.is_empty() {
+                attempts += 1;
+                if attempts < self.gap_policy.max_attempts && self.config.clock.can_sleep() {
+                    self.config.clock.sleep(self.gap_policy.retry_delay);
+                    continue;
+                }
+
+                if self.gap_policy.skip_to_keyload && self.keyload_ahead {
+                    if let Some(keyload_link) = self.last_keyload_link.clone() {
+                        self.keyload_ahead = false;
+                        attempts = 0;
+                        let after_tag = tags.last().cloned().unwrap_or(None);
+                        let _ = self.subscriber.receive_keyload(&keyload_link);
+                        let resumed = self.fetch_next_msgs();
+                        if let Some(first) = resumed.first() {
+                            log::warn!(
+                                target: LOG_TARGET,
+                                "Gap after {:?}, resuming at {}",
+                                after_tag, &first.link.msgid
+                            );
+                            self.gaps.push(Gap {
+                                after_tag: after_tag,
+                                resumed_at: first.link.msgid.to_string(),
+                            });
+                        }
+                        for msg in resumed {
+                            log::debug!(target: LOG_TARGET, "Message exists at {}", &msg.link.msgid);
+                            tags.push(Some(msg.link.msgid.to_string()));
+                            self.deliver(&ChannelMessage::from_unwrapped(&msg));
+                        }
+                        continue;
+                    }
+                }
+
+                log::debug!(target: LOG_TARGET, "No more messages in sequence");
+                break;
+            }
+
+            attempts = 0;
+            for msg in msgs {
+                log::debug!(target: LOG_TARGET, "Message exists at {}", &msg.link.msgid);
+                tags.push(Some(msg.link.msgid.to_string()));
+                let msg = ChannelMessage::from_unwrapped(&msg);
+                self.deliver(&msg);
+                if self.is_end_of_channel(&msg) {
+                    log::debug!(target: LOG_TARGET, "End of channel reached");
+                    self.is_closed = true;
+                    return tags;
+                }
+            }
+        }
+        tags
+    }
+}
+
+///
+/// Seed of a read-only subscriber, random: its key never signs, but a seed derived from
+/// the channel would let anyone knowing it act as the subscriber
+///
+fn read_only_seed() -> String {
+    random_seed::new()
+}
+
+fn unwrapped_size(msg: &UnwrappedMessage) -> usize {
+    match &msg.body {
+        MessageContent::SignedPacket {
+            public_payload,
+            masked_payload,
+            ..
+        }
+        | MessageContent::TaggedPacket {
+            public_payload,
+            masked_payload,
+        } => public_payload.0.len() + masked_payload.0.len(),
+        _ => 0,
+    }
+}
+
+///
+/// Public state and bookkeeping only, the seed and node credentials are redacted and
+/// the Streams state is left out
+///
+impl<T> fmt::Debug for Channel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel")
+            .field("node_url", &redact_credentials(&self.node_url))
+            .field("channel_address", &self.channel_address)
+            .field(
+                "announcement_tag",
+                &self.announcement_link.msgid.to_string(),
+            )
+            .field(
+                "subscription_tag",
+                &self.subscription_link.msgid.to_string(),
+            )
+            .field(
+                "last_keyload_tag",
+                &self
+                    .last_keyload_link
+                    .as_ref()
+                    .map(|link| link.msgid.to_string()),
+            )
+            .field("last_read_tag", &self.last_read_tag)
+            .field("read_only", &self.read_only)
+            .field("is_closed", &self.is_closed)
+            .field("is_connected", &self.is_connected)
+            .field("protocol_version", &self.protocol_version)
+            .field("multi_branching", &self.multi_branching)
+            .field("read_position", &self.read_position)
+            .field("pending", &self.pending.len())
+            .field("keyloads", &self.keyloads.len())
+            .field("gaps", &self.gaps.len())
+            .field("sequence_events", &self.sequence_events.len())
+            .field("transport_stats", self.transport.borrow().stats())
+            .field("shut_down", &self.shut_down)
+            .field("seed", &self.seed)
+            .finish()
+    }
+}
+
+///
+/// One-line summary, see `ChannelSummary`
+///
+impl<T> fmt::Display for Channel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.summary().fmt(f)
+    }
+}
+
+impl<T> Drop for Channel<T> {
+    fn drop(&mut self) {
+        if let (false, Some(attached)) = (self.shut_down, &self.store) {
+            log::warn!(
+                target: LOG_TARGET,
+                "Subscriber of channel {} dropped without shutdown, its read position was not persisted to {:?}",
+                self.channel_address,
+                attached.id
+            );
+        }
+    }
+}
+
+impl<T> ChannelReader for Channel<T>
+where
+    T: Transport<Address, Message>,
+    T::SendOptions: Copy + Default,
+    T::RecvOptions: Copy + Default,
+{
+    fn channel_address(&self) -> &str {
+        &self.channel_address
+    }
+
+    fn fetch_all(&mut self) -> Result<Vec<ChannelMessage>> {
+        self.poll_new()
+    }
+
+    fn read(&mut self, tag: &str) -> Result<ChannelMessage> {
+        match self.read_with_raw(tag.to_string())?.pop() {
+            Some(msg) => Ok(ChannelMessage { raw: None, ..msg }),
+            None => Err(ChannelError::MessageNotFound(tag.to_string())),
+        }
+    }
+}
+
+fn decode_data(data: &[u8], what: &str, tag: &str) -> Result<Option<String>> {
+    Payload::unwrap_bytes(data).map_err(|e| decode_error(e, what, tag))
+}
+
+fn decode_parts(data: &[u8], what: &str, tag: &str) -> Result<Parts> {
+    MultiPartPayload::decode(data).map_err(|e| decode_error(e, what, tag))
+}
+
+fn decode_error(e: ChannelError, what: &str, tag: &str) -> ChannelError {
+    match e {
+        ChannelError::PayloadEncoding(reason) => ChannelError::PayloadEncoding(format!(
+            "Failed to decode {} of {}: {}",
+            what, tag, reason
+        )),
+        e => e,
+    }
+}
+
+///
+/// Channel subscriber Builder
+///
+#[derive(Debug)]
+pub struct ChannelBuilder {
+    config: ChannelConfig,
+    channel_address: String,
+    announcement_tag: String,
+    seed: Option<Secret<String>>,
+    psk: Option<Secret<Psk>>,
+    announcement_cache: Option<AnnouncementCache>,
+    drop_expired: bool,
+    skip_own: bool,
+    sequence_guard: bool,
+    deduplicate: bool,
+    strict_protocol: bool,
+    strict_access: bool,
+    transport: Option<CustomTransport>,
+    read_transport: Option<CustomTransport>,
+}
+
+impl ChannelBuilder {
+    ///
+    /// Create Instance
+    ///
+    pub fn new(node: Network, channel_address: String, announcement_tag: String) -> Self {
+        ChannelBuilder {
+            config: ChannelConfig::new(node),
+            channel_address: channel_address,
+            announcement_tag: announcement_tag,
+            seed: None,
+            psk: None,
+            announcement_cache: None,
+            drop_expired: false,
+            skip_own: false,
+            sequence_guard: false,
+            deduplicate: true,
+            strict_protocol: false,
+            strict_access: false,
+            transport: None,
+            read_transport: None,
+        }
+    }
+
+    ///
+    /// Read the channel with a pre-shared key only, without seed nor subscription
+    ///
+    pub fn psk(&mut self, psk: Psk) -> &mut Self {
+        self.psk = Some(Secret::new(psk));
+        self
+    }
+
+    ///
+    /// Announcement cache shared with the other subscribers of the channel, the
+    /// announcement is fetched from the node once
+    ///
+    pub fn announcement_cache(&mut self, cache: AnnouncementCache) -> &mut Self {
+        self.announcement_cache = Some(cache);
+        self
+    }
+
+    ///
+    /// Skip the expired messages while walking the channel instead of flagging them
+    ///
+    pub fn drop_expired(&mut self, drop_expired: bool) -> &mut Self {
+        self.drop_expired = drop_expired;
+        self
+    }
+
+    ///
+    /// Skip the packets signed by this subscriber, see `Channel::set_skip_own`
+    ///
+    pub fn skip_own(&mut self, skip_own: bool) -> &mut Self {
+        self.skip_own = skip_own;
+        self
+    }
+
+    ///
+    /// Check the sequence numbers of the delivered messages, see
+    /// `Channel::set_sequence_guard`
+    ///
+    pub fn sequence_guard(&mut self, enabled: bool) -> &mut Self {
+        self.sequence_guard = enabled;
+        self
+    }
+
+    ///
+    /// Drop the messages fetched again by the message walk, see
+    /// `Channel::set_deduplicate`
+    ///
+    pub fn deduplicate(&mut self, enabled: bool) -> &mut Self {
+        self.deduplicate = enabled;
+        self
+    }
+
+    ///
+    /// Fail `connect` on the channels using a newer protocol version, see
+    /// `Channel::set_strict_protocol`
+    ///
+    pub fn strict_protocol(&mut self, strict: bool) -> &mut Self {
+        self.strict_protocol = strict;
+        self
+    }
+
+    ///
+    /// Fail the reads of the signed packets without access, see
+    /// `Channel::set_strict_access`
+    ///
+    pub fn strict_access(&mut self, strict: bool) -> &mut Self {
+        self.strict_access = strict;
+        self
+    }
+
+    ///
+    /// Subscriber seed, a random one is generated if not set
+    ///
+    pub fn seed(&mut self, seed: String) -> &mut Self {
+        self.seed = Some(Secret::new(seed));
+        self
+    }
+
+    node_settings!();
+
+    ///
+    /// Maximum transactions per message, for the nodes limiting the bundle size
+    ///
+    /// A message spanning more fails with `ChannelError::TooManyFragments` before
+    /// reaching the node
+    ///
+    pub fn max_fragments(&mut self, max: usize) -> &mut Self {
+        self.config.max_fragments = Some(max);
+        self
+    }
+
+    ///
+    /// Archive node, e.g. a permanode, queried for the messages pruned from the node
+    ///
+    /// Announcement and keyloads are needed to read anything later in the channel, so
+    /// syncing a long history usually requires one. Only the lookups of a given message
+    /// fall back to it: connecting, the keyload of `update_keyload` and
+    /// `read_from_keyload`, and the reads by tag. The walk for new messages queries the
+    /// node only
+    ///
+    pub fn archive_node(&mut self, node: &str) -> &mut Self {
+        self.config.archive_node = Some(node.to_string());
+        self
+    }
+
+    ///
+    /// Build
+    ///
+    pub fn build(&self) -> Result<Channel> {
+        let transport = match &self.transport {
+            Some(custom) => ChannelTransport::Custom(custom.clone()),
+            None => ChannelTransport::Tangle(self.config.tangle_transport()?),
+        };
+        let read_transport = match &self.read_transport {
+            Some(custom) => Some(ChannelTransport::Custom(custom.clone())),
+            None => self.config.read_transport()?.map(ChannelTransport::Tangle),
+        };
+        let seed = match (&self.seed, &self.psk) {
+            (None, Some(_)) => Some(read_only_seed()),
+            (seed, _) => seed.clone().map(Secret::into_inner),
+        };
+        let mut channel = Channel::with_tangle_config(
+            self.config.clone(),
+            transport,
+            self.channel_address.clone(),
+            self.announcement_tag.clone(),
+            seed,
+        )?;
+        channel.set_read_transport(read_transport);
+        if let Some(psk) = &self.psk {
+            channel.set_psk(psk.expose().clone());
+        }
+        if let Some(cache) = &self.announcement_cache {
+            channel.set_announcement_cache(cache.clone());
+        }
+        channel.set_drop_expired(self.drop_expired);
+        channel.set_skip_own(self.skip_own);
+        channel.set_sequence_guard(self.sequence_guard);
+        channel.set_deduplicate(self.deduplicate);
+        channel.set_strict_protocol(self.strict_protocol);
+        channel.set_strict_access(self.strict_access);
+        if self.config.verify_node {
+            channel.check_node_compatibility()?;
+            channel.check_node()?;
+        }
+        Ok(channel)
     }
 }
@@ -0,0 +1,60 @@
+//!
+//! Subscriber read position
+//!
+use crate::error::{ChannelError, Result};
+use crate::utils::sequence::SequenceGuard;
+use base64::{decode_config, encode_config, URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+
+///
+/// Version of the cursor token format
+///
+pub const CURSOR_VERSION: u32 = 1;
+
+///
+/// Read position of a subscriber, shared between processes as an opaque token
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Cursor {
+    /// Token format version
+    ///
+    pub version: u32,
+    /// Channel address
+    ///
+    pub channel_address: String,
+    /// Number of messages read since the announcement
+    ///
+    pub position: usize,
+    /// Tag of the last message read
+    ///
+    pub last_tag: Option<String>,
+    /// Sequence guard of the subscriber, if enabled
+    ///
+    #[serde(default)]
+    pub sequence_guard: Option<SequenceGuard>,
+}
+
+impl Cursor {
+    ///
+    /// Encode as an opaque token
+    ///
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        encode_config(&json, URL_SAFE_NO_PAD)
+    }
+
+    ///
+    /// Decode a token
+    ///
+    pub fn decode(token: &str) -> Result<Self> {
+        let json = decode_config(token.trim(), URL_SAFE_NO_PAD)?;
+        let cursor: Cursor = serde_json::from_slice(&json)?;
+        if cursor.version != CURSOR_VERSION {
+            return Err(ChannelError::PayloadEncoding(format!(
+                "Unsupported cursor version {}, expected {}",
+                cursor.version, CURSOR_VERSION
+            )));
+        }
+        Ok(cursor)
+    }
+}
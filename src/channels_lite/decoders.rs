@@ -0,0 +1,173 @@
+//!
+//! Decoders keyed by content type
+//!
+//! A channel mixing kinds of messages, written with `write_signed_typed` or
+//! `write_tagged_typed`, is decoded into the application types by registering a decoder
+//! per content type:
+//!
+//! ```ignore
+//! enum Feed {
+//!     Telemetry(Telemetry),
+//!     Config(Config),
+//! }
+//!
+//! let mut decoders = Decoders::new();
+//! decoders.register("telemetry/v1", |data| Ok(Feed::Telemetry(serde_json::from_str(data)?)))?;
+//! decoders.register("config/v1", |data| Ok(Feed::Config(serde_json::from_str(data)?)))?;
+//! for decoded in subscriber.fetch_all_decoded(&decoders)? { ... }
+//! ```
+//!
+use super::message::ChannelMessage;
+use crate::error::{ChannelError, Result};
+use std::collections::HashMap;
+use std::fmt;
+
+type Decoder<T> = Box<dyn Fn(&str) -> Result<T>>;
+
+///
+/// Message decoded by `Decoders`
+///
+#[derive(Debug)]
+pub enum Decoded<T> {
+    /// Decoded by the decoder of its content type
+    ///
+    Known {
+        /// Message tag
+        ///
+        tag: String,
+        /// Content type of the message
+        ///
+        content_type: String,
+        /// Value returned by the decoder
+        ///
+        value: T,
+    },
+    /// No decoder is registered for the content type, or the message has none
+    ///
+    Unknown {
+        /// Message tag
+        ///
+        tag: String,
+        /// Content type of the message, `None` for the messages written without
+        ///
+        content_type: Option<String>,
+        /// Public data of the message
+        ///
+        raw: Option<String>,
+    },
+    /// The decoder of the content type failed, the error carries the content type and
+    /// the tag
+    ///
+    Failed {
+        /// Message tag
+        ///
+        tag: String,
+        /// Content type of the message
+        ///
+        content_type: String,
+        /// Error of the decoder
+        ///
+        error: ChannelError,
+    },
+}
+
+///
+/// Registry of the decoders of the application types, one per content type
+///
+pub struct Decoders<T> {
+    decoders: HashMap<String, Decoder<T>>,
+}
+
+impl<T> Default for Decoders<T> {
+    fn default() -> Self {
+        Decoders {
+            decoders: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Decoders<T> {
+    ///
+    /// Empty registry
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Register the decoder of a content type, it receives the public data as JSON
+    ///
+    /// Fails with `ChannelError::DecoderConflict` if the content type already has a
+    /// decoder
+    ///
+    pub fn register<F>(&mut self, content_type: &str, decoder: F) -> Result<&mut Self>
+    where
+        F: Fn(&str) -> Result<T> + 'static,
+    {
+        if self.decoders.contains_key(content_type) {
+            return Err(ChannelError::DecoderConflict {
+                content_type: content_type.to_string(),
+            });
+        }
+        self.decoders
+            .insert(content_type.to_string(), Box::new(decoder));
+        Ok(self)
+    }
+
+    ///
+    /// Whether a decoder is registered for the content type
+    ///
+    pub fn contains(&self, content_type: &str) -> bool {
+        self.decoders.contains_key(content_type)
+    }
+
+    ///
+    /// Decode a message with the decoder of its content type
+    ///
+    /// The messages without public data are decoded from `null`
+    ///
+    pub fn decode(&self, msg: &ChannelMessage) -> Decoded<T> {
+        let (content_type, decoder) = match msg
+            .content_type
+            .as_ref()
+            .and_then(|content_type| Some((content_type, self.decoders.get(content_type)?)))
+        {
+            Some(found) => found,
+            None => {
+                return Decoded::Unknown {
+                    tag: msg.tag.clone(),
+                    content_type: msg.content_type.clone(),
+                    raw: msg.public.clone(),
+                }
+            }
+        };
+        match decoder(msg.public.as_deref().unwrap_or("null")) {
+            Ok(value) => Decoded::Known {
+                tag: msg.tag.clone(),
+                content_type: content_type.clone(),
+                value: value,
+            },
+            Err(e) => Decoded::Failed {
+                tag: msg.tag.clone(),
+                content_type: content_type.clone(),
+                error: ChannelError::PayloadEncoding(format!(
+                    "Failed to decode {} message {}: {}",
+                    content_type, msg.tag, e
+                )),
+            },
+        }
+    }
+}
+
+///
+/// Lists the content types registered
+///
+impl<T> fmt::Debug for Decoders<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut content_types: Vec<&String> = self.decoders.keys().collect();
+        content_types.sort();
+        f.debug_struct("Decoders")
+            .field("content_types", &content_types)
+            .finish()
+    }
+}
@@ -0,0 +1,403 @@
+//!
+//! Channel messages
+//!
+use super::audit::payload_digest;
+use crate::error::{ChannelError, Result};
+use crate::utils::{
+    ack::AckRequested,
+    content_type::Typed,
+    digest::{content_digest, slot_content},
+    end_of_channel::EndOfChannel,
+    expiry::Expiring,
+    payload::json::Payload,
+    protocol::ProtocolMarker,
+    sequence::{SequenceNumber, Sequenced},
+};
+use iota_streams::app_channels::api::tangle::{MessageContent, UnwrappedMessage};
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+///
+/// Kind of message found in the channel
+///
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    /// Channel announcement
+    ///
+    Announce,
+    /// Session key update
+    ///
+    Keyload,
+    /// Signed packet
+    ///
+    SignedPacket,
+    /// Tagged packet
+    ///
+    TaggedPacket,
+    /// Subscribe request
+    ///
+    Subscribe,
+    /// Any other message
+    ///
+    Other,
+}
+
+impl MessageKind {
+    ///
+    /// To string
+    ///
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Announce => "announce",
+            Self::Keyload => "keyload",
+            Self::SignedPacket => "signed_packet",
+            Self::TaggedPacket => "tagged_packet",
+            Self::Subscribe => "subscribe",
+            Self::Other => "other",
+        }
+    }
+}
+
+///
+/// Node a message was fetched from
+///
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageSource {
+    /// Node of the channel
+    ///
+    Primary,
+    /// Archive node, the message was pruned from the primary node
+    ///
+    Archive,
+}
+
+///
+/// Part of a message the subscriber could read
+///
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessLevel {
+    /// Message unwrapped, signature checked
+    ///
+    Full,
+    /// Public data of a signed packet read without the keyload it is linked to, the
+    /// masked data and the signature can't be checked
+    ///
+    PublicOnly,
+}
+
+impl Default for AccessLevel {
+    fn default() -> Self {
+        Self::Full
+    }
+}
+
+impl AccessLevel {
+    ///
+    /// Whether the message was unwrapped
+    ///
+    pub fn is_full(&self) -> bool {
+        *self == Self::Full
+    }
+}
+
+///
+/// Message read from the channel
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChannelMessage {
+    /// Message tag
+    ///
+    pub tag: String,
+    /// Message kind
+    ///
+    pub kind: MessageKind,
+    /// Unwrapped public data
+    ///
+    pub public: Option<String>,
+    /// Unwrapped masked data
+    ///
+    pub masked: Option<String>,
+    /// Errors raised while decoding the public and the masked data, joined with `; `
+    /// when both failed
+    ///
+    pub decode_error: Option<String>,
+    /// Raw message bytes as fetched from the tangle, only kept by `read_with_raw`
+    ///
+    pub raw: Option<Vec<u8>>,
+    /// Node the message was fetched from
+    ///
+    pub source: MessageSource,
+    /// Attachment time reported by the node, `None` if the node didn't report it
+    ///
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attached_at: Option<SystemTime>,
+    /// Time after which the message is stale, for the messages written with a ttl
+    ///
+    pub expires_at: Option<SystemTime>,
+    /// Whether the message had expired when the subscriber read it
+    ///
+    pub expired: bool,
+    /// Sequence number, for the messages written with sequence numbers
+    ///
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<SequenceNumber>,
+    /// Id of the acknowledgement request, for the messages written with
+    /// `write_signed_with_ack`
+    ///
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ack_request: Option<String>,
+    /// Content type, for the messages written with `write_signed_typed` or
+    /// `write_tagged_typed`
+    ///
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Digest of the public and masked payloads as received, envelopes included, see
+    /// `payload_digest`. Only for the signed and tagged packets
+    ///
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// Digest of the application data, envelopes removed, see `digest::content_digest`.
+    /// Only for the signed and tagged packets unwrapped
+    ///
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_digest: Option<String>,
+    /// Part of the message that could be read, `PublicOnly` when the subscriber reads a
+    /// signed packet without access to it
+    ///
+    #[serde(default, skip_serializing_if = "AccessLevel::is_full")]
+    pub access: AccessLevel,
+    /// Public key of the signer as lowercase hex, for the signed packets unwrapped
+    ///
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+}
+
+///
+/// Packet written by the author
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SentMessage {
+    /// Message tag
+    ///
+    pub tag: String,
+    /// Digest of the application data written, see `digest::content_digest`. The
+    /// subscribers find the same in `ChannelMessage::content_digest`
+    ///
+    pub digest: String,
+}
+
+impl ChannelMessage {
+    ///
+    /// Build from a message unwrapped by the subscriber
+    ///
+    pub(crate) fn from_unwrapped(msg: &UnwrappedMessage) -> Self {
+        let digest = match &msg.body {
+            MessageContent::SignedPacket {
+                public_payload,
+                masked_payload,
+                ..
+            }
+            | MessageContent::TaggedPacket {
+                public_payload,
+                masked_payload,
+            } => Some(payload_digest(&public_payload.0, &masked_payload.0)),
+            _ => None,
+        };
+        let (kind, public, masked) = match &msg.body {
+            MessageContent::Announce => (MessageKind::Announce, Ok(None), Ok(None)),
+            MessageContent::Keyload => (MessageKind::Keyload, Ok(None), Ok(None)),
+            MessageContent::Subscribe => (MessageKind::Subscribe, Ok(None), Ok(None)),
+            MessageContent::SignedPacket {
+                public_payload,
+                masked_payload,
+                ..
+            } => (
+                MessageKind::SignedPacket,
+                Payload::unwrap_bytes(&public_payload.0),
+                Payload::unwrap_bytes(&masked_payload.0),
+            ),
+            MessageContent::TaggedPacket {
+                public_payload,
+                masked_payload,
+            } => (
+                MessageKind::TaggedPacket,
+                Payload::unwrap_bytes(&public_payload.0),
+                Payload::unwrap_bytes(&masked_payload.0),
+            ),
+            _ => (MessageKind::Other, Ok(None), Ok(None)),
+        };
+        let signer = match &msg.body {
+            MessageContent::SignedPacket { pk, .. } => {
+                Some(pk.as_bytes().iter().map(|b| format!("{:02x}", b)).collect())
+            }
+            _ => None,
+        };
+        let mut channel_msg =
+            Self::from_parts(msg.link.msgid.to_string(), kind, public, masked, digest);
+        channel_msg.signer = signer;
+        if let MessageContent::SignedPacket {
+            public_payload,
+            masked_payload,
+            ..
+        }
+        | MessageContent::TaggedPacket {
+            public_payload,
+            masked_payload,
+        } = &msg.body
+        {
+            channel_msg.content_digest = Some(content_digest(
+                &slot_content(&public_payload.0, channel_msg.public.as_deref()),
+                &slot_content(&masked_payload.0, channel_msg.masked.as_deref()),
+            ));
+        }
+        channel_msg
+    }
+
+    ///
+    /// Build from the public payload of a signed packet read without unwrapping it
+    ///
+    pub(crate) fn public_only(tag: String, public_payload: &[u8]) -> Self {
+        let public = Payload::unwrap_bytes(public_payload);
+        ChannelMessage {
+            access: AccessLevel::PublicOnly,
+            ..Self::from_parts(tag, MessageKind::SignedPacket, public, Ok(None), None)
+        }
+    }
+
+    fn from_parts(
+        tag: String,
+        kind: MessageKind,
+        public: Result<Option<String>>,
+        masked: Result<Option<String>>,
+        digest: Option<String>,
+    ) -> Self {
+        let mut errors = Vec::new();
+        let public = public.unwrap_or_else(|e| {
+            errors.push(format!("Public data: {}", e));
+            None
+        });
+        let masked = masked.unwrap_or_else(|e| {
+            errors.push(format!("Masked data: {}", e));
+            None
+        });
+        let decode_error = if errors.is_empty() {
+            None
+        } else {
+            Some(errors.join("; "))
+        };
+
+        let mut public = public;
+        let mut seq = None;
+        if kind == MessageKind::SignedPacket || kind == MessageKind::TaggedPacket {
+            if let Some(envelope) = public.as_ref().and_then(|public| Sequenced::parse(public)) {
+                seq = Some(envelope.seq);
+                public = envelope.data.map(|data| data.to_string());
+            }
+        }
+        let mut expires_at = None;
+        if kind == MessageKind::SignedPacket {
+            if let Some(envelope) = public.as_ref().and_then(|public| Expiring::parse(public)) {
+                expires_at = envelope.expiry.expires_at();
+                public = envelope.data.map(|data| data.to_string());
+            }
+        }
+        let mut ack_request = None;
+        if kind == MessageKind::SignedPacket {
+            if let Some(envelope) = public
+                .as_ref()
+                .and_then(|public| AckRequested::parse(public))
+            {
+                ack_request = Some(envelope.ack_request);
+                public = envelope.data.map(|data| data.to_string());
+            }
+        }
+
+        let mut content_type = None;
+        if kind == MessageKind::SignedPacket || kind == MessageKind::TaggedPacket {
+            if let Some(envelope) = public.as_ref().and_then(|public| Typed::parse(public)) {
+                content_type = Some(envelope.content_type);
+                public = envelope.data.map(|data| data.to_string());
+            }
+        }
+
+        ChannelMessage {
+            tag: tag,
+            kind: kind,
+            public: public,
+            masked: masked,
+            decode_error: decode_error,
+            raw: None,
+            source: MessageSource::Primary,
+            attached_at: None,
+            expires_at: expires_at,
+            expired: false,
+            seq: seq,
+            ack_request: ack_request,
+            content_type: content_type,
+            digest: digest,
+            content_digest: None,
+            access: AccessLevel::Full,
+            signer: None,
+        }
+    }
+
+    ///
+    /// Check the digest of the application data received against the one of the author,
+    /// e.g. stored off-chain from `SentMessage::digest`
+    ///
+    /// Fails with `ChannelError::DigestMismatch` if they differ
+    ///
+    pub fn verify_digest(&self, expected: &str) -> Result<()> {
+        if self.content_digest.as_deref() == Some(expected) {
+            return Ok(());
+        }
+        Err(ChannelError::DigestMismatch {
+            tag: self.tag.clone(),
+            expected: expected.to_string(),
+            actual: self.content_digest.clone(),
+        })
+    }
+
+    ///
+    /// Whether the message is stale at `now`, never for the messages without ttl
+    ///
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        match self.expires_at {
+            Some(expires_at) => expires_at <= now,
+            None => false,
+        }
+    }
+
+    ///
+    /// Whether the message is the end of channel marker published by the author
+    ///
+    /// `author_key` is the public key of the channel author as lowercase hex, `None`
+    /// before the announcement is received: a marker signed by anyone else, e.g. a
+    /// subscriber, doesn't close the channel
+    ///
+    pub fn is_end_of_channel(&self, author_key: Option<&str>) -> bool {
+        match (&self.kind, &self.public, &self.signer, author_key) {
+            (MessageKind::SignedPacket, Some(public), Some(signer), Some(author_key)) => {
+                EndOfChannel::matches(public, signer, author_key)
+            }
+            _ => false,
+        }
+    }
+
+    ///
+    /// Protocol version, if the public data has the shape of the marker published by the
+    /// author after the announcement
+    ///
+    /// Only the message following the announcement is the marker, the readers check the
+    /// position before dropping it
+    ///
+    pub fn protocol_version(&self) -> Option<u32> {
+        match (&self.kind, &self.public) {
+            (MessageKind::SignedPacket, Some(public)) => ProtocolMarker::parse(public),
+            _ => None,
+        }
+    }
+}
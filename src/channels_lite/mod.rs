@@ -1,15 +1,43 @@
 //!
 //! Channels lite
 //!
+pub mod announcement_cache;
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+mod async_api;
+pub mod audit;
+#[doc(hidden)]
+pub mod author_state;
+#[macro_use]
+mod builder;
 pub mod channel_author;
 pub mod channel_subscriber;
-use iota_streams::app::transport::tangle::client::SendTrytesOptions;
+pub mod config;
+pub mod cursor;
+pub mod decoders;
+pub mod message;
+pub mod multi_subscriber;
+pub mod node;
+pub mod policy;
+pub mod provenance;
+pub mod reader;
+pub mod shutdown;
+pub mod snapshot;
+mod span;
+pub mod store;
+pub mod summary;
+
+use crate::error::{Error, Result};
+use crate::transport::tangle::TangleTransport;
+use config::NetworkConfig;
+use node::{NodeHealth, SendOptions, DEFAULT_MAX_MILESTONE_LAG};
+use std::{fmt, str::FromStr};
 
 ///
 /// Network Urls
 ///
 /// Pre-defined iota network urls
 ///
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Network {
     /// Main network
     ///
@@ -20,6 +48,9 @@ pub enum Network {
     /// Community network
     ///
     Comnet,
+    /// Local private tangle, with local PoW
+    ///
+    Local,
     /// Custom network URL
     ///
     /// Arguments:
@@ -39,21 +70,22 @@ impl Network {
             Self::Main => "https://nodes.thetangle.org:443",
             Self::Comnet => "https://nodes.comnet.thetangle.org:443",
             Self::Devnet => "https://nodes.devnet.iota.org:443",
+            Self::Local => "http://localhost:14265",
         }
     }
 
     ///
     /// Send Options
     ///
-    pub fn send_options(&self) -> SendTrytesOptions {
-        let mut send_opt = SendTrytesOptions::default();
+    pub fn send_options(&self) -> SendOptions {
+        let mut send_opt = SendOptions::default();
         match self {
             Self::Custom(_, mwm) => {
                 send_opt.min_weight_magnitude = *mwm;
                 send_opt.local_pow = false;
             }
             Self::Main => {
-                send_opt.min_weight_magnitude = 9;
+                send_opt.min_weight_magnitude = 14;
                 send_opt.local_pow = false;
             }
             Self::Comnet => {
@@ -61,10 +93,105 @@ impl Network {
                 send_opt.local_pow = false;
             }
             Self::Devnet => {
-                send_opt.min_weight_magnitude = 14;
+                send_opt.min_weight_magnitude = 9;
                 send_opt.local_pow = false;
             }
+            Self::Local => {
+                send_opt.min_weight_magnitude = 1;
+                send_opt.local_pow = true;
+            }
         }
         send_opt
     }
+
+    ///
+    /// Detect the send options from the node info
+    ///
+    /// Reads the minimum weight magnitude advertised by the node and prefers remote PoW
+    /// when the node offers it
+    ///
+    pub fn detect_send_options(&self) -> Result<SendOptions> {
+        let transport = TangleTransport::new(self, &NetworkConfig::default())?;
+        node::detect_send_options(transport.client(), self.send_options()).map_err(Error::transport)
+    }
+
+    ///
+    /// Check the node health
+    ///
+    /// Fails with `Error::NodeUnhealthy` when the milestone lag exceeds the default threshold
+    ///
+    pub fn check_health(&self) -> Result<NodeHealth> {
+        self.check_health_with(DEFAULT_MAX_MILESTONE_LAG)
+    }
+
+    ///
+    /// Check the node health with a custom milestone lag threshold
+    ///
+    pub fn check_health_with(&self, max_milestone_lag: u32) -> Result<NodeHealth> {
+        let transport = TangleTransport::new(self, &NetworkConfig::default())?;
+        let health =
+            node::query_health(transport.client(), self.as_string()).map_err(Error::transport)?;
+        health.ensure_synced(max_milestone_lag)?;
+        Ok(health)
+    }
+
+    ///
+    /// Check the node health without blocking, e.g. in the browser, see `check_health`
+    ///
+    pub async fn check_health_async(&self) -> Result<NodeHealth> {
+        let transport = TangleTransport::new(self, &NetworkConfig::default())?;
+        let health = node::query_health_async(transport.client(), self.as_string())
+            .await
+            .map_err(Error::transport)?;
+        health.ensure_synced(DEFAULT_MAX_MILESTONE_LAG)?;
+        Ok(health)
+    }
+}
+
+impl fmt::Display for Network {
+    ///
+    /// Name of a pre-defined network, `custom:<mwm>:<url>` for a custom one
+    ///
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Main => write!(f, "mainnet"),
+            Self::Devnet => write!(f, "devnet"),
+            Self::Comnet => write!(f, "comnet"),
+            Self::Local => write!(f, "local"),
+            Self::Custom(url, mwm) => write!(f, "custom:{}:{}", mwm, url),
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = Error;
+
+    ///
+    /// Parse a pre-defined network from its name, or a custom one as written by `Display`
+    ///
+    /// The url of a custom network is leaked to get the `'static` lifetime of
+    /// `Network::Custom`, parse it once and keep the network around
+    ///
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        let trimmed = name.trim();
+        match trimmed.to_lowercase().as_str() {
+            "main" | "mainnet" => Ok(Self::Main),
+            "dev" | "devnet" => Ok(Self::Devnet),
+            "comnet" => Ok(Self::Comnet),
+            "local" => Ok(Self::Local),
+            lower if lower.starts_with("custom:") => {
+                let mut parts = trimmed["custom:".len()..].splitn(2, ':');
+                let mwm = parts.next().and_then(|mwm| mwm.parse::<u8>().ok());
+                let url = parts.next().filter(|url| !url.is_empty());
+                match (mwm, url) {
+                    (Some(mwm), Some(url)) => Ok(Self::Custom(
+                        Box::leak(url.to_string().into_boxed_str()),
+                        mwm,
+                    )),
+                    _ => Err(Error::UnknownNetwork(name.to_string())),
+                }
+            }
+            _ => Err(Error::UnknownNetwork(name.to_string())),
+        }
+    }
 }
@@ -0,0 +1,177 @@
+//!
+//! Multi Subscriber
+//!
+use super::{channel_subscriber, message::ChannelMessage, shutdown::ShutdownReport, Network};
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+///
+/// Message tagged with the channel it was read from
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SourcedMessage {
+    /// Address of the source channel
+    ///
+    pub channel_address: String,
+    /// Message
+    ///
+    pub message: ChannelMessage,
+}
+
+///
+/// Follows several channels through the same node
+///
+pub struct MultiSubscriber {
+    subscribers: Vec<(String, channel_subscriber::Channel)>,
+}
+
+impl MultiSubscriber {
+    ///
+    /// Create a subscriber for each `(channel_address, announcement_tag)` pair
+    ///
+    /// Fails if the transport of the node can't be built
+    ///
+    pub fn new(
+        node: Network,
+        channels: Vec<(String, String)>,
+        seed: Option<String>,
+    ) -> Result<Self> {
+        let subscribers = channels
+            .into_iter()
+            .map(|(channel_address, announcement_tag)| {
+                let subscriber = channel_subscriber::Channel::new(
+                    node,
+                    channel_address.clone(),
+                    announcement_tag,
+                    seed.clone(),
+                )?;
+                Ok((channel_address, subscriber))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(MultiSubscriber {
+            subscribers: subscribers,
+        })
+    }
+
+    ///
+    /// Follow subscribers already built, e.g. on another transport
+    ///
+    pub fn from_subscribers(subscribers: Vec<channel_subscriber::Channel>) -> Self {
+        MultiSubscriber {
+            subscribers: subscribers
+                .into_iter()
+                .map(|subscriber| (subscriber.info().channel_address, subscriber))
+                .collect(),
+        }
+    }
+
+    ///
+    /// Connect to every channel
+    ///
+    /// Returns the subscription tag for each channel address
+    ///
+    pub fn connect_all(&mut self) -> Result<Vec<(String, String)>> {
+        let mut subscription_tags = Vec::new();
+        for (channel_address, subscriber) in self.subscribers.iter_mut() {
+            subscription_tags.push((channel_address.clone(), subscriber.connect()?));
+        }
+        Ok(subscription_tags)
+    }
+
+    ///
+    /// Subscriber of a channel
+    ///
+    pub fn subscriber(
+        &mut self,
+        channel_address: &str,
+    ) -> Option<&mut channel_subscriber::Channel> {
+        self.subscribers
+            .iter_mut()
+            .find(|(address, _)| address == channel_address)
+            .map(|(_, subscriber)| subscriber)
+    }
+
+    ///
+    /// Fetch the new messages of all the channels, tagged with their source channel
+    ///
+    pub fn fetch_all(&mut self) -> Vec<SourcedMessage> {
+        let mut msgs = Vec::new();
+        for (channel_address, subscriber) in self.subscribers.iter_mut() {
+            for message in subscriber.fetch_all() {
+                msgs.push(SourcedMessage {
+                    channel_address: channel_address.clone(),
+                    message: message,
+                });
+            }
+        }
+        msgs
+    }
+
+    ///
+    /// Merged stream of the new messages of all the channels, tagged with their source
+    /// channel
+    ///
+    /// The channels take turns, one message each, so a busy channel doesn't hold the
+    /// others back. The stream ends once no channel has a new message, the next call
+    /// picks up the messages arrived since
+    ///
+    pub fn messages(&mut self) -> SourcedMessages<'_> {
+        SourcedMessages {
+            subscribers: &mut self.subscribers,
+            next: 0,
+            idle: 0,
+        }
+    }
+
+    ///
+    /// Shut every subscriber down, see `channel_subscriber::Channel::shutdown`
+    ///
+    /// Returns the report of each channel address. Stops at the first failed persist,
+    /// the subscribers left are dropped with a warning
+    ///
+    pub fn shutdown(self) -> Result<Vec<(String, ShutdownReport)>> {
+        let mut reports = Vec::new();
+        for (channel_address, subscriber) in self.subscribers {
+            reports.push((channel_address, subscriber.shutdown()?));
+        }
+        Ok(reports)
+    }
+}
+
+///
+/// Merged stream of the messages of several channels, see `MultiSubscriber::messages`
+///
+pub struct SourcedMessages<'a> {
+    subscribers: &'a mut Vec<(String, channel_subscriber::Channel)>,
+    next: usize,
+    idle: usize,
+}
+
+impl<'a> Iterator for SourcedMessages<'a> {
+    type Item = Result<SourcedMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Ends after a full round of the channels without a message
+        while self.idle < self.subscribers.len() {
+            let (channel_address, subscriber) = &mut self.subscribers[self.next];
+            self.next = (self.next + 1) % self.subscribers.len();
+            match subscriber.next_message_blocking(Duration::from_secs(0)) {
+                Ok(Some(message)) => {
+                    self.idle = 0;
+                    return Some(Ok(SourcedMessage {
+                        channel_address: channel_address.clone(),
+                        message: message,
+                    }));
+                }
+                Ok(None) => self.idle += 1,
+                Err(e) => {
+                    self.idle += 1;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
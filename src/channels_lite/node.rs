@@ -0,0 +1,397 @@
+//!
+//! Node health
+//!
+use crate::error::{Error, Result};
+use crate::utils::clock::Clock;
+use anyhow::anyhow;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::executor::block_on;
+use instant::Instant;
+use iota::client as iota_client;
+use iota_streams::app::transport::tangle::client::SendTrytesOptions;
+use iota_streams::core::prelude::Rc;
+use serde::{Deserialize, Serialize};
+use std::{convert::TryInto, future::Future, time::Duration};
+
+///
+/// Default maximum milestone lag tolerated by the health check
+///
+pub const DEFAULT_MAX_MILESTONE_LAG: u32 = 5;
+
+///
+/// Default time the result of the sync check before a send is reused
+///
+pub const DEFAULT_SYNC_CHECK_TTL: Duration = Duration::from_secs(30);
+
+///
+/// Node software supported, with the first supported version and the first version
+/// dropping the legacy API
+///
+const SUPPORTED_NODES: &[(&str, (u32, u32), Option<(u32, u32)>)] =
+    &[("IRI", (1, 8), None), ("HORNET", (0, 4), Some((0, 6)))];
+
+///
+/// Options of the outgoing messages
+///
+/// Mirrors the send options of the Streams tangle client, kept in the crate so that a
+/// Streams upgrade doesn't change the public API
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SendOptions {
+    /// Depth of the tip selection
+    ///
+    pub depth: u8,
+    /// Minimum weight magnitude of the proof of work
+    ///
+    pub min_weight_magnitude: u8,
+    /// Whether the proof of work is computed locally
+    ///
+    pub local_pow: bool,
+    /// Threads of the local proof of work
+    ///
+    pub threads: usize,
+}
+
+impl Default for SendOptions {
+    fn default() -> Self {
+        Self::from_streams(&SendTrytesOptions::default())
+    }
+}
+
+impl SendOptions {
+    pub(crate) fn from_streams(options: &SendTrytesOptions) -> Self {
+        SendOptions {
+            depth: options.depth,
+            min_weight_magnitude: options.min_weight_magnitude,
+            local_pow: options.local_pow,
+            threads: options.threads,
+        }
+    }
+
+    pub(crate) fn to_streams(self) -> SendTrytesOptions {
+        SendTrytesOptions {
+            depth: self.depth,
+            min_weight_magnitude: self.min_weight_magnitude,
+            local_pow: self.local_pow,
+            threads: self.threads,
+        }
+    }
+}
+
+///
+/// Origin of the send options used by a channel
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SendOptionsSource {
+    /// Static defaults of the network
+    ///
+    Static,
+    /// Detected from the node info
+    ///
+    Detected,
+    /// Detection failed, the static defaults are used
+    ///
+    Fallback(String),
+}
+
+///
+/// Send options with their origin
+///
+#[derive(Clone, Debug)]
+pub struct TunedSendOptions {
+    /// Send options
+    ///
+    pub options: SendOptions,
+    /// Where the options come from
+    ///
+    pub source: SendOptionsSource,
+}
+
+///
+/// Node health report
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeHealth {
+    /// Node url
+    ///
+    pub node: String,
+    /// Name of the node software
+    ///
+    pub app_name: String,
+    /// Version of the node software
+    ///
+    pub app_version: String,
+    /// Index of the latest milestone
+    ///
+    pub latest_milestone_index: u32,
+    /// Index of the latest solid milestone
+    ///
+    pub latest_solid_milestone_index: u32,
+}
+
+impl NodeHealth {
+    ///
+    /// Number of milestones the node is behind
+    ///
+    pub fn milestone_lag(&self) -> u32 {
+        self.latest_milestone_index
+            .saturating_sub(self.latest_solid_milestone_index)
+    }
+
+    ///
+    /// Fail with `Error::NodeUnhealthy` when the lag exceeds `max_lag`
+    ///
+    pub fn ensure_synced(&self, max_lag: u32) -> Result<()> {
+        let lag = self.milestone_lag();
+        if lag > max_lag {
+            return Err(Error::NodeUnhealthy {
+                node: self.node.clone(),
+                lag: lag,
+                max_lag: max_lag,
+            });
+        }
+        Ok(())
+    }
+
+    ///
+    /// Fail with `Error::NodeNotSynced` when the lag exceeds `max_lag`, returns the lag
+    ///
+    pub fn check_sync(&self, max_lag: u32) -> Result<u32> {
+        let lag = self.milestone_lag();
+        if lag > max_lag {
+            return Err(Error::NodeNotSynced {
+                node: self.node.clone(),
+                lag: lag,
+                max_lag: max_lag,
+            });
+        }
+        Ok(lag)
+    }
+}
+
+///
+/// Sync check run before each send, the node is queried at most once per TTL
+///
+#[derive(Debug)]
+pub(crate) struct SyncCheck {
+    max_lag: u32,
+    ttl: Duration,
+    clock: Rc<dyn Clock>,
+    last: Option<(Instant, NodeHealth)>,
+}
+
+impl SyncCheck {
+    pub(crate) fn new(max_lag: u32, ttl: Duration, clock: Rc<dyn Clock>) -> Self {
+        SyncCheck {
+            max_lag: max_lag,
+            ttl: ttl,
+            clock: clock,
+            last: None,
+        }
+    }
+
+    pub(crate) fn ensure(
+        &mut self,
+        client: &iota_client::Client,
+        node: &str,
+    ) -> anyhow::Result<()> {
+        let now = self.clock.now();
+        let fresh = match &self.last {
+            Some((checked_at, _)) => now.duration_since(*checked_at) < self.ttl,
+            None => false,
+        };
+        if !fresh {
+            self.last = Some((now, query_health(client, node)?));
+        }
+        match &self.last {
+            Some((_, health)) => {
+                health.check_sync(self.max_lag)?;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+///
+/// Node software and network
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeInfo {
+    /// Node url
+    ///
+    pub node: String,
+    /// Name of the node software
+    ///
+    pub app_name: String,
+    /// Version of the node software
+    ///
+    pub app_version: String,
+    /// Coordinator address, identifies the network the node belongs to
+    ///
+    pub network_id: String,
+}
+
+impl NodeInfo {
+    ///
+    /// Fail with `Error::IncompatibleNode` when the node software is not supported
+    ///
+    pub fn ensure_compatible(&self) -> Result<()> {
+        let incompatible = |reason: String| -> Result<()> {
+            Err(Error::IncompatibleNode {
+                node: self.node.clone(),
+                app_name: self.app_name.clone(),
+                app_version: self.app_version.clone(),
+                reason: reason,
+            })
+        };
+
+        let (min, max) = match SUPPORTED_NODES
+            .iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(&self.app_name))
+        {
+            Some((_, min, max)) => (*min, *max),
+            None => return incompatible("unsupported node software".to_string()),
+        };
+        let version = match parse_version(&self.app_version) {
+            Some(version) => version,
+            None => return incompatible("unknown version format".to_string()),
+        };
+        if version < min {
+            return incompatible(format!("version {}.{} or later required", min.0, min.1));
+        }
+        if let Some(max) = max {
+            if version >= max {
+                return incompatible(format!(
+                    "versions from {}.{} don't provide the legacy API",
+                    max.0, max.1
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version
+        .trim_start_matches(|c| c == 'v' || c == 'V')
+        .split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+///
+/// Query the node software and network
+///
+pub(crate) fn query_info(client: &iota_client::Client, node: &str) -> anyhow::Result<NodeInfo> {
+    block_on_node(query_info_async(client, node))
+}
+
+///
+/// Query the node software and network, without blocking
+///
+pub(crate) async fn query_info_async(
+    client: &iota_client::Client,
+    node: &str,
+) -> anyhow::Result<NodeInfo> {
+    let info = client
+        .get_node_info()
+        .await
+        .map_err(|e| anyhow!("Node {} is unreachable: {}", node, e))?;
+
+    Ok(NodeInfo {
+        node: node.to_string(),
+        app_name: info.app_name,
+        app_version: info.app_version,
+        network_id: info.coordinator_address,
+    })
+}
+
+///
+/// Query the node info endpoint
+///
+pub(crate) fn query_health(client: &iota_client::Client, node: &str) -> anyhow::Result<NodeHealth> {
+    block_on_node(query_health_async(client, node))
+}
+
+///
+/// Query the node info endpoint, without blocking
+///
+pub(crate) async fn query_health_async(
+    client: &iota_client::Client,
+    node: &str,
+) -> anyhow::Result<NodeHealth> {
+    let info = client
+        .get_node_info()
+        .await
+        .map_err(|e| anyhow!("Node {} is unreachable: {}", node, e))?;
+
+    Ok(NodeHealth {
+        node: node.to_string(),
+        app_name: info.app_name,
+        app_version: info.app_version,
+        latest_milestone_index: info.latest_milestone_index.try_into().unwrap_or(u32::MAX),
+        latest_solid_milestone_index: info
+            .latest_solid_subtangle_milestone_index
+            .try_into()
+            .unwrap_or(u32::MAX),
+    })
+}
+
+///
+/// Tune the send options from the minimum weight magnitude and the PoW capabilities
+/// advertised by the node, preferring remote PoW when the node offers it
+///
+pub(crate) fn detect_send_options(
+    client: &iota_client::Client,
+    fallback: SendOptions,
+) -> anyhow::Result<SendOptions> {
+    block_on_node(detect_send_options_async(client, fallback))
+}
+
+///
+/// Tune the send options, see `detect_send_options`, without blocking
+///
+pub(crate) async fn detect_send_options_async(
+    client: &iota_client::Client,
+    fallback: SendOptions,
+) -> anyhow::Result<SendOptions> {
+    let info = client.get_node_info().await?;
+    let api_config = client.get_node_api_configuration().await?;
+
+    let mut send_opt = fallback;
+    send_opt.min_weight_magnitude = api_config.mwm.try_into().unwrap_or(u8::MAX);
+    send_opt.local_pow = !info
+        .features
+        .iter()
+        .any(|feature| feature.eq_ignore_ascii_case("RemotePOW"));
+    Ok(send_opt)
+}
+
+///
+/// Wait for a node query
+///
+#[cfg(not(target_arch = "wasm32"))]
+fn block_on_node<F, R>(query: F) -> anyhow::Result<R>
+where
+    F: Future<Output = anyhow::Result<R>>,
+{
+    block_on(query)
+}
+
+///
+/// The browser main thread can't block on a node query, the async queries are used
+/// instead. The blocking ones fail: the auto-tuned send options fall back to the
+/// static ones, the health checks must be awaited, see `Network::check_health_async`
+///
+#[cfg(target_arch = "wasm32")]
+fn block_on_node<F, R>(_query: F) -> anyhow::Result<R>
+where
+    F: Future<Output = anyhow::Result<R>>,
+{
+    Err(anyhow!(
+        "Blocking node queries are not available on wasm32, use the async ones"
+    ))
+}
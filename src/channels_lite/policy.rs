@@ -0,0 +1,72 @@
+//!
+//! Publishing policy of an author
+//!
+//! Limits enforced by the author on every packet it writes, whatever the method used.
+//! The message count is per keyload epoch: publishing a keyload starts a new epoch and
+//! resets it.
+//!
+use serde::{Deserialize, Serialize};
+
+///
+/// Limits of the packets written by an author, none by default
+///
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ChannelPolicy {
+    /// Packets allowed between two keyloads
+    ///
+    pub max_messages_per_epoch: Option<u32>,
+    /// Public and masked data allowed in a packet, in bytes, envelopes included
+    ///
+    pub max_payload_bytes: Option<usize>,
+}
+
+///
+/// Packets written in the current keyload epoch
+///
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct EpochCounters {
+    /// Tag of the keyload opening the epoch, empty before the first keyload
+    ///
+    pub keyload_tag: String,
+    /// Packets written in the epoch
+    ///
+    pub messages: u32,
+    /// Public and masked data written in the epoch, in bytes
+    ///
+    pub payload_bytes: u64,
+}
+
+impl EpochCounters {
+    ///
+    /// Start counting the epoch of `keyload_tag` if the counters are of another one
+    ///
+    pub(crate) fn enter(&mut self, keyload_tag: &str) {
+        if self.keyload_tag != keyload_tag {
+            *self = EpochCounters {
+                keyload_tag: keyload_tag.to_string(),
+                ..EpochCounters::default()
+            };
+        }
+    }
+
+    ///
+    /// Count a packet written
+    ///
+    pub(crate) fn count(&mut self, payload_bytes: usize) {
+        self.messages += 1;
+        self.payload_bytes += payload_bytes as u64;
+    }
+}
+
+///
+/// Policy of an author with the counters of the current epoch
+///
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PolicyStats {
+    /// Policy enforced
+    ///
+    pub policy: ChannelPolicy,
+    /// Counters of the current keyload epoch
+    ///
+    pub epoch: EpochCounters,
+}
@@ -0,0 +1,83 @@
+//!
+//! Provenance of the messages read by a subscriber
+//!
+//! A compliance export needs, for every message, who signed it, when the tangle got it
+//! and what it carried. `export_provenance` walks the channel and returns one record per
+//! message with the decoded payloads, the digests to match against the audit report of
+//! the author and against the data written, and the node the message was found on.
+//!
+//! This Streams version doesn't expose the inclusion states, whether a message is
+//! confirmed is left unknown: a node serving it only shows it is attached, see
+//! `is_attached` of the author.
+//!
+use super::message::{AccessLevel, ChannelMessage, MessageKind, MessageSource};
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+///
+/// Provenance of a message
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProvenanceRecord {
+    /// Message tag
+    ///
+    pub tag: String,
+    /// Message kind
+    ///
+    pub kind: MessageKind,
+    /// Public key of the signer as lowercase hex, for the signed packets unwrapped
+    ///
+    pub signer: Option<String>,
+    /// Attachment time reported by the node, `None` if the node didn't report it
+    ///
+    pub attached_at: Option<SystemTime>,
+    /// Whether a milestone confirmed the message, `None` when the node doesn't report
+    /// the inclusion states
+    ///
+    pub confirmed: Option<bool>,
+    /// Node the message was found on
+    ///
+    pub source: MessageSource,
+    /// Unwrapped public data, envelopes removed
+    ///
+    pub public: Option<String>,
+    /// Unwrapped masked data
+    ///
+    pub masked: Option<String>,
+    /// Digest of the payloads as received, see `audit::payload_digest`
+    ///
+    pub digest: Option<String>,
+    /// Digest of the application data, to match against `SentMessage::digest` or an
+    /// off-chain copy, see `digest::content_digest`
+    ///
+    #[serde(default)]
+    pub content_digest: Option<String>,
+    /// Part of the message the subscriber could read
+    ///
+    pub access: AccessLevel,
+    /// When the record was exported
+    ///
+    pub exported_at: SystemTime,
+}
+
+impl ProvenanceRecord {
+    ///
+    /// Record of a message walked by the subscriber
+    ///
+    pub(crate) fn new(msg: ChannelMessage, exported_at: SystemTime) -> Self {
+        ProvenanceRecord {
+            tag: msg.tag,
+            kind: msg.kind,
+            signer: msg.signer,
+            attached_at: msg.attached_at,
+            confirmed: None,
+            source: msg.source,
+            public: msg.public,
+            masked: msg.masked,
+            digest: msg.digest,
+            content_digest: msg.content_digest,
+            access: msg.access,
+            exported_at: exported_at,
+        }
+    }
+}
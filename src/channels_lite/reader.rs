@@ -0,0 +1,42 @@
+//!
+//! Reading a channel whatever the role
+//!
+use super::message::{ChannelMessage, MessageKind};
+use crate::error::Result;
+
+///
+/// Read access to a channel, implemented by the author and the subscriber
+///
+/// The subscriber walks the channel as `poll_new` does, the author reads back the
+/// packets it published
+///
+pub trait ChannelReader {
+    ///
+    /// Address of the channel
+    ///
+    fn channel_address(&self) -> &str;
+
+    ///
+    /// Messages not returned yet, in order
+    ///
+    fn fetch_all(&mut self) -> Result<Vec<ChannelMessage>>;
+
+    ///
+    /// Read a message of the channel
+    ///
+    fn read(&mut self, tag: &str) -> Result<ChannelMessage>;
+}
+
+///
+/// Public data of the packets not returned yet, whatever the role of the reader
+///
+pub fn fetch_public(reader: &mut dyn ChannelReader) -> Result<Vec<String>> {
+    Ok(reader
+        .fetch_all()?
+        .into_iter()
+        .filter(|msg| {
+            msg.kind == MessageKind::SignedPacket || msg.kind == MessageKind::TaggedPacket
+        })
+        .filter_map(|msg| msg.public)
+        .collect())
+}
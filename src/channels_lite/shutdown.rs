@@ -0,0 +1,54 @@
+//!
+//! Graceful shutdown of the channels
+//!
+//! Every send completes before the write methods return, there is no outbox to flush:
+//! shutting a channel down persists its state to the attached store, if any, cancels
+//! its waits and hands the final state token back in the report. A channel dropped
+//! without shutdown while a store is attached logs a warning, its state since the last
+//! persist is lost.
+//!
+//! Subscribers don't unsubscribe on shutdown, the Streams version in use has no
+//! unsubscribe message: the author removes them with `remove_subscriber`.
+//!
+use super::store::StateStore;
+use crate::utils::secret::Secret;
+use iota_streams::core::prelude::Rc;
+
+///
+/// What a shutdown did
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShutdownReport {
+    /// Id the state was persisted under, `None` without attached store
+    ///
+    pub persisted_as: Option<String>,
+    /// Messages fetched but not handed out yet, a restored subscriber fetches them again
+    ///
+    pub pending_messages: usize,
+    /// Final state, the `export_state` token of the author or the `cursor` token of the
+    /// subscriber
+    ///
+    pub final_state: String,
+    /// Acknowledgements requested by the author and not received, see `pending_acks`
+    ///
+    pub pending_acks: usize,
+}
+
+///
+/// Store the state is persisted to on shutdown
+///
+pub(crate) struct AttachedStore {
+    pub(crate) store: Rc<dyn StateStore>,
+    pub(crate) id: String,
+    pub(crate) password: Secret<String>,
+}
+
+impl AttachedStore {
+    pub(crate) fn new(store: Rc<dyn StateStore>, id: &str, password: &str) -> Self {
+        AttachedStore {
+            store: store,
+            id: id.to_string(),
+            password: Secret::new(password.to_string()),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//!
+//! Handover of the authorship
+//!
+//! A snapshot bundles everything an author needs to go on publishing from another
+//! machine: the seed, the bookkeeping of `export_state` (tags, keyload history, processed
+//! subscriptions, epoch counters) and the policy. It is sealed with a password like the
+//! stored states, see `store`.
+//!
+//! Only one machine may write at a time. Two authors restored from the same snapshot,
+//! or the original author still writing after the handover, publish diverging branches
+//! that the subscribers can't reconcile. Each snapshot raises the generation of the
+//! author, carried by the sequence numbers: with them enabled, the subscribers' guard
+//! reports the packets of an older generation as `StaleGeneration` and the packets
+//! numbered twice by two restores as `Duplicate`.
+//!
+//! The pre-shared keys are not part of it, the author only keeps their ids: they are
+//! provisioned again on the new machine.
+//!
+use super::policy::ChannelPolicy;
+use super::store::{open, seal};
+use crate::error::{ChannelError, Result};
+use crate::utils::secret::Secret;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+///
+/// Version of the snapshot format
+///
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+///
+/// Sealed snapshot of an author, see `Channel::snapshot`
+///
+/// The channel address and the generation are readable without the password, to tell
+/// the snapshots apart
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChannelSnapshot {
+    /// Snapshot format version
+    ///
+    pub version: u32,
+    /// Channel address
+    ///
+    pub channel_address: String,
+    /// Generation of the author restored from the snapshot
+    ///
+    pub generation: u64,
+    /// When the snapshot was taken
+    ///
+    pub taken_at: SystemTime,
+    sealed: Vec<u8>,
+}
+
+///
+/// Sealed part of a snapshot
+///
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SnapshotContent {
+    pub(crate) seed: Secret<String>,
+    pub(crate) state: String,
+    pub(crate) policy: ChannelPolicy,
+    pub(crate) generation: u64,
+}
+
+impl ChannelSnapshot {
+    pub(crate) fn seal(
+        channel_address: &str,
+        taken_at: SystemTime,
+        content: &SnapshotContent,
+        password: &str,
+    ) -> Result<Self> {
+        Ok(ChannelSnapshot {
+            version: SNAPSHOT_VERSION,
+            channel_address: channel_address.to_string(),
+            generation: content.generation,
+            taken_at: taken_at,
+            sealed: seal(password, &serde_json::to_vec(content)?)?,
+        })
+    }
+
+    pub(crate) fn open(&self, password: &str) -> Result<SnapshotContent> {
+        let content: SnapshotContent = serde_json::from_slice(&open(password, &self.sealed)?)?;
+        if content.generation != self.generation {
+            return Err(ChannelError::State(format!(
+                "Snapshot generation {} doesn't match the sealed one {}",
+                self.generation, content.generation
+            )));
+        }
+        Ok(content)
+    }
+
+    ///
+    /// Encode as bytes, to move the snapshot to the other machine
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    ///
+    /// Decode the bytes of `to_bytes`
+    ///
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let snapshot: ChannelSnapshot = serde_json::from_slice(bytes)?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(ChannelError::PayloadEncoding(format!(
+                "Unsupported snapshot version {}, expected {}",
+                snapshot.version, SNAPSHOT_VERSION
+            )));
+        }
+        Ok(snapshot)
+    }
+}
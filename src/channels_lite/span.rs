@@ -0,0 +1,108 @@
+//!
+//! Tracing spans of the channel operations
+//!
+//! Only emitted with the `tracing` feature, the spans are no-ops otherwise
+//!
+use crate::error::Result;
+#[cfg(feature = "tracing")]
+use instant::Instant;
+#[cfg(feature = "tracing")]
+use std::convert::TryFrom;
+
+///
+/// Span of a channel operation, carrying the channel address, the message tag, the
+/// payload size, the elapsed time and the error if any
+///
+pub(crate) struct OpSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl OpSpan {
+    ///
+    /// Span of an operation on a channel
+    ///
+    #[allow(clippy::let_and_return)]
+    pub(crate) fn new(operation: &'static str, channel: &str, payload_size: usize) -> Self {
+        #[cfg(feature = "tracing")]
+        let span = OpSpan {
+            span: tracing::info_span!(
+                "channel_operation",
+                operation = operation,
+                channel = channel,
+                payload_size = u64::try_from(payload_size).unwrap_or(u64::MAX),
+                tag = tracing::field::Empty,
+                elapsed_ms = tracing::field::Empty,
+                error = tracing::field::Empty,
+            ),
+        };
+        #[cfg(not(feature = "tracing"))]
+        let span = {
+            let _ = (operation, channel, payload_size);
+            OpSpan {}
+        };
+        span
+    }
+
+    ///
+    /// Run the operation inside the span, recording the elapsed time
+    ///
+    #[allow(clippy::let_and_return)]
+    pub(crate) fn in_scope<R, F>(&self, op: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        #[cfg(feature = "tracing")]
+        let res = {
+            let start = Instant::now();
+            let res = self.span.in_scope(op);
+            self.span.record(
+                "elapsed_ms",
+                &u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX),
+            );
+            res
+        };
+        #[cfg(not(feature = "tracing"))]
+        let res = op();
+        res
+    }
+
+    ///
+    /// Record the tag of the message the operation produced
+    ///
+    pub(crate) fn record_tag(&self, tag: &str) {
+        #[cfg(feature = "tracing")]
+        self.span.record("tag", &tag);
+        #[cfg(not(feature = "tracing"))]
+        let _ = tag;
+    }
+
+    ///
+    /// Record the payload size once known, e.g. after a fetch
+    ///
+    pub(crate) fn record_payload_size(&self, payload_size: usize) {
+        #[cfg(feature = "tracing")]
+        self.span.record(
+            "payload_size",
+            &u64::try_from(payload_size).unwrap_or(u64::MAX),
+        );
+        #[cfg(not(feature = "tracing"))]
+        let _ = payload_size;
+    }
+
+    ///
+    /// Record the outcome of the operation, the tag on success and the error otherwise
+    ///
+    pub(crate) fn record<R, F>(&self, res: &Result<R>, tag: F)
+    where
+        F: FnOnce(&R) -> &str,
+    {
+        match res {
+            Ok(res) => self.record_tag(tag(res)),
+            Err(_e) => {
+                #[cfg(feature = "tracing")]
+                self.span.record("error", &tracing::field::display(_e));
+            }
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//!
+//! Persistence of the channel state
+//!
+//! The states are sealed with a password before reaching the store: they carry the
+//! seed of the channel. The sealed form is
+//!
+//! `MAGIC | version: u8 | salt: 16 bytes | nonce: 12 bytes | ChaCha20-Poly1305 ciphertext`
+//!
+//! with the key derived from the password by PBKDF2-HMAC-SHA256. A truncated or
+//! tampered state fails to open instead of restoring garbage.
+//!
+use crate::error::{ChannelError, Result};
+use crate::utils::secret::Secret;
+use chacha20poly1305::aead::{generic_array::GenericArray, Aead, NewAead};
+use chacha20poly1305::ChaCha20Poly1305;
+use core::cell::RefCell;
+use hmac::Hmac;
+use iota_streams::core::prelude::Rc;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const MAGIC: &[u8] = b"CLST";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KDF_ROUNDS: u32 = 10_000;
+
+///
+/// Storage of the sealed channel states, by id
+///
+pub trait StateStore {
+    ///
+    /// Store the state, replacing the previous one
+    ///
+    fn save(&self, id: &str, bytes: &[u8]) -> Result<()>;
+
+    ///
+    /// Stored state, `None` if there is none
+    ///
+    fn load(&self, id: &str) -> Result<Option<Vec<u8>>>;
+
+    ///
+    /// Remove the state, nothing happens if there is none
+    ///
+    fn delete(&self, id: &str) -> Result<()>;
+}
+
+///
+/// States kept in memory, for tests and short-lived processes
+///
+/// Clones share the same states
+///
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    states: Rc<RefCell<HashMap<String, Vec<u8>>>>,
+}
+
+impl MemoryStore {
+    ///
+    /// Create an empty store
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+///
+/// The ids only, not the states
+///
+impl fmt::Debug for MemoryStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let states = self.states.borrow();
+        let mut ids: Vec<&String> = states.keys().collect();
+        ids.sort();
+        f.debug_struct("MemoryStore").field("ids", &ids).finish()
+    }
+}
+
+impl StateStore for MemoryStore {
+    fn save(&self, id: &str, bytes: &[u8]) -> Result<()> {
+        self.states
+            .borrow_mut()
+            .insert(id.to_string(), bytes.to_vec());
+        Ok(())
+    }
+
+    fn load(&self, id: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.states.borrow().get(id).cloned())
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        self.states.borrow_mut().remove(id);
+        Ok(())
+    }
+}
+
+///
+/// States kept in a directory, one `<id>.state` file each
+///
+/// A state is written to a temporary file, synced, then renamed over the previous one:
+/// a crash during a save leaves the previous state untouched
+///
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    ///
+    /// Store the states in `dir`, created if missing
+    ///
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(FileStore {
+            dir: dir.as_ref().to_path_buf(),
+        })
+    }
+
+    ///
+    /// File holding a state
+    ///
+    pub fn path(&self, id: &str) -> Result<PathBuf> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            && !id.starts_with('.');
+        if !valid {
+            return Err(ChannelError::State(format!(
+                "Invalid state id {:?}, only letters, digits, '-', '_' and '.' are allowed",
+                id
+            )));
+        }
+        Ok(self.dir.join(format!("{}.state", id)))
+    }
+}
+
+impl StateStore for FileStore {
+    fn save(&self, id: &str, bytes: &[u8]) -> Result<()> {
+        let path = self.path(id)?;
+        let tmp = path.with_extension("state.tmp");
+        {
+            let mut file = File::create(&tmp)?;
+            file.write_all(bytes)?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp, &path)?;
+        // Persist the rename itself, not supported on every platform
+        if let Ok(dir) = File::open(&self.dir) {
+            let _ = dir.sync_all();
+        }
+        Ok(())
+    }
+
+    fn load(&self, id: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.path(id)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read(path)?))
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        let path = self.path(id)?;
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// Persisted author
+///
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StoredAuthor {
+    pub(crate) seed: Secret<String>,
+    pub(crate) state: String,
+}
+
+///
+/// Persisted subscriber
+///
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StoredSubscriber {
+    pub(crate) seed: Secret<String>,
+    pub(crate) channel_address: String,
+    pub(crate) announcement_tag: String,
+    pub(crate) subscription_tag: Option<String>,
+    pub(crate) last_keyload_tag: Option<String>,
+    pub(crate) cursor: String,
+}
+
+///
+/// Seal and save a state
+///
+pub(crate) fn save_sealed<S, V>(store: &S, id: &str, password: &str, value: &V) -> Result<()>
+where
+    S: StateStore + ?Sized,
+    V: Serialize,
+{
+    let plaintext = serde_json::to_vec(value)?;
+    store.save(id, &seal(password, &plaintext)?)
+}
+
+///
+/// Load and open a state
+///
+pub(crate) fn load_sealed<S, V>(store: &S, id: &str, password: &str) -> Result<V>
+where
+    S: StateStore + ?Sized,
+    V: for<'de> Deserialize<'de>,
+{
+    let sealed = store
+        .load(id)?
+        .ok_or_else(|| ChannelError::State(format!("No state stored under {:?}", id)))?;
+    let plaintext = open(password, &sealed)?;
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+fn derive_key(password: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    key
+}
+
+pub(crate) fn seal(password: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut rng = rand::thread_rng();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt);
+    rng.fill(&mut nonce);
+
+    let key = derive_key(password, &salt);
+    let cipher = ChaCha20Poly1305::new(GenericArray::from_slice(&key));
+    let ciphertext = cipher
+        .encrypt(GenericArray::from_slice(&nonce), plaintext)
+        .map_err(|_| ChannelError::State("Failed to seal the state".to_string()))?;
+
+    let mut sealed = Vec::with_capacity(MAGIC.len() + 1 + SALT_LEN + NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.push(VERSION);
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+pub(crate) fn open(password: &str, sealed: &[u8]) -> Result<Vec<u8>> {
+    let header_len = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+    if sealed.len() < header_len || &sealed[..MAGIC.len()] != MAGIC {
+        return Err(ChannelError::State(
+            "Stored state is truncated or not a channel state".to_string(),
+        ));
+    }
+    let version = sealed[MAGIC.len()];
+    if version != VERSION {
+        return Err(ChannelError::State(format!(
+            "Unsupported state version {}, expected {}",
+            version, VERSION
+        )));
+    }
+    let salt = &sealed[MAGIC.len() + 1..MAGIC.len() + 1 + SALT_LEN];
+    let nonce = &sealed[MAGIC.len() + 1 + SALT_LEN..header_len];
+
+    let key = derive_key(password, salt);
+    let cipher = ChaCha20Poly1305::new(GenericArray::from_slice(&key));
+    cipher
+        .decrypt(GenericArray::from_slice(nonce), &sealed[header_len..])
+        .map_err(|_| {
+            ChannelError::State(
+                "Failed to open the stored state, wrong password or corrupted state".to_string(),
+            )
+        })
+}
@@ -0,0 +1,108 @@
+//!
+//! One-line summary of a channel
+//!
+//! What a support ticket or a log line needs about a channel: its role, address, how
+//! many messages it sent, where it stands and the node it talks to. Nothing secret goes
+//! in, the node credentials are redacted.
+//!
+use super::config::redact_credentials;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+///
+/// Chars of the addresses and tags kept by `Display`
+///
+pub const SUMMARY_ID_CHARS: usize = 12;
+
+///
+/// Side of the channel
+///
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelRole {
+    /// Author of the channel
+    ///
+    Author,
+    /// Subscriber of the channel
+    ///
+    Subscriber,
+}
+
+impl fmt::Display for ChannelRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Author => "author",
+            Self::Subscriber => "subscriber",
+        })
+    }
+}
+
+///
+/// Summary of a channel, for structured logging
+///
+/// `Display` prints it on one line with the address and head shortened:
+///
+/// ```text
+/// role=author addr=3f2a9c04b1d7… msgs_sent=42 head=9e0c1a77d2f4… node=https://nodes.devnet.iota.org:443
+/// ```
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChannelSummary {
+    /// Side of the channel
+    ///
+    pub role: ChannelRole,
+    /// Channel address, empty before `open` or `connect`
+    ///
+    pub channel_address: String,
+    /// Messages accepted by the node, retries excluded
+    ///
+    pub msgs_sent: u64,
+    /// Last message written by the author, or read by the subscriber
+    ///
+    pub head: Option<String>,
+    /// Node url, credentials redacted
+    ///
+    pub node: String,
+}
+
+impl ChannelSummary {
+    pub(crate) fn new(
+        role: ChannelRole,
+        channel_address: &str,
+        msgs_sent: u64,
+        head: Option<String>,
+        node: &str,
+    ) -> Self {
+        ChannelSummary {
+            role: role,
+            channel_address: channel_address.to_string(),
+            msgs_sent: msgs_sent,
+            head: head,
+            node: redact_credentials(node),
+        }
+    }
+}
+
+impl fmt::Display for ChannelSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "role={} addr={} msgs_sent={} head={} node={}",
+            self.role,
+            shorten(&self.channel_address),
+            self.msgs_sent,
+            self.head.as_deref().map_or("-".to_string(), shorten),
+            self.node
+        )
+    }
+}
+
+fn shorten(id: &str) -> String {
+    if id.is_empty() {
+        return "-".to_string();
+    }
+    match id.char_indices().nth(SUMMARY_ID_CHARS) {
+        Some((end, _)) => format!("{}…", &id[..end]),
+        None => id.to_string(),
+    }
+}
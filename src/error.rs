@@ -0,0 +1,584 @@
+//!
+//! Errors
+//!
+use crate::utils::secret::redact_trytes;
+use serde_json::{json, Value};
+use std::time::Duration;
+use thiserror::Error;
+
+///
+/// Result of the channel operations
+///
+pub type Result<T> = std::result::Result<T, ChannelError>;
+
+///
+/// Channel errors
+///
+/// Converts into `anyhow::Error`, so anyhow based applications can keep using `?`
+///
+#[derive(Debug, Error)]
+pub enum ChannelError {
+    /// The node could not be reached or failed to answer
+    ///
+    #[error("Transport error: {0}")]
+    Transport(anyhow::Error),
+    /// The announcement tag doesn't point to the announcement of the channel, e.g. the
+    /// address and tag of two different channels
+    ///
+    #[error(
+        "Announcement {announcement_tag} doesn't belong to channel {channel_address}: {reason}"
+    )]
+    AnnouncementMismatch {
+        /// Channel address given to the subscriber
+        ///
+        channel_address: String,
+        /// Announcement tag given to the subscriber
+        ///
+        announcement_tag: String,
+        /// What was found at the tag
+        ///
+        reason: String,
+    },
+    /// The subscriber is not connected to the channel
+    ///
+    #[error("Channel not connected")]
+    NotConnected,
+    /// The author has not opened the channel
+    ///
+    #[error("Channel not opened")]
+    NotOpened,
+    /// The channel address or message tag is malformed
+    ///
+    #[error("Invalid address {address}: {reason}")]
+    InvalidAddress {
+        /// Channel address and tag
+        ///
+        address: String,
+        /// Why the address was rejected
+        ///
+        reason: String,
+    },
+    /// The payload can't be encoded or decoded
+    ///
+    #[error("Payload encoding error: {0}")]
+    PayloadEncoding(String),
+    /// The payload was framed by a newer version of the crate
+    ///
+    #[error("Unsupported payload framing version {version}, this version reads up to {supported}")]
+    UnsupportedFraming {
+        /// Version byte found in the payload
+        ///
+        version: u8,
+        /// Latest framing version known to this crate
+        ///
+        supported: u8,
+    },
+    /// No message found at the tag
+    ///
+    #[error("Message {0} not found")]
+    MessageNotFound(String),
+    /// The message can't be read, no keyload granted access to this subscriber
+    ///
+    #[error("Access denied to message {0}, no keyload grants access to this subscriber")]
+    AccessDenied(String),
+    /// No keyload published yet, packets can't be written
+    ///
+    #[error(
+        "Channel {0} has no keyload, add a subscriber or a pre-shared key first, or enable the automatic keyload"
+    )]
+    NoKeyload(String),
+    /// The operation is not allowed in the current channel state
+    ///
+    #[error("Invalid channel state: {0}")]
+    State(String),
+    /// Message rejected by the Streams protocol
+    ///
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+    /// Local file error
+    ///
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Invalid configuration
+    ///
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    /// The node lags too far behind the latest milestone
+    ///
+    #[error("Node {node} is unhealthy: milestone lag {lag} exceeds {max_lag}")]
+    NodeUnhealthy {
+        /// Node url
+        ///
+        node: String,
+        /// Milestone lag reported by the node
+        ///
+        lag: u32,
+        /// Maximum tolerated milestone lag
+        ///
+        max_lag: u32,
+    },
+    /// The node is too far behind to publish through it
+    ///
+    #[error("Node {node} is not synced: milestone lag {lag} exceeds {max_lag}")]
+    NodeNotSynced {
+        /// Node url
+        ///
+        node: String,
+        /// Milestone lag reported by the node
+        ///
+        lag: u32,
+        /// Maximum tolerated milestone lag
+        ///
+        max_lag: u32,
+    },
+    /// The node software or version is not supported
+    ///
+    #[error("Node {node} running {app_name} {app_version} is incompatible: {reason}")]
+    IncompatibleNode {
+        /// Node url
+        ///
+        node: String,
+        /// Name of the node software
+        ///
+        app_name: String,
+        /// Version of the node software
+        ///
+        app_version: String,
+        /// Why the node is not supported
+        ///
+        reason: String,
+    },
+    /// The node did not answer in time
+    ///
+    #[error("Timed out after {}s talking to {node}", after.as_secs())]
+    Timeout {
+        /// Node url
+        ///
+        node: String,
+        /// Configured timeout
+        ///
+        after: Duration,
+    },
+    /// The network name doesn't match any pre-defined network nor `custom:<mwm>:<url>`
+    ///
+    #[error("Unknown network {0}")]
+    UnknownNetwork(String),
+    /// Not enough nodes returned the same content
+    ///
+    #[error(
+        "Quorum failure for {link}: {agreeing} nodes agree, {required} required (failed nodes: {failed_nodes:?})"
+    )]
+    QuorumFailure {
+        /// Tag of the message
+        ///
+        link: String,
+        /// Largest number of nodes agreeing on a content
+        ///
+        agreeing: usize,
+        /// Required number of agreeing nodes
+        ///
+        required: usize,
+        /// Nodes that failed to answer
+        ///
+        failed_nodes: Vec<String>,
+    },
+    /// Internal panic caught by the `panic_safe` layer
+    ///
+    #[error("Internal error: {0}")]
+    Internal(String),
+    /// The rate limit doesn't allow a send right now
+    ///
+    #[error("Rate limited, retry after {}ms", retry_after.as_millis())]
+    RateLimited {
+        /// Time to wait before the next send is allowed
+        ///
+        retry_after: Duration,
+    },
+    /// The payload doesn't conform to the JSON schema
+    ///
+    #[error("Message {tag} doesn't conform to the schema: {}", errors.join(", "))]
+    SchemaViolation {
+        /// Tag of the message
+        ///
+        tag: String,
+        /// Violations found
+        ///
+        errors: Vec<String>,
+    },
+    /// The channel was published by a newer version of the crate
+    ///
+    #[error(
+        "Channel protocol version {version} is not supported, this version reads up to {supported}"
+    )]
+    UnsupportedProtocol {
+        /// Protocol version announced by the author
+        ///
+        version: u32,
+        /// Latest protocol version known to this crate
+        ///
+        supported: u32,
+    },
+    /// The message spans more transactions than the node accepts in a bundle
+    ///
+    #[error("Message {tag} spans {fragments} transactions, the limit is {max}")]
+    TooManyFragments {
+        /// Tag of the message
+        ///
+        tag: String,
+        /// Transactions the message spans
+        ///
+        fragments: usize,
+        /// Maximum transactions per message configured
+        ///
+        max: usize,
+    },
+    /// The write would break the publishing policy of the author
+    ///
+    #[error(
+        "Policy violation: {reason}, {messages} messages of {payload_bytes} bytes since keyload {keyload_tag}"
+    )]
+    PolicyViolation {
+        /// Limit reached
+        ///
+        reason: String,
+        /// Tag of the keyload opening the current epoch
+        ///
+        keyload_tag: String,
+        /// Packets written in the current epoch
+        ///
+        messages: u32,
+        /// Payload bytes written in the current epoch
+        ///
+        payload_bytes: u64,
+    },
+    /// The digest of the payloads received doesn't match the expected one
+    ///
+    #[error(
+        "Digest mismatch on message {tag}: expected {expected}, got {}",
+        actual.as_deref().unwrap_or("none")
+    )]
+    DigestMismatch {
+        /// Tag of the message
+        ///
+        tag: String,
+        /// Digest expected, e.g. recorded by the author
+        ///
+        expected: String,
+        /// Digest of the payloads received, `None` for the messages without payload
+        ///
+        actual: Option<String>,
+    },
+    /// A decoder is already registered for the content type
+    ///
+    #[error("A decoder is already registered for content type {content_type}")]
+    DecoderConflict {
+        /// Content type registered twice
+        ///
+        content_type: String,
+    },
+}
+
+///
+/// Channel errors, former name of `ChannelError`
+///
+pub type Error = ChannelError;
+
+///
+/// Stable identity of a `ChannelError`
+///
+/// The numbers and names never change across releases, new errors get new codes. The
+/// numbers are the ones reported by `channels_lite_last_error_code` in the C bindings,
+/// 0 is never used.
+///
+#[repr(i32)]
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// The node could not be reached or failed to answer
+    ///
+    Transport = 1,
+    /// The announcement tag doesn't point to the announcement of the channel
+    ///
+    AnnouncementMismatch = 2,
+    /// The subscriber is not connected to the channel
+    ///
+    NotConnected = 3,
+    /// The author has not opened the channel
+    ///
+    NotOpened = 4,
+    /// The channel address or message tag is malformed
+    ///
+    InvalidAddress = 5,
+    /// The payload can't be encoded or decoded
+    ///
+    PayloadEncoding = 6,
+    /// The payload was framed by a newer version of the crate
+    ///
+    UnsupportedFraming = 7,
+    /// No message found at the tag
+    ///
+    MessageNotFound = 8,
+    /// No keyload grants access to the message
+    ///
+    AccessDenied = 9,
+    /// No keyload published yet
+    ///
+    NoKeyload = 10,
+    /// The operation is not allowed in the current channel state
+    ///
+    State = 11,
+    /// Message rejected by the Streams protocol
+    ///
+    Protocol = 12,
+    /// Local file error
+    ///
+    Io = 13,
+    /// Invalid configuration
+    ///
+    Config = 14,
+    /// The node lags too far behind the latest milestone
+    ///
+    NodeUnhealthy = 15,
+    /// The node is too far behind to publish through it
+    ///
+    NodeNotSynced = 16,
+    /// The node software or version is not supported
+    ///
+    IncompatibleNode = 17,
+    /// The node did not answer in time
+    ///
+    Timeout = 18,
+    /// The network name doesn't match any pre-defined network nor `custom:<mwm>:<url>`
+    ///
+    UnknownNetwork = 19,
+    /// Not enough nodes returned the same content
+    ///
+    QuorumFailure = 20,
+    /// Internal panic caught by the `panic_safe` layer
+    ///
+    Internal = 21,
+    /// The rate limit doesn't allow a send right now
+    ///
+    RateLimited = 22,
+    /// The payload doesn't conform to the JSON schema
+    ///
+    SchemaViolation = 23,
+    /// The channel was published by a newer version of the crate
+    ///
+    UnsupportedProtocol = 24,
+    /// The message spans more transactions than the node accepts in a bundle
+    ///
+    TooManyFragments = 25,
+    /// The write would break the publishing policy of the author
+    ///
+    PolicyViolation = 26,
+    /// The digest of the payloads received doesn't match the expected one
+    ///
+    DigestMismatch = 27,
+    /// A decoder is already registered for the content type
+    ///
+    DecoderConflict = 28,
+}
+
+impl ErrorCode {
+    ///
+    /// Numeric code
+    ///
+    pub fn as_i32(self) -> i32 {
+        self as i32
+    }
+
+    ///
+    /// Name of the code, e.g. `"not_connected"`
+    ///
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Transport => "transport",
+            Self::AnnouncementMismatch => "announcement_mismatch",
+            Self::NotConnected => "not_connected",
+            Self::NotOpened => "not_opened",
+            Self::InvalidAddress => "invalid_address",
+            Self::PayloadEncoding => "payload_encoding",
+            Self::UnsupportedFraming => "unsupported_framing",
+            Self::MessageNotFound => "message_not_found",
+            Self::AccessDenied => "access_denied",
+            Self::NoKeyload => "no_keyload",
+            Self::State => "state",
+            Self::Protocol => "protocol",
+            Self::Io => "io",
+            Self::Config => "config",
+            Self::NodeUnhealthy => "node_unhealthy",
+            Self::NodeNotSynced => "node_not_synced",
+            Self::IncompatibleNode => "incompatible_node",
+            Self::Timeout => "timeout",
+            Self::UnknownNetwork => "unknown_network",
+            Self::QuorumFailure => "quorum_failure",
+            Self::Internal => "internal",
+            Self::RateLimited => "rate_limited",
+            Self::SchemaViolation => "schema_violation",
+            Self::UnsupportedProtocol => "unsupported_protocol",
+            Self::TooManyFragments => "too_many_fragments",
+            Self::PolicyViolation => "policy_violation",
+            Self::DigestMismatch => "digest_mismatch",
+            Self::DecoderConflict => "decoder_conflict",
+        }
+    }
+}
+
+impl ChannelError {
+    ///
+    /// Whether the failure is transient and the operation worth retrying
+    ///
+    /// Only the transport errors and the timeouts are, a malformed payload or a protocol
+    /// error fails the same way on every attempt
+    ///
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Transport(_) | Self::Timeout { .. } => true,
+            _ => false,
+        }
+    }
+
+    ///
+    /// Stable code of the error
+    ///
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Transport(_) => ErrorCode::Transport,
+            Self::AnnouncementMismatch { .. } => ErrorCode::AnnouncementMismatch,
+            Self::NotConnected => ErrorCode::NotConnected,
+            Self::NotOpened => ErrorCode::NotOpened,
+            Self::InvalidAddress { .. } => ErrorCode::InvalidAddress,
+            Self::PayloadEncoding(_) => ErrorCode::PayloadEncoding,
+            Self::UnsupportedFraming { .. } => ErrorCode::UnsupportedFraming,
+            Self::MessageNotFound(_) => ErrorCode::MessageNotFound,
+            Self::AccessDenied(_) => ErrorCode::AccessDenied,
+            Self::NoKeyload(_) => ErrorCode::NoKeyload,
+            Self::State(_) => ErrorCode::State,
+            Self::Protocol(_) => ErrorCode::Protocol,
+            Self::Io(_) => ErrorCode::Io,
+            Self::Config(_) => ErrorCode::Config,
+            Self::NodeUnhealthy { .. } => ErrorCode::NodeUnhealthy,
+            Self::NodeNotSynced { .. } => ErrorCode::NodeNotSynced,
+            Self::IncompatibleNode { .. } => ErrorCode::IncompatibleNode,
+            Self::Timeout { .. } => ErrorCode::Timeout,
+            Self::UnknownNetwork(_) => ErrorCode::UnknownNetwork,
+            Self::QuorumFailure { .. } => ErrorCode::QuorumFailure,
+            Self::Internal(_) => ErrorCode::Internal,
+            Self::RateLimited { .. } => ErrorCode::RateLimited,
+            Self::SchemaViolation { .. } => ErrorCode::SchemaViolation,
+            Self::UnsupportedProtocol { .. } => ErrorCode::UnsupportedProtocol,
+            Self::TooManyFragments { .. } => ErrorCode::TooManyFragments,
+            Self::PolicyViolation { .. } => ErrorCode::PolicyViolation,
+            Self::DigestMismatch { .. } => ErrorCode::DigestMismatch,
+            Self::DecoderConflict { .. } => ErrorCode::DecoderConflict,
+        }
+    }
+
+    ///
+    /// Machine-readable rendering, for the consumers of the command line client and of
+    /// the C bindings
+    ///
+    /// `{"code": "not_connected", "message": "Channel not connected", "retryable": false}`
+    ///
+    pub fn as_json(&self) -> Value {
+        json!({
+            "code": self.code().as_str(),
+            "message": self.to_string(),
+            "retryable": self.is_retryable(),
+        })
+    }
+
+    ///
+    /// Wrap an error raised by a transport, keeping the channel errors as they are
+    ///
+    pub fn transport(e: anyhow::Error) -> Self {
+        match e.downcast::<ChannelError>() {
+            Ok(e) => e,
+            Err(e) => Self::Transport(e),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ChannelError {
+    ///
+    /// Recover the channel errors carried through Streams, anything else is a protocol
+    /// error, scrubbed of the seed fragments
+    ///
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<ChannelError>() {
+            Ok(e) => return e,
+            Err(e) => e,
+        };
+        match e.downcast::<ConfigError>() {
+            Ok(e) => Self::Config(e),
+            Err(e) => Self::Protocol(redact_trytes(&format!("{:#}", e))),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ChannelError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::PayloadEncoding(e.to_string())
+    }
+}
+
+impl From<base64::DecodeError> for ChannelError {
+    fn from(e: base64::DecodeError) -> Self {
+        Self::PayloadEncoding(e.to_string())
+    }
+}
+
+impl From<failure::Error> for ChannelError {
+    fn from(e: failure::Error) -> Self {
+        Self::PayloadEncoding(e.to_string())
+    }
+}
+
+///
+/// Configuration errors
+///
+#[derive(Debug, PartialEq, Error)]
+pub enum ConfigError {
+    /// The node url can't be used by the client
+    ///
+    #[error("Invalid node url {url:?}: {reason}")]
+    InvalidNodeUrl {
+        /// Node url
+        ///
+        url: String,
+        /// Why the url was rejected
+        ///
+        reason: String,
+    },
+    /// The proxy url can't be used by the client
+    ///
+    #[error("Invalid proxy url {url:?}: {reason}")]
+    InvalidProxy {
+        /// Proxy url, credentials redacted
+        ///
+        url: String,
+        /// Why the url was rejected
+        ///
+        reason: String,
+    },
+    /// A root certificate can't be parsed
+    ///
+    #[error("Invalid root certificate #{index}: {reason}")]
+    InvalidCertificate {
+        /// Position of the certificate in the configuration
+        ///
+        index: usize,
+        /// Why the certificate was rejected
+        ///
+        reason: String,
+    },
+    /// The seed or the seed alphabet can't be used, the seed itself is left out
+    ///
+    #[error("Invalid seed: {0}")]
+    InvalidSeed(String),
+    /// The JSON schema can't be parsed or compiled
+    ///
+    #[error("Invalid JSON schema: {0}")]
+    InvalidSchema(String),
+}
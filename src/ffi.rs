@@ -0,0 +1,711 @@
+//!
+//! C bindings
+//!
+//! `extern "C"` access to the author and the subscriber through opaque handles. The
+//! header is generated by cbindgen into `include/channels_lite.h` when building with
+//! the `ffi` feature.
+//!
+//! Every function returns a `ChannelsLiteError`, `CHANNELS_LITE_ERROR_OK` on success,
+//! and never unwinds into the caller: panics are reported as
+//! `CHANNELS_LITE_ERROR_INTERNAL`. On failure `channels_lite_last_error_message`
+//! describes the error and `channels_lite_last_error_code` gives its stable
+//! `ErrorCode`.
+//!
+//! Memory ownership:
+//! - handles are created by the `*_new` functions and released with the matching
+//!   `*_free` function
+//! - strings written to the `out_*` arguments belong to the caller, release them with
+//!   `channels_lite_string_free`
+//! - the string returned by `channels_lite_last_error_message` belongs to the library,
+//!   it is valid until the next call on the same thread and must not be freed
+//! - the arguments are borrowed for the duration of the call only
+//!
+// Raw pointers cross the boundary, each dereference is checked for null first
+#![allow(unsafe_code)]
+use crate::channels_lite::{channel_author, channel_subscriber, Network};
+use crate::error::ChannelError;
+use crate::panic_safe::guard;
+use crate::utils::payload::json::PayloadBuilder;
+use core::cell::RefCell;
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    ptr, slice,
+    str::FromStr,
+};
+
+///
+/// Status of a call
+///
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelsLiteError {
+    /// Success
+    ///
+    Ok = 0,
+    /// A required pointer argument is null
+    ///
+    NullArgument = 1,
+    /// A string argument is not valid UTF-8, or a JSON buffer is not valid JSON
+    ///
+    InvalidArgument = 2,
+    /// The node could not be reached or failed to answer
+    ///
+    Transport = 3,
+    /// The subscriber is not connected to the channel
+    ///
+    NotConnected = 4,
+    /// The author has not opened the channel
+    ///
+    NotOpened = 5,
+    /// The channel address or message tag is malformed
+    ///
+    InvalidAddress = 6,
+    /// The payload can't be encoded or decoded
+    ///
+    PayloadEncoding = 7,
+    /// No message found at the tag
+    ///
+    MessageNotFound = 8,
+    /// No keyload grants access to the message
+    ///
+    AccessDenied = 9,
+    /// The operation is not allowed in the current channel state
+    ///
+    State = 10,
+    /// Invalid configuration, e.g. an unknown network
+    ///
+    Config = 11,
+    /// Internal error, a panic caught at the boundary
+    ///
+    Internal = 12,
+    /// Any other error
+    ///
+    Other = 13,
+}
+
+///
+/// Channel author handle
+///
+pub struct ChannelsLiteAuthor {
+    channel: channel_author::Channel,
+}
+
+///
+/// Channel subscriber handle
+///
+pub struct ChannelsLiteSubscriber {
+    channel: channel_subscriber::Channel,
+}
+
+///
+/// In-memory tangle handle, shared by the channels created on it
+///
+#[cfg(feature = "test-transport")]
+pub struct ChannelsLiteMockTangle {
+    tangle: crate::transport::mock::MockTransport,
+}
+
+struct FfiError {
+    code: ChannelsLiteError,
+    /// `ErrorCode` of the channel error, 0 for the errors raised by the bindings
+    ///
+    error_code: i32,
+    message: String,
+}
+
+impl FfiError {
+    fn new(code: ChannelsLiteError, message: &str) -> Self {
+        FfiError {
+            code: code,
+            error_code: 0,
+            message: message.to_string(),
+        }
+    }
+}
+
+///
+/// Error of the last failed call on this thread
+///
+struct LastError {
+    code: i32,
+    message: CString,
+}
+
+impl From<ChannelError> for FfiError {
+    fn from(e: ChannelError) -> Self {
+        let code = match &e {
+            ChannelError::Transport(_)
+            | ChannelError::Timeout { .. }
+            | ChannelError::NodeUnhealthy { .. }
+            | ChannelError::NodeNotSynced { .. }
+            | ChannelError::QuorumFailure { .. } => ChannelsLiteError::Transport,
+            ChannelError::NotConnected => ChannelsLiteError::NotConnected,
+            ChannelError::NotOpened => ChannelsLiteError::NotOpened,
+            ChannelError::InvalidAddress { .. } | ChannelError::AnnouncementMismatch { .. } => {
+                ChannelsLiteError::InvalidAddress
+            }
+            ChannelError::PayloadEncoding(_)
+            | ChannelError::UnsupportedFraming { .. }
+            | ChannelError::SchemaViolation { .. }
+            | ChannelError::TooManyFragments { .. } => ChannelsLiteError::PayloadEncoding,
+            ChannelError::UnsupportedProtocol { .. } => ChannelsLiteError::Config,
+            ChannelError::MessageNotFound(_) => ChannelsLiteError::MessageNotFound,
+            ChannelError::AccessDenied(_) => ChannelsLiteError::AccessDenied,
+            ChannelError::State(_)
+            | ChannelError::NoKeyload(_)
+            | ChannelError::RateLimited { .. }
+            | ChannelError::PolicyViolation { .. }
+            | ChannelError::DecoderConflict { .. } => ChannelsLiteError::State,
+            ChannelError::Config(_)
+            | ChannelError::UnknownNetwork(_)
+            | ChannelError::IncompatibleNode { .. } => ChannelsLiteError::Config,
+            ChannelError::Internal(_) => ChannelsLiteError::Internal,
+            ChannelError::Protocol(_)
+            | ChannelError::Io(_)
+            | ChannelError::DigestMismatch { .. } => ChannelsLiteError::Other,
+        };
+        FfiError {
+            code: code,
+            error_code: e.code().as_i32(),
+            message: e.to_string(),
+        }
+    }
+}
+
+type FfiResult<T> = std::result::Result<T, FfiError>;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<LastError>> = RefCell::new(None);
+}
+
+///
+/// Run a call, recording its error and catching its panics
+///
+fn run<F>(op: F) -> ChannelsLiteError
+where
+    F: FnOnce() -> FfiResult<()>,
+{
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+    match guard(op) {
+        Ok(()) => ChannelsLiteError::Ok,
+        Err(e) => {
+            let message = CString::new(e.message.replace('\0', " ")).unwrap_or_default();
+            LAST_ERROR.with(|last| {
+                *last.borrow_mut() = Some(LastError {
+                    code: e.error_code,
+                    message: message,
+                })
+            });
+            e.code
+        }
+    }
+}
+
+unsafe fn str_arg<'a>(arg: *const c_char, name: &str) -> FfiResult<&'a str> {
+    if arg.is_null() {
+        return Err(FfiError::new(
+            ChannelsLiteError::NullArgument,
+            &format!("{} is null", name),
+        ));
+    }
+    CStr::from_ptr(arg).to_str().map_err(|e| {
+        FfiError::new(
+            ChannelsLiteError::InvalidArgument,
+            &format!("{} is not valid UTF-8: {}", name, e),
+        )
+    })
+}
+
+unsafe fn opt_str_arg<'a>(arg: *const c_char, name: &str) -> FfiResult<Option<&'a str>> {
+    if arg.is_null() {
+        return Ok(None);
+    }
+    str_arg(arg, name).map(Some)
+}
+
+unsafe fn json_arg(
+    data: *const u8,
+    len: usize,
+    name: &str,
+) -> FfiResult<Option<serde_json::Value>> {
+    if data.is_null() {
+        return Ok(None);
+    }
+    serde_json::from_slice(slice::from_raw_parts(data, len))
+        .map(Some)
+        .map_err(|e| {
+            FfiError::new(
+                ChannelsLiteError::InvalidArgument,
+                &format!("{} is not valid JSON: {}", name, e),
+            )
+        })
+}
+
+unsafe fn handle_arg<'a, H>(handle: *mut H) -> FfiResult<&'a mut H> {
+    handle
+        .as_mut()
+        .ok_or_else(|| FfiError::new(ChannelsLiteError::NullArgument, "handle is null"))
+}
+
+unsafe fn write_handle<H>(out: *mut *mut H, handle: H) -> FfiResult<()> {
+    if out.is_null() {
+        return Err(FfiError::new(
+            ChannelsLiteError::NullArgument,
+            "output argument is null",
+        ));
+    }
+    *out = Box::into_raw(Box::new(handle));
+    Ok(())
+}
+
+fn into_c_string(s: String) -> FfiResult<*mut c_char> {
+    CString::new(s).map(CString::into_raw).map_err(|e| {
+        FfiError::new(
+            ChannelsLiteError::PayloadEncoding,
+            &format!("string contains a NUL byte: {}", e),
+        )
+    })
+}
+
+unsafe fn write_string(out: *mut *mut c_char, s: String) -> FfiResult<()> {
+    if out.is_null() {
+        return Err(FfiError::new(
+            ChannelsLiteError::NullArgument,
+            "output argument is null",
+        ));
+    }
+    *out = into_c_string(s)?;
+    Ok(())
+}
+
+fn network_arg(network: &str) -> FfiResult<Network> {
+    Network::from_str(network).map_err(FfiError::from)
+}
+
+fn packets_json(packets: Vec<(Option<String>, Option<String>)>) -> FfiResult<String> {
+    let packets: Vec<serde_json::Value> = packets
+        .into_iter()
+        .map(|(public, masked)| serde_json::json!({ "public": public, "masked": masked }))
+        .collect();
+    serde_json::to_string(&packets).map_err(|e| ChannelError::from(e).into())
+}
+
+///
+/// Message of the last error raised on this thread, null if the last call succeeded
+///
+/// The string belongs to the library, do not free it
+///
+#[no_mangle]
+pub extern "C" fn channels_lite_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(error) => error.message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+///
+/// Stable `ErrorCode` of the last error raised on this thread
+///
+/// 0 if the last call succeeded or failed on its arguments, the returned status tells
+/// which
+///
+#[no_mangle]
+pub extern "C" fn channels_lite_last_error_code() -> i32 {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(error) => error.code,
+        None => 0,
+    })
+}
+
+///
+/// Release a string returned by the library
+///
+/// # Safety
+///
+/// `s` is null or a string written to an `out_*` argument, not freed yet
+///
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+///
+/// Create an author on a pre-defined network, e.g. `"devnet"`
+///
+/// `seed` is optional, a random one is generated when null
+///
+/// # Safety
+///
+/// The string arguments are null or NUL-terminated, `out_author` is writable
+///
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_author_new(
+    network: *const c_char,
+    seed: *const c_char,
+    out_author: *mut *mut ChannelsLiteAuthor,
+) -> ChannelsLiteError {
+    run(|| {
+        let node = network_arg(str_arg(network, "network")?)?;
+        let seed = opt_str_arg(seed, "seed")?.map(str::to_string);
+        let author = ChannelsLiteAuthor {
+            channel: channel_author::Channel::new(node, seed)?,
+        };
+        write_handle(out_author, author)
+    })
+}
+
+///
+/// Release an author
+///
+/// # Safety
+///
+/// `author` is null or a handle returned by `channels_lite_author_new`, not freed yet
+///
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_author_free(author: *mut ChannelsLiteAuthor) {
+    if !author.is_null() {
+        drop(Box::from_raw(author));
+    }
+}
+
+///
+/// Open the channel, writing its address and the announcement tag
+///
+/// # Safety
+///
+/// `author` is a live handle, the `out_*` arguments are writable
+///
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_author_open(
+    author: *mut ChannelsLiteAuthor,
+    out_channel_address: *mut *mut c_char,
+    out_announcement_tag: *mut *mut c_char,
+) -> ChannelsLiteError {
+    run(|| {
+        let author = handle_arg(author)?;
+        if out_channel_address.is_null() || out_announcement_tag.is_null() {
+            return Err(FfiError::new(
+                ChannelsLiteError::NullArgument,
+                "output argument is null",
+            ));
+        }
+        let (channel_address, announcement_tag) = author.channel.open()?;
+        let channel_address = into_c_string(channel_address)?;
+        match into_c_string(announcement_tag) {
+            Ok(announcement_tag) => {
+                *out_channel_address = channel_address;
+                *out_announcement_tag = announcement_tag;
+                Ok(())
+            }
+            Err(e) => {
+                channels_lite_string_free(channel_address);
+                Err(e)
+            }
+        }
+    })
+}
+
+///
+/// Grant a subscriber access, writing the keyload tag
+///
+/// # Safety
+///
+/// `author` is a live handle, `subscribe_tag` is NUL-terminated, `out_keyload_tag` is
+/// writable
+///
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_author_add_subscriber(
+    author: *mut ChannelsLiteAuthor,
+    subscribe_tag: *const c_char,
+    out_keyload_tag: *mut *mut c_char,
+) -> ChannelsLiteError {
+    run(|| {
+        let author = handle_arg(author)?;
+        let subscribe_tag = str_arg(subscribe_tag, "subscribe_tag")?;
+        let keyload_tag = author.channel.add_subscriber(subscribe_tag.to_string())?;
+        write_string(out_keyload_tag, keyload_tag)
+    })
+}
+
+///
+/// Write a signed packet from UTF-8 JSON buffers, writing the message tag
+///
+/// Either buffer may be null to leave that part of the packet empty
+///
+/// # Safety
+///
+/// `author` is a live handle, each buffer is null or `*_len` readable bytes,
+/// `out_tag` is writable
+///
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_author_write_signed(
+    author: *mut ChannelsLiteAuthor,
+    public_json: *const u8,
+    public_len: usize,
+    masked_json: *const u8,
+    masked_len: usize,
+    out_tag: *mut *mut c_char,
+) -> ChannelsLiteError {
+    run(|| {
+        let author = handle_arg(author)?;
+        let mut builder = PayloadBuilder::new();
+        if let Some(public) = json_arg(public_json, public_len, "public_json")? {
+            builder.public(&public)?;
+        }
+        if let Some(masked) = json_arg(masked_json, masked_len, "masked_json")? {
+            builder.masked(&masked)?;
+        }
+        let tag = author.channel.write_signed(builder.build())?;
+        write_string(out_tag, tag)
+    })
+}
+
+///
+/// Create a subscriber on a pre-defined network, e.g. `"devnet"`
+///
+/// `seed` is optional, a random one is generated when null
+///
+/// # Safety
+///
+/// The string arguments are null or NUL-terminated, `out_subscriber` is writable
+///
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_subscriber_new(
+    network: *const c_char,
+    channel_address: *const c_char,
+    announcement_tag: *const c_char,
+    seed: *const c_char,
+    out_subscriber: *mut *mut ChannelsLiteSubscriber,
+) -> ChannelsLiteError {
+    run(|| {
+        let node = network_arg(str_arg(network, "network")?)?;
+        let channel_address = str_arg(channel_address, "channel_address")?.to_string();
+        let announcement_tag = str_arg(announcement_tag, "announcement_tag")?.to_string();
+        let seed = opt_str_arg(seed, "seed")?.map(str::to_string);
+        let subscriber = ChannelsLiteSubscriber {
+            channel: channel_subscriber::Channel::new(
+                node,
+                channel_address,
+                announcement_tag,
+                seed,
+            )?,
+        };
+        write_handle(out_subscriber, subscriber)
+    })
+}
+
+///
+/// Release a subscriber
+///
+/// # Safety
+///
+/// `subscriber` is null or a handle returned by a `channels_lite_subscriber_new*`
+/// function, not freed yet
+///
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_subscriber_free(subscriber: *mut ChannelsLiteSubscriber) {
+    if !subscriber.is_null() {
+        drop(Box::from_raw(subscriber));
+    }
+}
+
+///
+/// Connect to the channel, writing the subscription tag
+///
+/// # Safety
+///
+/// `subscriber` is a live handle, `out_subscription_tag` is writable
+///
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_subscriber_connect(
+    subscriber: *mut ChannelsLiteSubscriber,
+    out_subscription_tag: *mut *mut c_char,
+) -> ChannelsLiteError {
+    run(|| {
+        let subscriber = handle_arg(subscriber)?;
+        let subscription_tag = subscriber.channel.connect()?;
+        write_string(out_subscription_tag, subscription_tag)
+    })
+}
+
+///
+/// Update the keyload
+///
+/// # Safety
+///
+/// `subscriber` is a live handle, `keyload_tag` is NUL-terminated
+///
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_subscriber_update_keyload(
+    subscriber: *mut ChannelsLiteSubscriber,
+    keyload_tag: *const c_char,
+) -> ChannelsLiteError {
+    run(|| {
+        let subscriber = handle_arg(subscriber)?;
+        let keyload_tag = str_arg(keyload_tag, "keyload_tag")?;
+        subscriber
+            .channel
+            .update_keyload(keyload_tag.to_string())
+            .map_err(FfiError::from)
+    })
+}
+
+///
+/// Read a signed packet, writing a JSON array of `{"public": .., "masked": ..}`
+///
+/// # Safety
+///
+/// `subscriber` is a live handle, `signed_packet_tag` is NUL-terminated, `out_json`
+/// is writable
+///
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_subscriber_read_signed(
+    subscriber: *mut ChannelsLiteSubscriber,
+    signed_packet_tag: *const c_char,
+    out_json: *mut *mut c_char,
+) -> ChannelsLiteError {
+    run(|| {
+        let subscriber = handle_arg(subscriber)?;
+        let signed_packet_tag = str_arg(signed_packet_tag, "signed_packet_tag")?;
+        let packets = subscriber
+            .channel
+            .read_signed(signed_packet_tag.to_string())?;
+        write_string(out_json, packets_json(packets)?)
+    })
+}
+
+///
+/// New messages since the previous call, writing a JSON array of
+/// `{"tag": .., "kind": .., "public": .., "masked": ..}`
+///
+/// # Safety
+///
+/// `subscriber` is a live handle, `out_json` is writable
+///
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_subscriber_poll_new(
+    subscriber: *mut ChannelsLiteSubscriber,
+    out_json: *mut *mut c_char,
+) -> ChannelsLiteError {
+    run(|| {
+        let subscriber = handle_arg(subscriber)?;
+        let msgs: Vec<serde_json::Value> = subscriber
+            .channel
+            .poll_new()?
+            .into_iter()
+            .map(|msg| {
+                serde_json::json!({
+                    "tag": msg.tag,
+                    "kind": msg.kind.as_str(),
+                    "public": msg.public,
+                    "masked": msg.masked,
+                })
+            })
+            .collect();
+        let json = serde_json::to_string(&msgs).map_err(ChannelError::from)?;
+        write_string(out_json, json)
+    })
+}
+
+///
+/// Create an in-memory tangle, for tests
+///
+/// # Safety
+///
+/// `out_tangle` is writable
+///
+#[cfg(feature = "test-transport")]
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_mock_tangle_new(
+    out_tangle: *mut *mut ChannelsLiteMockTangle,
+) -> ChannelsLiteError {
+    run(|| {
+        let tangle = ChannelsLiteMockTangle {
+            tangle: crate::transport::mock::MockTransport::new(),
+        };
+        write_handle(out_tangle, tangle)
+    })
+}
+
+///
+/// Release an in-memory tangle, the channels created on it keep it alive
+///
+/// # Safety
+///
+/// `tangle` is null or a handle returned by `channels_lite_mock_tangle_new`, not freed
+/// yet
+///
+#[cfg(feature = "test-transport")]
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_mock_tangle_free(tangle: *mut ChannelsLiteMockTangle) {
+    if !tangle.is_null() {
+        drop(Box::from_raw(tangle));
+    }
+}
+
+///
+/// Create an author on an in-memory tangle, for tests
+///
+/// # Safety
+///
+/// `tangle` is a live handle, `seed` is null or NUL-terminated, `out_author` is
+/// writable
+///
+#[cfg(feature = "test-transport")]
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_author_new_mock(
+    tangle: *mut ChannelsLiteMockTangle,
+    seed: *const c_char,
+    out_author: *mut *mut ChannelsLiteAuthor,
+) -> ChannelsLiteError {
+    run(|| {
+        let tangle = handle_arg(tangle)?;
+        let seed = opt_str_arg(seed, "seed")?.map(str::to_string);
+        let author = ChannelsLiteAuthor {
+            channel: channel_author::Channel::new_with_transport(
+                Network::Devnet,
+                crate::transport::ChannelTransport::Mock(tangle.tangle.clone()),
+                seed,
+            )?,
+        };
+        write_handle(out_author, author)
+    })
+}
+
+///
+/// Create a subscriber on an in-memory tangle, for tests
+///
+/// # Safety
+///
+/// `tangle` is a live handle, the string arguments are null or NUL-terminated,
+/// `out_subscriber` is writable
+///
+#[cfg(feature = "test-transport")]
+#[no_mangle]
+pub unsafe extern "C" fn channels_lite_subscriber_new_mock(
+    tangle: *mut ChannelsLiteMockTangle,
+    channel_address: *const c_char,
+    announcement_tag: *const c_char,
+    seed: *const c_char,
+    out_subscriber: *mut *mut ChannelsLiteSubscriber,
+) -> ChannelsLiteError {
+    run(|| {
+        let tangle = handle_arg(tangle)?;
+        let channel_address = str_arg(channel_address, "channel_address")?.to_string();
+        let announcement_tag = str_arg(announcement_tag, "announcement_tag")?.to_string();
+        let seed = opt_str_arg(seed, "seed")?.map(str::to_string);
+        let subscriber = ChannelsLiteSubscriber {
+            channel: channel_subscriber::Channel::new_with_transport(
+                Network::Devnet,
+                crate::transport::ChannelTransport::Mock(tangle.tangle.clone()),
+                channel_address,
+                announcement_tag,
+                seed,
+            )?,
+        };
+        write_handle(out_subscriber, subscriber)
+    })
+}
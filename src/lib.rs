@@ -11,6 +11,17 @@
 #![cfg_attr(not(debug_assertions), deny(warnings))]
 
 pub mod channels_lite;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "panic_safe")]
+pub mod panic_safe;
 pub use channels_lite as channels;
+pub mod prelude;
+#[cfg(feature = "testing")]
+pub mod testing;
 
+pub mod transport;
 pub mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
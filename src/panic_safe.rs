@@ -0,0 +1,61 @@
+//!
+//! Panic to Result conversion
+//!
+//! Stopgap protecting long-running services from the panics still hidden behind
+//! `unwrap` calls in the channels: any panic raised while running a channel operation
+//! is converted into `Error::Internal`. The real fix is removing those unwraps.
+//!
+use crate::error::Error;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+///
+/// Run an operation, converting a panic into `Error::Internal`
+///
+/// Works with both the channel results and `anyhow::Result`
+///
+pub fn guard<R, E, F>(op: F) -> Result<R, E>
+where
+    E: From<Error>,
+    F: FnOnce() -> Result<R, E>,
+{
+    match catch_unwind(AssertUnwindSafe(op)) {
+        Ok(res) => res,
+        Err(panic) => {
+            let msg = if let Some(msg) = panic.downcast_ref::<&str>() {
+                msg.to_string()
+            } else if let Some(msg) = panic.downcast_ref::<String>() {
+                msg.clone()
+            } else {
+                "unknown panic".to_string()
+            };
+            Err(Error::Internal(msg).into())
+        }
+    }
+}
+
+///
+/// Panic safe access to the public methods of a channel
+///
+/// ```ignore
+/// let tag = channel.safe(|channel| channel.write_signed(payload))?;
+/// ```
+///
+pub trait PanicSafe {
+    ///
+    /// Run a channel operation, converting a panic into `Error::Internal`
+    ///
+    fn safe<R, E, F>(&mut self, op: F) -> Result<R, E>
+    where
+        E: From<Error>,
+        F: FnOnce(&mut Self) -> Result<R, E>;
+}
+
+impl<C> PanicSafe for C {
+    fn safe<R, E, F>(&mut self, op: F) -> Result<R, E>
+    where
+        E: From<Error>,
+        F: FnOnce(&mut Self) -> Result<R, E>,
+    {
+        guard(|| op(self))
+    }
+}
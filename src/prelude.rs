@@ -0,0 +1,40 @@
+//!
+//! Prelude
+//!
+//! Re-exports what a typical author or subscriber needs, so that the code using the
+//! channels imports from a single module:
+//!
+//! ```ignore
+//! use channels_lite::prelude::*;
+//!
+//! let mut author = AuthorChannel::new(Network::Devnet, None)?;
+//! let tag = author.write_signed(PayloadBuilder::new().public(&data)?.build())?;
+//! ```
+//!
+//! The Streams types are not wrapped: `Transport` with its `Address` and `Message`
+//! types, and the `Bytes` of `PacketPayload`, are the ones of IOTA Streams, re-exported
+//! so they don't need a direct dependency. They change with the Streams version used by
+//! this crate
+//!
+pub use crate::channels_lite::{
+    channel_author::{Channel as AuthorChannel, ChannelBuilder as AuthorBuilder},
+    channel_subscriber::{Channel as SubscriberChannel, ChannelBuilder as SubscriberBuilder},
+    config::{ChannelConfig, NetworkConfig, PowMode},
+    decoders::{Decoded, Decoders},
+    message::{AccessLevel, ChannelMessage, MessageKind, MessageSource, SentMessage},
+    multi_subscriber::MultiSubscriber,
+    node::SendOptions,
+    provenance::ProvenanceRecord,
+    reader::ChannelReader,
+    shutdown::ShutdownReport,
+    snapshot::ChannelSnapshot,
+    store::{FileStore, MemoryStore, StateStore},
+    summary::{ChannelRole, ChannelSummary},
+    Network,
+};
+pub use crate::error::{ChannelError, ErrorCode};
+pub use crate::transport::{Address, ChannelTransport, Message, Transport};
+pub use crate::utils::payload::{json::PayloadBuilder, PacketPayload};
+pub use crate::utils::psk::{new_psk, Psk};
+#[cfg(feature = "wasm")]
+pub use crate::wasm::MessageEnvelope;
@@ -0,0 +1,104 @@
+//!
+//! Channels with a known set of messages already published
+//!
+//! Built on the test tangle with the fixed seeds: the same fixture gives the same
+//! addresses, tags and payloads on every run, for the crates testing their own code
+//! against a populated channel.
+//!
+//! ```ignore
+//! let mut fixture = fixture::populated_channel()?;
+//! let msgs = fixture.channel.subscribers[0].poll_new()?;
+//! assert_eq!(msgs.len(), fixture.messages.len());
+//! ```
+//!
+use super::{TestChannel, TestTangle};
+use crate::channels_lite::message::MessageKind;
+use crate::error::Result;
+use crate::utils::payload::json::PayloadBuilder;
+
+///
+/// Number of packets published by `populated_channel`
+///
+pub const DEFAULT_FIXTURE_MESSAGES: usize = 4;
+
+///
+/// Packet published by a fixture, as the subscribers read it
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixtureMessage {
+    /// Message tag
+    ///
+    pub tag: String,
+    /// Signed packet for the even positions, tagged packet for the odd ones
+    ///
+    pub kind: MessageKind,
+    /// Public data, the position of the packet as JSON
+    ///
+    pub public: String,
+    /// Masked data, `"masked <position>"` as JSON
+    ///
+    pub masked: String,
+}
+
+///
+/// Populated channel on its test tangle
+///
+pub struct Fixture {
+    /// Tangle of the channel, its clock drives the channels
+    ///
+    pub tangle: TestTangle,
+    /// Author and subscribers, the subscribers haven't read the packets yet
+    ///
+    pub channel: TestChannel,
+    /// Packets published after the keyload, in order
+    ///
+    pub messages: Vec<FixtureMessage>,
+}
+
+///
+/// One subscriber and `DEFAULT_FIXTURE_MESSAGES` packets
+///
+pub fn populated_channel() -> Result<Fixture> {
+    populated_channel_with(1, DEFAULT_FIXTURE_MESSAGES)
+}
+
+///
+/// Channel with `subscribers` subscribers granted access and `messages` packets
+/// published after the keyload
+///
+pub fn populated_channel_with(subscribers: usize, messages: usize) -> Result<Fixture> {
+    let tangle = TestTangle::new();
+    let mut channel = tangle.channel(subscribers)?;
+
+    let mut published = Vec::with_capacity(messages);
+    for position in 0..messages {
+        let masked = format!("masked {}", position);
+        let payload = PayloadBuilder::new()
+            .public(&position)?
+            .masked(&masked)?
+            .build();
+        let (tag, kind) = if position % 2 == 0 {
+            (
+                channel.author.write_signed(payload)?,
+                MessageKind::SignedPacket,
+            )
+        } else {
+            (
+                channel.author.write_tagged(payload)?,
+                MessageKind::TaggedPacket,
+            )
+        };
+        published.push(FixtureMessage {
+            tag: tag,
+            kind: kind,
+            public: position.to_string(),
+            masked: format!("{:?}", masked),
+        });
+    }
+
+    Ok(Fixture {
+        tangle: tangle,
+        channel: channel,
+        messages: published,
+    })
+}
@@ -0,0 +1,269 @@
+//!
+//! Deterministic test utilities
+//!
+//! Everything runs on the in-memory transport with a manually driven clock: the seeds,
+//! the addresses and the timestamps are the same on every run.
+//!
+//! ```ignore
+//! let tangle = TestTangle::new();
+//! let mut channel = tangle.channel(2)?;
+//! let tag = channel.author.write_signed(payload)?;
+//! assert_readable(&mut channel.subscribers[0], &tag);
+//! ```
+//!
+//! The `fixture` module builds channels with a known set of messages already published.
+//!
+pub mod fixture;
+
+use crate::channels_lite::{
+    channel_author, channel_subscriber, message::ChannelMessage, reader::ChannelReader, Network,
+};
+use crate::error::Result;
+use crate::transport::{mock::MockTransport, ChannelTransport};
+use crate::utils::{
+    clock::{Clock, MockClock},
+    random_seed::{DEFAULT_SEED_ALPHABET, DEFAULT_SEED_LEN},
+};
+use core::cell::Cell;
+use iota_streams::core::prelude::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+///
+/// Wall-clock time the test clocks start at, 2020-09-13T12:26:40Z
+///
+pub const TEST_EPOCH_SECS: u64 = 1_600_000_000;
+
+///
+/// Author on the test tangle
+///
+pub type TestAuthor = channel_author::Channel<ChannelTransport>;
+
+///
+/// Subscriber on the test tangle
+///
+pub type TestSubscriber = channel_subscriber::Channel<ChannelTransport>;
+
+///
+/// Valid seed, the same for the same `n` on every run
+///
+pub fn fixed_seed(n: u8) -> String {
+    let charset: Vec<char> = DEFAULT_SEED_ALPHABET.chars().collect();
+    format!("channels-lite fixed seed {}", n)
+        .bytes()
+        .cycle()
+        .take(DEFAULT_SEED_LEN)
+        .enumerate()
+        .map(|(i, b)| charset[usize::from(b).wrapping_add(i.wrapping_mul(31)) % charset.len()])
+        .collect()
+}
+
+///
+/// Channel opened on the test tangle, with its subscribers connected and granted
+/// access by the last keyload
+///
+pub struct TestChannel {
+    /// Author of the channel
+    ///
+    pub author: TestAuthor,
+    /// Subscribers, in creation order
+    ///
+    pub subscribers: Vec<TestSubscriber>,
+    /// Channel address
+    ///
+    pub channel_address: String,
+    /// Announcement tag
+    ///
+    pub announcement_tag: String,
+    /// Keyload granting access to every subscriber, `None` without subscribers
+    ///
+    pub keyload_tag: Option<String>,
+}
+
+///
+/// In-memory tangle and clock shared by the channels of a test
+///
+/// The channels get the fixed seeds 0, 1, 2... in creation order, a test creating its
+/// channels in the same order gets the same addresses on every run
+///
+pub struct TestTangle {
+    transport: MockTransport,
+    clock: MockClock,
+    next_seed: Cell<u8>,
+}
+
+impl Default for TestTangle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestTangle {
+    ///
+    /// Create an empty tangle, the clock starts at `TEST_EPOCH_SECS`
+    ///
+    pub fn new() -> Self {
+        TestTangle {
+            transport: MockTransport::new(),
+            clock: MockClock::new(UNIX_EPOCH + Duration::from_secs(TEST_EPOCH_SECS)),
+            next_seed: Cell::new(0),
+        }
+    }
+
+    ///
+    /// Transport on the tangle, for the channels created by hand
+    ///
+    pub fn transport(&self) -> ChannelTransport {
+        ChannelTransport::Mock(self.transport.clone())
+    }
+
+    ///
+    /// Underlying mock transport
+    ///
+    pub fn mock(&self) -> &MockTransport {
+        &self.transport
+    }
+
+    ///
+    /// Clock of the tangle and of the channels created by it
+    ///
+    pub fn clock(&self) -> &MockClock {
+        &self.clock
+    }
+
+    ///
+    /// Current wall-clock time of the tangle
+    ///
+    pub fn now(&self) -> SystemTime {
+        self.clock.system_time()
+    }
+
+    ///
+    /// Author with the next fixed seed, not opened yet
+    ///
+    pub fn author(&self) -> TestAuthor {
+        let mut author = channel_author::Channel::new_with_transport(
+            Network::Devnet,
+            self.transport(),
+            Some(self.next_seed()),
+        )
+        .expect("a channel on the mock tangle is always created");
+        author.set_clock(Rc::new(self.clock.clone()));
+        author
+    }
+
+    ///
+    /// Subscriber of a channel with the next fixed seed, not connected yet
+    ///
+    pub fn subscriber(&self, channel_address: &str, announcement_tag: &str) -> TestSubscriber {
+        let mut subscriber = channel_subscriber::Channel::new_with_transport(
+            Network::Devnet,
+            self.transport(),
+            channel_address.to_string(),
+            announcement_tag.to_string(),
+            Some(self.next_seed()),
+        )
+        .expect("a channel on the mock tangle is always created");
+        subscriber.set_clock(Rc::new(self.clock.clone()));
+        subscriber
+    }
+
+    ///
+    /// Open a channel and connect `subscribers` subscribers to it
+    ///
+    /// Every subscriber is granted access by the last keyload, the messages written
+    /// afterwards are readable by all of them
+    ///
+    pub fn channel(&self, subscribers: usize) -> Result<TestChannel> {
+        let mut author = self.author();
+        let (channel_address, announcement_tag) = author.open()?;
+
+        let mut connected = Vec::with_capacity(subscribers);
+        let mut keyload_tag = None;
+        for _ in 0..subscribers {
+            let mut subscriber = self.subscriber(&channel_address, &announcement_tag);
+            let subscription_tag = subscriber.connect()?;
+            keyload_tag = Some(author.add_subscriber(subscription_tag)?);
+            connected.push(subscriber);
+        }
+        if let Some(keyload_tag) = &keyload_tag {
+            for subscriber in connected.iter_mut() {
+                subscriber.update_keyload(keyload_tag.clone())?;
+            }
+        }
+
+        Ok(TestChannel {
+            author: author,
+            subscribers: connected,
+            channel_address: channel_address,
+            announcement_tag: announcement_tag,
+            keyload_tag: keyload_tag,
+        })
+    }
+
+    ///
+    /// Delay the messages sent from now on, they become readable once the clock has
+    /// moved `delay` forward
+    ///
+    pub fn set_propagation_delay(&self, delay: Duration) {
+        self.transport
+            .set_propagation_delay(Rc::new(self.clock.clone()), delay);
+    }
+
+    ///
+    /// Stamp the messages sent from now on with the attachment time of the clock, as a
+    /// node reporting attachment timestamps
+    ///
+    pub fn stamp_attachments(&self) {
+        self.transport
+            .set_attachment_clock(Rc::new(self.clock.clone()));
+    }
+
+    ///
+    /// Move the clock forward, the messages sent `duration` ago or earlier become
+    /// readable
+    ///
+    pub fn advance(&self, duration: Duration) {
+        self.clock.advance(duration);
+    }
+
+    ///
+    /// Make every message in flight readable, without moving the clock
+    ///
+    pub fn propagate_all(&self) {
+        self.transport
+            .propagate_all()
+            .expect("the mock tangle accepts every message");
+    }
+
+    ///
+    /// Number of messages sent but not readable yet
+    ///
+    pub fn in_flight(&self) -> usize {
+        self.transport.in_flight()
+    }
+
+    fn next_seed(&self) -> String {
+        let n = self.next_seed.get();
+        self.next_seed.set(n.wrapping_add(1));
+        fixed_seed(n)
+    }
+}
+
+///
+/// Read a message, panics if the reader can't
+///
+pub fn assert_readable(reader: &mut dyn ChannelReader, tag: &str) -> ChannelMessage {
+    match reader.read(tag) {
+        Ok(msg) => msg,
+        Err(e) => panic!("message {} is not readable: {}", tag, e),
+    }
+}
+
+///
+/// Check a message can't be read, panics if the reader can
+///
+pub fn assert_not_readable(reader: &mut dyn ChannelReader, tag: &str) {
+    if let Ok(msg) = reader.read(tag) {
+        panic!("message {} is readable: {:?}", tag, msg);
+    }
+}
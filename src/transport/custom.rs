@@ -0,0 +1,81 @@
+//!
+//! Transports implemented outside the crate
+//!
+use anyhow::Result;
+use core::cell::RefCell;
+use core::fmt;
+use iota_streams::app::transport::Transport;
+use iota_streams::app_channels::api::tangle::{Address, Message};
+use iota_streams::core::prelude::Rc;
+
+///
+/// Transport provided by the application, e.g. replaying recorded fixtures or going
+/// through a proxy
+///
+/// Any `Transport` of the channel messages with default send and receive options can
+/// be wrapped. Clones share the same transport.
+///
+#[derive(Clone)]
+pub struct CustomTransport {
+    inner: Rc<RefCell<dyn DynTransport>>,
+}
+
+impl CustomTransport {
+    ///
+    /// Wrap a transport, the messages are sent and received with its default options
+    ///
+    pub fn new<T>(transport: T) -> Self
+    where
+        T: Transport<Address, Message> + 'static,
+        T::SendOptions: Default,
+        T::RecvOptions: Default,
+    {
+        CustomTransport {
+            inner: Rc::new(RefCell::new(transport)),
+        }
+    }
+}
+
+impl fmt::Debug for CustomTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomTransport").finish()
+    }
+}
+
+impl Transport<Address, Message> for CustomTransport {
+    type SendOptions = ();
+
+    fn send_message_with_options(&mut self, msg: &Message, _opt: ()) -> Result<()> {
+        self.inner.borrow_mut().send(msg)
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options(&mut self, link: &Address, _opt: ()) -> Result<Vec<Message>> {
+        self.inner.borrow_mut().recv(link)
+    }
+}
+
+///
+/// Object safe view of a transport with default options
+///
+trait DynTransport {
+    fn send(&mut self, msg: &Message) -> Result<()>;
+
+    fn recv(&mut self, link: &Address) -> Result<Vec<Message>>;
+}
+
+impl<T> DynTransport for T
+where
+    T: Transport<Address, Message>,
+    T::SendOptions: Default,
+    T::RecvOptions: Default,
+{
+    fn send(&mut self, msg: &Message) -> Result<()> {
+        self.send_message_with_options(msg, T::SendOptions::default())
+    }
+
+    fn recv(&mut self, link: &Address) -> Result<Vec<Message>> {
+        self.recv_messages_with_options(link, T::RecvOptions::default())
+    }
+}
@@ -0,0 +1,195 @@
+//!
+//! Offline file transport
+//!
+//! Lets an author run on an air-gapped machine: outgoing messages are written to a
+//! directory as numbered files, the directory is carried to a connected host and
+//! replayed to a node with `publish_directory`. PoW and tip selection happen at
+//! publication time, so the files only hold the binary message and its link.
+//!
+use crate::channels_lite::{config::NetworkConfig, Network};
+use crate::transport::tangle::TangleTransport;
+use anyhow::{anyhow, Result};
+use base64::{decode_config, encode_config, URL_SAFE_NO_PAD};
+use iota_streams::app::message::{BinaryBody, BinaryMessage};
+use iota_streams::app::transport::Transport;
+use iota_streams::app_channels::api::tangle::{Address, Message};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const INBOX_DIR: &str = "inbox";
+const PUBLISHED_FILE: &str = "published";
+const MESSAGE_EXTENSION: &str = "msg";
+
+///
+/// Message as stored on disk
+///
+#[derive(Serialize, Deserialize, Debug)]
+struct StoredMessage {
+    /// Channel address
+    ///
+    appinst: String,
+    /// Message id
+    ///
+    msgid: String,
+    /// Binary body of the message, base64 encoded
+    ///
+    body: String,
+}
+
+impl StoredMessage {
+    fn from_message(msg: &Message) -> Self {
+        StoredMessage {
+            appinst: msg.binary.link.appinst.to_string(),
+            msgid: msg.binary.link.msgid.to_string(),
+            body: encode_config(&msg.binary.body.bytes, URL_SAFE_NO_PAD),
+        }
+    }
+
+    fn to_message(&self) -> Result<Message> {
+        let link = Address::from_str(&self.appinst, &self.msgid)
+            .map_err(|_| anyhow!("Invalid link {}:{}", self.appinst, self.msgid))?;
+        let body = decode_config(&self.body, URL_SAFE_NO_PAD)?;
+        Ok(Message::new(BinaryMessage::new(
+            link,
+            BinaryBody::from(body),
+        )))
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let stored = serde_json::from_slice(&fs::read(path)?)
+            .map_err(|e| anyhow!("Invalid message file {}: {}", path.display(), e))?;
+        Ok(stored)
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+}
+
+///
+/// Transport writing the outgoing messages to a directory
+///
+/// Reads are served from the messages already written and from the ones imported
+/// from the connected side with `import_messages`, e.g. the subscriptions.
+///
+#[derive(Clone, Debug)]
+pub struct FileTransport {
+    dir: PathBuf,
+}
+
+impl FileTransport {
+    ///
+    /// Write the messages to the directory, creating it if missing
+    ///
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(dir.join(INBOX_DIR))?;
+        Ok(FileTransport { dir: dir })
+    }
+
+    ///
+    /// Directory holding the messages
+    ///
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Transport<Address, Message> for FileTransport {
+    type SendOptions = ();
+
+    fn send_message_with_options(&mut self, msg: &Message, _opt: ()) -> Result<()> {
+        let index = message_files(&self.dir)?.len();
+        StoredMessage::from_message(msg).write(&message_path(&self.dir, index))
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options(&mut self, link: &Address, _opt: ()) -> Result<Vec<Message>> {
+        let mut files = message_files(&self.dir)?;
+        files.extend(message_files(&self.dir.join(INBOX_DIR))?);
+
+        let mut msgs = Vec::new();
+        for file in files {
+            let stored = StoredMessage::read(&file)?;
+            if stored.appinst == link.appinst.to_string() && stored.msgid == link.msgid.to_string()
+            {
+                msgs.push(stored.to_message()?);
+            }
+        }
+        Ok(msgs)
+    }
+}
+
+///
+/// Import messages from the connected side, e.g. subscriptions, into the directory
+///
+pub fn import_messages<P: AsRef<Path>>(dir: P, msgs: &[Message]) -> Result<()> {
+    let inbox = dir.as_ref().join(INBOX_DIR);
+    fs::create_dir_all(&inbox)?;
+    let offset = message_files(&inbox)?.len();
+    for (i, msg) in msgs.iter().enumerate() {
+        StoredMessage::from_message(msg).write(&message_path(&inbox, offset + i))?;
+    }
+    Ok(())
+}
+
+///
+/// Replay the messages of the directory to a transport, in order
+///
+/// Messages replayed by a previous call are skipped, so the same directory can be
+/// carried over several times. Returns the ids of the replayed messages.
+///
+pub fn replay_directory<P, T>(dir: P, transport: &mut T) -> Result<Vec<String>>
+where
+    P: AsRef<Path>,
+    T: Transport<Address, Message>,
+    T::SendOptions: Default,
+{
+    let dir = dir.as_ref();
+    let published_path = dir.join(PUBLISHED_FILE);
+    let published: usize = match fs::read_to_string(&published_path) {
+        Ok(count) => count.trim().parse()?,
+        Err(_) => 0,
+    };
+
+    let mut msgids = Vec::new();
+    for (i, file) in message_files(dir)?.iter().enumerate().skip(published) {
+        let stored = StoredMessage::read(file)?;
+        transport.send_message(&stored.to_message()?)?;
+        fs::write(&published_path, (i + 1).to_string())?;
+        msgids.push(stored.msgid);
+    }
+    Ok(msgids)
+}
+
+///
+/// Publish the messages of the directory to a node, in order
+///
+pub fn publish_directory<P: AsRef<Path>>(dir: P, node: Network) -> Result<Vec<String>> {
+    let mut transport = TangleTransport::new(&node, &NetworkConfig::default())?;
+    replay_directory(dir, &mut transport)
+}
+
+fn message_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("{:06}.{}", index, MESSAGE_EXTENSION))
+}
+
+fn message_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .map_or(false, |ext| ext == MESSAGE_EXTENSION)
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
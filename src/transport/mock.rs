@@ -0,0 +1,143 @@
+//!
+//! In-memory mock transport
+//!
+use crate::utils::clock::Clock;
+use anyhow::Result;
+use core::cell::RefCell;
+use instant::Instant;
+use iota_streams::app::transport::{BucketTransport, Transport};
+use iota_streams::app_channels::api::tangle::{Address, Message};
+use iota_streams::core::prelude::Rc;
+use std::time::{Duration, UNIX_EPOCH};
+
+///
+/// Mock transport storing the messages in an in-process map
+///
+/// Clones share the same storage, so an author and its subscribers can be wired to
+/// the same mock tangle.
+///
+/// With a propagation delay, a sent message only becomes readable once the clock has
+/// moved past the delay, as on a node the message has not reached yet.
+///
+/// With an attachment clock, the sent messages are stamped with its wall-clock time, as
+/// the attachment timestamp reported by a node.
+///
+#[derive(Clone)]
+pub struct MockTransport {
+    bucket: Rc<RefCell<BucketTransport<Address, Message>>>,
+    propagation: Rc<RefCell<Propagation>>,
+}
+
+#[derive(Default)]
+struct Propagation {
+    clock: Option<Rc<dyn Clock>>,
+    delay: Duration,
+    attachment_clock: Option<Rc<dyn Clock>>,
+    in_flight: Vec<(Instant, Message)>,
+}
+
+impl MockTransport {
+    ///
+    /// Create an empty mock tangle
+    ///
+    pub fn new() -> Self {
+        MockTransport {
+            bucket: Rc::new(RefCell::new(BucketTransport::new())),
+            propagation: Rc::new(RefCell::new(Propagation::default())),
+        }
+    }
+
+    ///
+    /// Delay the messages sent from now on by `delay` on the clock
+    ///
+    /// A zero delay makes the messages readable as soon as they are sent
+    ///
+    pub fn set_propagation_delay(&self, clock: Rc<dyn Clock>, delay: Duration) {
+        let mut propagation = self.propagation.borrow_mut();
+        propagation.clock = Some(clock);
+        propagation.delay = delay;
+    }
+
+    ///
+    /// Stamp the messages sent from now on with the wall-clock time of `clock`
+    ///
+    pub fn set_attachment_clock(&self, clock: Rc<dyn Clock>) {
+        self.propagation.borrow_mut().attachment_clock = Some(clock);
+    }
+
+    ///
+    /// Make every message in flight readable, whatever the time
+    ///
+    pub fn propagate_all(&self) -> Result<()> {
+        let in_flight: Vec<(Instant, Message)> =
+            self.propagation.borrow_mut().in_flight.drain(..).collect();
+        let mut bucket = self.bucket.borrow_mut();
+        for (_, msg) in in_flight.iter() {
+            bucket.send_message(msg)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Number of messages sent but not readable yet
+    ///
+    pub fn in_flight(&self) -> usize {
+        self.propagation.borrow().in_flight.len()
+    }
+
+    fn propagate_due(&self) -> Result<()> {
+        let due = {
+            let mut propagation = self.propagation.borrow_mut();
+            let now = match &propagation.clock {
+                Some(clock) => clock.now(),
+                None => return Ok(()),
+            };
+            let (due, in_flight): (Vec<_>, Vec<_>) = propagation
+                .in_flight
+                .drain(..)
+                .partition(|(visible_at, _)| *visible_at <= now);
+            propagation.in_flight = in_flight;
+            due
+        };
+        let mut bucket = self.bucket.borrow_mut();
+        for (_, msg) in due.iter() {
+            bucket.send_message(msg)?;
+        }
+        Ok(())
+    }
+}
+
+impl Transport<Address, Message> for MockTransport {
+    type SendOptions = ();
+
+    fn send_message_with_options(&mut self, msg: &Message, _opt: ()) -> Result<()> {
+        let mut msg = msg.clone();
+        {
+            let mut propagation = self.propagation.borrow_mut();
+            if let Some(clock) = &propagation.attachment_clock {
+                msg.timestamp = clock
+                    .system_time()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since_epoch| since_epoch.as_millis() as u64);
+            }
+            let visible_at = match &propagation.clock {
+                Some(clock) if propagation.delay > Duration::from_secs(0) => {
+                    Some(clock.now() + propagation.delay)
+                }
+                _ => None,
+            };
+            if let Some(visible_at) = visible_at {
+                propagation.in_flight.push((visible_at, msg));
+                return Ok(());
+            }
+        }
+        self.bucket.borrow_mut().send_message(&msg)
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options(&mut self, link: &Address, _opt: ()) -> Result<Vec<Message>> {
+        self.propagate_due()?;
+        self.bucket.borrow_mut().recv_messages(link)
+    }
+}
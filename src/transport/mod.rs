@@ -0,0 +1,213 @@
+//!
+//! Transports used by the channels
+//!
+pub mod custom;
+#[cfg(feature = "file-transport")]
+pub mod file;
+#[cfg(feature = "test-transport")]
+pub mod mock;
+pub mod rate_limit;
+pub mod retry;
+pub mod stats;
+pub mod tangle;
+
+use crate::channels_lite::{
+    config::NetworkConfig,
+    node::{self, NodeHealth, NodeInfo, SendOptions},
+};
+use anyhow::{bail, Result};
+use iota_streams::app::transport::tangle::client::{RecvOptions, SendTrytesOptions};
+use retry::RetryingTransport;
+
+///
+/// Streams trait implemented by the custom transports, with its address and message
+/// types, re-exported as they are, not wrapped: the transports use them without a
+/// direct dependency on Streams, they follow the Streams version of this crate
+///
+pub use iota_streams::app::transport::Transport;
+pub use iota_streams::app_channels::api::tangle::{Address, Message};
+
+///
+/// Transport
+///
+pub enum ChannelTransport {
+    /// Iota node client
+    ///
+    Tangle(tangle::TangleTransport),
+    /// In-memory transport, for tests
+    ///
+    #[cfg(feature = "test-transport")]
+    Mock(mock::MockTransport),
+    /// Offline transport writing the messages to a directory
+    ///
+    #[cfg(feature = "file-transport")]
+    File(file::FileTransport),
+    /// Transport provided by the application
+    ///
+    Custom(custom::CustomTransport),
+}
+
+impl Transport<Address, Message> for ChannelTransport {
+    type SendOptions = SendTrytesOptions;
+
+    fn send_message_with_options(&mut self, msg: &Message, opt: SendTrytesOptions) -> Result<()> {
+        match self {
+            Self::Tangle(tangle) => tangle.send_message_with_options(msg, opt),
+            #[cfg(feature = "test-transport")]
+            Self::Mock(mock) => mock.send_message(msg),
+            #[cfg(feature = "file-transport")]
+            Self::File(file) => file.send_message(msg),
+            Self::Custom(custom) => custom.send_message(msg),
+        }
+    }
+
+    type RecvOptions = RecvOptions;
+
+    fn recv_messages_with_options(
+        &mut self,
+        link: &Address,
+        opt: RecvOptions,
+    ) -> Result<Vec<Message>> {
+        match self {
+            Self::Tangle(tangle) => tangle.recv_messages_with_options(link, opt),
+            #[cfg(feature = "test-transport")]
+            Self::Mock(mock) => mock.recv_messages(link),
+            #[cfg(feature = "file-transport")]
+            Self::File(file) => file.recv_messages(link),
+            Self::Custom(custom) => custom.recv_messages(link),
+        }
+    }
+}
+
+impl ChannelTransport {
+    ///
+    /// Detect the send options advertised by the node
+    ///
+    pub(crate) fn detect_send_options(&self, fallback: SendOptions) -> Result<SendOptions> {
+        match self {
+            Self::Tangle(tangle) => node::detect_send_options(tangle.client(), fallback),
+            #[cfg(feature = "test-transport")]
+            Self::Mock(_) => bail!("Send options can't be detected on the mock transport"),
+            #[cfg(feature = "file-transport")]
+            Self::File(_) => bail!("Send options can't be detected on the file transport"),
+            Self::Custom(_) => bail!("Send options can't be detected on a custom transport"),
+        }
+    }
+
+    ///
+    /// Replace the send options applied to every outgoing message
+    ///
+    pub(crate) fn set_send_options(&mut self, send_options: SendOptions) {
+        match self {
+            Self::Tangle(tangle) => tangle.set_send_options(send_options),
+            #[cfg(feature = "test-transport")]
+            Self::Mock(_) => {}
+            #[cfg(feature = "file-transport")]
+            Self::File(_) => {}
+            Self::Custom(_) => {}
+        }
+    }
+
+    ///
+    /// Network configuration of the node client, if any
+    ///
+    pub fn network_config(&self) -> Option<&NetworkConfig> {
+        match self {
+            Self::Tangle(tangle) => Some(tangle.network_config()),
+            #[cfg(feature = "test-transport")]
+            Self::Mock(_) => None,
+            #[cfg(feature = "file-transport")]
+            Self::File(_) => None,
+            Self::Custom(_) => None,
+        }
+    }
+
+    ///
+    /// Query the health of the node behind the transport
+    ///
+    pub(crate) fn node_health(&self, node: &str) -> Result<NodeHealth> {
+        match self {
+            Self::Tangle(tangle) => node::query_health(tangle.client(), node),
+            #[cfg(feature = "test-transport")]
+            Self::Mock(_) => bail!("Node health is not available on the mock transport"),
+            #[cfg(feature = "file-transport")]
+            Self::File(_) => bail!("Node health is not available on the file transport"),
+            Self::Custom(_) => bail!("Node health is not available on a custom transport"),
+        }
+    }
+
+    ///
+    /// Query the software and network of the node behind the transport
+    ///
+    pub(crate) fn node_info(&self, node: &str) -> Result<NodeInfo> {
+        match self {
+            Self::Tangle(tangle) => node::query_info(tangle.client(), node),
+            #[cfg(feature = "test-transport")]
+            Self::Mock(_) => bail!("Node info is not available on the mock transport"),
+            #[cfg(feature = "file-transport")]
+            Self::File(_) => bail!("Node info is not available on the file transport"),
+            Self::Custom(_) => bail!("Node info is not available on a custom transport"),
+        }
+    }
+
+    ///
+    /// Last messages sent through a tangle transport, at most
+    /// `tangle::SENT_LOG_CAPACITY`, with where their proof of work was computed
+    ///
+    pub fn sent_log(&self) -> &[tangle::SentRecord] {
+        match self {
+            Self::Tangle(tangle) => tangle.sent_log(),
+            #[cfg(feature = "test-transport")]
+            Self::Mock(_) => &[],
+            #[cfg(feature = "file-transport")]
+            Self::File(_) => &[],
+            Self::Custom(_) => &[],
+        }
+    }
+}
+
+///
+/// Node checks shared by the author and the subscriber, on the transport they own
+///
+impl RetryingTransport<ChannelTransport> {
+    ///
+    /// Health of the node, fails with `Error::NodeUnhealthy` when the milestone lag
+    /// exceeds `max_lag`
+    ///
+    pub(crate) fn check_node(&self, node: &str, max_lag: u32) -> crate::error::Result<NodeHealth> {
+        let health = self.inner().node_health(node)?;
+        health.ensure_synced(max_lag)?;
+        Ok(health)
+    }
+
+    ///
+    /// Milestone lag of the node, fails with `Error::NodeNotSynced` when it exceeds
+    /// `max_lag`
+    ///
+    pub(crate) fn check_sync(&self, node: &str, max_lag: u32) -> crate::error::Result<u32> {
+        self.inner().node_health(node)?.check_sync(max_lag)
+    }
+
+    ///
+    /// Software and network of the node, fails with `Error::IncompatibleNode` when the
+    /// software is not supported
+    ///
+    pub(crate) fn check_node_compatibility(&self, node: &str) -> crate::error::Result<NodeInfo> {
+        let info = self.inner().node_info(node)?;
+        info.ensure_compatible()?;
+        Ok(info)
+    }
+
+    ///
+    /// Send and fetch through another node, health-checked first
+    ///
+    pub(crate) fn switch_node(
+        &mut self,
+        transport: tangle::TangleTransport,
+        max_lag: u32,
+    ) -> crate::error::Result<()> {
+        node::query_health(transport.client(), transport.node())?.ensure_synced(max_lag)?;
+        *self.inner_mut() = ChannelTransport::Tangle(transport);
+        Ok(())
+    }
+}
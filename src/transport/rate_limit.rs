@@ -0,0 +1,118 @@
+//!
+//! Rate limiting of outgoing messages
+//!
+use instant::Instant;
+use serde::{Deserialize, Serialize};
+use std::{convert::TryInto, time::Duration};
+
+///
+/// Maximum number of sends over a period
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RateLimit {
+    /// Sends allowed over the period, also the size of a burst
+    ///
+    pub max_sends: u32,
+    /// Period
+    ///
+    pub per: Duration,
+}
+
+impl RateLimit {
+    ///
+    /// Allow `max_sends` sends every `per`
+    ///
+    pub fn new(max_sends: u32, per: Duration) -> Self {
+        RateLimit {
+            max_sends: max_sends,
+            per: per,
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.per / self.max_sends.max(1)
+    }
+}
+
+///
+/// Token bucket enforcing a rate limit
+///
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    tokens: u32,
+    last_refill: Option<Instant>,
+}
+
+impl RateLimiter {
+    ///
+    /// Full bucket for the rate limit
+    ///
+    pub fn new(limit: RateLimit) -> Self {
+        RateLimiter {
+            tokens: limit.max_sends,
+            limit: limit,
+            last_refill: None,
+        }
+    }
+
+    ///
+    /// Rate limit enforced
+    ///
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    ///
+    /// Check a send is allowed now, returns the time to wait otherwise
+    ///
+    pub fn ready(&mut self, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+        if self.tokens > 0 {
+            return Ok(());
+        }
+        let next_token = self.last_refill.unwrap_or(now) + self.limit.interval();
+        Err(next_token.saturating_duration_since(now))
+    }
+
+    ///
+    /// Take a token if one is available, returns the time to wait otherwise
+    ///
+    pub fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        self.ready(now)?;
+        self.tokens -= 1;
+        Ok(())
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let last_refill = match self.last_refill {
+            Some(last_refill) => last_refill,
+            None => {
+                self.last_refill = Some(now);
+                return;
+            }
+        };
+        if self.tokens >= self.limit.max_sends {
+            self.last_refill = Some(now);
+            return;
+        }
+
+        let interval = self.limit.interval();
+        if interval == Duration::from_secs(0) {
+            self.tokens = self.limit.max_sends;
+            self.last_refill = Some(now);
+            return;
+        }
+        let new_tokens: u32 = (now.saturating_duration_since(last_refill).as_nanos()
+            / interval.as_nanos())
+        .try_into()
+        .unwrap_or(u32::MAX);
+        if new_tokens > 0 {
+            self.tokens = self
+                .tokens
+                .saturating_add(new_tokens)
+                .min(self.limit.max_sends);
+            self.last_refill = Some(last_refill + interval * new_tokens);
+        }
+    }
+}
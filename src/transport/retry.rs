@@ -0,0 +1,534 @@
+//!
+//! Retrying transport
+//!
+use super::{
+    rate_limit::{RateLimit, RateLimiter},
+    stats::TransportStats,
+    tangle::{fragments, TangleTransport},
+};
+use crate::error::ChannelError;
+use crate::utils::clock::{Clock, SystemClock};
+use anyhow::Result;
+use iota_streams::app::transport::Transport;
+use iota_streams::app_channels::api::tangle::{Address, Message};
+use iota_streams::core::prelude::Rc;
+use rand::Rng;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+///
+/// Number of messages the attachment time, the link and the archive source are
+/// remembered for, the oldest are forgotten first
+///
+pub const ATTACHMENT_CAPACITY: usize = 10_000;
+
+///
+/// Retry configuration
+///
+#[derive(Clone)]
+pub struct RetryConfig {
+    /// Maximum attempts for fetch operations
+    ///
+    pub max_fetch_attempts: u32,
+    /// Maximum attempts for send operations
+    ///
+    pub max_send_attempts: u32,
+    /// Delay before the first retry, doubled on each further attempt
+    ///
+    pub base_delay: Duration,
+    /// Upper bound of the backoff delay
+    ///
+    pub max_delay: Duration,
+    /// Maximum random delay added to each backoff
+    ///
+    pub jitter: Duration,
+    /// Decides whether an error is worth retrying, by default only the transport errors
+    /// and the timeouts are
+    ///
+    /// Errors of the wrapped transport that are not a `ChannelError` are seen as
+    /// `ChannelError::Transport`
+    ///
+    pub is_retryable: fn(&ChannelError) -> bool,
+}
+
+impl RetryConfig {
+    ///
+    /// Configuration that never retries
+    ///
+    pub fn none() -> Self {
+        RetryConfig {
+            max_fetch_attempts: 1,
+            max_send_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    ///
+    /// Backoff delay before the given retry attempt
+    ///
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .checked_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        let jitter_ms = self.jitter.as_millis() as u64;
+        if jitter_ms == 0 {
+            return backoff;
+        }
+        backoff + Duration::from_millis(rand::thread_rng().gen_range(0, jitter_ms + 1))
+    }
+}
+
+impl fmt::Debug for RetryConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryConfig")
+            .field("max_fetch_attempts", &self.max_fetch_attempts)
+            .field("max_send_attempts", &self.max_send_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .finish()
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_fetch_attempts: 5,
+            max_send_attempts: 2,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            jitter: Duration::from_millis(250),
+            is_retryable: ChannelError::is_retryable,
+        }
+    }
+}
+
+///
+/// Transport wrapper retrying failed operations with jittered exponential backoff
+///
+/// The fetches go through the read transport when one is set, e.g. a permanode while
+/// the sends go through a low-latency node. Messages not found are looked up on the
+/// archive node, if one is configured, unless the archive lookups are suspended, e.g.
+/// during a walk. Errors of the wrapped transport are returned as
+/// `ChannelError::Transport`, unless already a `ChannelError`.
+///
+pub struct RetryingTransport<T> {
+    inner: T,
+    reader: Option<T>,
+    config: RetryConfig,
+    stats: TransportStats,
+    clock: Rc<dyn Clock>,
+    archive: Option<TangleTransport>,
+    archived: RecentMessages<()>,
+    archive_lookups: bool,
+    attached: RecentMessages<SystemTime>,
+    links: RecentMessages<String>,
+    rate_limiter: Option<RateLimiter>,
+    max_fragments: Option<usize>,
+}
+
+impl<T> RetryingTransport<T> {
+    ///
+    /// Wrap a transport
+    ///
+    pub fn new(inner: T, config: RetryConfig) -> Self {
+        RetryingTransport {
+            inner: inner,
+            reader: None,
+            config: config,
+            stats: TransportStats::default(),
+            clock: Rc::new(SystemClock),
+            archive: None,
+            archived: RecentMessages::with_capacity(ATTACHMENT_CAPACITY),
+            archive_lookups: true,
+            attached: RecentMessages::with_capacity(ATTACHMENT_CAPACITY),
+            links: RecentMessages::with_capacity(ATTACHMENT_CAPACITY),
+            rate_limiter: None,
+            max_fragments: None,
+        }
+    }
+
+    ///
+    /// Limit the rate of the sends, each attempt waits for a token
+    ///
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.stats.rate_limit = Some(limit.clone());
+        self.rate_limiter = Some(RateLimiter::new(limit));
+        self
+    }
+
+    ///
+    /// Refuse the messages spanning more than `max` transactions, before any attempt
+    ///
+    pub fn with_max_fragments(mut self, max: usize) -> Self {
+        self.max_fragments = Some(max);
+        self
+    }
+
+    ///
+    /// Maximum transactions per message, `None` if not limited
+    ///
+    pub fn max_fragments(&self) -> Option<usize> {
+        self.max_fragments
+    }
+
+    ///
+    /// Check a send is allowed right away, returns the time to wait otherwise
+    ///
+    pub fn send_ready(&mut self) -> Result<(), Duration> {
+        let now = self.clock.now();
+        let ready = match &mut self.rate_limiter {
+            Some(limiter) => limiter.ready(now),
+            None => Ok(()),
+        };
+        if ready.is_err() {
+            self.stats.rate_limited += 1;
+        }
+        ready
+    }
+
+    ///
+    /// Fetch the messages through another transport, the wrapped one is used for the sends
+    /// only
+    ///
+    pub fn with_reader(mut self, reader: T) -> Self {
+        self.reader = Some(reader);
+        self
+    }
+
+    ///
+    /// Replace the transport of the fetches, `None` to fetch through the wrapped one
+    ///
+    pub fn set_reader(&mut self, reader: Option<T>) {
+        self.reader = reader;
+    }
+
+    ///
+    /// Transport of the fetches, `None` if they go through the wrapped transport
+    ///
+    pub fn reader(&self) -> Option<&T> {
+        self.reader.as_ref()
+    }
+
+    ///
+    /// Look up the messages not found on the wrapped transport on an archive node
+    ///
+    pub fn with_archive(mut self, archive: TangleTransport) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    ///
+    /// Whether the message was served by the archive node, among the last
+    /// `ATTACHMENT_CAPACITY` it served
+    ///
+    pub fn served_by_archive(&self, msgid: &str) -> bool {
+        self.archived.get(msgid).is_some()
+    }
+
+    ///
+    /// Look up the messages not found on the archive node or not, enabled by default
+    ///
+    /// The walk polls the next message of every publisher, most of them don't exist yet:
+    /// the subscribers suspend the lookups while walking so that the end of the channel
+    /// doesn't query the archive on every poll
+    ///
+    pub(crate) fn set_archive_lookups(&mut self, enabled: bool) {
+        self.archive_lookups = enabled;
+    }
+
+    ///
+    /// Attachment time of the message as reported by the node, `None` if the message
+    /// was not fetched through this transport, is not among the last
+    /// `ATTACHMENT_CAPACITY` fetched, or the node didn't report it
+    ///
+    pub fn attached_at(&self, msgid: &str) -> Option<SystemTime> {
+        self.attached.get(msgid).copied()
+    }
+
+    ///
+    /// Id of the message a message fetched through this transport is linked to, `None`
+    /// if it is not among the last `ATTACHMENT_CAPACITY` fetched
+    ///
+    pub(crate) fn linked_msgid(&self, msgid: &str) -> Option<String> {
+        self.links.get(msgid).cloned()
+    }
+
+    ///
+    /// Use the given time source for the backoff and the statistics
+    ///
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    ///
+    /// Replace the time source of the backoff and the statistics
+    ///
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    ///
+    /// Wrapped transport
+    ///
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    ///
+    /// Wrapped transport, mutably
+    ///
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    ///
+    /// Retry configuration
+    ///
+    pub fn config(&self) -> &RetryConfig {
+        &self.config
+    }
+
+    ///
+    /// Statistics of the operations made so far
+    ///
+    pub fn stats(&self) -> &TransportStats {
+        &self.stats
+    }
+
+    ///
+    /// Reset the statistics
+    ///
+    pub fn reset_stats(&mut self) {
+        self.stats = TransportStats {
+            rate_limit: self.stats.rate_limit.take(),
+            ..TransportStats::default()
+        };
+    }
+}
+
+impl<T> Transport<Address, Message> for RetryingTransport<T>
+where
+    T: Transport<Address, Message>,
+    T::SendOptions: Clone,
+    T::RecvOptions: Clone,
+{
+    type SendOptions = T::SendOptions;
+
+    fn send_message_with_options(&mut self, msg: &Message, opt: T::SendOptions) -> Result<()> {
+        if let Some(max) = self.max_fragments {
+            let fragments = fragments(msg.binary.body.bytes.len());
+            if fragments > max {
+                return Err(ChannelError::TooManyFragments {
+                    tag: msg.link.msgid.to_string(),
+                    fragments: fragments,
+                    max: max,
+                }
+                .into());
+            }
+        }
+        let inner = &mut self.inner;
+        let stats = &mut self.stats;
+        let clock = &*self.clock;
+        let rate_limiter = &mut self.rate_limiter;
+        with_retry(&self.config, clock, self.config.max_send_attempts, || {
+            if let Some(limiter) = rate_limiter.as_mut() {
+                let mut throttled = false;
+                while let Err(wait) = limiter.try_acquire(clock.now()) {
+                    if !clock.can_sleep() {
+                        return Err(ChannelError::RateLimited { retry_after: wait }.into());
+                    }
+                    throttled = true;
+                    stats.rate_limit_wait += wait;
+                    clock.sleep(wait);
+                }
+                if throttled {
+                    stats.rate_limited += 1;
+                }
+            }
+            let start = clock.now();
+            let res = inner.send_message_with_options(msg, opt.clone());
+            stats.send.record(
+                "send",
+                clock.now().duration_since(start),
+                msg.binary.body.bytes.len(),
+                res.is_ok(),
+            );
+            res
+        })
+        .map_err(anyhow::Error::from)
+    }
+
+    type RecvOptions = T::RecvOptions;
+
+    fn recv_messages_with_options(
+        &mut self,
+        link: &Address,
+        opt: T::RecvOptions,
+    ) -> Result<Vec<Message>> {
+        let inner = match &mut self.reader {
+            Some(reader) => reader,
+            None => &mut self.inner,
+        };
+        let stats = &mut self.stats;
+        let clock = &*self.clock;
+        let msgs = with_retry(&self.config, clock, self.config.max_fetch_attempts, || {
+            let start = clock.now();
+            let res = inner.recv_messages_with_options(link, opt.clone());
+            let bytes = res.as_ref().map_or(0, |msgs| {
+                msgs.iter().map(|msg| msg.binary.body.bytes.len()).sum()
+            });
+            stats.recv.record(
+                "recv",
+                clock.now().duration_since(start),
+                bytes,
+                res.is_ok(),
+            );
+            res
+        })?;
+
+        let archive = match &mut self.archive {
+            Some(archive) if msgs.is_empty() && self.archive_lookups => archive,
+            _ => {
+                record_attachment(&mut self.attached, &msgs);
+                record_links(&mut self.links, &msgs);
+                return Ok(msgs);
+            }
+        };
+        let msgs = with_retry(&self.config, clock, self.config.max_fetch_attempts, || {
+            archive.recv_messages(link)
+        })?;
+        if !msgs.is_empty() {
+            self.archived.insert(link.msgid.to_string(), ());
+        }
+        record_attachment(&mut self.attached, &msgs);
+        record_links(&mut self.links, &msgs);
+        Ok(msgs)
+    }
+}
+
+impl<T> RetryingTransport<T>
+where
+    T: Transport<Address, Message>,
+    T::SendOptions: Clone,
+    T::RecvOptions: Clone,
+{
+    ///
+    /// Attachment time of a message as reported by the node, fetching the message when
+    /// it was not seen yet
+    ///
+    /// Fails if the message is not found or the node doesn't report its attachment time
+    ///
+    pub(crate) fn attachment_time(&mut self, link: &Address) -> crate::error::Result<SystemTime> {
+        let msgid = link.msgid.to_string();
+        if let Some(attached_at) = self.attached_at(&msgid) {
+            return Ok(attached_at);
+        }
+        let msgs = self.recv_messages(link).map_err(ChannelError::from)?;
+        if msgs.is_empty() {
+            return Err(ChannelError::MessageNotFound(msgid));
+        }
+        self.attached_at(&msgid).ok_or_else(|| {
+            ChannelError::State(format!(
+                "The node doesn't report the attachment time of {}",
+                msgid
+            ))
+        })
+    }
+}
+
+///
+/// Record the attachment timestamps of the messages, in milliseconds since the epoch,
+/// zero when the node didn't report one
+///
+fn record_attachment(attached: &mut RecentMessages<SystemTime>, msgs: &[Message]) {
+    for msg in msgs {
+        if msg.timestamp != 0 {
+            attached.insert(
+                msg.binary.link.msgid.to_string(),
+                UNIX_EPOCH + Duration::from_millis(msg.timestamp),
+            );
+        }
+    }
+}
+
+///
+/// Record the message each message is linked to, read from its header
+///
+fn record_links(links: &mut RecentMessages<String>, msgs: &[Message]) {
+    for msg in msgs {
+        if let Ok(preparsed) = msg.binary.parse_header() {
+            links.insert(
+                msg.binary.link.msgid.to_string(),
+                preparsed.header.link.msgid.to_string(),
+            );
+        }
+    }
+}
+
+///
+/// Bounded map of the last messages fetched, the oldest are forgotten first
+///
+struct RecentMessages<V> {
+    capacity: usize,
+    values: HashMap<String, V>,
+    order: VecDeque<String>,
+}
+
+impl<V> RecentMessages<V> {
+    fn with_capacity(capacity: usize) -> Self {
+        RecentMessages {
+            capacity: capacity.max(1),
+            values: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&self, msgid: &str) -> Option<&V> {
+        self.values.get(msgid)
+    }
+
+    fn insert(&mut self, msgid: String, value: V) {
+        if let Some(known) = self.values.get_mut(&msgid) {
+            *known = value;
+            return;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.values.remove(&oldest);
+            }
+        }
+        self.values.insert(msgid.clone(), value);
+        self.order.push_back(msgid);
+    }
+}
+
+fn with_retry<R, F>(
+    config: &RetryConfig,
+    clock: &dyn Clock,
+    max_attempts: u32,
+    mut op: F,
+) -> std::result::Result<R, ChannelError>
+where
+    F: FnMut() -> Result<R>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().map_err(ChannelError::transport) {
+            Ok(res) => return Ok(res),
+            Err(e) => {
+                if attempt >= max_attempts || !clock.can_sleep() || !(config.is_retryable)(&e) {
+                    return Err(e);
+                }
+                clock.sleep(config.delay(attempt));
+                attempt += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//!
+//! Transport statistics
+//!
+use super::rate_limit::RateLimit;
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::{TryFrom, TryInto},
+    time::Duration,
+};
+
+///
+/// Statistics of a single kind of transport operation
+///
+/// Every attempt is counted, retries included.
+///
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct OperationStats {
+    /// Attempts made
+    ///
+    pub count: u64,
+    /// Attempts that failed
+    ///
+    pub errors: u64,
+    /// Message bytes carried by the successful attempts
+    ///
+    pub bytes: u64,
+    /// Time spent in the attempts
+    ///
+    pub total_time: Duration,
+    /// Slowest attempt
+    ///
+    pub max_time: Duration,
+}
+
+impl OperationStats {
+    ///
+    /// Average time of an attempt
+    ///
+    pub fn average_time(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::from_secs(0);
+        }
+        let count = self.count.try_into().unwrap_or(u32::MAX);
+        self.total_time / count
+    }
+
+    pub(crate) fn record(&mut self, operation: &str, elapsed: Duration, bytes: usize, ok: bool) {
+        self.count += 1;
+        self.total_time += elapsed;
+        self.max_time = self.max_time.max(elapsed);
+        if ok {
+            self.bytes += u64::try_from(bytes).unwrap_or(u64::MAX);
+        } else {
+            self.errors += 1;
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            operation = operation,
+            elapsed_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+            bytes = u64::try_from(bytes).unwrap_or(u64::MAX),
+            ok = ok,
+            "transport operation"
+        );
+        #[cfg(not(feature = "tracing"))]
+        let _ = operation;
+    }
+}
+
+///
+/// Snapshot of the transport statistics of a channel
+///
+/// The client doesn't expose the tip selection, PoW and broadcast phases, the send
+/// time covers all of them.
+///
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TransportStats {
+    /// Sent messages
+    ///
+    pub send: OperationStats,
+    /// Fetched messages
+    ///
+    pub recv: OperationStats,
+    /// Rate limit applied to the sends, if any
+    ///
+    pub rate_limit: Option<RateLimit>,
+    /// Sends delayed or refused by the rate limit
+    ///
+    pub rate_limited: u64,
+    /// Time spent waiting for the rate limit
+    ///
+    pub rate_limit_wait: Duration,
+}
@@ -0,0 +1,434 @@
+//!
+//! Tangle transport
+//!
+#[cfg(target_arch = "wasm32")]
+use crate::channels_lite::config::redact_credentials;
+use crate::channels_lite::{
+    config::{default_pow_threads, validate_node_url, NetworkConfig, PowMode, QuorumConfig},
+    node::{SendOptions, SyncCheck},
+    Network,
+};
+#[cfg(target_arch = "wasm32")]
+use crate::error::ConfigError;
+use crate::error::Error;
+use anyhow::Result;
+use iota::client as iota_client;
+use iota_streams::app::transport::{
+    tangle::client::{RecvOptions, SendTrytesOptions},
+    Transport,
+};
+use iota_streams::app_channels::api::tangle::{Address, Message};
+use serde::{Deserialize, Serialize};
+
+///
+/// Bytes of message carried by a transaction
+///
+pub use iota_streams::app::transport::tangle::PAYLOAD_BYTES;
+
+///
+/// Messages kept in the sent log, the oldest are dropped first
+///
+pub const SENT_LOG_CAPACITY: usize = 1_000;
+
+///
+/// Message sent by the transport
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SentRecord {
+    /// Message tag
+    ///
+    pub tag: String,
+    /// Where the proof of work was computed, `Local` or `Remote`
+    ///
+    pub pow: PowMode,
+}
+
+///
+/// Transport talking to a single iota node through a dedicated client
+///
+pub struct TangleTransport {
+    client: iota_client::Client,
+    node: String,
+    network: NetworkConfig,
+    send_options: SendTrytesOptions,
+    quorum: Option<(Vec<(String, iota_client::Client)>, usize)>,
+    pow_mode: Option<PowMode>,
+    pow_client: Option<(String, iota_client::Client)>,
+    sent_log: Vec<SentRecord>,
+    sync_check: Option<SyncCheck>,
+}
+
+impl TangleTransport {
+    ///
+    /// Build a client talking only to the node of the network
+    ///
+    pub fn new(node: &Network, network: &NetworkConfig) -> Result<Self> {
+        Self::from_url(node.as_string(), network, node.send_options())
+    }
+
+    ///
+    /// Build a client talking only to the given node url
+    ///
+    pub fn from_url(
+        node: &str,
+        network: &NetworkConfig,
+        send_options: SendOptions,
+    ) -> Result<Self> {
+        Ok(TangleTransport {
+            client: build_client(node, network)?,
+            node: node.to_string(),
+            network: network.clone(),
+            send_options: SendTrytesOptions {
+                threads: default_pow_threads(),
+                ..send_options.to_streams()
+            },
+            quorum: None,
+            pow_mode: None,
+            pow_client: None,
+            sent_log: Vec::new(),
+            sync_check: None,
+        })
+    }
+
+    ///
+    /// Build a client talking to an archive node, e.g. a permanode, used for reads only
+    ///
+    pub fn archive(node: &str, network: &NetworkConfig) -> Result<Self> {
+        Self::from_url(node, network, SendOptions::default())
+    }
+
+    ///
+    /// Enable quorum reads against the additional nodes of the configuration
+    ///
+    pub fn with_quorum(mut self, quorum: &QuorumConfig) -> Result<Self> {
+        let mut clients = Vec::new();
+        for node in quorum.nodes.iter() {
+            clients.push((node.clone(), build_client(node, &self.network)?));
+        }
+        self.quorum = Some((clients, quorum.min_agreement));
+        Ok(self)
+    }
+
+    ///
+    /// Send the messages with remote proof of work through another node, e.g. a PoW service
+    ///
+    pub fn with_pow_node(mut self, node: &str) -> Result<Self> {
+        self.pow_client = Some((node.to_string(), build_client(node, &self.network)?));
+        Ok(self)
+    }
+
+    ///
+    /// Node attaching the messages with remote proof of work
+    ///
+    pub fn pow_node(&self) -> &str {
+        match &self.pow_client {
+            Some((node, _)) => node,
+            None => &self.node,
+        }
+    }
+
+    ///
+    /// Underlying iota client
+    ///
+    pub(crate) fn client(&self) -> &iota_client::Client {
+        &self.client
+    }
+
+    ///
+    /// Node url
+    ///
+    pub fn node(&self) -> &str {
+        &self.node
+    }
+
+    ///
+    /// Network configuration applied to the client
+    ///
+    pub fn network_config(&self) -> &NetworkConfig {
+        &self.network
+    }
+
+    ///
+    /// Send options applied to every outgoing message
+    ///
+    pub fn send_options(&self) -> SendOptions {
+        SendOptions::from_streams(&self.send_options)
+    }
+
+    ///
+    /// Replace the send options applied to every outgoing message
+    ///
+    pub fn set_send_options(&mut self, send_options: SendOptions) {
+        self.send_options = SendTrytesOptions {
+            threads: self.send_options.threads,
+            ..send_options.to_streams()
+        };
+    }
+
+    ///
+    /// Proof of work mode, overriding the send options when set, and local PoW threads
+    ///
+    pub fn set_pow(&mut self, mode: Option<PowMode>, threads: usize) {
+        self.pow_mode = mode;
+        self.send_options.threads = threads;
+    }
+
+    ///
+    /// Check the node sync before each send
+    ///
+    pub(crate) fn set_sync_check(&mut self, sync_check: SyncCheck) {
+        self.sync_check = Some(sync_check);
+    }
+
+    ///
+    /// Last messages sent, at most `SENT_LOG_CAPACITY`, with where their proof of work
+    /// was computed
+    ///
+    pub fn sent_log(&self) -> &[SentRecord] {
+        &self.sent_log
+    }
+
+    fn send_with_pow(&mut self, msg: &Message, local_pow: bool) -> Result<()> {
+        let mut send_options = self.send_options.clone();
+        send_options.local_pow = local_pow;
+        let sent = match &mut self.pow_client {
+            Some((_, client)) if !local_pow => client.send_message_with_options(msg, send_options),
+            _ => self.client.send_message_with_options(msg, send_options),
+        };
+        let node = if local_pow {
+            self.node.as_str()
+        } else {
+            self.pow_node()
+        };
+        sent.map_err(|e| self.map_err(e, node))?;
+        if self.sent_log.len() == SENT_LOG_CAPACITY {
+            self.sent_log.remove(0);
+        }
+        self.sent_log.push(SentRecord {
+            tag: msg.binary.link.msgid.to_string(),
+            pow: if local_pow {
+                PowMode::Local
+            } else {
+                PowMode::Remote
+            },
+        });
+        Ok(())
+    }
+
+    ///
+    /// Report the timeouts of the client as `Error::Timeout`, with the connect or the
+    /// request timeout depending on the one that fired
+    ///
+    fn map_err(&self, e: anyhow::Error, node: &str) -> anyhow::Error {
+        let after = match reqwest_error(&e) {
+            Some(e) if e.is_timeout() && is_connect(e) => self.network.connect_timeout,
+            Some(e) if e.is_timeout() => self.network.request_timeout,
+            _ => return e,
+        };
+        Error::Timeout {
+            node: node.to_string(),
+            after: after,
+        }
+        .into()
+    }
+}
+
+impl Transport<Address, Message> for TangleTransport {
+    type SendOptions = SendTrytesOptions;
+
+    ///
+    /// The options configured on the transport take precedence over the ones passed in
+    ///
+    fn send_message_with_options(&mut self, msg: &Message, _opt: SendTrytesOptions) -> Result<()> {
+        if let Some(sync_check) = &mut self.sync_check {
+            sync_check.ensure(&self.client, &self.node)?;
+        }
+        match self.pow_mode {
+            None => self.send_with_pow(msg, self.send_options.local_pow),
+            Some(PowMode::Local) => self.send_with_pow(msg, true),
+            Some(PowMode::Remote) => self.send_with_pow(msg, false),
+            Some(PowMode::PreferRemote) => match self.send_with_pow(msg, false) {
+                Ok(()) => Ok(()),
+                Err(e) if !is_pow_rejection(&e) => Err(e),
+                Err(e) => {
+                    log::warn!(
+                        target: "channels_lite::transport",
+                        "Remote PoW rejected by {}, falling back to local PoW: {}",
+                        self.pow_node(), e
+                    );
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(node = %self.pow_node(), error = %e, "remote PoW fallback");
+                    self.send_with_pow(msg, true)
+                }
+            },
+        }
+    }
+
+    type RecvOptions = RecvOptions;
+
+    fn recv_messages_with_options(
+        &mut self,
+        link: &Address,
+        opt: RecvOptions,
+    ) -> Result<Vec<Message>> {
+        let msgs = self
+            .client
+            .recv_messages_with_options(link, opt.clone())
+            .map_err(|e| self.map_err(e, &self.node))?;
+
+        let (clients, min_agreement) = match &mut self.quorum {
+            Some((clients, min_agreement)) => (clients, *min_agreement),
+            None => return Ok(msgs),
+        };
+
+        let mut results = vec![msgs];
+        let mut failed_nodes = Vec::new();
+        for (node, client) in clients.iter_mut() {
+            match client.recv_messages_with_options(link, opt.clone()) {
+                Ok(msgs) => results.push(msgs),
+                Err(_) => failed_nodes.push(node.clone()),
+            }
+        }
+
+        let mut best = 0;
+        let mut best_votes = 0;
+        for (i, candidate) in results.iter().enumerate() {
+            let votes = results
+                .iter()
+                .filter(|other| same_content(candidate, other))
+                .count();
+            if votes > best_votes {
+                best = i;
+                best_votes = votes;
+            }
+        }
+
+        if best_votes < min_agreement {
+            return Err(Error::QuorumFailure {
+                link: link.msgid.to_string(),
+                agreeing: best_votes,
+                required: min_agreement,
+                failed_nodes: failed_nodes,
+            }
+            .into());
+        }
+
+        Ok(results.swap_remove(best))
+    }
+}
+
+///
+/// Whether the node refused to attach with remote proof of work, e.g. `attachToTangle`
+/// disabled or protected. The connection failures and the timeouts aren't refusals:
+/// computing the proof of work locally wouldn't get the message through
+///
+fn is_pow_rejection(e: &anyhow::Error) -> bool {
+    if e.downcast_ref::<Error>().is_some() {
+        return false;
+    }
+    if let Some(e) = reqwest_error(e) {
+        return e.status().map_or(false, |status| status.is_client_error());
+    }
+    let msg = e.to_string().to_lowercase();
+    msg.contains("attachtotangle") || msg.contains("pow")
+}
+
+///
+/// HTTP error behind a failure of the client, if any
+///
+fn reqwest_error(e: &anyhow::Error) -> Option<&reqwest::Error> {
+    e.chain()
+        .find_map(|cause| cause.downcast_ref::<reqwest::Error>())
+}
+
+///
+/// Whether the request failed while connecting
+///
+#[cfg(not(target_arch = "wasm32"))]
+fn is_connect(e: &reqwest::Error) -> bool {
+    e.is_connect()
+}
+
+///
+/// The browser doesn't tell the connection failures apart
+///
+#[cfg(target_arch = "wasm32")]
+fn is_connect(_e: &reqwest::Error) -> bool {
+    false
+}
+
+///
+/// The client is built once per transport and keeps its connections alive, so all the
+/// operations of a channel reuse the same pool
+///
+#[cfg(not(target_arch = "wasm32"))]
+fn build_client(node: &str, network: &NetworkConfig) -> Result<iota_client::Client> {
+    validate_node_url(node)?;
+    let mut builder = iota_client::ClientBuilder::new()
+        .node(node)?
+        .connect_timeout(network.connect_timeout)
+        .request_timeout(network.request_timeout)
+        .pool_max_idle_per_host(network.max_idle_connections);
+    if let Some(proxy) = network.http.parse_proxy()? {
+        builder = builder.proxy(proxy);
+    }
+    for certificate in network.http.parse_certificates()? {
+        builder = builder.add_root_certificate(certificate);
+    }
+    if let Some(user_agent) = &network.http.user_agent {
+        builder = builder.user_agent(user_agent);
+    }
+    Ok(builder.build()?)
+}
+
+///
+/// In the browser the requests go through fetch: the timeouts, the pool, the proxy and
+/// the certificates are the browser's, only the node is configured
+///
+#[cfg(target_arch = "wasm32")]
+fn build_client(node: &str, network: &NetworkConfig) -> Result<iota_client::Client> {
+    validate_node_url(node)?;
+    if let Some(proxy) = &network.http.proxy {
+        return Err(ConfigError::InvalidProxy {
+            url: redact_credentials(proxy),
+            reason: "not supported in the browser".to_string(),
+        }
+        .into());
+    }
+    if !network.http.root_certificates.is_empty() {
+        return Err(ConfigError::InvalidCertificate {
+            index: 0,
+            reason: "not supported in the browser".to_string(),
+        }
+        .into());
+    }
+    Ok(iota_client::ClientBuilder::new().node(node)?.build()?)
+}
+
+///
+/// Transactions of the bundle carrying a message of `bytes` bytes, at least one
+///
+pub fn fragments(bytes: usize) -> usize {
+    ((bytes + PAYLOAD_BYTES - 1) / PAYLOAD_BYTES).max(1)
+}
+
+///
+/// Upper bound of the bytes a packet adds to its public and masked data: header, links,
+/// sequence state and signature
+///
+pub const PACKET_OVERHEAD_BYTES: usize = 256;
+
+///
+/// Transactions a packet with this public and masked data spans at most, to refuse it
+/// before Streams wraps it
+///
+pub(crate) fn packet_fragments(public: usize, masked: usize) -> usize {
+    fragments(public + masked + PACKET_OVERHEAD_BYTES)
+}
+
+fn same_content(a: &[Message], b: &[Message]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b.iter())
+            .all(|(a, b)| a.binary.body == b.binary.body)
+}
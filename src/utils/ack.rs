@@ -0,0 +1,209 @@
+//!
+//! Message acknowledgement
+//!
+//! A message written with `write_signed_with_ack` carries its public data in an
+//! envelope holding the id of the acknowledgement request:
+//!
+//! `{"channels_lite_envelope": "ack_requested", "ack_request": <request id>, "data": <public data>}`
+//!
+//! The acknowledgement of the subscriber references the acknowledged tag and, for those
+//! messages, the request id, so that the author matches it to the pending request. See
+//! `envelope` for how the envelopes are recognised
+//!
+use super::envelope::{required_data, EnvelopeKind};
+use super::payload::{
+    json::{Payload, PayloadBuilder},
+    PacketPayload,
+};
+use crate::error::Result;
+use iota_streams::ddml::types::Bytes;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+///
+/// Default time an acknowledgement is waited for
+///
+pub const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+///
+/// Time the author remembers a request after its deadline, the acknowledgements
+/// received later are reported as `AckOutcome::UnknownRequest`
+///
+pub const ACK_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+///
+/// Number of acknowledgement requests remembered by the author and by each subscriber,
+/// the oldest are forgotten first
+///
+pub const ACK_REQUEST_CAPACITY: usize = 10_000;
+
+///
+/// Public payload of an acknowledgement sent by a subscriber
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Ack {
+    /// Tag of the acknowledged message
+    ///
+    pub ack: String,
+    /// Id of the acknowledgement request carried by the message, if any
+    ///
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<String>,
+}
+
+impl Ack {
+    ///
+    /// Parse an unwrapped public payload as an acknowledgement
+    ///
+    pub fn parse(public: &str) -> Option<Self> {
+        serde_json::from_str(public).ok()
+    }
+}
+
+///
+/// Public payload of a message requesting an acknowledgement
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AckRequested {
+    /// Kind of envelope, always `EnvelopeKind::AckRequested`
+    ///
+    #[serde(rename = "channels_lite_envelope")]
+    pub envelope: EnvelopeKind,
+    /// Id of the acknowledgement request
+    ///
+    pub ack_request: String,
+    /// Public data of the message
+    ///
+    #[serde(deserialize_with = "required_data")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl AckRequested {
+    ///
+    /// Parse an unwrapped public payload as an acknowledgement request envelope
+    ///
+    pub fn parse(public: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(public)
+            .ok()
+            .filter(|envelope| envelope.envelope == EnvelopeKind::AckRequested)
+    }
+}
+
+///
+/// New random acknowledgement request id, 16 bytes as lowercase hex
+///
+pub fn new_request_id() -> String {
+    let bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+///
+/// Payload with its public data wrapped in an `AckRequested` envelope
+///
+pub(crate) struct AckRequestPayload {
+    public: Bytes,
+    masked: Bytes,
+}
+
+impl AckRequestPayload {
+    ///
+    /// Wrap the JSON public data of a payload
+    ///
+    pub(crate) fn wrap<P>(payload: &P, request_id: &str) -> Result<Self>
+    where
+        P: PacketPayload,
+    {
+        let data = match Payload::unwrap_bytes(&payload.public_data().0)? {
+            Some(public) => Some(serde_json::from_str(&public)?),
+            None => None,
+        };
+        let envelope = AckRequested {
+            envelope: EnvelopeKind::AckRequested,
+            ack_request: request_id.to_string(),
+            data: data,
+        };
+        let public = PayloadBuilder::new().public(&envelope)?.build();
+        Ok(AckRequestPayload {
+            public: public.public_data().clone(),
+            masked: payload.masked_data().clone(),
+        })
+    }
+}
+
+impl PacketPayload for AckRequestPayload {
+    fn public_data(&self) -> &Bytes {
+        &self.public
+    }
+    fn masked_data(&self) -> &Bytes {
+        &self.masked
+    }
+}
+
+///
+/// Acknowledgement requested by the author and not received yet
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PendingAck {
+    /// Id of the acknowledgement request
+    ///
+    pub request_id: String,
+    /// Tag of the message to acknowledge
+    ///
+    pub tag: String,
+    /// When the message was written
+    ///
+    pub requested_at: SystemTime,
+    /// Time after which `poll_acks` reports the request as timed out
+    ///
+    pub deadline: SystemTime,
+}
+
+///
+/// What `poll_acks` found for a request
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AckOutcome {
+    /// Acknowledged before the deadline
+    ///
+    Acknowledged,
+    /// Acknowledged after the deadline, the timeout was reported before
+    ///
+    Late,
+    /// No acknowledgement before the deadline, reported once
+    ///
+    TimedOut,
+    /// Acknowledgement of a request this author doesn't know, e.g. requested before a
+    /// restart or forged
+    ///
+    UnknownRequest,
+}
+
+///
+/// Acknowledgement event reported by `poll_acks`
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AckReceived {
+    /// Id of the acknowledgement request
+    ///
+    pub request_id: String,
+    /// Tag of the acknowledged message, as referenced by the acknowledgement for the
+    /// unknown requests
+    ///
+    pub tag: String,
+    /// Tag of the acknowledgement, `None` for a timeout
+    ///
+    pub ack_tag: Option<String>,
+    /// When the message was written, `None` for the unknown requests
+    ///
+    pub requested_at: Option<SystemTime>,
+    /// Attachment time of the acknowledgement when the node reports it, the time of the
+    /// poll otherwise, or the deadline for a timeout
+    ///
+    pub at: SystemTime,
+    /// What was found
+    ///
+    pub outcome: AckOutcome,
+}
@@ -0,0 +1,41 @@
+//!
+//! Cooperative cancellation of the waits
+//!
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+///
+/// Flag checked by the waiting read methods between two fetches
+///
+/// Clones share the same flag and can be sent to another thread, e.g. a signal handler.
+/// Once cancelled, the waits of the channel return right away for good
+///
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    ///
+    /// Token not cancelled yet
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Cancel the waits
+    ///
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    ///
+    /// Whether `cancel` was called
+    ///
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,131 @@
+//!
+//! Time source
+//!
+use core::cell::Cell;
+use instant::Instant;
+use iota_streams::core::prelude::Rc;
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+///
+/// Source of time for the sleeps, timeouts and timestamps of the channels
+///
+pub trait Clock: fmt::Debug {
+    ///
+    /// Current monotonic time
+    ///
+    fn now(&self) -> Instant;
+
+    ///
+    /// Current wall-clock time
+    ///
+    fn system_time(&self) -> SystemTime;
+
+    ///
+    /// Block for the given duration
+    ///
+    fn sleep(&self, duration: Duration);
+
+    ///
+    /// Whether `sleep` waits, `false` where the thread can't block, e.g. the browser main
+    /// thread. The retry and poll loops make a single attempt then
+    ///
+    fn can_sleep(&self) -> bool {
+        true
+    }
+}
+
+///
+/// Clock backed by the system time
+///
+/// On wasm32 the time comes from the browser and sleeping is a no-op, the browser
+/// main thread can't block: the retries and waits are skipped
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn system_time(&self) -> SystemTime {
+        // SystemTime::now panics on wasm32-unknown-unknown
+        let millis = js_sys::Date::now().max(0.0);
+        std::time::UNIX_EPOCH + Duration::from_secs_f64(millis / 1000.0)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn sleep(&self, _duration: Duration) {}
+
+    #[cfg(target_arch = "wasm32")]
+    fn can_sleep(&self) -> bool {
+        false
+    }
+}
+
+///
+/// Manually driven clock, for tests
+///
+/// Sleeping advances the clock immediately. Clones share the same time.
+///
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    start: Instant,
+    system_start: SystemTime,
+    elapsed: Rc<Cell<Duration>>,
+}
+
+impl MockClock {
+    ///
+    /// Create a clock starting at the given wall-clock time
+    ///
+    pub fn new(system_start: SystemTime) -> Self {
+        MockClock {
+            start: Instant::now(),
+            system_start: system_start,
+            elapsed: Rc::new(Cell::new(Duration::from_secs(0))),
+        }
+    }
+
+    ///
+    /// Move the clock forward
+    ///
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed.set(self.elapsed.get() + duration);
+    }
+
+    ///
+    /// Time elapsed since the clock was created
+    ///
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed.get()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed.get()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.system_start + self.elapsed.get()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
@@ -0,0 +1,91 @@
+//!
+//! Content types
+//!
+//! A channel mixing kinds of messages labels each with a content type, e.g.
+//! `telemetry/v1`. The public data is carried in an envelope:
+//!
+//! `{"channels_lite_envelope": "typed", "content_type": <content type>, "data": <public data>}`
+//!
+//! The subscriber picks the decoder of the content type, see `Decoders`. See `envelope`
+//! for how the envelopes are recognised
+//!
+use super::envelope::{required_data, EnvelopeKind};
+use super::payload::{
+    json::{Payload, PayloadBuilder},
+    PacketPayload,
+};
+use crate::error::Result;
+use iota_streams::ddml::types::Bytes;
+use serde::{Deserialize, Serialize};
+
+///
+/// Public payload of a message labelled with a content type
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Typed {
+    /// Kind of envelope, always `EnvelopeKind::Typed`
+    ///
+    #[serde(rename = "channels_lite_envelope")]
+    pub envelope: EnvelopeKind,
+    /// Content type of the message
+    ///
+    pub content_type: String,
+    /// Public data of the message
+    ///
+    #[serde(deserialize_with = "required_data")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl Typed {
+    ///
+    /// Parse an unwrapped public payload as a content type envelope
+    ///
+    pub fn parse(public: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(public)
+            .ok()
+            .filter(|envelope| envelope.envelope == EnvelopeKind::Typed)
+    }
+}
+
+///
+/// Payload with its public data wrapped in a `Typed` envelope
+///
+pub(crate) struct TypedPayload {
+    public: Bytes,
+    masked: Bytes,
+}
+
+impl TypedPayload {
+    ///
+    /// Wrap the JSON public data of a payload
+    ///
+    pub(crate) fn wrap<P>(payload: &P, content_type: &str) -> Result<Self>
+    where
+        P: PacketPayload,
+    {
+        let data = match Payload::unwrap_bytes(&payload.public_data().0)? {
+            Some(public) => Some(serde_json::from_str(&public)?),
+            None => None,
+        };
+        let envelope = Typed {
+            envelope: EnvelopeKind::Typed,
+            content_type: content_type.to_string(),
+            data: data,
+        };
+        let public = PayloadBuilder::new().public(&envelope)?.build();
+        Ok(TypedPayload {
+            public: public.public_data().clone(),
+            masked: payload.masked_data().clone(),
+        })
+    }
+}
+
+impl PacketPayload for TypedPayload {
+    fn public_data(&self) -> &Bytes {
+        &self.public
+    }
+    fn masked_data(&self) -> &Bytes {
+        &self.masked
+    }
+}
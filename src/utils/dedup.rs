@@ -0,0 +1,90 @@
+//!
+//! Deduplication of the fetched messages
+//!
+//! An unreliable fetch path, or a walk re-anchored on a keyload, can return a message
+//! already handed to the caller. The subscriber remembers the last message ids seen and
+//! drops the repeated ones.
+//!
+use std::collections::{HashSet, VecDeque};
+
+///
+/// Default number of message ids remembered
+///
+pub const DEFAULT_DEDUP_CAPACITY: usize = 10_000;
+
+///
+/// Bounded set of the message ids seen, the oldest are forgotten first
+///
+#[derive(Clone, Debug)]
+pub struct Deduplicator {
+    capacity: usize,
+    seen: HashSet<String>,
+    order: VecDeque<String>,
+    dropped: usize,
+}
+
+impl Default for Deduplicator {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_DEDUP_CAPACITY)
+    }
+}
+
+impl Deduplicator {
+    ///
+    /// Remember the last `DEFAULT_DEDUP_CAPACITY` message ids
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Remember the last `capacity` message ids, at least one
+    ///
+    pub fn with_capacity(capacity: usize) -> Self {
+        Deduplicator {
+            capacity: capacity.max(1),
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    ///
+    /// Record a message id, `false` if it was already seen
+    ///
+    pub fn first_seen(&mut self, msgid: &str) -> bool {
+        if self.seen.contains(msgid) {
+            self.dropped += 1;
+            return false;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.seen.insert(msgid.to_string());
+        self.order.push_back(msgid.to_string());
+        true
+    }
+
+    ///
+    /// Number of message ids remembered
+    ///
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    ///
+    /// Whether no message id was recorded yet
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    ///
+    /// Number of repeated messages dropped so far
+    ///
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+}
@@ -0,0 +1,126 @@
+//!
+//! Content digests
+//!
+//! Both ends of a channel compute the same digest of a message from what the
+//! application wrote, so that an off-chain copy of the payload is matched against the
+//! channel. The digest is a BLAKE3 hash of the public and masked data before the
+//! envelopes, the framing and the trytes encoding:
+//!
+//! `len(public): u64 | public | len(masked): u64 | masked`
+//!
+//! with big-endian lengths. The JSON data is hashed in canonical form, members sorted
+//! and no whitespace, since the envelopes re-serialize the data they carry. Data that
+//! is not JSON, e.g. a multi-part payload, is hashed as decoded from the slot.
+//!
+use super::payload::{
+    check_trytes,
+    json::{Payload, PayloadBuilder},
+    Bytes, PacketPayload,
+};
+use crate::error::Result;
+use base64::{decode_config, URL_SAFE_NO_PAD};
+use iota_conversion::trytes_converter::to_string as trytes_to_string;
+
+///
+/// Hex BLAKE3 digest of the public and masked application data
+///
+pub fn content_digest(public: &[u8], masked: &[u8]) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&(public.len() as u64).to_be_bytes());
+    hasher.update(public);
+    hasher.update(&(masked.len() as u64).to_be_bytes());
+    hasher.update(masked);
+    hasher.finalize().to_hex().to_string()
+}
+
+///
+/// Canonical form of a JSON document, members sorted and no whitespace, the data as is
+/// if it isn't JSON
+///
+pub fn canonical_json(data: &[u8]) -> Vec<u8> {
+    match serde_json::from_slice::<serde_json::Value>(data) {
+        Ok(value) => value.to_string().into_bytes(),
+        Err(_) => data.to_vec(),
+    }
+}
+
+///
+/// Application data of a payload slot, `unwrapped` being the JSON data left once the
+/// envelopes are removed, if any
+///
+pub(crate) fn slot_content(slot: &[u8], unwrapped: Option<&str>) -> Vec<u8> {
+    if let Some(data) = unwrapped {
+        return canonical_json(data.as_bytes());
+    }
+    match Payload::unwrap_bytes(slot) {
+        Ok(_) => Vec::new(),
+        // Not a JSON payload, e.g. multi-part
+        Err(_) => decode_slot(slot).unwrap_or_else(|| slot.to_vec()),
+    }
+}
+
+///
+/// Digest of a payload about to be written, see `content_digest`
+///
+pub(crate) fn payload_content_digest(public: &[u8], masked: &[u8]) -> String {
+    let unwrapped = |slot: &[u8]| Payload::unwrap_bytes(slot).ok().flatten();
+    content_digest(
+        &slot_content(public, unwrapped(public).as_deref()),
+        &slot_content(masked, unwrapped(masked).as_deref()),
+    )
+}
+
+///
+/// Payload with its JSON data re-serialized in canonical form, see
+/// `set_canonical_json` of the author
+///
+pub(crate) struct CanonicalPayload {
+    public: Bytes,
+    masked: Bytes,
+}
+
+impl CanonicalPayload {
+    ///
+    /// Re-serialize the JSON data of the payload, the other slots are kept as they are
+    ///
+    pub(crate) fn wrap<P>(payload: &P) -> Result<Self>
+    where
+        P: PacketPayload,
+    {
+        Ok(CanonicalPayload {
+            public: canonical_slot(payload.public_data())?,
+            masked: canonical_slot(payload.masked_data())?,
+        })
+    }
+}
+
+impl PacketPayload for CanonicalPayload {
+    fn public_data(&self) -> &Bytes {
+        &self.public
+    }
+    fn masked_data(&self) -> &Bytes {
+        &self.masked
+    }
+}
+
+fn canonical_slot(slot: &Bytes) -> Result<Bytes> {
+    let value = match Payload::unwrap_bytes(&slot.0) {
+        Ok(Some(data)) => serde_json::from_str::<serde_json::Value>(&data).ok(),
+        _ => None,
+    };
+    match value {
+        Some(value) => Ok(PayloadBuilder::new()
+            .public(&value)?
+            .build()
+            .public_data()
+            .clone()),
+        None => Ok(slot.clone()),
+    }
+}
+
+fn decode_slot(slot: &[u8]) -> Option<Vec<u8>> {
+    let trytes = std::str::from_utf8(slot).ok()?;
+    check_trytes(trytes).ok()?;
+    let raw = trytes_to_string(trytes).ok()?;
+    decode_config(&raw, URL_SAFE_NO_PAD).ok()
+}
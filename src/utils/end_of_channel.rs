@@ -0,0 +1,42 @@
+//!
+//! End of channel marker
+//!
+use serde::{Deserialize, Serialize};
+
+///
+/// Public payload of the terminal message published by `close`
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EndOfChannel {
+    /// Always true, identifies the marker
+    ///
+    pub end_of_channel: bool,
+}
+
+impl EndOfChannel {
+    ///
+    /// Create the marker
+    ///
+    pub fn new() -> Self {
+        EndOfChannel {
+            end_of_channel: true,
+        }
+    }
+
+    ///
+    /// Check whether an unwrapped public payload is the end of channel marker
+    ///
+    /// Only the author closes the channel: the payload counts when `signer`, the key of
+    /// the signed packet carrying it, is `author_key`. Both are public keys as lowercase
+    /// hex
+    ///
+    pub fn matches(public: &str, signer: &str, author_key: &str) -> bool {
+        if signer != author_key {
+            return false;
+        }
+        match serde_json::from_str::<EndOfChannel>(public) {
+            Ok(marker) => marker.end_of_channel,
+            Err(_) => false,
+        }
+    }
+}
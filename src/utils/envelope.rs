@@ -0,0 +1,51 @@
+//!
+//! Envelopes of the public data
+//!
+//! The envelopes wrapping the public data of a packet name their kind under the
+//! `channels_lite_envelope` member, so that an application payload with the same members
+//! is never taken for one:
+//!
+//! `{"channels_lite_envelope": "expiring", "expiry": {..}, "data": <public data>}`
+//!
+//! A payload is only unwrapped when the kind matches and it has no other member. The
+//! `data` member is required, `null` for a packet without public data.
+//!
+use serde::{Deserialize, Deserializer, Serialize};
+
+///
+/// Member naming the kind of an envelope
+///
+pub const ENVELOPE_KEY: &str = "channels_lite_envelope";
+
+///
+/// Kind of envelope
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvelopeKind {
+    /// Sequence number, see `sequence::Sequenced`
+    ///
+    Sequenced,
+    /// Time to live, see `expiry::Expiring`
+    ///
+    Expiring,
+    /// Acknowledgement request, see `ack::AckRequested`
+    ///
+    AckRequested,
+    /// Content type, see `content_type::Typed`
+    ///
+    Typed,
+}
+
+///
+/// Deserialize the `data` member of an envelope, failing when it is missing instead of
+/// defaulting to `None`
+///
+pub(crate) fn required_data<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<serde_json::Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::deserialize(deserializer)
+}
@@ -0,0 +1,127 @@
+//!
+//! Message expiry
+//!
+//! A message written with a time to live carries its public data in an envelope with
+//! the time it was written:
+//!
+//! `{"channels_lite_envelope": "expiring", "expiry": {"timestamp": <unix seconds>, "ttl": <seconds>}, "data": <public data>}`
+//!
+//! See `envelope` for how the envelopes are recognised
+//!
+use super::envelope::{required_data, EnvelopeKind};
+use super::payload::{
+    json::{Payload, PayloadBuilder},
+    PacketPayload,
+};
+use crate::error::{ChannelError, Result};
+use iota_streams::ddml::types::Bytes;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+///
+/// Expiry of a message
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Expiry {
+    /// Time the message was written, in seconds since the unix epoch
+    ///
+    pub timestamp: u64,
+    /// Time to live, in seconds
+    ///
+    pub ttl: u64,
+}
+
+impl Expiry {
+    ///
+    /// Expiry of a message written at `now`
+    ///
+    pub fn new(now: SystemTime, ttl: Duration) -> Result<Self> {
+        let timestamp = now
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| ChannelError::State(format!("Clock before the unix epoch: {}", e)))?;
+        Ok(Expiry {
+            timestamp: timestamp.as_secs(),
+            ttl: ttl.as_secs(),
+        })
+    }
+
+    ///
+    /// Time after which the message is stale, `None` when it is beyond the times the
+    /// platform represents, the message never expires then
+    ///
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        UNIX_EPOCH.checked_add(Duration::from_secs(self.timestamp.saturating_add(self.ttl)))
+    }
+}
+
+///
+/// Public payload of a message written with a time to live
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Expiring {
+    /// Kind of envelope, always `EnvelopeKind::Expiring`
+    ///
+    #[serde(rename = "channels_lite_envelope")]
+    pub envelope: EnvelopeKind,
+    /// Expiry of the message
+    ///
+    pub expiry: Expiry,
+    /// Public data of the message
+    ///
+    #[serde(deserialize_with = "required_data")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl Expiring {
+    ///
+    /// Parse an unwrapped public payload as an expiring envelope
+    ///
+    pub fn parse(public: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(public)
+            .ok()
+            .filter(|envelope| envelope.envelope == EnvelopeKind::Expiring)
+    }
+}
+
+///
+/// Payload with its public data wrapped in an `Expiring` envelope
+///
+pub(crate) struct ExpiringPayload {
+    public: Bytes,
+    masked: Bytes,
+}
+
+impl ExpiringPayload {
+    ///
+    /// Wrap the JSON public data of a payload
+    ///
+    pub(crate) fn wrap<P>(payload: &P, expiry: Expiry) -> Result<Self>
+    where
+        P: PacketPayload,
+    {
+        let data = match Payload::unwrap_bytes(&payload.public_data().0)? {
+            Some(public) => Some(serde_json::from_str(&public)?),
+            None => None,
+        };
+        let envelope = Expiring {
+            envelope: EnvelopeKind::Expiring,
+            expiry: expiry,
+            data: data,
+        };
+        let public = PayloadBuilder::new().public(&envelope)?.build();
+        Ok(ExpiringPayload {
+            public: public.public_data().clone(),
+            masked: payload.masked_data().clone(),
+        })
+    }
+}
+
+impl PacketPayload for ExpiringPayload {
+    fn public_data(&self) -> &Bytes {
+        &self.public
+    }
+    fn masked_data(&self) -> &Bytes {
+        &self.masked
+    }
+}
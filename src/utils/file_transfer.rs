@@ -0,0 +1,41 @@
+//!
+//! File Transfer Module
+//!
+use serde::{Deserialize, Serialize};
+
+///
+/// Size in bytes of the raw file data carried by a single chunk message
+///
+pub const FILE_CHUNK_SIZE: usize = 1024;
+
+///
+/// Manifest describing a file sent through the channel
+///
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileManifest {
+    /// Name of the file
+    ///
+    pub file_name: String,
+    /// Total size of the file in bytes
+    ///
+    pub total_size: u64,
+    /// Number of chunks the file was split into
+    ///
+    pub chunk_count: usize,
+    /// Tags of the chunk messages, in order
+    ///
+    pub chunk_tags: Vec<String>,
+}
+
+///
+/// Single chunk of a file
+///
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileChunk {
+    /// Position of the chunk in the file
+    ///
+    pub index: usize,
+    /// Base64 encoded chunk data
+    ///
+    pub data: String,
+}
@@ -0,0 +1,154 @@
+//!
+//! Idempotent writes
+//!
+//! A publish triggered twice, a double click or a retried request, sends the same packet
+//! twice. The author remembers the idempotency keys used recently with the digest of
+//! their payload and the tag produced, and hands the tag back instead of sending again.
+//!
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+
+///
+/// Default number of idempotency keys remembered
+///
+pub const DEFAULT_IDEMPOTENCY_CAPACITY: usize = 1_000;
+
+///
+/// Default time a key is remembered after the write
+///
+pub const DEFAULT_IDEMPOTENCY_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+///
+/// Write remembered under an idempotency key
+///
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IdempotentWrite {
+    /// Idempotency key given by the caller
+    ///
+    pub key: String,
+    /// Digest of the payload written, as in the audit report
+    ///
+    pub digest: String,
+    /// Tag of the packet written
+    ///
+    pub tag: String,
+    /// When the packet was written
+    ///
+    pub written_at: SystemTime,
+}
+
+///
+/// Bounded set of the recent idempotent writes, the least recently used are forgotten
+/// first and every key is forgotten once the window has elapsed
+///
+#[derive(Clone, Debug)]
+pub struct IdempotencyCache {
+    capacity: usize,
+    window: Duration,
+    writes: VecDeque<IdempotentWrite>,
+}
+
+impl Default for IdempotencyCache {
+    fn default() -> Self {
+        Self::with_limits(DEFAULT_IDEMPOTENCY_CAPACITY, DEFAULT_IDEMPOTENCY_WINDOW)
+    }
+}
+
+impl IdempotencyCache {
+    ///
+    /// Remember `DEFAULT_IDEMPOTENCY_CAPACITY` keys for `DEFAULT_IDEMPOTENCY_WINDOW`
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Remember `capacity` keys, at least one, for `window`
+    ///
+    pub fn with_limits(capacity: usize, window: Duration) -> Self {
+        IdempotencyCache {
+            capacity: capacity.max(1),
+            window: window,
+            writes: VecDeque::new(),
+        }
+    }
+
+    ///
+    /// Time a key is remembered after the write
+    ///
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    ///
+    /// Change the time a key is remembered, the writes already remembered included
+    ///
+    pub fn set_window(&mut self, window: Duration) {
+        self.window = window;
+    }
+
+    ///
+    /// Write remembered under `key` and still within the window at `now`
+    ///
+    /// A hit makes the key the most recently used
+    ///
+    pub fn lookup(&mut self, key: &str, now: SystemTime) -> Option<IdempotentWrite> {
+        self.forget_expired(now);
+        let position = self.writes.iter().position(|write| write.key == key)?;
+        let write = self.writes.remove(position)?;
+        self.writes.push_back(write.clone());
+        Some(write)
+    }
+
+    ///
+    /// Remember a write, replacing the one under the same key
+    ///
+    pub fn insert(&mut self, write: IdempotentWrite, now: SystemTime) {
+        self.forget_expired(now);
+        self.writes.retain(|remembered| remembered.key != write.key);
+        if self.writes.len() == self.capacity {
+            self.writes.pop_front();
+        }
+        self.writes.push_back(write);
+    }
+
+    ///
+    /// Writes remembered, least recently used first
+    ///
+    pub fn writes(&self) -> Vec<IdempotentWrite> {
+        self.writes.iter().cloned().collect()
+    }
+
+    ///
+    /// Replace the writes remembered, least recently used first
+    ///
+    pub fn restore(&mut self, writes: Vec<IdempotentWrite>) {
+        let skip = writes.len().saturating_sub(self.capacity);
+        self.writes = writes.into_iter().skip(skip).collect();
+    }
+
+    ///
+    /// Number of writes remembered
+    ///
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    ///
+    /// Whether no write is remembered
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    fn forget_expired(&mut self, now: SystemTime) {
+        let window = self.window;
+        self.writes
+            .retain(|write| match now.duration_since(write.written_at) {
+                Ok(elapsed) => elapsed < window,
+                // Written in the future of the clock, kept
+                Err(_) => true,
+            });
+    }
+}
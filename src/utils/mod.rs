@@ -1,6 +1,28 @@
 //!
 //! Utils Module
 //!
+pub mod ack;
+pub mod cancel;
+pub mod clock;
+pub mod content_type;
+pub mod dedup;
+pub mod digest;
+#[doc(hidden)]
+pub mod end_of_channel;
+pub mod envelope;
+#[doc(hidden)]
+pub mod expiry;
+pub mod file_transfer;
+pub mod idempotency;
+pub mod multipart;
 pub mod payload;
+#[doc(hidden)]
+pub mod protocol;
+pub mod psk;
 pub mod random_seed;
+#[doc(hidden)]
 pub mod response_write_signed;
+#[cfg(feature = "json-schema")]
+pub mod schema;
+pub mod secret;
+pub mod sequence;
@@ -0,0 +1,197 @@
+//!
+//! Multi-part payloads
+//!
+//! Carries several named attachments in the public and masked slots of a message. Each
+//! slot holds a small framing header followed by the parts:
+//!
+//! `MAGIC | version: u8 | part count: u16 | (name len: u16 | name | data len: u32 | data)*`
+//!
+//! with big-endian integers, then base64 and trytes encoded like the JSON payloads.
+//!
+use super::payload::{check_trytes, PacketPayload};
+use crate::error::{ChannelError, Result};
+use base64::{decode_config, encode_config, URL_SAFE_NO_PAD};
+use iota_conversion::trytes_converter::{to_string as trytes_to_string, to_trytes};
+use iota_streams::ddml::types::Bytes;
+use std::{
+    collections::BTreeMap,
+    convert::{TryFrom, TryInto},
+};
+
+const MAGIC: &[u8] = b"CLMP";
+const VERSION: u8 = 1;
+
+///
+/// Named parts of a payload slot
+///
+pub type Parts = BTreeMap<String, Vec<u8>>;
+
+///
+/// Payload made of named public and masked parts
+///
+pub struct MultiPartPayload {
+    public: Bytes,
+    masked: Bytes,
+}
+
+impl MultiPartPayload {
+    ///
+    /// Decode the parts of a payload slot as received by the subscriber
+    ///
+    pub fn decode(data: &[u8]) -> Result<Parts> {
+        if data.is_empty() {
+            return Ok(Parts::new());
+        }
+        let trytes = std::str::from_utf8(data).map_err(|e| {
+            ChannelError::PayloadEncoding(format!("Payload is not valid trytes: {}", e))
+        })?;
+        check_trytes(trytes)?;
+        let raw = trytes_to_string(trytes)?;
+        let frame = decode_config(&raw, URL_SAFE_NO_PAD)?;
+        decode_frame(&frame)
+    }
+}
+
+impl PacketPayload for MultiPartPayload {
+    fn public_data(&self) -> &Bytes {
+        &self.public
+    }
+    fn masked_data(&self) -> &Bytes {
+        &self.masked
+    }
+}
+
+///
+/// Multi-part payload builder
+///
+#[derive(Default)]
+pub struct MultiPartPayloadBuilder {
+    public: Parts,
+    masked: Parts,
+}
+
+impl MultiPartPayloadBuilder {
+    ///
+    /// Create Instance
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Add a public part, replacing a previous part with the same name
+    ///
+    pub fn public(&mut self, name: &str, data: &[u8]) -> &mut Self {
+        self.public.insert(name.to_string(), data.to_vec());
+        self
+    }
+
+    ///
+    /// Add a masked part, replacing a previous part with the same name
+    ///
+    pub fn masked(&mut self, name: &str, data: &[u8]) -> &mut Self {
+        self.masked.insert(name.to_string(), data.to_vec());
+        self
+    }
+
+    ///
+    /// Build
+    ///
+    pub fn build(&self) -> Result<MultiPartPayload> {
+        Ok(MultiPartPayload {
+            public: encode_slot(&self.public)?,
+            masked: encode_slot(&self.masked)?,
+        })
+    }
+}
+
+fn encode_slot(parts: &Parts) -> Result<Bytes> {
+    if parts.is_empty() {
+        return Ok(Bytes(Vec::new()));
+    }
+    let frame = encode_frame(parts)?;
+    let trytes = to_trytes(&encode_config(&frame, URL_SAFE_NO_PAD))?;
+    Ok(Bytes(trytes.as_bytes().to_vec()))
+}
+
+fn encode_frame(parts: &Parts) -> Result<Vec<u8>> {
+    let mut frame = MAGIC.to_vec();
+    frame.push(VERSION);
+    let count = u16::try_from(parts.len())
+        .map_err(|_| ChannelError::PayloadEncoding("Too many parts".to_string()))?;
+    frame.extend_from_slice(&count.to_be_bytes());
+    for (name, data) in parts.iter() {
+        let name_len = u16::try_from(name.len()).map_err(|_| {
+            ChannelError::PayloadEncoding(format!("Part name {} is too long", name))
+        })?;
+        let data_len = u32::try_from(data.len())
+            .map_err(|_| ChannelError::PayloadEncoding(format!("Part {} is too large", name)))?;
+        frame.extend_from_slice(&name_len.to_be_bytes());
+        frame.extend_from_slice(name.as_bytes());
+        frame.extend_from_slice(&data_len.to_be_bytes());
+        frame.extend_from_slice(data);
+    }
+    Ok(frame)
+}
+
+fn decode_frame(frame: &[u8]) -> Result<Parts> {
+    let mut reader = FrameReader {
+        frame: frame,
+        pos: 0,
+    };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(ChannelError::PayloadEncoding(
+            "Not a multi-part payload".to_string(),
+        ));
+    }
+    let version = reader.take(1)?[0];
+    if version != VERSION {
+        return Err(ChannelError::PayloadEncoding(format!(
+            "Unsupported multi-part payload version {}",
+            version
+        )));
+    }
+
+    let count = u16::from_be_bytes(reader.take(2)?.try_into().map_err(corrupt)?);
+    let mut parts = Parts::new();
+    for _ in 0..count {
+        let name_len = u16::from_be_bytes(reader.take(2)?.try_into().map_err(corrupt)?);
+        let name =
+            String::from_utf8(reader.take(usize::from(name_len))?.to_vec()).map_err(corrupt)?;
+        let data_len = u32::from_be_bytes(reader.take(4)?.try_into().map_err(corrupt)?);
+        let data = reader
+            .take(usize::try_from(data_len).map_err(corrupt)?)?
+            .to_vec();
+        parts.insert(name, data);
+    }
+    if reader.pos != frame.len() {
+        return Err(ChannelError::PayloadEncoding(
+            "Trailing data after the parts".to_string(),
+        ));
+    }
+    Ok(parts)
+}
+
+fn corrupt(e: impl std::fmt::Display) -> ChannelError {
+    ChannelError::PayloadEncoding(format!("Corrupted multi-part payload: {}", e))
+}
+
+struct FrameReader<'a> {
+    frame: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> FrameReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.frame.len())
+            .ok_or_else(|| {
+                ChannelError::PayloadEncoding("Truncated multi-part payload".to_string())
+            })?;
+        let data = &self.frame[self.pos..end];
+        self.pos = end;
+        Ok(data)
+    }
+}
@@ -1,12 +1,37 @@
 //!
 //! Payload Module
 //!
+//! The serialized public and masked data are framed as
+//!
+//! `version: u8 | serialized data`
+//!
+//! then base64 and trytes encoded. The data written before the framing had no version
+//! byte, it is recognized by its first byte being the start of a JSON document and read
+//! as is.
+//!
+//! Whatever the slot holds, unwrapping returns an error rather than panicking: the
+//! data comes from the tangle and anyone can publish there.
+//!
+use crate::error::{ChannelError, Result};
 use base64::{decode_config, encode_config, URL_SAFE_NO_PAD};
 use iota_conversion::trytes_converter::{to_string as trytes_to_string, to_trytes};
-use iota_streams::ddml::types::Bytes;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Serialize,
+};
 use std::marker::PhantomData;
 
+///
+/// Streams byte buffer holding the framed data, returned by `PacketPayload`, re-exported
+/// as it is
+///
+pub use iota_streams::ddml::types::Bytes;
+
+///
+/// Version of the payload framing written by this crate
+///
+pub const FRAMING_VERSION: u8 = 1;
+
 ///
 /// Simple Trait to transform the payload to string using any serde serializer
 ///
@@ -14,12 +39,12 @@ pub trait PayloadSerializer {
     ///
     /// Transform data to String
     ///
-    fn serialize_data<T: Serialize>(data: &T) -> failure::Fallible<String>;
+    fn serialize_data<T: Serialize>(data: &T) -> Result<String>;
 
     ///
     /// Transform data to String
     ///
-    fn deserialize_data<T: DeserializeOwned>(data: &[u8]) -> failure::Fallible<T>;
+    fn deserialize_data<T: DeserializeOwned>(data: &[u8]) -> Result<T>;
 }
 
 ///
@@ -52,14 +77,34 @@ where
     ///
     /// Unwrap JSON Data
     ///
-    pub fn unwrap_data(data: &str) -> failure::Fallible<Option<String>> {
-        let data_str = data.to_string();
-        if data_str.len() == 0 {
+    /// Fails if the data is not trytes, or if the decoded data is not valid UTF-8 or
+    /// can't be deserialized. An empty slot, or a frame without data, unwraps to `None`
+    ///
+    pub fn unwrap_data(data: &str) -> Result<Option<String>> {
+        if data.is_empty() {
+            return Ok(None);
+        }
+        check_trytes(data)?;
+        let raw = trytes_to_string(data)?;
+        let decode_data = unframe(decode_config(&raw, URL_SAFE_NO_PAD)?)?;
+        if decode_data.is_empty() {
             return Ok(None);
         }
-        let raw = trytes_to_string(&data.to_string())?;
-        let decode_data = decode_config(&raw, URL_SAFE_NO_PAD)?;
-        Ok(Some(String::from_utf8(decode_data).unwrap()))
+        S::deserialize_data::<IgnoredAny>(&decode_data)?;
+        let text = String::from_utf8(decode_data).map_err(|e| {
+            ChannelError::PayloadEncoding(format!("Payload is not valid UTF-8: {}", e))
+        })?;
+        Ok(Some(text))
+    }
+
+    ///
+    /// Unwrap the raw bytes of a message payload
+    ///
+    pub fn unwrap_bytes(data: &[u8]) -> Result<Option<String>> {
+        let data_str = String::from_utf8(data.to_vec()).map_err(|e| {
+            ChannelError::PayloadEncoding(format!("Payload is not valid trytes: {}", e))
+        })?;
+        Self::unwrap_data(&data_str)
     }
 }
 
@@ -99,26 +144,26 @@ where
     ///
     /// Public Data
     ///
-    pub fn public<T>(&mut self, data: &T) -> failure::Fallible<&mut Self>
+    pub fn public<T>(&mut self, data: &T) -> Result<&mut Self>
     where
         T: Serialize,
     {
         // let json_payload = serde_json::to_string(data).unwrap();
         let payload_str = S::serialize_data(data)?;
-        self.p_data = to_trytes(&encode_config(&payload_str, URL_SAFE_NO_PAD))?;
+        self.p_data = to_trytes(&encode_config(&frame(&payload_str), URL_SAFE_NO_PAD))?;
         Ok(self)
     }
 
     ///
     /// Public Data
     ///
-    pub fn masked<T>(&mut self, data: &T) -> failure::Fallible<&mut Self>
+    pub fn masked<T>(&mut self, data: &T) -> Result<&mut Self>
     where
         T: Serialize,
     {
         // let json_payload = serde_json::to_string(data).unwrap();
         let payload_str = S::serialize_data(data)?;
-        self.m_data = to_trytes(&encode_config(&payload_str, URL_SAFE_NO_PAD))?;
+        self.m_data = to_trytes(&encode_config(&frame(&payload_str), URL_SAFE_NO_PAD))?;
         Ok(self)
     }
 
@@ -134,26 +179,128 @@ where
     }
 }
 
+///
+/// Check the data only holds trytes, two per encoded char
+///
+/// The trytes converter assumes both and panics on other input
+///
+pub(crate) fn check_trytes(data: &str) -> Result<()> {
+    if let Some(c) = data.chars().find(|c| *c != '9' && !c.is_ascii_uppercase()) {
+        return Err(ChannelError::PayloadEncoding(format!(
+            "Payload is not valid trytes: unexpected {:?}",
+            c
+        )));
+    }
+    if data.len() % 2 != 0 {
+        return Err(ChannelError::PayloadEncoding(
+            "Payload is not valid trytes: odd length".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn frame(data: &str) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(data.len() + 1);
+    framed.push(FRAMING_VERSION);
+    framed.extend_from_slice(data.as_bytes());
+    framed
+}
+
+fn unframe(mut data: Vec<u8>) -> Result<Vec<u8>> {
+    let version = match data.first() {
+        Some(b) => *b,
+        None => return Ok(data),
+    };
+    match version {
+        // Written before the framing, the data starts with the JSON document itself
+        b'{'
+        | b'['
+        | b'"'
+        | b'-'
+        | b'0'..=b'9'
+        | b't'
+        | b'f'
+        | b'n'
+        | b' '
+        | b'\t'
+        | b'\n'
+        | b'\r' => Ok(data),
+        FRAMING_VERSION => {
+            data.remove(0);
+            Ok(data)
+        }
+        version => Err(ChannelError::UnsupportedFraming {
+            version: version,
+            supported: FRAMING_VERSION,
+        }),
+    }
+}
+
 pub mod json {
     //!
     //! JSON Payload Serialization module
     //!
     use super::PayloadSerializer;
+    use crate::error::{ChannelError, Result};
     use serde::{de::DeserializeOwned, Serialize};
 
+    ///
+    /// Deepest nesting of arrays and objects in a JSON payload
+    ///
+    /// Below the recursion limit of the JSON decoder: deeper payloads would be published
+    /// but no subscriber could read them
+    ///
+    pub const MAX_NESTING: usize = 100;
+
     ///
     /// Implementation of JSON Serialize
     ///
     pub struct JsonSerializer;
 
     impl PayloadSerializer for JsonSerializer {
-        fn serialize_data<T: Serialize>(data: &T) -> failure::Fallible<String> {
-            serde_json::to_string(data).map_err(|e| failure::format_err!("{:#?}", e))
+        fn serialize_data<T: Serialize>(data: &T) -> Result<String> {
+            let json = serde_json::to_string(data)
+                .map_err(|e| ChannelError::PayloadEncoding(format!("{:#?}", e)))?;
+            check_nesting(&json)?;
+            Ok(json)
+        }
+
+        fn deserialize_data<T: DeserializeOwned>(data: &[u8]) -> Result<T> {
+            serde_json::from_slice(data)
+                .map_err(|e| ChannelError::PayloadEncoding(format!("{:#?}", e)))
         }
+    }
 
-        fn deserialize_data<T: DeserializeOwned>(data: &[u8]) -> failure::Fallible<T> {
-            serde_json::from_slice(data).map_err(|e| failure::format_err!("{:#?}", e))
+    fn check_nesting(json: &str) -> Result<()> {
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+        for b in json.bytes() {
+            if in_string {
+                match b {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => (),
+                }
+                continue;
+            }
+            match b {
+                b'"' => in_string = true,
+                b'[' | b'{' => {
+                    depth += 1;
+                    if depth > MAX_NESTING {
+                        return Err(ChannelError::PayloadEncoding(format!(
+                            "Payload is nested deeper than {} levels",
+                            MAX_NESTING
+                        )));
+                    }
+                }
+                b']' | b'}' => depth = depth.saturating_sub(1),
+                _ => (),
+            }
         }
+        Ok(())
     }
 
     /// Payload JSON
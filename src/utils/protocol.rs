@@ -0,0 +1,50 @@
+//!
+//! Protocol version marker
+//!
+//! The author publishes the marker right after the announcement, linked to it, so that
+//! the subscribers learn which version of the envelopes, chunking and audit formats the
+//! channel uses before reading anything else. The channels opened before the marker
+//! existed don't have one.
+//!
+//! Only the packet at the position following the announcement is taken for the marker,
+//! and only when `channels_lite_protocol` is its single member: an application packet
+//! with the same member elsewhere in the channel is delivered as any other.
+//!
+use serde::{Deserialize, Serialize};
+
+///
+/// Protocol version published by this crate
+///
+pub const PROTOCOL_VERSION: u32 = 1;
+
+///
+/// Public payload of the packet published by `open` after the announcement
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ProtocolMarker {
+    /// Protocol version of the channel
+    ///
+    pub channels_lite_protocol: u32,
+}
+
+impl ProtocolMarker {
+    ///
+    /// Marker of the version published by this crate
+    ///
+    pub fn new() -> Self {
+        ProtocolMarker {
+            channels_lite_protocol: PROTOCOL_VERSION,
+        }
+    }
+
+    ///
+    /// Protocol version carried by an unwrapped public payload, `None` if it isn't the
+    /// marker
+    ///
+    pub fn parse(public: &str) -> Option<u32> {
+        serde_json::from_str::<ProtocolMarker>(public)
+            .ok()
+            .map(|marker| marker.channels_lite_protocol)
+    }
+}
@@ -0,0 +1,80 @@
+//!
+//! Pre-shared keys
+//!
+//! A pre-shared key grants access to the masked data of a channel without a subscribe
+//! handshake, the author adds it to a keyload and the readers provisioned with it
+//! decrypt the messages anchored after that keyload
+//!
+use super::secret::REDACTED;
+use iota_streams::app_channels::api::{
+    psk_from_seed, pskid_from_psk, Psk as StreamsPsk, PskId as StreamsPskId,
+};
+use std::fmt;
+
+///
+/// Pre-shared key, printed as `[redacted]`
+///
+#[derive(Clone, PartialEq)]
+pub struct Psk(StreamsPsk);
+
+impl Psk {
+    ///
+    /// Key bytes, keep them out of the logs
+    ///
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+
+    pub(crate) fn into_streams(self) -> StreamsPsk {
+        self.0
+    }
+}
+
+impl fmt::Debug for Psk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+///
+/// Identifier of a pre-shared key, published in the keyloads
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PskId(StreamsPskId);
+
+impl PskId {
+    ///
+    /// Identifier bytes
+    ///
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+
+    ///
+    /// Identifier as lowercase hex, as listed in the audit reports
+    ///
+    pub fn to_hex(&self) -> String {
+        self.as_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub(crate) fn into_streams(self) -> StreamsPskId {
+        self.0
+    }
+}
+
+///
+/// Pre-shared key derived from a shared secret
+///
+pub fn new_psk(secret: &[u8]) -> Psk {
+    Psk(psk_from_seed(secret))
+}
+
+///
+/// Identifier of a pre-shared key, published in the keyloads
+///
+pub fn psk_id(psk: &Psk) -> PskId {
+    PskId(pskid_from_psk(&psk.0))
+}
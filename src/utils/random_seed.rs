@@ -2,22 +2,68 @@
 //! To Generate a new Random Seed
 //!
 extern crate rand;
+use crate::error::{ConfigError, Result};
 use rand::Rng;
 
+///
+/// Default seed length
+///
+pub const DEFAULT_SEED_LEN: usize = 81;
+
+///
+/// Default seed alphabet, the trytes A..Z and 9
+///
+pub const DEFAULT_SEED_ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ9";
+
 ///
 /// Generates a new random String of 81 Chars of A..Z and 9
 ///
 pub fn new() -> String {
-    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ9";
-    const SEED_LEN: usize = 81;
+    let charset: Vec<char> = DEFAULT_SEED_ALPHABET.chars().collect();
+    generate(DEFAULT_SEED_LEN, &charset)
+}
+
+///
+/// Generates a new random String of `len` Chars of the alphabet, fails on an empty
+/// alphabet
+///
+pub fn new_with(len: usize, alphabet: &str) -> Result<String> {
+    let charset: Vec<char> = alphabet.chars().collect();
+    if charset.is_empty() {
+        return Err(ConfigError::InvalidSeed("the alphabet is empty".to_string()).into());
+    }
+    Ok(generate(len, &charset))
+}
+
+fn generate(len: usize, charset: &[char]) -> String {
     let mut rng = rand::thread_rng();
+    (0..len)
+        .map(|_| charset[rng.gen_range(0, charset.len())])
+        .collect()
+}
 
-    let seed: String = (0..SEED_LEN)
-        .map(|_| {
-            let idx = rng.gen_range(0, CHARSET.len());
-            CHARSET[idx] as char
-        })
-        .collect();
+///
+/// Check a seed has 81 Chars of A..Z and 9
+///
+pub fn validate(seed: &str) -> Result<()> {
+    validate_with(seed, DEFAULT_SEED_LEN, DEFAULT_SEED_ALPHABET)
+}
 
-    seed
+///
+/// Check a seed has `len` Chars of the alphabet
+///
+pub fn validate_with(seed: &str, len: usize, alphabet: &str) -> Result<()> {
+    let seed_len = seed.chars().count();
+    if seed_len != len {
+        return Err(
+            ConfigError::InvalidSeed(format!("{} chars, {} expected", seed_len, len)).into(),
+        );
+    }
+    // The seed chars are secret, the error doesn't tell which one is wrong
+    if seed.chars().any(|c| !alphabet.contains(c)) {
+        return Err(
+            ConfigError::InvalidSeed(format!("chars not in the alphabet {}", alphabet)).into(),
+        );
+    }
+    Ok(())
 }
@@ -0,0 +1,61 @@
+//!
+//! JSON schema validation
+//!
+use crate::error::{ConfigError, Result};
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::{fmt, rc::Rc};
+
+///
+/// JSON schema the payloads are validated against
+///
+/// The schema is compiled once, in the constructor. The compiled schema borrows the
+/// schema for the life of the program, create it once and share it.
+///
+#[derive(Clone)]
+pub struct JsonSchema {
+    schema: &'static Value,
+    compiled: Rc<JSONSchema<'static>>,
+}
+
+impl JsonSchema {
+    ///
+    /// Check, compile and wrap a JSON schema
+    ///
+    pub fn new(schema: Value) -> Result<Self> {
+        let schema: &'static Value = Box::leak(Box::new(schema));
+        let compiled = JSONSchema::compile(schema, None)
+            .map_err(|e| ConfigError::InvalidSchema(e.to_string()))?;
+        Ok(JsonSchema {
+            schema: schema,
+            compiled: Rc::new(compiled),
+        })
+    }
+
+    ///
+    /// Parse a JSON schema
+    ///
+    pub fn from_str(schema: &str) -> Result<Self> {
+        let schema =
+            serde_json::from_str(schema).map_err(|e| ConfigError::InvalidSchema(e.to_string()))?;
+        Self::new(schema)
+    }
+
+    ///
+    /// Validate a JSON value, returns the violations found
+    ///
+    pub fn violations(&self, instance: &Value) -> Vec<String> {
+        match self.compiled.validate(instance) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.map(|e| e.to_string()).collect(),
+        }
+    }
+}
+
+impl fmt::Debug for JsonSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonSchema")
+            .field("schema", self.schema)
+            .finish()
+    }
+}
@@ -0,0 +1,101 @@
+//!
+//! Secrets kept out of the logs
+//!
+//! The seeds and pre-shared keys kept by the channels are held in a `Secret`, printed
+//! as a placeholder by `Debug` and `Display`. The errors raised by Streams are scrubbed
+//! of anything looking like a seed before becoming a `ChannelError`: the channels only
+//! put tags and addresses in their messages, both public.
+//!
+use super::random_seed::DEFAULT_SEED_ALPHABET;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+///
+/// Placeholder printed instead of a secret
+///
+pub const REDACTED: &str = "[redacted]";
+
+///
+/// Shortest run of seed chars considered a seed fragment by `redact_trytes`
+///
+pub const MIN_REDACTED_RUN: usize = 16;
+
+///
+/// Value printed as `[redacted]`
+///
+/// Serialized as the value itself, the stores seal it before it reaches the disk
+///
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    ///
+    /// Wrap a secret
+    ///
+    pub fn new(value: T) -> Self {
+        Secret(value)
+    }
+
+    ///
+    /// The secret itself, keep it out of the logs
+    ///
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    ///
+    /// Unwrap the secret
+    ///
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Secret(value)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+///
+/// Replace the runs of `MIN_REDACTED_RUN` or more seed chars, `A..Z` and `9`, by
+/// `[redacted]`
+///
+/// The tags and addresses are lowercase hex and go through untouched
+///
+pub fn redact_trytes(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut run = String::new();
+    for c in text.chars() {
+        if DEFAULT_SEED_ALPHABET.contains(c) {
+            run.push(c);
+            continue;
+        }
+        flush_run(&mut redacted, &mut run);
+        redacted.push(c);
+    }
+    flush_run(&mut redacted, &mut run);
+    redacted
+}
+
+fn flush_run(redacted: &mut String, run: &mut String) {
+    if run.chars().count() >= MIN_REDACTED_RUN {
+        redacted.push_str(REDACTED);
+    } else {
+        redacted.push_str(run);
+    }
+    run.clear();
+}
@@ -0,0 +1,253 @@
+//!
+//! Sequence numbers
+//!
+//! A sender numbering its messages carries the public data in an envelope:
+//!
+//! `{"channels_lite_envelope": "sequenced", "seq": {"sender": <sender id>, "n": <number>}, "data": <public data>}`
+//!
+//! The numbers of a sender follow each other, the `SequenceGuard` of the subscriber
+//! reports the replayed and the missing ones. An author restored from a snapshot adds
+//! its generation, `"generation": <generation>`, so that the packets of the author it
+//! replaced are told apart
+//!
+//! The sender id of the envelope is only a claim, the subscriber tracks the signed
+//! packets under their signer. See `envelope` for how the envelopes are recognised
+//!
+use super::envelope::{required_data, EnvelopeKind};
+use super::payload::{
+    json::{Payload, PayloadBuilder},
+    PacketPayload,
+};
+use crate::error::Result;
+use iota_streams::ddml::types::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+///
+/// Position of a message among the messages of its sender
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SequenceNumber {
+    /// Sender id, the channel address for the author
+    ///
+    pub sender: String,
+    /// Sequence number, starting at 0
+    ///
+    pub n: u64,
+    /// Generation of the sender, raised by each snapshot of the author
+    ///
+    #[serde(default, skip_serializing_if = "is_first_generation")]
+    pub generation: u64,
+}
+
+fn is_first_generation(generation: &u64) -> bool {
+    *generation == 0
+}
+
+///
+/// Public payload of a numbered message
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Sequenced {
+    /// Kind of envelope, always `EnvelopeKind::Sequenced`
+    ///
+    #[serde(rename = "channels_lite_envelope")]
+    pub envelope: EnvelopeKind,
+    /// Sequence number of the message
+    ///
+    pub seq: SequenceNumber,
+    /// Public data of the message
+    ///
+    #[serde(deserialize_with = "required_data")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl Sequenced {
+    ///
+    /// Parse an unwrapped public payload as a sequence envelope
+    ///
+    pub fn parse(public: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(public)
+            .ok()
+            .filter(|envelope| envelope.envelope == EnvelopeKind::Sequenced)
+    }
+}
+
+///
+/// Payload with its public data wrapped in a `Sequenced` envelope
+///
+pub(crate) struct SequencedPayload {
+    public: Bytes,
+    masked: Bytes,
+}
+
+impl SequencedPayload {
+    ///
+    /// Wrap the JSON public data of a payload
+    ///
+    pub(crate) fn wrap<P>(payload: &P, seq: SequenceNumber) -> Result<Self>
+    where
+        P: PacketPayload,
+    {
+        let data = match Payload::unwrap_bytes(&payload.public_data().0)? {
+            Some(public) => Some(serde_json::from_str(&public)?),
+            None => None,
+        };
+        let envelope = Sequenced {
+            envelope: EnvelopeKind::Sequenced,
+            seq: seq,
+            data: data,
+        };
+        let public = PayloadBuilder::new().public(&envelope)?.build();
+        Ok(SequencedPayload {
+            public: public.public_data().clone(),
+            masked: payload.masked_data().clone(),
+        })
+    }
+}
+
+impl PacketPayload for SequencedPayload {
+    fn public_data(&self) -> &Bytes {
+        &self.public
+    }
+    fn masked_data(&self) -> &Bytes {
+        &self.masked
+    }
+}
+
+///
+/// Irregularity found by the `SequenceGuard`
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceEvent {
+    /// The number was already delivered, the message is a replay
+    ///
+    Duplicate {
+        /// Sender id
+        ///
+        sender: String,
+        /// Sequence number
+        ///
+        seq: u64,
+        /// Message tag
+        ///
+        tag: String,
+    },
+    /// Numbers are missing before the message
+    ///
+    Gap {
+        /// Sender id
+        ///
+        sender: String,
+        /// Next number expected
+        ///
+        expected: u64,
+        /// Number found
+        ///
+        got: u64,
+        /// Message tag
+        ///
+        tag: String,
+    },
+    /// The message was written by an older generation of the sender, e.g. an author
+    /// still writing after handing over with a snapshot
+    ///
+    StaleGeneration {
+        /// Sender id
+        ///
+        sender: String,
+        /// Generation of the message
+        ///
+        generation: u64,
+        /// Latest generation seen for the sender
+        ///
+        current: u64,
+        /// Message tag
+        ///
+        tag: String,
+    },
+}
+
+///
+/// Highest sequence number delivered per sender
+///
+/// The first number seen for a sender is taken as is, the subscriber may have joined
+/// after the first messages
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SequenceGuard {
+    high_water: BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    generations: BTreeMap<String, u64>,
+}
+
+impl SequenceGuard {
+    ///
+    /// Guard without any sender seen
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Highest number delivered for the sender
+    ///
+    pub fn high_water(&self, sender: &str) -> Option<u64> {
+        self.high_water.get(sender).copied()
+    }
+
+    ///
+    /// Check the number of a delivered message under the sender id of its envelope
+    ///
+    pub fn check(&mut self, seq: &SequenceNumber, tag: &str) -> Option<SequenceEvent> {
+        self.check_from(&seq.sender, seq, tag)
+    }
+
+    ///
+    /// Check the number of a delivered message under `sender`, raising the high-water
+    /// mark
+    ///
+    /// The subscriber passes the verified signer of the packet, so that a packet claiming
+    /// the sender id of another never moves its numbers. The messages of an older
+    /// generation than the latest seen are reported without touching the high-water mark
+    ///
+    pub fn check_from(
+        &mut self,
+        sender: &str,
+        seq: &SequenceNumber,
+        tag: &str,
+    ) -> Option<SequenceEvent> {
+        let current = self.generations.get(sender).copied().unwrap_or(0);
+        if seq.generation < current {
+            return Some(SequenceEvent::StaleGeneration {
+                sender: sender.to_string(),
+                generation: seq.generation,
+                current: current,
+                tag: tag.to_string(),
+            });
+        }
+        if seq.generation > current {
+            self.generations.insert(sender.to_string(), seq.generation);
+        }
+        let event = match self.high_water(sender) {
+            Some(high_water) if seq.n <= high_water => {
+                return Some(SequenceEvent::Duplicate {
+                    sender: sender.to_string(),
+                    seq: seq.n,
+                    tag: tag.to_string(),
+                })
+            }
+            Some(high_water) if seq.n > high_water + 1 => Some(SequenceEvent::Gap {
+                sender: sender.to_string(),
+                expected: high_water + 1,
+                got: seq.n,
+                tag: tag.to_string(),
+            }),
+            _ => None,
+        };
+        self.high_water.insert(sender.to_string(), seq.n);
+        event
+    }
+}
@@ -0,0 +1,137 @@
+//!
+//! Browser bindings of the subscriber
+//!
+//! Thin `wasm-bindgen` wrapper over the channel subscriber, the messages are returned
+//! to JavaScript as plain objects
+//!
+//! The browser main thread can't block: the blocking node queries fail, the send
+//! options of the constructor fall back to the static ones of the network, and the
+//! retries and waits make a single attempt. Check the node with the async `checkNode`
+//!
+// The code generated by wasm_bindgen is unsafe
+#![allow(unsafe_code)]
+use crate::channels_lite::{channel_subscriber, message::ChannelMessage, Network};
+use crate::error::ChannelError;
+use serde::Serialize;
+use std::{str::FromStr, time::SystemTime};
+use wasm_bindgen::prelude::*;
+
+///
+/// Message as seen from JavaScript
+///
+#[derive(Debug, Serialize)]
+pub struct MessageEnvelope {
+    /// Message tag
+    ///
+    pub tag: String,
+    /// Message kind, e.g. `signed_packet`
+    ///
+    pub kind: &'static str,
+    /// Unwrapped public data
+    ///
+    pub public: Option<String>,
+    /// Unwrapped masked data
+    ///
+    pub masked: Option<String>,
+    /// Attachment time reported by the node, `None` if the node didn't report it
+    ///
+    pub attached_at: Option<SystemTime>,
+}
+
+impl From<ChannelMessage> for MessageEnvelope {
+    fn from(msg: ChannelMessage) -> Self {
+        MessageEnvelope {
+            tag: msg.tag,
+            kind: msg.kind.as_str(),
+            public: msg.public,
+            masked: msg.masked,
+            attached_at: msg.attached_at,
+        }
+    }
+}
+
+///
+/// Channel subscriber for the browser
+///
+#[wasm_bindgen]
+pub struct WasmSubscriber {
+    channel: channel_subscriber::Channel,
+}
+
+#[wasm_bindgen]
+impl WasmSubscriber {
+    ///
+    /// Initialize the subscriber on a pre-defined network, e.g. `devnet`
+    ///
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        network: &str,
+        channel_address: String,
+        announcement_tag: String,
+        seed: Option<String>,
+    ) -> Result<WasmSubscriber, JsValue> {
+        let node = Network::from_str(network).map_err(to_js)?;
+        Ok(WasmSubscriber {
+            channel: channel_subscriber::Channel::new(
+                node,
+                channel_address,
+                announcement_tag,
+                seed,
+            )
+            .map_err(to_js)?,
+        })
+    }
+
+    ///
+    /// Connect to the channel, returns the subscription tag
+    ///
+    pub fn connect(&mut self) -> Result<String, JsValue> {
+        self.channel.connect().map_err(to_js)
+    }
+
+    ///
+    /// Update the keyload
+    ///
+    #[wasm_bindgen(js_name = updateKeyload)]
+    pub fn update_keyload(&mut self, keyload_tag: String) -> Result<(), JsValue> {
+        self.channel.update_keyload(keyload_tag).map_err(to_js)
+    }
+
+    ///
+    /// New messages since the previous call, as an array of envelopes
+    ///
+    #[wasm_bindgen(js_name = fetchAll)]
+    pub fn fetch_all(&mut self) -> Result<JsValue, JsValue> {
+        let msgs: Vec<MessageEnvelope> = self
+            .channel
+            .poll_new()
+            .map_err(to_js)?
+            .into_iter()
+            .map(MessageEnvelope::from)
+            .collect();
+        serde_wasm_bindgen::to_value(&msgs).map_err(JsValue::from)
+    }
+}
+
+impl WasmSubscriber {
+    ///
+    /// Wrap an existing subscriber, e.g. on the in-memory transport
+    ///
+    pub fn from_channel(channel: channel_subscriber::Channel) -> Self {
+        WasmSubscriber { channel: channel }
+    }
+}
+
+///
+/// Health of the node of a pre-defined network, e.g. `devnet`, fails if it is not synced
+///
+#[wasm_bindgen(js_name = checkNode)]
+pub async fn check_node(network: String) -> Result<JsValue, JsValue> {
+    let node = Network::from_str(&network).map_err(to_js)?;
+    let health = node.check_health_async().await.map_err(to_js)?;
+    serde_wasm_bindgen::to_value(&health).map_err(JsValue::from)
+}
+
+fn to_js(e: ChannelError) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
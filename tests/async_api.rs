@@ -0,0 +1,111 @@
+use channels_lite::channels::{channel_author, channel_subscriber, Network};
+use channels_lite::error::ChannelError;
+use channels_lite::transport::{mock::MockTransport, ChannelTransport};
+use channels_lite::utils::payload::json::PayloadBuilder;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+#[tokio::test(threaded_scheduler)]
+async fn async_flow_over_mock_transport() {
+    let tangle = MockTransport::new();
+
+    let mut author = channel_author::Channel::new_with_transport(
+        Network::Devnet,
+        ChannelTransport::Mock(tangle.clone()),
+        None,
+    )
+    .unwrap();
+    let (channel_address, announcement_tag) = author.open_async().await.unwrap();
+
+    let mut subscriber = channel_subscriber::Channel::new_with_transport(
+        Network::Devnet,
+        ChannelTransport::Mock(tangle.clone()),
+        channel_address,
+        announcement_tag,
+        None,
+    )
+    .unwrap();
+    let subscription_tag = subscriber.connect_async().await.unwrap();
+    let keyload_tag = author.add_subscriber_async(subscription_tag).await.unwrap();
+    subscriber.update_keyload_async(keyload_tag).await.unwrap();
+
+    let signed_tag = author
+        .write_signed_async(PayloadBuilder::new().public(&1).unwrap().build())
+        .await
+        .unwrap();
+
+    let msg = subscriber
+        .next_message_async(Duration::from_secs(1))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(msg.tag, signed_tag);
+    assert!(subscriber
+        .next_message_async(Duration::from_millis(0))
+        .await
+        .unwrap()
+        .is_none());
+
+    let signed = subscriber.read_signed_async(signed_tag).await.unwrap();
+    assert_eq!(signed[0].0.as_deref(), Some("1"));
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn consume_waits_for_the_handler() {
+    let tangle = MockTransport::new();
+
+    let mut author = channel_author::Channel::new_with_transport(
+        Network::Devnet,
+        ChannelTransport::Mock(tangle.clone()),
+        None,
+    )
+    .unwrap();
+    let (channel_address, announcement_tag) = author.open_async().await.unwrap();
+    let mut subscriber = channel_subscriber::Channel::new_with_transport(
+        Network::Devnet,
+        ChannelTransport::Mock(tangle.clone()),
+        channel_address,
+        announcement_tag,
+        None,
+    )
+    .unwrap();
+    let subscription_tag = subscriber.connect_async().await.unwrap();
+    let keyload_tag = author.add_subscriber_async(subscription_tag).await.unwrap();
+    subscriber.update_keyload_async(keyload_tag).await.unwrap();
+
+    let mut tags = Vec::new();
+    for i in 0..3 {
+        tags.push(
+            author
+                .write_signed_async(PayloadBuilder::new().public(&i).unwrap().build())
+                .await
+                .unwrap(),
+        );
+    }
+    let end_tag = author.close_async().await.unwrap();
+
+    let handled = Rc::new(RefCell::new(Vec::new()));
+    let in_flight = Rc::new(Cell::new(false));
+    subscriber
+        .consume(|msg| {
+            let handled = handled.clone();
+            let in_flight = in_flight.clone();
+            async move {
+                // The previous message was fully handled before this one was handed over
+                assert!(!in_flight.replace(true));
+                tokio::time::delay_for(Duration::from_millis(10)).await;
+                handled.borrow_mut().push(msg.tag);
+                in_flight.set(false);
+                Ok::<_, ChannelError>(())
+            }
+        })
+        .await
+        .unwrap();
+
+    let handled = handled.borrow();
+    let packets: Vec<&String> = handled.iter().filter(|tag| tags.contains(tag)).collect();
+    assert_eq!(packets, tags.iter().collect::<Vec<_>>());
+    assert_eq!(handled.last(), Some(&end_tag));
+    assert!(subscriber.is_closed());
+}
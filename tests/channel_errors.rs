@@ -0,0 +1,245 @@
+use base64::{encode_config, URL_SAFE_NO_PAD};
+use channels_lite::channels::{author_state::AuthorState, policy::ChannelPolicy};
+use channels_lite::error::ChannelError;
+use channels_lite::testing::{TestChannel, TestTangle};
+use channels_lite::utils::payload::{
+    json::{Payload, PayloadBuilder},
+    PacketPayload, FRAMING_VERSION,
+};
+use channels_lite::utils::psk::new_psk;
+use iota_conversion::trytes_converter::to_trytes;
+
+fn payload(value: u32) -> Payload {
+    PayloadBuilder::new().public(&value).unwrap().build()
+}
+
+#[test]
+fn write_before_open_is_not_opened() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+
+    match author.write_signed(payload(1)) {
+        Err(ChannelError::NotOpened) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_subscribe_tag_is_invalid_address() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    author.open().unwrap();
+
+    match author.add_subscriber("not a tag".to_string()) {
+        Err(ChannelError::InvalidAddress { .. }) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn write_before_keyload_is_no_keyload() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    let (channel_address, _) = author.open().unwrap();
+
+    match author.write_signed(payload(1)) {
+        Err(ChannelError::NoKeyload(address)) => assert_eq!(address, channel_address),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn automatic_keyload_on_first_write() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    author.open().unwrap();
+    author.set_auto_keyload(true);
+
+    author.write_signed(payload(1)).unwrap();
+    author.write_signed(payload(2)).unwrap();
+    assert_eq!(author.audit_report().keyloads.len(), 1);
+}
+
+#[test]
+fn write_after_close_is_state_error() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.channel(1).unwrap().author;
+    author.close().unwrap();
+
+    match author.write_signed(payload(1)) {
+        Err(ChannelError::State(_)) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn read_before_connect_is_not_connected() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+    let mut subscriber = tangle.subscriber(&channel_address, &announcement_tag);
+
+    match subscriber.read_signed(announcement_tag) {
+        Err(ChannelError::NotConnected) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn unknown_tag_is_message_not_found() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+    let mut subscriber = tangle.subscriber(&channel_address, &announcement_tag);
+    subscriber.connect().unwrap();
+
+    // A tag of another channel, well formed but absent from this one
+    let (_, other_tag) = tangle.author().open().unwrap();
+    match subscriber.read_signed(other_tag.clone()) {
+        Err(ChannelError::MessageNotFound(tag)) => assert_eq!(tag, other_tag),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn tag_of_another_message_is_announcement_mismatch() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+    let mut first = tangle.subscriber(&channel_address, &announcement_tag);
+    let keyload_tag = author.add_subscriber(first.connect().unwrap()).unwrap();
+
+    // The keyload tag pasted in place of the announcement tag
+    let mut subscriber = tangle.subscriber(&channel_address, &keyload_tag);
+    match subscriber.connect() {
+        Err(ChannelError::AnnouncementMismatch {
+            channel_address: address,
+            announcement_tag: tag,
+            ..
+        }) => {
+            assert_eq!(address, channel_address);
+            assert_eq!(tag, keyload_tag);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn packet_without_keyload_is_access_denied() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+    let mut subscriber = tangle.subscriber(&channel_address, &announcement_tag);
+    let subscription_tag = subscriber.connect().unwrap();
+    author.add_subscriber(subscription_tag).unwrap();
+    let signed_tag = author.write_signed(payload(1)).unwrap();
+
+    subscriber.set_strict_access(true);
+    match subscriber.read_signed(signed_tag.clone()) {
+        Err(ChannelError::AccessDenied(tag)) => assert_eq!(tag, signed_tag),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn keyload_of_other_subscribers_is_access_denied() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+    let mut subscriber = tangle.subscriber(&channel_address, &announcement_tag);
+    let subscription_tag = subscriber.connect().unwrap();
+    let keyload_tag = author.add_subscriber(subscription_tag).unwrap();
+    let mut outsider = tangle.subscriber(&channel_address, &announcement_tag);
+    outsider.connect().unwrap();
+
+    match outsider.read_from_keyload(keyload_tag.clone()) {
+        Err(ChannelError::AccessDenied(tag)) => assert_eq!(tag, keyload_tag),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn unknown_framing_version_is_rejected() {
+    let framed = payload(7);
+    assert_eq!(
+        Payload::unwrap_bytes(&framed.public_data().0).unwrap(),
+        Some("7".to_string())
+    );
+
+    let unframed = to_trytes(&encode_config("{\"a\":1}", URL_SAFE_NO_PAD)).unwrap();
+    assert_eq!(
+        Payload::unwrap_data(&unframed).unwrap(),
+        Some("{\"a\":1}".to_string())
+    );
+
+    let mut future = vec![FRAMING_VERSION + 1];
+    future.extend_from_slice(b"{}");
+    let future = to_trytes(&encode_config(&future, URL_SAFE_NO_PAD)).unwrap();
+    match Payload::unwrap_data(&future) {
+        Err(ChannelError::UnsupportedFraming { version, supported }) => {
+            assert_eq!(version, FRAMING_VERSION + 1);
+            assert_eq!(supported, FRAMING_VERSION);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn channel_errors_convert_to_anyhow() {
+    fn write_before_open() -> anyhow::Result<()> {
+        let tangle = TestTangle::new();
+        tangle.author().write_signed(payload(1))?;
+        Ok(())
+    }
+
+    let error = write_before_open().unwrap_err();
+    match error.downcast_ref::<ChannelError>() {
+        Some(ChannelError::NotOpened) => (),
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[test]
+fn writes_over_the_epoch_limit_are_policy_violations() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        keyload_tag,
+        ..
+    } = tangle.channel(1).unwrap();
+    author.set_policy(ChannelPolicy {
+        max_messages_per_epoch: Some(2),
+        max_payload_bytes: None,
+    });
+
+    author.write_signed(payload(1)).unwrap();
+    author.write_tagged(payload(2)).unwrap();
+    match author.write_signed(payload(3)) {
+        Err(ChannelError::PolicyViolation {
+            keyload_tag: epoch,
+            messages,
+            ..
+        }) => {
+            assert_eq!(Some(epoch), keyload_tag);
+            assert_eq!(messages, 2);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert_eq!(author.stats().epoch.messages, 2);
+
+    // A new keyload opens a new epoch, the counters survive a restart
+    author.add_psk(new_psk(b"epoch")).unwrap();
+    assert_eq!(author.stats().epoch.messages, 0);
+    author.write_signed(payload(4)).unwrap();
+    let state = AuthorState::decode(&author.export_state()).unwrap();
+    assert_eq!(state.epoch.messages, 1);
+
+    author.set_policy(ChannelPolicy {
+        max_messages_per_epoch: None,
+        max_payload_bytes: Some(8),
+    });
+    match author.write_signed(payload(1_000_000)) {
+        Err(ChannelError::PolicyViolation { messages, .. }) => assert_eq!(messages, 1),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
@@ -0,0 +1,2072 @@
+use channels_lite::channels::{
+    announcement_cache::AnnouncementCache,
+    audit::AuditReport,
+    channel_author, channel_subscriber,
+    channel_subscriber::{GapPolicy, KeyloadInfo, MissingTimestampPolicy},
+    cursor::Cursor,
+    decoders::{Decoded, Decoders},
+    message::{AccessLevel, MessageKind},
+    policy::ChannelPolicy,
+    reader::{fetch_public, ChannelReader},
+    summary::ChannelRole,
+    Network,
+};
+use channels_lite::error::{ChannelError, ErrorCode};
+use channels_lite::testing::{
+    assert_not_readable, assert_readable, fixed_seed, fixture, TestAuthor, TestChannel, TestTangle,
+};
+use channels_lite::transport::{
+    mock::MockTransport,
+    retry::RetryConfig,
+    tangle::{fragments, PAYLOAD_BYTES},
+    Address, Message, Transport,
+};
+use channels_lite::utils::ack::{AckOutcome, ACK_RETENTION};
+use channels_lite::utils::dedup::Deduplicator;
+use channels_lite::utils::digest::{canonical_json, content_digest};
+use channels_lite::utils::end_of_channel::EndOfChannel;
+use channels_lite::utils::multipart::MultiPartPayloadBuilder;
+use channels_lite::utils::payload::{json::PayloadBuilder, Bytes, PacketPayload};
+use channels_lite::utils::protocol::PROTOCOL_VERSION;
+use channels_lite::utils::psk::new_psk;
+use channels_lite::utils::sequence::{SequenceEvent, SequenceGuard, SequenceNumber};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SensorData {
+    presure: f32,
+}
+
+#[test]
+fn full_flow_over_mock_transport() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+
+    let signed_tag = author
+        .write_signed(
+            PayloadBuilder::new()
+                .public(&SensorData { presure: 1.0 })
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+    let tagged_tag = author
+        .write_tagged(
+            PayloadBuilder::new()
+                .public(&SensorData { presure: 2.0 })
+                .unwrap()
+                .masked(&SensorData { presure: 3.0 })
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+
+    let signed = subscriber.read_signed(signed_tag).unwrap();
+    assert_eq!(signed.len(), 1);
+    let public: SensorData = serde_json::from_str(signed[0].0.as_ref().unwrap()).unwrap();
+    assert_eq!(public, SensorData { presure: 1.0 });
+
+    let tagged = subscriber.read_tagged(tagged_tag).unwrap();
+    assert_eq!(tagged.len(), 1);
+    let public: SensorData = serde_json::from_str(tagged[0].0.as_ref().unwrap()).unwrap();
+    let masked: SensorData = serde_json::from_str(tagged[0].1.as_ref().unwrap()).unwrap();
+    assert_eq!(public, SensorData { presure: 2.0 });
+    assert_eq!(masked, SensorData { presure: 3.0 });
+}
+
+#[test]
+fn multi_part_payload_over_mock_transport() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+
+    let signed_tag = author
+        .write_signed(
+            MultiPartPayloadBuilder::new()
+                .public("reading", b"{\"presure\":1.0}")
+                .public("thumbnail", &[0, 1, 2, 255])
+                .masked("metadata", b"calibrated")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+    let parts = subscriber.read_signed_parts(signed_tag).unwrap();
+    assert_eq!(parts.len(), 1);
+    let (public, masked) = &parts[0];
+    assert_eq!(public["reading"], b"{\"presure\":1.0}".to_vec());
+    assert_eq!(public["thumbnail"], vec![0, 1, 2, 255]);
+    assert_eq!(masked["metadata"], b"calibrated".to_vec());
+}
+
+#[test]
+fn subtree_under_keyload_over_mock_transport() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        keyload_tag,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    let keyload_tag = keyload_tag.unwrap();
+
+    let mut signed_tags = Vec::new();
+    for presure in 0u8..2 {
+        signed_tags.push(
+            author
+                .write_signed(
+                    PayloadBuilder::new()
+                        .public(&SensorData {
+                            presure: f32::from(presure),
+                        })
+                        .unwrap()
+                        .build(),
+                )
+                .unwrap(),
+        );
+    }
+
+    let subtree = subscriber.read_subtree(keyload_tag.clone()).unwrap();
+    let tags: Vec<String> = subtree.iter().map(|msg| msg.tag.clone()).collect();
+    assert_eq!(
+        tags,
+        vec![keyload_tag, signed_tags[0].clone(), signed_tags[1].clone()]
+    );
+
+    let subtree = subscriber.read_subtree(signed_tags[1].clone()).unwrap();
+    assert_eq!(subtree.len(), 1);
+}
+
+#[test]
+fn subtree_reads_dont_fetch_the_messages_outside_it_again() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+
+    let mut tags = Vec::new();
+    for presure in 0u8..10 {
+        tags.push(
+            author
+                .write_signed(
+                    PayloadBuilder::new()
+                        .public(&SensorData {
+                            presure: f32::from(presure),
+                        })
+                        .unwrap()
+                        .build(),
+                )
+                .unwrap(),
+        );
+    }
+    assert_eq!(subscriber.poll_new().unwrap().len(), 10);
+
+    let before = subscriber.transport_stats().recv.count;
+    assert!(subscriber.poll_new().unwrap().is_empty());
+    let walk = subscriber.transport_stats().recv.count - before;
+
+    let before = subscriber.transport_stats().recv.count;
+    let subtree = subscriber.read_subtree(tags[9].clone()).unwrap();
+    assert_eq!(subtree.len(), 1);
+    // The walk, then the anchor
+    assert!(subscriber.transport_stats().recv.count - before <= walk + 1);
+}
+
+#[test]
+fn raw_reads_fetch_the_message_once() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    let tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+
+    let before = subscriber.transport_stats().recv.count;
+    let msgs = subscriber.read_with_raw(tag.clone()).unwrap();
+    assert_eq!(subscriber.transport_stats().recv.count - before, 1);
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].tag, tag);
+    assert_eq!(msgs[0].public.as_deref(), Some("1"));
+    assert!(msgs[0].raw.is_some());
+}
+
+#[test]
+fn late_subscriber_reads_from_its_keyload() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        channel_address,
+        announcement_tag,
+        ..
+    } = tangle.channel(1).unwrap();
+    author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+
+    let mut late = tangle.subscriber(&channel_address, &announcement_tag);
+    let subscription_tag = late.connect().unwrap();
+    let keyload_tag = author.add_subscriber(subscription_tag).unwrap();
+    let signed_tag = author
+        .write_signed(PayloadBuilder::new().masked(&2).unwrap().build())
+        .unwrap();
+
+    let msgs = late.read_from_keyload(keyload_tag).unwrap();
+    let signed = msgs.iter().find(|msg| msg.tag == signed_tag).unwrap();
+    assert_eq!(signed.masked.as_deref(), Some("2"));
+}
+
+#[test]
+fn predicted_addresses_match_the_published_messages() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.channel(1).unwrap().author;
+
+    let predicted: Vec<String> = (0..3)
+        .map(|index| author.predict_message_address(index).unwrap())
+        .collect();
+    let published: Vec<String> = (0u8..3)
+        .map(|presure| {
+            author
+                .write_signed(
+                    PayloadBuilder::new()
+                        .public(&SensorData {
+                            presure: f32::from(presure),
+                        })
+                        .unwrap()
+                        .build(),
+                )
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(predicted, published);
+}
+
+#[test]
+fn read_only_subscriber_with_psk_over_mock_transport() {
+    let tangle = TestTangle::new();
+    let psk = new_psk(b"fleet secret");
+
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+    let keyload_tag = author.add_psk(psk.clone()).unwrap();
+    let tagged_tag = author
+        .write_tagged(
+            PayloadBuilder::new()
+                .masked(&SensorData { presure: 3.0 })
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+
+    let mut reader = channel_subscriber::Channel::new_read_only_with_transport(
+        Network::Devnet,
+        tangle.transport(),
+        channel_address,
+        announcement_tag.clone(),
+        psk,
+    )
+    .unwrap();
+    assert!(reader.is_read_only());
+    assert_eq!(reader.connect().unwrap(), announcement_tag);
+
+    let msgs = reader.poll_new().unwrap();
+    let tags: Vec<String> = msgs.iter().map(|msg| msg.tag.clone()).collect();
+    assert_eq!(tags, vec![keyload_tag, tagged_tag.clone()]);
+    let masked: SensorData = serde_json::from_str(msgs[1].masked.as_ref().unwrap()).unwrap();
+    assert_eq!(masked, SensorData { presure: 3.0 });
+
+    assert!(reader.acknowledge(tagged_tag).is_err());
+}
+
+#[test]
+fn subscribers_share_the_announcement_cache() {
+    let tangle = TestTangle::new();
+    let cache = AnnouncementCache::new();
+
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+
+    let mut subscribers: Vec<_> = (0..2)
+        .map(|_| {
+            let mut subscriber = tangle.subscriber(&channel_address, &announcement_tag);
+            subscriber.set_announcement_cache(cache.clone());
+            subscriber
+        })
+        .collect();
+
+    // The announcement, then the subscribe message fetched back
+    subscribers[0].connect().unwrap();
+    assert!(cache.contains(&channel_address, &announcement_tag));
+    assert_eq!(subscribers[0].transport_stats().recv.count, 2);
+
+    let subscription_tag = subscribers[1].connect().unwrap();
+    assert_eq!(subscribers[1].transport_stats().recv.count, 1);
+    assert_eq!(cache.len(), 1);
+
+    let keyload_tag = author.add_subscriber(subscription_tag).unwrap();
+    subscribers[1].update_keyload(keyload_tag).unwrap();
+    let signed_tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    let tags: Vec<String> = subscribers[1]
+        .poll_new()
+        .unwrap()
+        .into_iter()
+        .map(|msg| msg.tag)
+        .collect();
+    assert!(tags.contains(&signed_tag));
+}
+
+#[test]
+fn expired_messages_are_flagged_or_dropped() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(2).unwrap();
+    subscribers[1].set_drop_expired(true);
+
+    let stale_tag = author
+        .write_signed_ttl(
+            PayloadBuilder::new().public(&1).unwrap().build(),
+            Duration::from_secs(60),
+        )
+        .unwrap();
+    let fresh_tag = author
+        .write_signed_ttl(
+            PayloadBuilder::new().public(&2).unwrap().build(),
+            Duration::from_secs(3600),
+        )
+        .unwrap();
+    tangle.advance(Duration::from_secs(61));
+
+    let msgs = subscribers[0].poll_new().unwrap();
+    let stale = msgs.iter().find(|msg| msg.tag == stale_tag).unwrap();
+    assert!(stale.expired);
+    assert_eq!(stale.public.as_deref(), Some("1"));
+    let fresh = msgs.iter().find(|msg| msg.tag == fresh_tag).unwrap();
+    assert!(!fresh.expired);
+    assert!(fresh.expires_at.is_some());
+    assert_eq!(fresh.public.as_deref(), Some("2"));
+
+    let tags: Vec<String> = subscribers[1]
+        .poll_new()
+        .unwrap()
+        .into_iter()
+        .map(|msg| msg.tag)
+        .collect();
+    assert!(!tags.contains(&stale_tag));
+    assert!(tags.contains(&fresh_tag));
+}
+
+#[test]
+fn messages_carry_the_attachment_time_of_the_node() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    tangle.stamp_attachments();
+
+    let attached_at = tangle.now();
+    let tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    tangle.advance(Duration::from_secs(30));
+
+    let msgs = subscribers[0].poll_new().unwrap();
+    let msg = msgs.iter().find(|msg| msg.tag == tag).unwrap();
+    assert_eq!(msg.attached_at, Some(attached_at));
+    assert_eq!(subscribers[0].attachment_time(&tag).unwrap(), attached_at);
+    assert_eq!(author.attachment_time(&tag).unwrap(), attached_at);
+}
+
+#[test]
+fn the_ndjson_export_carries_the_attachment_time() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    tangle.stamp_attachments();
+
+    let attached_at = tangle.now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    tangle.advance(Duration::from_secs(3600));
+
+    let mut out = Vec::new();
+    subscribers[0].export_ndjson(&mut out).unwrap();
+    let lines: Vec<serde_json::Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let line = lines
+        .iter()
+        .find(|line| line["tag"] == tag.as_str())
+        .unwrap();
+    // The time the message was attached, not the time it was read
+    assert_eq!(line["timestamp"], attached_at);
+    assert_eq!(line["public"], "1");
+}
+
+#[test]
+fn fetch_window_returns_the_messages_attached_in_the_range() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(2).unwrap();
+    // The announcement and keyload carry no attachment time
+    tangle.stamp_attachments();
+
+    let day = Duration::from_secs(24 * 60 * 60);
+    let start = tangle.now();
+    let mut tags = Vec::new();
+    for i in 0..3 {
+        tags.push(
+            author
+                .write_signed(PayloadBuilder::new().public(&i).unwrap().build())
+                .unwrap(),
+        );
+        tangle.advance(day);
+    }
+    let from = start + day / 2;
+    let to = start + day + day / 2;
+
+    match subscribers[0].fetch_window(to, from, MissingTimestampPolicy::Exclude) {
+        Err(ChannelError::State(_)) => (),
+        other => panic!("Expected a state error, got {:?}", other),
+    }
+    let msgs = subscribers[0]
+        .fetch_window(from, to, MissingTimestampPolicy::Exclude)
+        .unwrap();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].tag, tags[1]);
+    assert_eq!(msgs[0].public.as_deref(), Some("1"));
+    // The walk moved past the window, the messages outside it were not delivered
+    assert!(subscribers[0].poll_new().unwrap().is_empty());
+    assert_eq!(
+        subscribers[0].read_latest().unwrap().map(|msg| msg.tag),
+        Some(tags[1].clone())
+    );
+
+    let msgs = subscribers[1]
+        .fetch_window(from, to, MissingTimestampPolicy::Include)
+        .unwrap();
+    assert!(msgs.iter().any(|msg| msg.tag == tags[1]));
+    assert!(msgs
+        .iter()
+        .all(|msg| msg.tag == tags[1] || msg.attached_at.is_none()));
+    assert!(!msgs
+        .iter()
+        .any(|msg| msg.tag == tags[0] || msg.tag == tags[2]));
+}
+
+#[test]
+fn processed_subscriptions_survive_a_restart() {
+    let tangle = TestTangle::new();
+
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+
+    let mut subscription_tags = Vec::new();
+    for _ in 0..2 {
+        let mut subscriber = tangle.subscriber(&channel_address, &announcement_tag);
+        let subscription_tag = subscriber.connect().unwrap();
+        author.add_subscriber(subscription_tag.clone()).unwrap();
+        subscription_tags.push(subscription_tag);
+    }
+    assert_eq!(author.processed_subscriptions(), subscription_tags);
+    let state = author.export_state();
+
+    let mut restarted = channel_author::Channel::new_with_transport(
+        Network::Devnet,
+        tangle.transport(),
+        Some(fixed_seed(0)),
+    )
+    .unwrap();
+    restarted.restore_state(&state).unwrap();
+    assert_eq!(restarted.processed_subscriptions(), subscription_tags);
+    assert_eq!(restarted.export_state(), state);
+
+    let mut other = tangle.author();
+    assert!(other.restore_state(&state).is_err());
+}
+
+#[test]
+fn author_and_subscriber_read_through_the_same_trait() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        channel_address,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+
+    let signed_tag = author
+        .write_signed(
+            PayloadBuilder::new()
+                .public(&SensorData { presure: 1.0 })
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+    author
+        .write_tagged(
+            PayloadBuilder::new()
+                .public(&SensorData { presure: 2.0 })
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+
+    let expected = vec![
+        serde_json::to_string(&SensorData { presure: 1.0 }).unwrap(),
+        serde_json::to_string(&SensorData { presure: 2.0 }).unwrap(),
+    ];
+    let readers: Vec<&mut dyn ChannelReader> = vec![&mut author, &mut subscriber];
+    for reader in readers {
+        assert_eq!(reader.channel_address(), channel_address);
+        assert_eq!(fetch_public(reader).unwrap(), expected);
+        assert!(fetch_public(reader).unwrap().is_empty());
+
+        let signed = reader.read(&signed_tag).unwrap();
+        assert_eq!(signed.tag, signed_tag);
+        assert_eq!(signed.public.as_ref(), Some(&expected[0]));
+    }
+}
+
+#[test]
+fn mirror_republishes_into_another_channel() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        author: mut source,
+        subscribers: mut relays,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut relay = relays.remove(0);
+    let TestChannel {
+        author: mut mirror,
+        subscribers: mut readers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut reader = readers.remove(0);
+
+    source
+        .write_signed(
+            PayloadBuilder::new()
+                .public(&SensorData { presure: 1.0 })
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+    source
+        .write_tagged(
+            PayloadBuilder::new()
+                .public(&SensorData { presure: 2.0 })
+                .unwrap()
+                .masked(&SensorData { presure: 3.0 })
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+
+    let tags = mirror.mirror_from(&mut relay).unwrap();
+    assert_eq!(tags.len(), 2);
+    assert!(mirror.mirror_from(&mut relay).unwrap().is_empty());
+
+    let msgs: Vec<_> = reader
+        .poll_new()
+        .unwrap()
+        .into_iter()
+        .filter(|msg| tags.contains(&msg.tag))
+        .collect();
+    assert_eq!(msgs.len(), 2);
+    let data = |json: &Option<String>| -> SensorData {
+        serde_json::from_str(json.as_ref().unwrap()).unwrap()
+    };
+    assert_eq!(msgs[0].tag, tags[0]);
+    assert_eq!(data(&msgs[0].public), SensorData { presure: 1.0 });
+    assert_eq!(msgs[1].tag, tags[1]);
+    assert_eq!(data(&msgs[1].public), SensorData { presure: 2.0 });
+    assert_eq!(data(&msgs[1].masked), SensorData { presure: 3.0 });
+}
+
+#[test]
+fn mirror_leaves_the_message_it_fails_to_write_to_the_source() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        author: mut source,
+        subscribers: mut relays,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut relay = relays.remove(0);
+    let TestChannel {
+        author: mut mirror,
+        subscribers: mut readers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut reader = readers.remove(0);
+    mirror.set_policy(ChannelPolicy {
+        max_messages_per_epoch: Some(1),
+        ..ChannelPolicy::default()
+    });
+
+    // Not JSON, skipped
+    source
+        .write_signed(
+            MultiPartPayloadBuilder::new()
+                .public("reading", b"1")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    for value in 1..3 {
+        source
+            .write_signed(PayloadBuilder::new().public(&value).unwrap().build())
+            .unwrap();
+    }
+
+    match mirror.mirror_from(&mut relay) {
+        Err(ChannelError::PolicyViolation { .. }) => (),
+        other => panic!("Expected a policy violation, got {:?}", other),
+    }
+    let mirrored: Vec<_> = reader
+        .poll_new()
+        .unwrap()
+        .into_iter()
+        .filter(|msg| msg.kind == MessageKind::SignedPacket)
+        .collect();
+    assert_eq!(mirrored.len(), 1);
+    assert_eq!(mirrored[0].public.as_deref(), Some("1"));
+
+    let left = relay.poll_new().unwrap();
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].public.as_deref(), Some("2"));
+}
+
+#[test]
+fn audit_report_is_verified_by_a_subscriber() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        keyload_tag,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    let keyload_tag = keyload_tag.unwrap();
+    let subscription_tag = author.processed_subscriptions().remove(0);
+
+    let signed_tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    author
+        .write_tagged(
+            PayloadBuilder::new()
+                .public(&2)
+                .unwrap()
+                .masked(&3)
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+
+    let mut exported = Vec::new();
+    author.export_audit_report(&mut exported).unwrap();
+    let mut again = Vec::new();
+    author.export_audit_report(&mut again).unwrap();
+    assert_eq!(exported, again);
+
+    let mut report = AuditReport::from_reader(exported.as_slice()).unwrap();
+    let kinds: Vec<MessageKind> = report.messages.iter().map(|m| m.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            MessageKind::Announce,
+            MessageKind::SignedPacket,
+            MessageKind::Keyload,
+            MessageKind::SignedPacket,
+            MessageKind::TaggedPacket
+        ]
+    );
+    assert_eq!(report.messages[3].parent.as_ref(), Some(&keyload_tag));
+    assert_eq!(report.keyloads.len(), 1);
+    assert_eq!(report.keyloads[0].subscriptions, vec![subscription_tag]);
+
+    let verification = subscriber.verify_audit_report(&report).unwrap();
+    assert!(verification.is_valid());
+    assert_eq!(verification.verified.len(), 5);
+
+    report.messages[3].digest = "00".repeat(32);
+    let verification = subscriber.verify_audit_report(&report).unwrap();
+    assert!(!verification.is_valid());
+    assert_eq!(verification.mismatched, vec![signed_tag]);
+}
+
+#[test]
+fn sequence_numbers_are_checked_by_the_guard() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        channel_address,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    author.set_sequence_numbers(true);
+    subscriber.set_sequence_guard(true);
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let seen = events.clone();
+    subscriber.on_sequence_event(move |event| seen.borrow_mut().push(event.clone()));
+
+    for value in 0..3 {
+        author
+            .write_signed(PayloadBuilder::new().public(&value).unwrap().build())
+            .unwrap();
+    }
+
+    let packets: Vec<_> = subscriber
+        .poll_new()
+        .unwrap()
+        .into_iter()
+        .filter(|msg| msg.seq.is_some())
+        .collect();
+    assert_eq!(packets.len(), 3);
+    for (n, msg) in packets.iter().enumerate() {
+        assert_eq!(
+            msg.seq,
+            Some(SequenceNumber {
+                sender: channel_address.clone(),
+                n: n as u64,
+                generation: 0,
+            })
+        );
+        assert_eq!(msg.public, Some(n.to_string()));
+    }
+    assert!(subscriber.sequence_events().is_empty());
+    assert!(events.borrow().is_empty());
+
+    let cursor = Cursor::decode(&subscriber.cursor()).unwrap();
+    assert_eq!(
+        cursor.sequence_guard.unwrap().high_water(&channel_address),
+        Some(2)
+    );
+}
+
+#[test]
+fn sequence_numbers_are_tracked_under_the_signer() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        channel_address,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    author.set_sequence_numbers(true);
+    subscriber.set_sequence_guard(true);
+
+    author
+        .write_signed(PayloadBuilder::new().public(&0).unwrap().build())
+        .unwrap();
+    // A packet claiming another sender is still tracked under its signer, the author
+    author.set_sequence_numbers(false);
+    let spoofed = serde_json::json!({
+        "channels_lite_envelope": "sequenced",
+        "seq": { "sender": "SPOOFED", "n": 0 },
+        "data": 1,
+    });
+    let spoofed_tag = author
+        .write_signed(PayloadBuilder::new().public(&spoofed).unwrap().build())
+        .unwrap();
+    subscriber.poll_new().unwrap();
+
+    let guard = subscriber.sequence_guard().unwrap();
+    assert_eq!(guard.high_water("SPOOFED"), None);
+    assert_eq!(guard.high_water(&channel_address), Some(0));
+    assert_eq!(
+        subscriber.sequence_events(),
+        &[SequenceEvent::Duplicate {
+            sender: channel_address,
+            seq: 0,
+            tag: spoofed_tag,
+        }]
+    );
+}
+
+#[test]
+fn sequence_guard_reports_duplicates_and_gaps() {
+    let seq = |n| SequenceNumber {
+        sender: "SENDER".to_string(),
+        n: n,
+        generation: 0,
+    };
+    let mut guard = SequenceGuard::new();
+
+    assert_eq!(guard.check(&seq(4), "A"), None);
+    assert_eq!(guard.check(&seq(5), "B"), None);
+    assert_eq!(
+        guard.check(&seq(5), "C"),
+        Some(SequenceEvent::Duplicate {
+            sender: "SENDER".to_string(),
+            seq: 5,
+            tag: "C".to_string(),
+        })
+    );
+    assert_eq!(
+        guard.check(&seq(8), "D"),
+        Some(SequenceEvent::Gap {
+            sender: "SENDER".to_string(),
+            expected: 6,
+            got: 8,
+            tag: "D".to_string(),
+        })
+    );
+    assert_eq!(guard.high_water("SENDER"), Some(8));
+    assert_eq!(guard.check(&seq(9), "E"), None);
+}
+
+#[test]
+fn fixtures_are_populated_and_reproducible() {
+    let mut fixture = fixture::populated_channel().unwrap();
+    assert_eq!(fixture.messages.len(), fixture::DEFAULT_FIXTURE_MESSAGES);
+
+    let msgs = fixture.channel.subscribers[0].poll_new().unwrap();
+    for expected in fixture.messages.iter() {
+        let msg = msgs.iter().find(|msg| msg.tag == expected.tag).unwrap();
+        assert_eq!(msg.kind, expected.kind);
+        assert_eq!(msg.public.as_deref(), Some(expected.public.as_str()));
+        assert_eq!(msg.masked.as_deref(), Some(expected.masked.as_str()));
+    }
+
+    let again = fixture::populated_channel_with(2, 2).unwrap();
+    assert_eq!(again.channel.subscribers.len(), 2);
+    assert_eq!(again.messages[..], fixture.messages[..2]);
+}
+
+#[test]
+fn fixed_seeds_give_the_same_channel() {
+    let first = TestTangle::new().channel(1).unwrap();
+    let second = TestTangle::new().channel(1).unwrap();
+    assert_eq!(first.channel_address, second.channel_address);
+    assert_eq!(first.announcement_tag, second.announcement_tag);
+    assert_eq!(first.keyload_tag, second.keyload_tag);
+    assert_ne!(fixed_seed(0), fixed_seed(1));
+}
+
+#[test]
+fn wait_for_attachment_polls_until_the_node_serves_the_message() {
+    let tangle = TestTangle::new();
+    let TestChannel { mut author, .. } = tangle.channel(1).unwrap();
+    tangle.set_propagation_delay(Duration::from_secs(10));
+
+    let tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    let start = tangle.now();
+    match author.wait_for_attachment(&tag, Duration::from_secs(5)) {
+        Err(ChannelError::Timeout { after, .. }) => assert_eq!(after, Duration::from_secs(5)),
+        other => panic!("Expected a timeout, got {:?}", other),
+    }
+
+    author
+        .wait_for_attachment(&tag, Duration::from_secs(60))
+        .unwrap();
+    assert!(author.is_attached(&tag).unwrap());
+    // Returned on the first poll after the propagation, not at the timeout
+    let waited = tangle.now().duration_since(start).unwrap();
+    assert!(waited >= Duration::from_secs(10) && waited < Duration::from_secs(12));
+}
+
+#[test]
+fn messages_become_readable_once_propagated() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    tangle.set_propagation_delay(Duration::from_secs(10));
+
+    let signed_tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    assert_eq!(tangle.in_flight(), 1);
+    assert_not_readable(&mut subscribers[0], &signed_tag);
+
+    tangle.advance(Duration::from_secs(9));
+    assert_not_readable(&mut subscribers[0], &signed_tag);
+
+    tangle.advance(Duration::from_secs(1));
+    let signed = assert_readable(&mut subscribers[0], &signed_tag);
+    assert_eq!(signed.public.as_deref(), Some("1"));
+    assert_eq!(tangle.in_flight(), 0);
+
+    let tagged_tag = author
+        .write_tagged(PayloadBuilder::new().public(&2).unwrap().build())
+        .unwrap();
+    tangle.propagate_all();
+    assert_readable(&mut subscribers[0], &tagged_tag);
+}
+
+#[test]
+fn read_latest_returns_the_newest_packet() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let subscriber = &mut subscribers[0];
+    assert_eq!(subscriber.read_latest().unwrap(), None);
+
+    for value in 0..3 {
+        author
+            .write_signed(PayloadBuilder::new().public(&value).unwrap().build())
+            .unwrap();
+    }
+    let tagged_tag = author
+        .write_tagged(PayloadBuilder::new().public(&3).unwrap().build())
+        .unwrap();
+
+    let latest = subscriber.read_latest().unwrap().unwrap();
+    assert_eq!(latest.tag, tagged_tag);
+    assert_eq!(latest.kind, MessageKind::TaggedPacket);
+    assert_eq!(latest.public.as_deref(), Some("3"));
+    assert!(subscriber.poll_new().unwrap().is_empty());
+
+    // Nothing new and the end of channel marker is not a value
+    author.close().unwrap();
+    let latest = subscriber.read_latest().unwrap().unwrap();
+    assert_eq!(latest.tag, tagged_tag);
+    assert!(subscriber.is_closed());
+}
+
+#[test]
+fn only_the_marker_signed_by_the_author_closes_the_channel() {
+    let marker = serde_json::to_string(&EndOfChannel::new()).unwrap();
+    assert!(EndOfChannel::matches(&marker, "aa", "aa"));
+    assert!(!EndOfChannel::matches(&marker, "bb", "aa"));
+    assert!(!EndOfChannel::matches(
+        r#"{"end_of_channel":false}"#,
+        "aa",
+        "aa"
+    ));
+
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    let close_tag = author.close().unwrap();
+
+    let msgs = subscriber.poll_new().unwrap();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].tag, close_tag);
+    assert!(subscriber.is_closed());
+    let author_key = msgs[0].signer.clone();
+    assert!(author_key.is_some());
+    assert!(msgs[0].is_end_of_channel(author_key.as_deref()));
+    assert!(!msgs[0].is_end_of_channel(Some("a subscriber key")));
+    assert!(!msgs[0].is_end_of_channel(None));
+}
+
+/// Mock tangle counting the messages going through it
+#[derive(Clone)]
+struct RecordingTransport {
+    tangle: MockTransport,
+    sent: Rc<Cell<usize>>,
+    fetched: Rc<Cell<usize>>,
+}
+
+impl Transport<Address, Message> for RecordingTransport {
+    type SendOptions = ();
+
+    fn send_message_with_options(&mut self, msg: &Message, opt: ()) -> anyhow::Result<()> {
+        self.sent.set(self.sent.get() + 1);
+        self.tangle.send_message_with_options(msg, opt)
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options(
+        &mut self,
+        link: &Address,
+        opt: (),
+    ) -> anyhow::Result<Vec<Message>> {
+        self.fetched.set(self.fetched.get() + 1);
+        self.tangle.recv_messages_with_options(link, opt)
+    }
+}
+
+impl RecordingTransport {
+    fn new(tangle: &TestTangle) -> Self {
+        RecordingTransport {
+            tangle: tangle.mock().clone(),
+            sent: Rc::new(Cell::new(0)),
+            fetched: Rc::new(Cell::new(0)),
+        }
+    }
+}
+
+///
+/// Accepts the sends but drops the first ones, as a node failing to attach them
+///
+#[derive(Clone)]
+struct DroppingTransport {
+    tangle: MockTransport,
+    drops: Rc<Cell<usize>>,
+}
+
+impl Transport<Address, Message> for DroppingTransport {
+    type SendOptions = ();
+
+    fn send_message_with_options(&mut self, msg: &Message, opt: ()) -> anyhow::Result<()> {
+        if self.drops.get() > 0 {
+            self.drops.set(self.drops.get() - 1);
+            return Ok(());
+        }
+        self.tangle.send_message_with_options(msg, opt)
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options(
+        &mut self,
+        link: &Address,
+        opt: (),
+    ) -> anyhow::Result<Vec<Message>> {
+        self.tangle.recv_messages_with_options(link, opt)
+    }
+}
+
+#[test]
+fn connect_sends_the_subscribe_again_until_it_lands() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+    let subscriber = |seed: u8, drops: usize| {
+        channel_subscriber::ChannelBuilder::new(
+            Network::Devnet,
+            channel_address.clone(),
+            announcement_tag.clone(),
+        )
+        .seed(fixed_seed(seed))
+        .clock(Rc::new(tangle.clock().clone()))
+        .retry(RetryConfig {
+            max_send_attempts: 3,
+            jitter: Duration::from_secs(0),
+            ..RetryConfig::default()
+        })
+        .transport(DroppingTransport {
+            tangle: tangle.mock().clone(),
+            drops: Rc::new(Cell::new(drops)),
+        })
+        .build()
+        .unwrap()
+    };
+
+    let mut subscriber_one = subscriber(1, 2);
+    let subscription_tag = subscriber_one.connect().unwrap();
+    let keyload_tag = author.add_subscriber(subscription_tag).unwrap();
+    subscriber_one.update_keyload(keyload_tag).unwrap();
+
+    // Every attempt dropped
+    let mut subscriber_two = subscriber(2, 3);
+    match subscriber_two.connect() {
+        Err(ChannelError::MessageNotFound(_)) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+    match subscriber_two.poll_new() {
+        Err(ChannelError::NotConnected) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn builders_accept_a_custom_transport() {
+    let tangle = TestTangle::new();
+    let recorder = RecordingTransport::new(&tangle);
+
+    let mut author = channel_author::ChannelBuilder::new(Network::Devnet)
+        .seed(fixed_seed(0))
+        .transport(recorder.clone())
+        .build()
+        .unwrap();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+    // The announcement and the protocol marker
+    assert_eq!(recorder.sent.get(), 2);
+
+    // The subscriber reads from the same tangle without going through the recorder
+    let mut subscriber =
+        channel_subscriber::ChannelBuilder::new(Network::Devnet, channel_address, announcement_tag)
+            .seed(fixed_seed(1))
+            .transport(tangle.mock().clone())
+            .build()
+            .unwrap();
+    let subscription_tag = subscriber.connect().unwrap();
+    let keyload_tag = author.add_subscriber(subscription_tag).unwrap();
+    subscriber.update_keyload(keyload_tag).unwrap();
+    assert_eq!(recorder.sent.get(), 3);
+
+    let tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    assert_eq!(recorder.sent.get(), 4);
+    let signed = subscriber.read_signed(tag).unwrap();
+    assert_eq!(signed[0].0.as_deref(), Some("1"));
+}
+
+#[test]
+fn encoded_size_includes_the_sequence_number() {
+    let tangle = TestTangle::new();
+    let TestChannel { mut author, .. } = tangle.channel(1).unwrap();
+    let payload = PayloadBuilder::new()
+        .public(&SensorData { presure: 1.0 })
+        .unwrap()
+        .masked(&SensorData { presure: 2.0 })
+        .unwrap()
+        .build();
+    let plain = payload.public_data().0.len() + payload.masked_data().0.len();
+    assert_eq!(author.encoded_size(&payload).unwrap(), plain);
+
+    author.set_sequence_numbers(true);
+    let numbered = author.encoded_size(&payload).unwrap();
+    assert!(numbered > plain);
+    // Nothing was sent, the size doesn't move the sequence
+    assert_eq!(author.encoded_size(&payload).unwrap(), numbered);
+}
+
+#[test]
+fn messages_fetched_again_are_not_handed_out_twice() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let subscriber = &mut subscribers[0];
+    for value in 0..2 {
+        author
+            .write_signed(PayloadBuilder::new().public(&value).unwrap().build())
+            .unwrap();
+    }
+    let first: Vec<Option<String>> = subscriber.get_next_message();
+    assert!(first.len() >= 2);
+
+    // The end of the channel is not a gap, the walk is not re-anchored on the keyload
+    subscriber.set_gap_policy(GapPolicy {
+        skip_to_keyload: true,
+        ..GapPolicy::default()
+    });
+    let dropped = subscriber.duplicates_dropped();
+    let again = subscriber.get_next_message();
+    assert!(again.is_empty());
+    assert_eq!(subscriber.duplicates_dropped(), dropped);
+    assert_eq!(subscriber.gaps().len(), 0);
+}
+
+#[test]
+fn walk_resumes_after_a_missing_message() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let subscriber = &mut subscribers[0];
+    subscriber.set_gap_policy(GapPolicy {
+        skip_to_keyload: true,
+        ..GapPolicy::default()
+    });
+    let write = |author: &mut TestAuthor, value: i32| {
+        author
+            .write_signed(PayloadBuilder::new().public(&value).unwrap().build())
+            .unwrap()
+    };
+    let before = write(&mut author, 1);
+    // Never reaches the node
+    tangle.set_propagation_delay(Duration::from_secs(24 * 60 * 60));
+    let missing = write(&mut author, 2);
+    tangle.set_propagation_delay(Duration::from_secs(0));
+    let keyload_tag = author.add_psk(new_psk(b"rotation")).unwrap();
+    let after = write(&mut author, 3);
+
+    let walked = subscriber.get_next_message();
+    assert!(walked.contains(&Some(before.clone())));
+    assert!(!walked.contains(&Some(after.clone())));
+    // Nothing is known to be missing yet, the walk stops at the gap
+    assert!(subscriber.get_next_message().is_empty());
+    assert_eq!(subscriber.gaps().len(), 0);
+
+    subscriber.update_keyload(keyload_tag).unwrap();
+    let resumed = subscriber.get_next_message();
+    assert!(resumed.contains(&Some(after)));
+    assert!(!resumed.contains(&Some(before)));
+    assert!(!resumed.contains(&Some(missing)));
+}
+
+#[test]
+fn deduplicator_forgets_the_oldest_ids() {
+    let mut dedup = Deduplicator::with_capacity(2);
+    assert!(dedup.first_seen("a"));
+    assert!(dedup.first_seen("b"));
+    assert!(!dedup.first_seen("a"));
+    assert!(dedup.first_seen("c"));
+    assert_eq!(dedup.len(), 2);
+    // "a" was evicted by "c"
+    assert!(dedup.first_seen("a"));
+    assert!(!dedup.first_seen("c"));
+    assert_eq!(dedup.dropped(), 2);
+}
+
+#[test]
+fn subscribers_read_the_protocol_version_on_connect() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    assert_eq!(subscriber.protocol_version(), Some(PROTOCOL_VERSION));
+
+    // The marker isn't handed out as a message
+    let tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    let msgs = subscriber.poll_new().unwrap();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].tag, tag);
+
+    let mut old_author = channel_author::ChannelBuilder::new(Network::Devnet)
+        .seed(fixed_seed(2))
+        .transport(tangle.mock().clone())
+        .protocol_marker(false)
+        .build()
+        .unwrap();
+    let (channel_address, announcement_tag) = old_author.open().unwrap();
+    let mut old_subscriber =
+        channel_subscriber::ChannelBuilder::new(Network::Devnet, channel_address, announcement_tag)
+            .seed(fixed_seed(3))
+            .transport(tangle.mock().clone())
+            .strict_protocol(true)
+            .build()
+            .unwrap();
+    old_subscriber.connect().unwrap();
+    assert_eq!(old_subscriber.protocol_version(), None);
+}
+
+#[test]
+fn packets_shaped_like_the_protocol_marker_are_delivered() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+
+    // Only the packet following the announcement is the marker
+    let public = serde_json::json!({ "channels_lite_protocol": 7 });
+    let tag = author
+        .write_signed(PayloadBuilder::new().public(&public).unwrap().build())
+        .unwrap();
+    let msgs = subscriber.poll_new().unwrap();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].tag, tag);
+    assert_eq!(subscriber.protocol_version(), Some(PROTOCOL_VERSION));
+
+    let msgs = ChannelReader::fetch_all(&mut author).unwrap();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].tag, tag);
+}
+
+#[test]
+fn rebroadcast_announcement_keeps_the_tag() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+
+    assert_eq!(author.rebroadcast_announcement().unwrap(), announcement_tag);
+    let link = Address::from_str(&channel_address, &announcement_tag).unwrap();
+    assert_eq!(tangle.mock().clone().recv_messages(&link).unwrap().len(), 2);
+
+    let mut subscriber = tangle.subscriber(&channel_address, &announcement_tag);
+    subscriber.connect().unwrap();
+}
+
+#[test]
+fn summaries_describe_the_channel_without_secrets() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        channel_address,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    let tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    subscriber.poll_new().unwrap();
+
+    let summary = author.summary();
+    assert_eq!(summary.role, ChannelRole::Author);
+    assert_eq!(summary.channel_address, channel_address);
+    // Announcement, protocol marker, keyload and packet
+    assert_eq!(summary.msgs_sent, 4);
+    assert_eq!(summary.head.as_ref(), Some(&tag));
+    assert_eq!(author.to_string(), summary.to_string());
+    assert!(author.to_string().starts_with("role=author addr="));
+
+    let summary = subscriber.summary();
+    assert_eq!(summary.role, ChannelRole::Subscriber);
+    assert_eq!(summary.msgs_sent, 1);
+    assert_eq!(summary.head, Some(tag));
+
+    let author_dump = format!("{:?}", author);
+    let subscriber_dump = format!("{:?}", subscriber);
+    assert!(!author_dump.contains(&fixed_seed(0)));
+    assert!(!subscriber_dump.contains(&fixed_seed(1)));
+    assert!(author_dump.contains("transport_stats"));
+}
+
+struct RawPayload {
+    public: Bytes,
+    masked: Bytes,
+}
+
+impl PacketPayload for RawPayload {
+    fn public_data(&self) -> &Bytes {
+        &self.public
+    }
+
+    fn masked_data(&self) -> &Bytes {
+        &self.masked
+    }
+}
+
+#[test]
+fn tagged_halves_are_read_independently() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+
+    let json = PayloadBuilder::new().public(&1).unwrap().build();
+    let tag = author
+        .write_tagged(RawPayload {
+            public: json.public_data().clone(),
+            masked: Bytes(vec![0xff, 0xfe]),
+        })
+        .unwrap();
+
+    assert_eq!(
+        subscriber
+            .read_tagged_public(tag.clone())
+            .unwrap()
+            .as_deref(),
+        Some("1")
+    );
+    match subscriber.read_tagged_masked(tag.clone()) {
+        Err(ChannelError::PayloadEncoding(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(subscriber.read_tagged(tag).is_err());
+}
+
+#[test]
+fn both_decode_errors_are_reported() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+
+    author
+        .write_tagged(RawPayload {
+            public: Bytes(vec![0xff, 0xfe]),
+            masked: Bytes(vec![0xff, 0xfe]),
+        })
+        .unwrap();
+
+    let msgs = subscriber.poll_new().unwrap();
+    assert_eq!(msgs.len(), 1);
+    let error = msgs[0].decode_error.as_deref().unwrap();
+    assert!(error.contains("Public data"), "{}", error);
+    assert!(error.contains("Masked data"), "{}", error);
+}
+
+#[test]
+fn idempotent_writes_are_sent_once_per_key() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    author.set_idempotency_window(Duration::from_secs(60));
+    let payload = || PayloadBuilder::new().public(&1).unwrap().build();
+
+    let tag = author
+        .write_signed_idempotent(payload(), "publish-1")
+        .unwrap();
+    assert_eq!(
+        author
+            .write_signed_idempotent(payload(), "publish-1")
+            .unwrap(),
+        tag
+    );
+    assert!(author
+        .write_signed_idempotent(
+            PayloadBuilder::new().public(&2).unwrap().build(),
+            "publish-1"
+        )
+        .is_err());
+    assert_eq!(subscriber.poll_new().unwrap().len(), 1);
+
+    // The keys survive a restart
+    let state = author.export_state();
+    let mut restarted = channel_author::Channel::new_with_transport(
+        Network::Devnet,
+        tangle.transport(),
+        Some(fixed_seed(0)),
+    )
+    .unwrap();
+    restarted.set_clock(Rc::new(tangle.clock().clone()));
+    restarted.restore_state(&state).unwrap();
+    assert_eq!(
+        restarted
+            .write_signed_idempotent(payload(), "publish-1")
+            .unwrap(),
+        tag
+    );
+
+    // Once the window elapsed the same key sends again
+    tangle.advance(Duration::from_secs(61));
+    let again = author
+        .write_signed_idempotent(payload(), "publish-1")
+        .unwrap();
+    assert_ne!(again, tag);
+    assert_eq!(subscriber.poll_new().unwrap().len(), 1);
+}
+
+#[test]
+fn messages_over_the_fragment_limit_are_refused() {
+    let tangle = TestTangle::new();
+    let mut author = channel_author::ChannelBuilder::new(Network::Devnet)
+        .seed(fixed_seed(0))
+        .transport(tangle.mock().clone())
+        .auto_keyload(true)
+        .max_fragments(1)
+        .build()
+        .unwrap();
+    author.open().unwrap();
+
+    author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    let large = "x".repeat(4 * PAYLOAD_BYTES);
+    match author.write_signed(PayloadBuilder::new().public(&large).unwrap().build()) {
+        Err(ChannelError::TooManyFragments { fragments, max, .. }) => {
+            assert!(fragments > 4);
+            assert_eq!(max, 1);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert_eq!(fragments(PAYLOAD_BYTES), 1);
+    assert_eq!(fragments(PAYLOAD_BYTES + 1), 2);
+}
+
+#[test]
+fn the_channel_stays_readable_after_a_refused_write() {
+    let tangle = TestTangle::new();
+    let mut author = channel_author::ChannelBuilder::new(Network::Devnet)
+        .seed(fixed_seed(100))
+        .transport(tangle.mock().clone())
+        .max_fragments(1)
+        .build()
+        .unwrap();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+    let mut subscriber = tangle.subscriber(&channel_address, &announcement_tag);
+    let subscription_tag = subscriber.connect().unwrap();
+    let keyload_tag = author.add_subscriber(subscription_tag).unwrap();
+    subscriber.update_keyload(keyload_tag).unwrap();
+
+    let first = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    let large = "x".repeat(4 * PAYLOAD_BYTES);
+    assert!(author
+        .write_signed(PayloadBuilder::new().public(&large).unwrap().build())
+        .is_err());
+    let second = author
+        .write_signed(PayloadBuilder::new().public(&2).unwrap().build())
+        .unwrap();
+
+    let tags: Vec<String> = subscriber
+        .poll_new()
+        .unwrap()
+        .into_iter()
+        .map(|msg| msg.tag)
+        .collect();
+    assert_eq!(tags, vec![first, second]);
+}
+
+#[test]
+fn acknowledgements_are_matched_to_their_requests() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    author.set_ack_timeout(Duration::from_secs(60));
+
+    let pending = author
+        .write_signed_with_ack(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    let msgs = subscriber.poll_new().unwrap();
+    assert_eq!(msgs[0].public.as_deref(), Some("1"));
+    assert_eq!(msgs[0].ack_request.as_ref(), Some(&pending.request_id));
+    let ack_tag = subscriber.acknowledge(pending.tag.clone()).unwrap();
+
+    let received = author.poll_acks().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].request_id, pending.request_id);
+    assert_eq!(received[0].tag, pending.tag);
+    assert_eq!(received[0].ack_tag.as_ref(), Some(&ack_tag));
+    assert_eq!(received[0].outcome, AckOutcome::Acknowledged);
+    assert!(author.pending_acks().is_empty());
+
+    // Not acknowledged in time, the timeout is reported once then the late ack
+    let late = author
+        .write_signed_with_ack(PayloadBuilder::new().public(&2).unwrap().build())
+        .unwrap();
+    tangle.advance(Duration::from_secs(61));
+    let received = author.poll_acks().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].outcome, AckOutcome::TimedOut);
+    assert!(author.poll_acks().unwrap().is_empty());
+    assert_eq!(author.pending_acks(), vec![late.clone()]);
+
+    subscriber.acknowledge(late.tag.clone()).unwrap();
+    let received = author.poll_acks().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].request_id, late.request_id);
+    assert_eq!(received[0].outcome, AckOutcome::Late);
+}
+
+#[test]
+fn acknowledgement_requests_are_forgotten_after_the_retention() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    author.set_ack_timeout(Duration::from_secs(60));
+
+    let pending = author
+        .write_signed_with_ack(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    subscriber.poll_new().unwrap();
+    tangle.advance(Duration::from_secs(61) + ACK_RETENTION);
+
+    // The timeout is still reported once before the request is forgotten
+    let received = author.poll_acks().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].outcome, AckOutcome::TimedOut);
+    assert!(author.pending_acks().is_empty());
+
+    subscriber.acknowledge(pending.tag.clone()).unwrap();
+    let received = author.poll_acks().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].request_id, pending.request_id);
+    assert_eq!(received[0].outcome, AckOutcome::UnknownRequest);
+}
+
+#[test]
+fn own_messages_are_skipped() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(2).unwrap();
+    let mut other = subscribers.pop().unwrap();
+    let mut subscriber = subscribers.pop().unwrap();
+    subscriber.set_skip_own(true);
+
+    let tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    assert_eq!(subscriber.poll_new().unwrap().len(), 1);
+    let ack_tag = subscriber.acknowledge(tag).unwrap();
+    let next = author
+        .write_signed(PayloadBuilder::new().public(&2).unwrap().build())
+        .unwrap();
+
+    let tags: Vec<String> = subscriber
+        .poll_new()
+        .unwrap()
+        .into_iter()
+        .map(|msg| msg.tag)
+        .collect();
+    assert_eq!(tags, vec![next.clone()]);
+
+    // The other subscribers read the acknowledgement
+    let tags: Vec<String> = other
+        .poll_new()
+        .unwrap()
+        .into_iter()
+        .map(|msg| msg.tag)
+        .collect();
+    assert!(tags.contains(&ack_tag));
+    assert!(tags.contains(&next));
+}
+
+#[test]
+fn public_data_is_read_without_keyload() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+    let mut subscriber = tangle.subscriber(&channel_address, &announcement_tag);
+    let subscription_tag = subscriber.connect().unwrap();
+    author.add_subscriber(subscription_tag).unwrap();
+    let mut outsider = tangle.subscriber(&channel_address, &announcement_tag);
+    outsider.connect().unwrap();
+
+    let tag = author
+        .write_signed(
+            PayloadBuilder::new()
+                .public(&"public")
+                .unwrap()
+                .masked(&"masked")
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+    assert_eq!(
+        subscriber.read_signed(tag.clone()).unwrap(),
+        vec![(
+            Some("\"public\"".to_string()),
+            Some("\"masked\"".to_string())
+        )]
+    );
+    assert_eq!(
+        outsider.read_signed(tag.clone()).unwrap(),
+        vec![(Some("\"public\"".to_string()), None)]
+    );
+
+    let msg = outsider.read(&tag).unwrap();
+    assert_eq!(msg.kind, MessageKind::SignedPacket);
+    assert_eq!(msg.public.as_deref(), Some("\"public\""));
+    assert_eq!(msg.masked, None);
+    assert_eq!(msg.access, AccessLevel::PublicOnly);
+    assert_eq!(subscriber.read(&tag).unwrap().access, AccessLevel::Full);
+
+    outsider.set_strict_access(true);
+    match outsider.read_signed(tag.clone()) {
+        Err(ChannelError::AccessDenied(denied)) => assert_eq!(denied, tag),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn provenance_is_exported_per_message() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(2).unwrap();
+    tangle.stamp_attachments();
+
+    let attached_at = tangle.now();
+    let first = author
+        .write_signed_with_digest(
+            PayloadBuilder::new()
+                .public(&1)
+                .unwrap()
+                .masked(&"secret")
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+    let ack_tag = subscribers[1].acknowledge(first.tag.clone()).unwrap();
+    tangle.advance(Duration::from_secs(30));
+    let second = author
+        .write_signed(PayloadBuilder::new().public(&2).unwrap().build())
+        .unwrap();
+
+    let records = subscribers[0].export_provenance().unwrap();
+    let record = |tag: &str| records.iter().find(|record| record.tag == tag).unwrap();
+    let first_record = record(&first.tag);
+    assert_eq!(first_record.kind, MessageKind::SignedPacket);
+    assert_eq!(first_record.attached_at, Some(attached_at));
+    assert_eq!(first_record.confirmed, None);
+    assert_eq!(first_record.public.as_deref(), Some("1"));
+    assert_eq!(first_record.masked.as_deref(), Some("\"secret\""));
+    assert_eq!(first_record.content_digest.as_ref(), Some(&first.digest));
+    assert_eq!(first_record.access, AccessLevel::Full);
+    assert_eq!(first_record.exported_at, tangle.now());
+
+    let author_key = first_record.signer.clone().unwrap();
+    assert_eq!(author_key.len(), 64);
+    assert_eq!(record(&second).signer.as_ref(), Some(&author_key));
+    let subscriber_key = record(&ack_tag).signer.clone().unwrap();
+    assert_ne!(subscriber_key, author_key);
+
+    // Exported messages are not returned again
+    assert!(subscribers[0].export_provenance().unwrap().is_empty());
+}
+
+#[test]
+fn branching_mode_is_read_from_the_announcement() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+
+    let mut subscriber = tangle.subscriber(&channel_address, &announcement_tag);
+    assert_eq!(subscriber.is_multi_branching(), None);
+    subscriber.connect().unwrap();
+    assert_eq!(subscriber.is_multi_branching(), Some(false));
+}
+
+#[test]
+fn messages_are_decoded_by_content_type() {
+    #[derive(Debug, PartialEq)]
+    enum Feed {
+        Reading(u32),
+        Label(String),
+    }
+
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+
+    let reading = author
+        .write_signed_typed(
+            "reading/v1",
+            PayloadBuilder::new().public(&21).unwrap().build(),
+        )
+        .unwrap();
+    let label = author
+        .write_tagged_typed(
+            "label/v1",
+            PayloadBuilder::new().public(&"kitchen").unwrap().build(),
+        )
+        .unwrap();
+    let broken = author
+        .write_signed_typed(
+            "reading/v1",
+            PayloadBuilder::new()
+                .public(&"not a number")
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+    let untyped = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+
+    let mut decoders = Decoders::new();
+    decoders
+        .register("reading/v1", |data| {
+            Ok(Feed::Reading(serde_json::from_str(data)?))
+        })
+        .unwrap()
+        .register("label/v1", |data| {
+            Ok(Feed::Label(serde_json::from_str(data)?))
+        })
+        .unwrap();
+    match decoders.register("label/v1", |_| Ok(Feed::Reading(0))) {
+        Err(ChannelError::DecoderConflict { content_type }) => {
+            assert_eq!(content_type, "label/v1")
+        }
+        other => panic!("Expected a decoder conflict, got {:?}", other.map(|_| ())),
+    }
+
+    let decoded = subscriber.fetch_all_decoded(&decoders).unwrap();
+    assert_eq!(decoded.len(), 4);
+    match &decoded[0] {
+        Decoded::Known {
+            tag,
+            content_type,
+            value,
+        } => {
+            assert_eq!(tag, &reading);
+            assert_eq!(content_type, "reading/v1");
+            assert_eq!(value, &Feed::Reading(21));
+        }
+        other => panic!("Expected a known message, got {:?}", other),
+    }
+    match &decoded[1] {
+        Decoded::Known { tag, value, .. } => {
+            assert_eq!(tag, &label);
+            assert_eq!(value, &Feed::Label("kitchen".to_string()));
+        }
+        other => panic!("Expected a known message, got {:?}", other),
+    }
+    match &decoded[2] {
+        Decoded::Failed { tag, error, .. } => {
+            assert_eq!(tag, &broken);
+            assert_eq!(error.code(), ErrorCode::PayloadEncoding);
+            assert!(error.to_string().contains("reading/v1"));
+        }
+        other => panic!("Expected a failed message, got {:?}", other),
+    }
+    match &decoded[3] {
+        Decoded::Unknown {
+            tag,
+            content_type,
+            raw,
+        } => {
+            assert_eq!(tag, &untyped);
+            assert_eq!(content_type, &None);
+            assert_eq!(raw.as_deref(), Some("1"));
+        }
+        other => panic!("Expected an unknown message, got {:?}", other),
+    }
+}
+
+#[test]
+fn reads_and_writes_go_through_their_own_nodes() {
+    let tangle = TestTangle::new();
+    let mut author = tangle.author();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+
+    let writer = RecordingTransport::new(&tangle);
+    let reader = RecordingTransport::new(&tangle);
+    let mut subscriber =
+        channel_subscriber::ChannelBuilder::new(Network::Devnet, channel_address, announcement_tag)
+            .seed(fixed_seed(1))
+            .transport(writer.clone())
+            .read_transport(reader.clone())
+            .build()
+            .unwrap();
+    let subscription_tag = subscriber.connect().unwrap();
+    let keyload_tag = author.add_subscriber(subscription_tag).unwrap();
+    subscriber.update_keyload(keyload_tag).unwrap();
+    let tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    let signed = subscriber.read_signed(tag).unwrap();
+    assert_eq!(signed[0].0.as_deref(), Some("1"));
+
+    // The subscription is sent through the write node, everything is read from the other
+    assert_eq!(writer.sent.get(), 1);
+    assert_eq!(writer.fetched.get(), 0);
+    assert_eq!(reader.sent.get(), 0);
+    assert!(reader.fetched.get() > 0);
+    assert_eq!(subscriber.read_node(), None);
+
+    // Back to a single node
+    subscriber.set_read_transport(None);
+    let tag = author
+        .write_signed(PayloadBuilder::new().public(&2).unwrap().build())
+        .unwrap();
+    subscriber.read_signed(tag).unwrap();
+    assert!(writer.fetched.get() > 0);
+}
+
+#[test]
+fn digests_match_between_the_author_and_the_subscriber() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+
+    let large = "x".repeat(3 * PAYLOAD_BYTES);
+    let sent = vec![
+        author
+            .write_signed_with_digest(PayloadBuilder::new().public(&1).unwrap().build())
+            .unwrap(),
+        author
+            .write_signed_with_digest(PayloadBuilder::new().masked(&"secret").unwrap().build())
+            .unwrap(),
+        author
+            .write_tagged_with_digest(
+                PayloadBuilder::new()
+                    .public(&2)
+                    .unwrap()
+                    .masked(&3)
+                    .unwrap()
+                    .build(),
+            )
+            .unwrap(),
+        author
+            .write_signed_with_digest(
+                MultiPartPayloadBuilder::new()
+                    .public("reading", b"{\"presure\":1.0}")
+                    .masked("metadata", b"calibrated")
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap(),
+        // Spans several transactions
+        author
+            .write_tagged_with_digest(PayloadBuilder::new().masked(&large).unwrap().build())
+            .unwrap(),
+    ];
+    let mut digests: Vec<&String> = sent.iter().map(|sent| &sent.digest).collect();
+    digests.sort();
+    digests.dedup();
+    assert_eq!(digests.len(), sent.len());
+
+    let msgs = subscriber.poll_new().unwrap();
+    for sent in sent.iter() {
+        let msg = msgs.iter().find(|msg| msg.tag == sent.tag).unwrap();
+        assert_eq!(msg.content_digest.as_ref(), Some(&sent.digest));
+        msg.verify_digest(&sent.digest).unwrap();
+    }
+
+    let msg = msgs.iter().find(|msg| msg.tag == sent[0].tag).unwrap();
+    match msg.verify_digest(&sent[1].digest) {
+        Err(ChannelError::DigestMismatch {
+            tag,
+            expected,
+            actual,
+        }) => {
+            assert_eq!(tag, sent[0].tag);
+            assert_eq!(expected, sent[1].digest);
+            assert_eq!(actual, Some(sent[0].digest.clone()));
+        }
+        other => panic!("Expected a digest mismatch, got {:?}", other),
+    }
+}
+
+#[derive(Serialize)]
+struct Alarm {
+    zone: String,
+    raised: bool,
+}
+
+#[test]
+fn digests_cover_the_canonical_application_data() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    let alarm = Alarm {
+        zone: "north".to_string(),
+        raised: true,
+    };
+    let off_chain = content_digest(
+        &canonical_json(br#"{ "zone": "north", "raised": true }"#),
+        &canonical_json(b"[1, 2]"),
+    );
+    let payload = || {
+        PayloadBuilder::new()
+            .public(&alarm)
+            .unwrap()
+            .masked(&[1, 2])
+            .unwrap()
+            .build()
+    };
+
+    // The sequence envelope re-serializes the public data
+    author.set_sequence_numbers(true);
+    let numbered = author.write_signed_with_digest(payload()).unwrap();
+    assert_eq!(numbered.digest, off_chain);
+    author.set_sequence_numbers(false);
+    author.set_canonical_json(true);
+    let canonical = author.write_signed_with_digest(payload()).unwrap();
+    assert_eq!(canonical.digest, off_chain);
+
+    let msgs = subscriber.poll_new().unwrap();
+    assert_eq!(msgs.len(), 2);
+    for msg in msgs.iter() {
+        msg.verify_digest(&off_chain).unwrap();
+    }
+    assert_eq!(
+        msgs[1].public.as_deref(),
+        Some(r#"{"raised":true,"zone":"north"}"#)
+    );
+}
+
+#[test]
+fn keyload_history_reports_the_keyloads_granting_access() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        channel_address,
+        announcement_tag,
+        keyload_tag,
+        ..
+    } = tangle.channel(1).unwrap();
+    let first_keyload = keyload_tag.unwrap();
+    let mut late = tangle.subscriber(&channel_address, &announcement_tag);
+    let subscription_tag = late.connect().unwrap();
+    let second_keyload = author.add_subscriber(subscription_tag).unwrap();
+    let mut outsider = tangle.subscriber(&channel_address, &announcement_tag);
+    outsider.connect().unwrap();
+
+    let authorized = |history: Vec<KeyloadInfo>| {
+        history
+            .into_iter()
+            .map(|keyload| (keyload.tag, keyload.authorized))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        authorized(late.keyload_history().unwrap()),
+        vec![
+            (first_keyload.clone(), false),
+            (second_keyload.clone(), true)
+        ]
+    );
+    assert_eq!(
+        authorized(outsider.keyload_history().unwrap()),
+        vec![(first_keyload, false), (second_keyload, false)]
+    );
+}
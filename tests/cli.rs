@@ -0,0 +1,43 @@
+#![cfg(feature = "cli")]
+
+#[allow(dead_code)]
+#[path = "../src/bin/channels_lite.rs"]
+mod cli;
+
+use clap::ErrorKind;
+use std::fs;
+
+#[test]
+fn the_password_is_required() {
+    let matches = cli::app().get_matches_from(vec!["channels-lite", "author", "open"]);
+    if std::env::var_os("CHANNELS_LITE_PASSWORD").is_none() {
+        assert!(cli::Options::from_matches(&matches).is_err());
+    }
+
+    match cli::app().get_matches_from_safe(vec![
+        "channels-lite",
+        "--password",
+        "",
+        "author",
+        "open",
+    ]) {
+        Err(e) => assert_eq!(e.kind, ErrorKind::EmptyValue),
+        Ok(_) => panic!("an empty password is accepted"),
+    }
+}
+
+#[test]
+fn the_options_take_the_password() {
+    let dir = std::env::temp_dir().join(format!("channels_lite_cli_{}", std::process::id()));
+    let matches = cli::app().get_matches_from(vec![
+        "channels-lite",
+        "--password",
+        "secret",
+        "--state-dir",
+        dir.to_str().unwrap(),
+        "author",
+        "open",
+    ]);
+    assert!(cli::Options::from_matches(&matches).is_ok());
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,190 @@
+//!
+//! Fixtures shared by the integration tests
+//!
+use channels_lite::error::{ChannelError, ConfigError};
+use std::time::Duration;
+
+///
+/// One error of each variant, with its code number and name
+///
+pub fn every_error() -> Vec<(ChannelError, i32, &'static str)> {
+    vec![
+        (
+            ChannelError::Transport(anyhow::anyhow!("connection refused")),
+            1,
+            "transport",
+        ),
+        (
+            ChannelError::AnnouncementMismatch {
+                channel_address: "A".to_string(),
+                announcement_tag: "T".to_string(),
+                reason: "keyload".to_string(),
+            },
+            2,
+            "announcement_mismatch",
+        ),
+        (ChannelError::NotConnected, 3, "not_connected"),
+        (ChannelError::NotOpened, 4, "not_opened"),
+        (
+            ChannelError::InvalidAddress {
+                address: "A:T".to_string(),
+                reason: "bad trytes".to_string(),
+            },
+            5,
+            "invalid_address",
+        ),
+        (
+            ChannelError::PayloadEncoding("eof".to_string()),
+            6,
+            "payload_encoding",
+        ),
+        (
+            ChannelError::UnsupportedFraming {
+                version: 9,
+                supported: 1,
+            },
+            7,
+            "unsupported_framing",
+        ),
+        (
+            ChannelError::MessageNotFound("T".to_string()),
+            8,
+            "message_not_found",
+        ),
+        (
+            ChannelError::AccessDenied("T".to_string()),
+            9,
+            "access_denied",
+        ),
+        (ChannelError::NoKeyload("A".to_string()), 10, "no_keyload"),
+        (ChannelError::State("closed".to_string()), 11, "state"),
+        (
+            ChannelError::Protocol("bad link".to_string()),
+            12,
+            "protocol",
+        ),
+        (
+            ChannelError::Io(std::io::Error::new(std::io::ErrorKind::Other, "disk")),
+            13,
+            "io",
+        ),
+        (
+            ChannelError::Config(ConfigError::InvalidNodeUrl {
+                url: "nowhere".to_string(),
+                reason: "no scheme".to_string(),
+            }),
+            14,
+            "config",
+        ),
+        (
+            ChannelError::NodeUnhealthy {
+                node: "http://node".to_string(),
+                lag: 10,
+                max_lag: 2,
+            },
+            15,
+            "node_unhealthy",
+        ),
+        (
+            ChannelError::NodeNotSynced {
+                node: "http://node".to_string(),
+                lag: 10,
+                max_lag: 2,
+            },
+            16,
+            "node_not_synced",
+        ),
+        (
+            ChannelError::IncompatibleNode {
+                node: "http://node".to_string(),
+                app_name: "HORNET".to_string(),
+                app_version: "0.1.0".to_string(),
+                reason: "too old".to_string(),
+            },
+            17,
+            "incompatible_node",
+        ),
+        (
+            ChannelError::Timeout {
+                node: "http://node".to_string(),
+                after: Duration::from_secs(5),
+            },
+            18,
+            "timeout",
+        ),
+        (
+            ChannelError::UnknownNetwork("nowhere".to_string()),
+            19,
+            "unknown_network",
+        ),
+        (
+            ChannelError::QuorumFailure {
+                link: "A:T".to_string(),
+                agreeing: 1,
+                required: 2,
+                failed_nodes: vec![],
+            },
+            20,
+            "quorum_failure",
+        ),
+        (ChannelError::Internal("panic".to_string()), 21, "internal"),
+        (
+            ChannelError::RateLimited {
+                retry_after: Duration::from_millis(100),
+            },
+            22,
+            "rate_limited",
+        ),
+        (
+            ChannelError::SchemaViolation {
+                tag: "T".to_string(),
+                errors: vec!["missing field".to_string()],
+            },
+            23,
+            "schema_violation",
+        ),
+        (
+            ChannelError::UnsupportedProtocol {
+                version: 9,
+                supported: 1,
+            },
+            24,
+            "unsupported_protocol",
+        ),
+        (
+            ChannelError::TooManyFragments {
+                tag: "TAG".to_string(),
+                fragments: 4,
+                max: 2,
+            },
+            25,
+            "too_many_fragments",
+        ),
+        (
+            ChannelError::PolicyViolation {
+                reason: "at most 2 messages per keyload epoch".to_string(),
+                keyload_tag: "TAG".to_string(),
+                messages: 2,
+                payload_bytes: 64,
+            },
+            26,
+            "policy_violation",
+        ),
+        (
+            ChannelError::DigestMismatch {
+                tag: "TAG".to_string(),
+                expected: "00".to_string(),
+                actual: Some("ff".to_string()),
+            },
+            27,
+            "digest_mismatch",
+        ),
+        (
+            ChannelError::DecoderConflict {
+                content_type: "reading/v1".to_string(),
+            },
+            28,
+            "decoder_conflict",
+        ),
+    ]
+}
@@ -0,0 +1,154 @@
+use channels_lite::channels::{channel_author::ChannelBuilder, Network};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::thread;
+
+const NODE_INFO: &str = r#"{
+    "appName": "HORNET",
+    "appVersion": "0.4.2",
+    "jreAvailableProcessors": 4,
+    "jreFreeMemory": 0,
+    "jreMaxMemory": 0,
+    "jreTotalMemory": 0,
+    "jreVersion": "",
+    "latestMilestone": "999999999999999999999999999999999999999999999999999999999999999999999999999999999",
+    "latestMilestoneIndex": 100,
+    "latestSolidSubtangleMilestone": "999999999999999999999999999999999999999999999999999999999999999999999999999999999",
+    "latestSolidSubtangleMilestoneIndex": 100,
+    "milestoneStartIndex": 1,
+    "lastSnapshottedMilestoneIndex": 1,
+    "neighbors": 0,
+    "packetsQueueSize": 0,
+    "time": 0,
+    "tips": 0,
+    "transactionsToRequest": 0,
+    "features": [],
+    "coordinatorAddress": "999999999999999999999999999999999999999999999999999999999999999999999999999999999",
+    "duration": 0
+}"#;
+
+///
+/// Minimal keep-alive HTTP server answering every request with the node info
+///
+fn serve(listener: TcpListener, connections: Arc<AtomicUsize>, requests: Arc<AtomicUsize>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => return,
+        };
+        connections.fetch_add(1, Ordering::SeqCst);
+        let requests = requests.clone();
+        thread::spawn(move || handle(stream, requests));
+    }
+}
+
+fn handle(stream: TcpStream, requests: Arc<AtomicUsize>) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut content_length = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            let header = line.trim_end().to_lowercase();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(len) = header.strip_prefix("content-length:") {
+                content_length = len.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        requests.fetch_add(1, Ordering::SeqCst);
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            NODE_INFO.len(),
+            NODE_INFO
+        );
+        if writer.write_all(response.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+///
+/// Node started on a free port
+///
+struct TestNode {
+    url: &'static str,
+    connections: Arc<AtomicUsize>,
+    requests: Arc<AtomicUsize>,
+}
+
+impl TestNode {
+    fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url: &'static str =
+            Box::leak(format!("http://{}", listener.local_addr().unwrap()).into_boxed_str());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let requests = Arc::new(AtomicUsize::new(0));
+        let (server_connections, server_requests) = (connections.clone(), requests.clone());
+        thread::spawn(move || serve(listener, server_connections, server_requests));
+        TestNode {
+            url: url,
+            connections: connections,
+            requests: requests,
+        }
+    }
+
+    fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+#[test]
+fn sequential_requests_reuse_the_connection() {
+    let node = TestNode::start();
+
+    let author = ChannelBuilder::new(Network::Custom(node.url, 9))
+        .build()
+        .unwrap();
+
+    // Node info requests go through the same client as the sends
+    for _ in 0..50 {
+        author.check_node().unwrap();
+    }
+
+    let opened = node.connections.load(Ordering::SeqCst);
+    assert!(opened <= 2, "{} connections opened for 50 requests", opened);
+}
+
+#[test]
+fn channels_on_different_nodes_have_their_own_client() {
+    let first_node = TestNode::start();
+    let second_node = TestNode::start();
+
+    let first = ChannelBuilder::new(Network::Custom(first_node.url, 9))
+        .build()
+        .unwrap();
+    let second = ChannelBuilder::new(Network::Custom(second_node.url, 9))
+        .build()
+        .unwrap();
+    assert_eq!(first.node(), first_node.url);
+    assert_eq!(second.node(), second_node.url);
+
+    // Creating the second channel didn't redirect the first one
+    let (first_before, second_before) = (first_node.requests(), second_node.requests());
+    first.check_node().unwrap();
+    assert!(first_node.requests() > first_before);
+    assert_eq!(second_node.requests(), second_before);
+
+    let first_before = first_node.requests();
+    second.check_node().unwrap();
+    assert!(second_node.requests() > second_before);
+    assert_eq!(first_node.requests(), first_before);
+}
@@ -0,0 +1,172 @@
+use channels_lite::channels::cursor::{Cursor, CURSOR_VERSION};
+use channels_lite::error::ChannelError;
+use channels_lite::testing::{TestAuthor, TestChannel, TestTangle};
+use channels_lite::utils::payload::json::PayloadBuilder;
+
+fn write(author: &mut TestAuthor, n: u32) -> String {
+    author
+        .write_signed(PayloadBuilder::new().public(&n).unwrap().build())
+        .unwrap()
+}
+
+#[test]
+fn cursor_tokens_round_trip() {
+    let cursor = Cursor {
+        version: CURSOR_VERSION,
+        channel_address: "channel".to_string(),
+        position: 3,
+        last_tag: Some("tag".to_string()),
+        sequence_guard: None,
+    };
+    assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+    assert_eq!(
+        Cursor::decode(&format!(" {}\n", cursor.encode())).unwrap(),
+        cursor
+    );
+}
+
+#[test]
+fn another_instance_resumes_from_the_cursor() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(2).unwrap();
+    let mut resumed = subscribers.pop().unwrap();
+    let mut first = subscribers.pop().unwrap();
+
+    write(&mut author, 1);
+    write(&mut author, 2);
+    assert_eq!(first.poll_new().unwrap().len(), 2);
+
+    resumed.resume_from_cursor(&first.cursor()).unwrap();
+    assert_eq!(resumed.cursor(), first.cursor());
+    assert!(resumed.poll_new().unwrap().is_empty());
+
+    let tag = write(&mut author, 3);
+    let msgs = resumed.poll_new().unwrap();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].tag, tag);
+}
+
+#[test]
+fn cursors_of_another_version_are_refused() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut subscribers,
+        channel_address,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+
+    let token = Cursor {
+        version: CURSOR_VERSION + 1,
+        channel_address: channel_address,
+        position: 0,
+        last_tag: None,
+        sequence_guard: None,
+    }
+    .encode();
+    assert!(matches!(
+        Cursor::decode(&token),
+        Err(ChannelError::PayloadEncoding(_))
+    ));
+    assert!(matches!(
+        subscriber.resume_from_cursor(&token),
+        Err(ChannelError::PayloadEncoding(_))
+    ));
+}
+
+#[test]
+fn cursors_of_another_channel_are_refused() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    write(&mut author, 1);
+
+    let token = Cursor {
+        version: CURSOR_VERSION,
+        channel_address: "another channel".to_string(),
+        position: 1,
+        last_tag: None,
+        sequence_guard: None,
+    }
+    .encode();
+    match subscriber.resume_from_cursor(&token) {
+        Err(ChannelError::State(reason)) => assert!(reason.contains("another channel")),
+        other => panic!("unexpected result {:?}", other),
+    }
+    // The refused cursor didn't move the read position
+    assert_eq!(subscriber.poll_new().unwrap().len(), 1);
+}
+
+#[test]
+fn cursors_behind_the_read_position_are_refused() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    let earlier = subscriber.cursor();
+
+    write(&mut author, 1);
+    assert_eq!(subscriber.poll_new().unwrap().len(), 1);
+    match subscriber.resume_from_cursor(&earlier) {
+        Err(ChannelError::State(reason)) => assert!(reason.contains("behind")),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn cursors_past_the_end_of_the_channel_are_refused() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        channel_address,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    write(&mut author, 1);
+
+    let token = Cursor {
+        version: CURSOR_VERSION,
+        channel_address: channel_address,
+        position: 10,
+        last_tag: None,
+        sequence_guard: None,
+    }
+    .encode();
+    match subscriber.resume_from_cursor(&token) {
+        Err(ChannelError::State(reason)) => assert!(reason.contains("not reached")),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn cursors_expecting_another_message_are_refused() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(2).unwrap();
+    let mut resumed = subscribers.pop().unwrap();
+    let mut first = subscribers.pop().unwrap();
+    write(&mut author, 1);
+    assert_eq!(first.poll_new().unwrap().len(), 1);
+
+    let mut cursor = Cursor::decode(&first.cursor()).unwrap();
+    cursor.last_tag = Some("another message".to_string());
+    match resumed.resume_from_cursor(&cursor.encode()) {
+        Err(ChannelError::State(reason)) => assert!(reason.contains("another message")),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
@@ -0,0 +1,163 @@
+//! Recognition of the envelopes wrapping the public data, an application payload must
+//! never be taken for one
+
+use channels_lite::utils::ack::AckRequested;
+use channels_lite::utils::content_type::Typed;
+use channels_lite::utils::envelope::EnvelopeKind;
+use channels_lite::utils::expiry::{Expiring, Expiry};
+use channels_lite::utils::protocol::ProtocolMarker;
+use channels_lite::utils::sequence::Sequenced;
+use serde_json::json;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[test]
+fn expiring_envelope_is_recognised_by_its_marker_only() {
+    let envelope = json!({
+        "channels_lite_envelope": "expiring",
+        "expiry": { "timestamp": 60, "ttl": 30 },
+        "data": { "a": 1 },
+    });
+    let parsed = Expiring::parse(&envelope.to_string()).unwrap();
+    assert_eq!(parsed.envelope, EnvelopeKind::Expiring);
+    assert_eq!(parsed.data, Some(json!({ "a": 1 })));
+    assert_eq!(
+        parsed.expiry.expires_at(),
+        Some(UNIX_EPOCH + Duration::from_secs(90))
+    );
+
+    // Application payloads with an expiry member
+    for payload in vec![
+        json!({ "expiry": { "timestamp": 60, "ttl": 30 }, "data": 1 }),
+        json!({ "expiry": { "timestamp": 60, "ttl": 30 } }),
+        json!({
+            "channels_lite_envelope": "expiring",
+            "expiry": { "timestamp": 60, "ttl": 30 },
+        }),
+        json!({
+            "channels_lite_envelope": "expiring",
+            "expiry": { "timestamp": 60, "ttl": 30 },
+            "data": 1,
+            "other": 2,
+        }),
+        json!({
+            "channels_lite_envelope": "typed",
+            "expiry": { "timestamp": 60, "ttl": 30 },
+            "data": 1,
+        }),
+    ] {
+        assert_eq!(Expiring::parse(&payload.to_string()), None, "{}", payload);
+    }
+}
+
+#[test]
+fn expiry_beyond_the_representable_times_never_expires() {
+    let expiry = Expiry {
+        timestamp: u64::MAX,
+        ttl: u64::MAX,
+    };
+    assert_eq!(expiry.expires_at(), None);
+}
+
+#[test]
+fn protocol_marker_has_a_single_member() {
+    assert_eq!(
+        ProtocolMarker::parse(r#"{"channels_lite_protocol":1}"#),
+        Some(1)
+    );
+    assert_eq!(
+        ProtocolMarker::parse(r#"{"channels_lite_protocol":1,"reading":20}"#),
+        None
+    );
+    assert_eq!(
+        ProtocolMarker::parse(r#"{"channels_lite_protocol":"1"}"#),
+        None
+    );
+}
+
+#[test]
+fn sequence_envelope_is_recognised_by_its_marker_only() {
+    let envelope = json!({
+        "channels_lite_envelope": "sequenced",
+        "seq": { "sender": "SENDER", "n": 3 },
+        "data": null,
+    });
+    let parsed = Sequenced::parse(&envelope.to_string()).unwrap();
+    assert_eq!(parsed.seq.n, 3);
+    assert_eq!(parsed.data, None);
+
+    for payload in vec![
+        json!({ "seq": { "sender": "SENDER", "n": 3 }, "data": 1 }),
+        json!({ "channels_lite_envelope": "sequenced", "seq": { "sender": "SENDER", "n": 3 } }),
+        json!({
+            "channels_lite_envelope": "sequenced",
+            "seq": { "sender": "SENDER", "n": 3 },
+            "data": 1,
+            "other": 2,
+        }),
+        json!({
+            "channels_lite_envelope": "expiring",
+            "seq": { "sender": "SENDER", "n": 3 },
+            "data": 1,
+        }),
+    ] {
+        assert_eq!(Sequenced::parse(&payload.to_string()), None, "{}", payload);
+    }
+}
+
+#[test]
+fn ack_request_envelope_is_recognised_by_its_marker_only() {
+    let envelope = json!({
+        "channels_lite_envelope": "ack_requested",
+        "ack_request": "00ff",
+        "data": "reading",
+    });
+    let parsed = AckRequested::parse(&envelope.to_string()).unwrap();
+    assert_eq!(parsed.ack_request, "00ff");
+    assert_eq!(parsed.data, Some(json!("reading")));
+
+    for payload in vec![
+        json!({ "ack_request": "00ff", "data": 1 }),
+        json!({ "channels_lite_envelope": "ack_requested", "ack_request": "00ff" }),
+        json!({
+            "channels_lite_envelope": "ack_requested",
+            "ack_request": "00ff",
+            "data": 1,
+            "other": 2,
+        }),
+        json!({ "channels_lite_envelope": "typed", "ack_request": "00ff", "data": 1 }),
+    ] {
+        assert_eq!(
+            AckRequested::parse(&payload.to_string()),
+            None,
+            "{}",
+            payload
+        );
+    }
+}
+
+#[test]
+fn typed_envelope_is_recognised_by_its_marker_only() {
+    let envelope = json!({
+        "channels_lite_envelope": "typed",
+        "content_type": "reading/v1",
+        "data": { "value": 3 },
+    });
+    let parsed = Typed::parse(&envelope.to_string()).unwrap();
+    assert_eq!(parsed.content_type, "reading/v1");
+    assert_eq!(parsed.data, Some(json!({ "value": 3 })));
+
+    // e.g. an HTTP-like record of the application
+    for payload in vec![
+        json!({ "content_type": "text/plain", "data": "body" }),
+        json!({ "channels_lite_envelope": "typed", "content_type": "reading/v1" }),
+        json!({
+            "channels_lite_envelope": "typed",
+            "content_type": "reading/v1",
+            "data": 1,
+            "status": 200,
+        }),
+        json!({ "channels_lite_envelope": "sequenced", "content_type": "reading/v1", "data": 1 }),
+    ] {
+        assert_eq!(Typed::parse(&payload.to_string()), None, "{}", payload);
+    }
+}
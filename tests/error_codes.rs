@@ -0,0 +1,47 @@
+mod common;
+
+use channels_lite::error::{ChannelError, ErrorCode};
+use common::every_error;
+use serde_json::json;
+use std::collections::HashSet;
+use std::time::Duration;
+
+#[test]
+fn error_codes_never_change() {
+    for (error, number, name) in every_error() {
+        assert_eq!(error.code().as_i32(), number, "{:?}", error);
+        assert_eq!(error.code().as_str(), name, "{:?}", error);
+    }
+}
+
+#[test]
+fn error_codes_are_unique() {
+    let errors = every_error();
+    let numbers: HashSet<i32> = errors.iter().map(|(e, _, _)| e.code().as_i32()).collect();
+    let names: HashSet<&str> = errors.iter().map(|(e, _, _)| e.code().as_str()).collect();
+    assert_eq!(numbers.len(), errors.len());
+    assert_eq!(names.len(), errors.len());
+    assert!(!numbers.contains(&0));
+}
+
+#[test]
+fn errors_render_as_json() {
+    assert_eq!(
+        ChannelError::NotConnected.as_json(),
+        json!({
+            "code": "not_connected",
+            "message": "Channel not connected",
+            "retryable": false,
+        })
+    );
+    let timeout = ChannelError::Timeout {
+        node: "http://node".to_string(),
+        after: Duration::from_secs(5),
+    };
+    assert_eq!(timeout.code(), ErrorCode::Timeout);
+    assert_eq!(timeout.as_json()["retryable"], json!(true));
+    assert_eq!(
+        timeout.as_json()["message"],
+        json!("Timed out after 5s talking to http://node")
+    );
+}
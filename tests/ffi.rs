@@ -0,0 +1,144 @@
+use channels_lite::error::ErrorCode;
+use channels_lite::ffi::*;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+unsafe fn take_string(s: *mut c_char) -> String {
+    let owned = CStr::from_ptr(s).to_str().unwrap().to_string();
+    channels_lite_string_free(s);
+    owned
+}
+
+#[test]
+fn author_and_subscriber_through_the_c_api() {
+    unsafe {
+        let mut tangle = ptr::null_mut();
+        let mut author = ptr::null_mut();
+        let mut subscriber = ptr::null_mut();
+        assert_eq!(
+            channels_lite_mock_tangle_new(&mut tangle),
+            ChannelsLiteError::Ok
+        );
+        assert_eq!(
+            channels_lite_author_new_mock(tangle, ptr::null(), &mut author),
+            ChannelsLiteError::Ok
+        );
+
+        let mut channel_address = ptr::null_mut();
+        let mut announcement_tag = ptr::null_mut();
+        assert_eq!(
+            channels_lite_author_open(author, &mut channel_address, &mut announcement_tag),
+            ChannelsLiteError::Ok
+        );
+        assert_eq!(
+            channels_lite_subscriber_new_mock(
+                tangle,
+                channel_address,
+                announcement_tag,
+                ptr::null(),
+                &mut subscriber,
+            ),
+            ChannelsLiteError::Ok
+        );
+        take_string(channel_address);
+        take_string(announcement_tag);
+
+        let mut subscription_tag = ptr::null_mut();
+        assert_eq!(
+            channels_lite_subscriber_connect(subscriber, &mut subscription_tag),
+            ChannelsLiteError::Ok
+        );
+        let mut keyload_tag = ptr::null_mut();
+        assert_eq!(
+            channels_lite_author_add_subscriber(author, subscription_tag, &mut keyload_tag),
+            ChannelsLiteError::Ok
+        );
+        take_string(subscription_tag);
+        assert_eq!(
+            channels_lite_subscriber_update_keyload(subscriber, keyload_tag),
+            ChannelsLiteError::Ok
+        );
+        take_string(keyload_tag);
+
+        let public = br#"{"temperature":21.5}"#;
+        let mut signed_tag = ptr::null_mut();
+        assert_eq!(
+            channels_lite_author_write_signed(
+                author,
+                public.as_ptr(),
+                public.len(),
+                ptr::null(),
+                0,
+                &mut signed_tag,
+            ),
+            ChannelsLiteError::Ok
+        );
+        let mut json = ptr::null_mut();
+        assert_eq!(
+            channels_lite_subscriber_read_signed(subscriber, signed_tag, &mut json),
+            ChannelsLiteError::Ok
+        );
+        take_string(signed_tag);
+        assert!(take_string(json).contains("temperature"));
+
+        channels_lite_subscriber_free(subscriber);
+        channels_lite_author_free(author);
+        channels_lite_mock_tangle_free(tangle);
+    }
+}
+
+#[test]
+fn errors_are_reported_through_the_status() {
+    unsafe {
+        let mut author = ptr::null_mut();
+        let network = CString::new("nowhere").unwrap();
+        assert_eq!(
+            channels_lite_author_new(network.as_ptr(), ptr::null(), &mut author),
+            ChannelsLiteError::Config
+        );
+        assert!(author.is_null());
+        let message = CStr::from_ptr(channels_lite_last_error_message());
+        assert!(message.to_str().unwrap().contains("nowhere"));
+        assert_eq!(
+            channels_lite_last_error_code(),
+            ErrorCode::UnknownNetwork.as_i32()
+        );
+
+        let mut json = ptr::null_mut();
+        assert_eq!(
+            channels_lite_subscriber_connect(ptr::null_mut(), &mut json),
+            ChannelsLiteError::NullArgument
+        );
+        assert!(json.is_null());
+        assert_eq!(channels_lite_last_error_code(), 0);
+
+        let mut tangle = ptr::null_mut();
+        assert_eq!(
+            channels_lite_mock_tangle_new(&mut tangle),
+            ChannelsLiteError::Ok
+        );
+        assert!(channels_lite_last_error_message().is_null());
+        assert_eq!(
+            channels_lite_author_new_mock(tangle, ptr::null(), &mut author),
+            ChannelsLiteError::Ok
+        );
+        let invalid = b"{not json";
+        let mut tag = ptr::null_mut();
+        assert_eq!(
+            channels_lite_author_write_signed(
+                author,
+                invalid.as_ptr(),
+                invalid.len(),
+                ptr::null(),
+                0,
+                &mut tag,
+            ),
+            ChannelsLiteError::InvalidArgument
+        );
+        assert!(tag.is_null());
+
+        channels_lite_author_free(author);
+        channels_lite_mock_tangle_free(tangle);
+    }
+}
@@ -0,0 +1,91 @@
+/*
+ * C test of the ffi feature, run with tests/ffi/run.sh
+ *
+ * Every string written to an out argument is freed with channels_lite_string_free,
+ * the last error message is never freed.
+ */
+#define CHANNELS_LITE_TEST_TRANSPORT
+#include "channels_lite.h"
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#define CHECK(call)                                                              \
+    do {                                                                         \
+        ChannelsLiteError status = (call);                                       \
+        if (status != CHANNELS_LITE_ERROR_OK) {                                  \
+            fprintf(stderr, "%s:%d: %s failed with %d: %s\n", __FILE__, __LINE__, \
+                    #call, (int)status, channels_lite_last_error_message());     \
+            exit(1);                                                             \
+        }                                                                        \
+    } while (0)
+
+#define EXPECT(cond)                                                             \
+    do {                                                                         \
+        if (!(cond)) {                                                           \
+            fprintf(stderr, "%s:%d: expected %s\n", __FILE__, __LINE__, #cond);  \
+            exit(1);                                                             \
+        }                                                                        \
+    } while (0)
+
+int main(void) {
+    ChannelsLiteMockTangle *tangle = NULL;
+    ChannelsLiteAuthor *author = NULL;
+    ChannelsLiteSubscriber *subscriber = NULL;
+    char *channel_address = NULL;
+    char *announcement_tag = NULL;
+    char *subscription_tag = NULL;
+    char *keyload_tag = NULL;
+    char *signed_tag = NULL;
+    char *json = NULL;
+    const char *public_json = "{\"temperature\":21.5}";
+    const char *invalid_json = "{not json";
+
+    CHECK(channels_lite_mock_tangle_new(&tangle));
+    CHECK(channels_lite_author_new_mock(tangle, NULL, &author));
+    CHECK(channels_lite_author_open(author, &channel_address, &announcement_tag));
+
+    CHECK(channels_lite_subscriber_new_mock(tangle, channel_address, announcement_tag, NULL,
+                                            &subscriber));
+    CHECK(channels_lite_subscriber_connect(subscriber, &subscription_tag));
+    CHECK(channels_lite_author_add_subscriber(author, subscription_tag, &keyload_tag));
+    CHECK(channels_lite_subscriber_update_keyload(subscriber, keyload_tag));
+
+    CHECK(channels_lite_author_write_signed(author, (const uint8_t *)public_json,
+                                            strlen(public_json), NULL, 0, &signed_tag));
+    CHECK(channels_lite_subscriber_read_signed(subscriber, signed_tag, &json));
+    EXPECT(strstr(json, "temperature") != NULL);
+    channels_lite_string_free(json);
+    json = NULL;
+
+    /* Errors are reported through the status and the last error message */
+    EXPECT(channels_lite_author_write_signed(author, (const uint8_t *)invalid_json,
+                                             strlen(invalid_json), NULL, 0, &signed_tag) ==
+           CHANNELS_LITE_ERROR_INVALID_ARGUMENT);
+    EXPECT(channels_lite_last_error_message() != NULL);
+    EXPECT(channels_lite_subscriber_update_keyload(subscriber, NULL) ==
+           CHANNELS_LITE_ERROR_NULL_ARGUMENT);
+    EXPECT(channels_lite_subscriber_connect(NULL, &json) == CHANNELS_LITE_ERROR_NULL_ARGUMENT);
+    EXPECT(json == NULL);
+    EXPECT(channels_lite_last_error_code() == 0);
+
+    /* A successful call clears the last error */
+    CHECK(channels_lite_subscriber_poll_new(subscriber, &json));
+    EXPECT(channels_lite_last_error_message() == NULL);
+    channels_lite_string_free(json);
+
+    channels_lite_string_free(channel_address);
+    channels_lite_string_free(announcement_tag);
+    channels_lite_string_free(subscription_tag);
+    channels_lite_string_free(keyload_tag);
+    channels_lite_string_free(signed_tag);
+    channels_lite_string_free(NULL);
+
+    channels_lite_subscriber_free(subscriber);
+    channels_lite_author_free(author);
+    channels_lite_mock_tangle_free(tangle);
+
+    printf("ffi test passed\n");
+    return 0;
+}
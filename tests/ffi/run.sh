@@ -0,0 +1,9 @@
+#!/bin/sh
+# Builds the static library with the C bindings and runs the C test against it
+set -e
+cd "$(dirname "$0")/../.."
+
+cargo build --features "ffi test-transport"
+cc -Wall -Werror -o target/debug/ffi_test tests/ffi/main.c \
+    -Iinclude target/debug/libchannels_lite.a -lssl -lcrypto -lpthread -ldl -lm
+target/debug/ffi_test
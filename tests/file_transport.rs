@@ -0,0 +1,75 @@
+use channels_lite::channels::{channel_author, channel_subscriber, Network};
+use channels_lite::transport::{
+    file::{import_messages, replay_directory, FileTransport},
+    mock::MockTransport,
+    Address, ChannelTransport, Transport,
+};
+use channels_lite::utils::payload::json::PayloadBuilder;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SensorData {
+    presure: f32,
+}
+
+#[test]
+fn offline_author_replayed_to_mock_tangle() {
+    let dir = std::env::temp_dir().join(format!("channels_lite_file_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let mut tangle = MockTransport::new();
+
+    let mut author = channel_author::Channel::new_with_transport(
+        Network::Devnet,
+        ChannelTransport::File(FileTransport::new(&dir).unwrap()),
+        None,
+    )
+    .unwrap();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+    // The announcement and the protocol marker
+    let replayed = replay_directory(&dir, &mut tangle).unwrap();
+    assert_eq!(replayed.len(), 2);
+    assert_eq!(replayed[0], announcement_tag);
+
+    let mut subscriber = channel_subscriber::Channel::new_with_transport(
+        Network::Devnet,
+        ChannelTransport::Mock(tangle.clone()),
+        channel_address.clone(),
+        announcement_tag,
+        None,
+    )
+    .unwrap();
+    let subscription_tag = subscriber.connect().unwrap();
+
+    // Carry the subscription back to the air-gapped author
+    let subscription = tangle
+        .recv_messages(&Address::from_str(&channel_address, &subscription_tag).unwrap())
+        .unwrap();
+    import_messages(&dir, &subscription).unwrap();
+
+    let keyload_tag = author.add_subscriber(subscription_tag).unwrap();
+    let signed_tag = author
+        .write_signed(
+            PayloadBuilder::new()
+                .public(&SensorData { presure: 1.0 })
+                .unwrap()
+                .masked(&SensorData { presure: 2.0 })
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+    assert_eq!(
+        replay_directory(&dir, &mut tangle).unwrap(),
+        vec![keyload_tag.clone(), signed_tag.clone()]
+    );
+
+    subscriber.update_keyload(keyload_tag).unwrap();
+    let signed = subscriber.read_signed(signed_tag).unwrap();
+    assert_eq!(signed.len(), 1);
+    let public: SensorData = serde_json::from_str(signed[0].0.as_ref().unwrap()).unwrap();
+    let masked: SensorData = serde_json::from_str(signed[0].1.as_ref().unwrap()).unwrap();
+    assert_eq!(public, SensorData { presure: 1.0 });
+    assert_eq!(masked, SensorData { presure: 2.0 });
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,57 @@
+use channels_lite::testing::{TestChannel, TestTangle};
+use channels_lite::utils::payload::json::PayloadBuilder;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::sync::Mutex;
+
+struct CapturingLogger {
+    records: Mutex<Vec<(Level, String, String)>>,
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.records.lock().unwrap().push((
+            record.level(),
+            record.target().to_string(),
+            record.args().to_string(),
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger {
+    records: Mutex::new(Vec::new()),
+};
+
+#[test]
+fn message_walk_logs_at_debug() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = TestTangle::new().channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    for presure in 0..3 {
+        author
+            .write_signed(PayloadBuilder::new().public(&presure).unwrap().build())
+            .unwrap();
+    }
+
+    let tags = subscriber.get_next_message();
+    assert_eq!(tags.len(), 3);
+
+    let records = LOGGER.records.lock().unwrap();
+    let walk: Vec<_> = records
+        .iter()
+        .filter(|(_, target, _)| target == "channels_lite::subscriber")
+        .collect();
+    assert!(walk.len() >= tags.len());
+    assert!(walk.iter().all(|(level, _, _)| *level == Level::Debug));
+}
@@ -0,0 +1,53 @@
+use channels_lite::channels::multi_subscriber::MultiSubscriber;
+use channels_lite::testing::{TestAuthor, TestChannel, TestTangle};
+use channels_lite::utils::payload::json::PayloadBuilder;
+
+fn write(author: &mut TestAuthor, n: u32) -> String {
+    author
+        .write_signed(PayloadBuilder::new().public(&n).unwrap().build())
+        .unwrap()
+}
+
+#[test]
+fn the_channels_take_turns_in_the_merged_stream() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        author: mut first_author,
+        subscribers: mut first,
+        channel_address: first_address,
+        ..
+    } = tangle.channel(1).unwrap();
+    let TestChannel {
+        author: mut second_author,
+        subscribers: mut second,
+        channel_address: second_address,
+        ..
+    } = tangle.channel(1).unwrap();
+
+    let first_tags = vec![write(&mut first_author, 1), write(&mut first_author, 2)];
+    let second_tag = write(&mut second_author, 3);
+
+    let mut multi = MultiSubscriber::from_subscribers(vec![first.remove(0), second.remove(0)]);
+    let msgs: Vec<(String, String)> = multi
+        .messages()
+        .map(|msg| {
+            let msg = msg.unwrap();
+            (msg.channel_address, msg.message.tag)
+        })
+        .collect();
+    assert_eq!(
+        msgs,
+        vec![
+            (first_address.clone(), first_tags[0].clone()),
+            (second_address, second_tag),
+            (first_address.clone(), first_tags[1].clone()),
+        ]
+    );
+
+    assert!(multi.messages().next().is_none());
+    let tag = write(&mut first_author, 4);
+    let msgs: Vec<_> = multi.messages().map(|msg| msg.unwrap()).collect();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].channel_address, first_address);
+    assert_eq!(msgs[0].message.tag, tag);
+}
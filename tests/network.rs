@@ -0,0 +1,101 @@
+use channels_lite::channels::Network;
+use channels_lite::error::ChannelError;
+use std::str::FromStr;
+
+const NETWORKS: [Network; 5] = [
+    Network::Main,
+    Network::Devnet,
+    Network::Comnet,
+    Network::Local,
+    Network::Custom("https://node.example.org:443", 12),
+];
+
+#[test]
+fn networks_round_trip_through_their_names() {
+    for network in NETWORKS.iter() {
+        assert_eq!(
+            Network::from_str(&network.to_string()).unwrap(),
+            *network,
+            "{}",
+            network
+        );
+    }
+}
+
+#[test]
+fn network_names() {
+    assert_eq!(Network::Main.to_string(), "mainnet");
+    assert_eq!(Network::Devnet.to_string(), "devnet");
+    assert_eq!(Network::Comnet.to_string(), "comnet");
+    assert_eq!(Network::Local.to_string(), "local");
+    assert_eq!(
+        Network::Custom("http://10.0.0.1:14265", 9).to_string(),
+        "custom:9:http://10.0.0.1:14265"
+    );
+
+    assert_eq!(Network::from_str("main").unwrap(), Network::Main);
+    assert_eq!(Network::from_str(" DEV ").unwrap(), Network::Devnet);
+    // The url of a custom network keeps its case
+    assert_eq!(
+        Network::from_str("Custom:14:https://Node.Example.org").unwrap(),
+        Network::Custom("https://Node.Example.org", 14)
+    );
+}
+
+#[test]
+fn unknown_networks_are_refused() {
+    for name in [
+        "",
+        "testnet",
+        "https://node.example.org",
+        "custom:",
+        "custom:9",
+        "custom:9:",
+        "custom:x:https://node.example.org",
+        "custom:300:https://node.example.org",
+    ]
+    .iter()
+    {
+        match Network::from_str(name) {
+            Err(ChannelError::UnknownNetwork(n)) => assert_eq!(n, *name),
+            other => panic!("unexpected result {:?} for {:?}", other, name),
+        }
+    }
+}
+
+#[test]
+fn network_urls() {
+    let urls = [
+        (Network::Main, "https://nodes.thetangle.org:443"),
+        (Network::Devnet, "https://nodes.devnet.iota.org:443"),
+        (Network::Comnet, "https://nodes.comnet.thetangle.org:443"),
+        (Network::Local, "http://localhost:14265"),
+        (
+            Network::Custom("https://node.example.org:443", 12),
+            "https://node.example.org:443",
+        ),
+    ];
+    for (network, url) in urls.iter() {
+        assert_eq!(network.as_string(), *url, "{}", network);
+    }
+}
+
+#[test]
+fn network_send_options() {
+    let options = [
+        (Network::Main, 14, false),
+        (Network::Devnet, 9, false),
+        (Network::Comnet, 10, false),
+        (Network::Local, 1, true),
+        (
+            Network::Custom("https://node.example.org:443", 12),
+            12,
+            false,
+        ),
+    ];
+    for (network, mwm, local_pow) in options.iter() {
+        let send_options = network.send_options();
+        assert_eq!(send_options.min_weight_magnitude, *mwm, "{}", network);
+        assert_eq!(send_options.local_pow, *local_pow, "{}", network);
+    }
+}
@@ -0,0 +1,87 @@
+use channels_lite::channels::node::NodeInfo;
+use channels_lite::error::ChannelError;
+
+fn node(app_name: &str, app_version: &str) -> NodeInfo {
+    NodeInfo {
+        node: "https://node".to_string(),
+        app_name: app_name.to_string(),
+        app_version: app_version.to_string(),
+        network_id: "COO".to_string(),
+    }
+}
+
+///
+/// Reason of the incompatibility, `None` for a supported node
+///
+fn incompatibility(app_name: &str, app_version: &str) -> Option<String> {
+    match node(app_name, app_version).ensure_compatible().err()? {
+        ChannelError::IncompatibleNode { reason, .. } => Some(reason),
+        e => panic!("unexpected error {}", e),
+    }
+}
+
+#[test]
+fn supported_node_versions() {
+    let supported = [
+        ("IRI", "1.8.0"),
+        ("IRI", "1.8.6-RELEASE"),
+        ("IRI", "v1.8.6"),
+        ("IRI", "2.0.0"),
+        ("iri", "1.8.6"),
+        ("HORNET", "0.4.0"),
+        ("HORNET", "0.4.2"),
+        ("HORNET", "0.5.3-rc1"),
+        ("Hornet", "0.5.0"),
+    ];
+    for (app_name, app_version) in supported.iter() {
+        assert_eq!(
+            incompatibility(app_name, app_version),
+            None,
+            "{} {}",
+            app_name,
+            app_version
+        );
+    }
+}
+
+#[test]
+fn unsupported_node_versions() {
+    let unsupported = [
+        ("IRI", "1.7.9", "version 1.8 or later required"),
+        ("IRI", "0.9", "version 1.8 or later required"),
+        ("HORNET", "0.3.9", "version 0.4 or later required"),
+        (
+            "HORNET",
+            "0.6.0",
+            "versions from 0.6 don't provide the legacy API",
+        ),
+        (
+            "HORNET",
+            "1.0.0",
+            "versions from 0.6 don't provide the legacy API",
+        ),
+        ("Bee", "0.1.0", "unsupported node software"),
+        ("", "1.8.6", "unsupported node software"),
+    ];
+    for (app_name, app_version, reason) in unsupported.iter() {
+        assert_eq!(
+            incompatibility(app_name, app_version).as_deref(),
+            Some(*reason),
+            "{} {}",
+            app_name,
+            app_version
+        );
+    }
+}
+
+#[test]
+fn malformed_node_versions() {
+    for app_version in ["", "1", "v", "latest", "1.", ".8", "1.x", "one.eight"].iter() {
+        assert_eq!(
+            incompatibility("IRI", app_version).as_deref(),
+            Some("unknown version format"),
+            "{:?}",
+            app_version
+        );
+    }
+}
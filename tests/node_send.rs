@@ -0,0 +1,234 @@
+//! Sends through a scripted node: where the proof of work is computed and when the node
+//! sync is checked
+
+use channels_lite::channels::{channel_author::ChannelBuilder, config::PowMode, Network};
+use channels_lite::transport::retry::RetryConfig;
+use channels_lite::utils::clock::MockClock;
+use channels_lite::utils::payload::json::PayloadBuilder;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const NODE_INFO: &str = r#"{
+    "appName": "HORNET",
+    "appVersion": "0.4.2",
+    "jreAvailableProcessors": 4,
+    "jreFreeMemory": 0,
+    "jreMaxMemory": 0,
+    "jreTotalMemory": 0,
+    "jreVersion": "",
+    "latestMilestone": "999999999999999999999999999999999999999999999999999999999999999999999999999999999",
+    "latestMilestoneIndex": 100,
+    "latestSolidSubtangleMilestone": "999999999999999999999999999999999999999999999999999999999999999999999999999999999",
+    "latestSolidSubtangleMilestoneIndex": 100,
+    "milestoneStartIndex": 1,
+    "lastSnapshottedMilestoneIndex": 1,
+    "neighbors": 0,
+    "packetsQueueSize": 0,
+    "time": 0,
+    "tips": 0,
+    "transactionsToRequest": 0,
+    "features": [],
+    "coordinatorAddress": "999999999999999999999999999999999999999999999999999999999999999999999999999999999",
+    "duration": 0
+}"#;
+
+const TIPS: &str = r#"{
+    "trunkTransaction": "999999999999999999999999999999999999999999999999999999999999999999999999999999999",
+    "branchTransaction": "999999999999999999999999999999999999999999999999999999999999999999999999999999999",
+    "duration": 0
+}"#;
+
+const POW_DISABLED: &str =
+    r#"{"error": "COMMAND attachToTangle is not available on this node", "duration": 0}"#;
+
+///
+/// Node answering the API commands, refusing `attachToTangle`, and recording the
+/// commands it received
+///
+struct TestNode {
+    url: &'static str,
+    commands: Arc<Mutex<Vec<String>>>,
+}
+
+impl TestNode {
+    fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url: &'static str =
+            Box::leak(format!("http://{}", listener.local_addr().unwrap()).into_boxed_str());
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let server_commands = commands.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let commands = server_commands.clone();
+                        thread::spawn(move || handle(stream, commands));
+                    }
+                    Err(_) => return,
+                }
+            }
+        });
+        TestNode {
+            url: url,
+            commands: commands,
+        }
+    }
+
+    fn received(&self, command: &str) -> usize {
+        self.commands
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|received| received.as_str() == command)
+            .count()
+    }
+}
+
+fn handle(stream: TcpStream, commands: Arc<Mutex<Vec<String>>>) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut content_length = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            let header = line.trim_end().to_lowercase();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(len) = header.strip_prefix("content-length:") {
+                content_length = len.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        let request: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+        let command = request["command"].as_str().unwrap_or_default().to_string();
+        commands.lock().unwrap().push(command.clone());
+
+        let (status, body) = match command.as_str() {
+            "getNodeInfo" => ("200 OK", NODE_INFO),
+            "getTransactionsToApprove" => ("200 OK", TIPS),
+            "attachToTangle" => ("400 Bad Request", POW_DISABLED),
+            _ => ("200 OK", r#"{"duration": 0}"#),
+        };
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        if writer.write_all(response.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+#[test]
+fn local_pow_never_asks_the_node_to_attach() {
+    let node = TestNode::start();
+    let mut author = ChannelBuilder::new(Network::Custom(node.url, 9))
+        .pow_mode(PowMode::Local)
+        .retry(RetryConfig::none())
+        .build()
+        .unwrap();
+    author.open().unwrap();
+
+    assert_eq!(node.received("attachToTangle"), 0);
+    assert!(node.received("storeTransactions") > 0);
+    let log = author.sent_log();
+    assert!(!log.is_empty());
+    assert!(log.iter().all(|record| record.pow == PowMode::Local));
+}
+
+#[test]
+fn remote_pow_fails_when_the_node_refuses_it() {
+    let node = TestNode::start();
+    let mut author = ChannelBuilder::new(Network::Custom(node.url, 9))
+        .pow_mode(PowMode::Remote)
+        .retry(RetryConfig::none())
+        .build()
+        .unwrap();
+
+    assert!(author.open().is_err());
+    assert!(node.received("attachToTangle") > 0);
+    assert_eq!(node.received("storeTransactions"), 0);
+    assert!(author.sent_log().is_empty());
+}
+
+#[test]
+fn prefer_remote_falls_back_when_the_node_refuses_remote_pow() {
+    let node = TestNode::start();
+    let mut author = ChannelBuilder::new(Network::Custom(node.url, 9))
+        .pow_mode(PowMode::PreferRemote)
+        .retry(RetryConfig::none())
+        .build()
+        .unwrap();
+    author.open().unwrap();
+
+    assert!(node.received("attachToTangle") > 0);
+    assert!(node.received("storeTransactions") > 0);
+    let log = author.sent_log();
+    assert!(!log.is_empty());
+    assert!(log.iter().all(|record| record.pow == PowMode::Local));
+}
+
+#[test]
+fn prefer_remote_doesnt_fall_back_when_the_pow_node_is_unreachable() {
+    let node = TestNode::start();
+    // Nothing listens on the port of a dropped listener
+    let unreachable = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    };
+    let mut author = ChannelBuilder::new(Network::Custom(node.url, 9))
+        .pow_mode(PowMode::PreferRemote)
+        .pow_node(&unreachable)
+        .retry(RetryConfig::none())
+        .build()
+        .unwrap();
+
+    assert!(author.open().is_err());
+    assert_eq!(node.received("getTransactionsToApprove"), 0);
+    assert_eq!(node.received("storeTransactions"), 0);
+    assert!(author.sent_log().is_empty());
+}
+
+#[test]
+fn the_sync_check_is_reused_within_its_ttl() {
+    let node = TestNode::start();
+    let clock = MockClock::new(SystemTime::now());
+    let mut author = ChannelBuilder::new(Network::Custom(node.url, 9))
+        .pow_mode(PowMode::Local)
+        .retry(RetryConfig::none())
+        .clock(Rc::new(clock.clone()))
+        .verify_sync(true)
+        .sync_check_ttl(Duration::from_secs(30))
+        .auto_keyload(true)
+        .build()
+        .unwrap();
+    let built = node.received("getNodeInfo");
+
+    // The announcement and the protocol marker, one check
+    author.open().unwrap();
+    assert_eq!(node.received("getNodeInfo"), built + 1);
+
+    clock.advance(Duration::from_secs(29));
+    author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    assert_eq!(node.received("getNodeInfo"), built + 1);
+
+    clock.advance(Duration::from_secs(1));
+    author
+        .write_signed(PayloadBuilder::new().public(&2).unwrap().build())
+        .unwrap();
+    assert_eq!(node.received("getNodeInfo"), built + 2);
+}
@@ -0,0 +1,42 @@
+use channels_lite::channels::config::validate_node_url;
+use channels_lite::channels::{channel_author, channel_subscriber, Network};
+use channels_lite::error::{ChannelError, ConfigError};
+
+fn reason(url: &str) -> String {
+    match validate_node_url(url) {
+        Err(ConfigError::InvalidNodeUrl { reason, .. }) => reason,
+        Ok(()) => panic!("{} accepted", url),
+    }
+}
+
+#[test]
+fn accepts_http_and_https_nodes() {
+    assert!(validate_node_url("https://nodes.devnet.iota.org:443").is_ok());
+    assert!(validate_node_url("http://localhost:14265").is_ok());
+    assert!(validate_node_url("http://[::1]:14265/api").is_ok());
+    assert!(validate_node_url("https://node.example.com").is_ok());
+}
+
+#[test]
+fn rejects_invalid_nodes() {
+    assert_eq!(reason(""), "empty url");
+    assert_eq!(reason("node.example.com"), "missing scheme");
+    assert_eq!(reason("htp://node"), "scheme must be http or https");
+    assert_eq!(reason("https://:443"), "missing host");
+    assert_eq!(reason("https://node:port"), "port must be a number");
+}
+
+#[test]
+fn invalid_node_fails_the_constructors() {
+    let node = Network::Custom("node.example.com", 9);
+    match channel_author::Channel::new(node, None) {
+        Err(ChannelError::Config(ConfigError::InvalidNodeUrl { .. })) => {}
+        Ok(_) => panic!("author created"),
+        Err(e) => panic!("unexpected error {}", e),
+    }
+    match channel_subscriber::Channel::new(node, "A".to_string(), "T".to_string(), None) {
+        Err(ChannelError::Config(ConfigError::InvalidNodeUrl { .. })) => {}
+        Ok(_) => panic!("subscriber created"),
+        Err(e) => panic!("unexpected error {}", e),
+    }
+}
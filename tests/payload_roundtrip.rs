@@ -0,0 +1,127 @@
+use channels_lite::utils::multipart::MultiPartPayload;
+use channels_lite::utils::payload::{
+    json::{Payload, PayloadBuilder, MAX_NESTING},
+    PacketPayload,
+};
+use proptest::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+fn round_trip<T>(public: &T, masked: &T) -> (T, T)
+where
+    T: Serialize + DeserializeOwned,
+{
+    let payload = PayloadBuilder::new()
+        .public(public)
+        .unwrap()
+        .masked(masked)
+        .unwrap()
+        .build();
+    let unwrap = |data: &[u8]| -> T {
+        let text = Payload::unwrap_bytes(data).unwrap().unwrap();
+        serde_json::from_str(&text).unwrap()
+    };
+    (
+        unwrap(&payload.public_data().0),
+        unwrap(&payload.masked_data().0),
+    )
+}
+
+fn nested(depth: usize) -> Value {
+    (0..depth).fold(Value::from(1), |inner, _| Value::Array(vec![inner]))
+}
+
+fn json_value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        any::<u64>().prop_map(Value::from),
+        any::<f64>()
+            .prop_filter("JSON numbers are finite", |f| f.is_finite())
+            .prop_map(Value::from),
+        any::<String>().prop_map(Value::String),
+    ];
+    leaf.prop_recursive(8, 256, 8, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..8).prop_map(Value::Array),
+            prop::collection::btree_map(any::<String>(), inner, 0..8)
+                .prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+proptest! {
+    #[test]
+    fn strings_round_trip(public in any::<String>(), masked in any::<String>()) {
+        prop_assert_eq!(round_trip(&public, &masked), (public, masked));
+    }
+
+    #[test]
+    fn bytes_round_trip(
+        public in prop::collection::vec(any::<u8>(), 0..512),
+        masked in prop::collection::vec(any::<u8>(), 0..512),
+    ) {
+        prop_assert_eq!(round_trip(&public, &masked), (public, masked));
+    }
+
+    #[test]
+    fn json_values_round_trip(public in json_value(), masked in json_value()) {
+        prop_assert_eq!(round_trip(&public, &masked), (public, masked));
+    }
+
+    #[test]
+    fn nesting_up_to_the_limit_round_trips(depth in 1..=MAX_NESTING) {
+        let value = nested(depth);
+        prop_assert_eq!(round_trip(&value, &value), (value.clone(), value));
+    }
+
+    #[test]
+    fn arbitrary_bytes_never_panic(data in any::<Vec<u8>>()) {
+        let _ = Payload::unwrap_bytes(&data);
+        let _ = MultiPartPayload::decode(&data);
+    }
+
+    #[test]
+    fn arbitrary_trytes_never_panic(data in "[A-Z9]{0,128}") {
+        let _ = Payload::unwrap_data(&data);
+        let _ = MultiPartPayload::decode(data.as_bytes());
+    }
+}
+
+#[test]
+fn empty_slots_unwrap_to_none() {
+    let payload = PayloadBuilder::new().build();
+    assert_eq!(
+        Payload::unwrap_bytes(&payload.public_data().0).unwrap(),
+        None
+    );
+    assert_eq!(
+        Payload::unwrap_bytes(&payload.masked_data().0).unwrap(),
+        None
+    );
+
+    let payload = PayloadBuilder::new().masked(&"").unwrap().build();
+    assert_eq!(
+        Payload::unwrap_bytes(&payload.public_data().0).unwrap(),
+        None
+    );
+    assert_eq!(
+        Payload::unwrap_bytes(&payload.masked_data().0).unwrap(),
+        Some("\"\"".to_string())
+    );
+}
+
+#[test]
+fn nesting_beyond_the_limit_is_refused_when_building() {
+    assert!(PayloadBuilder::new()
+        .public(&nested(MAX_NESTING + 1))
+        .is_err());
+}
+
+#[test]
+fn malformed_trytes_are_errors() {
+    for data in vec!["é", "ABC", "abcd", "AB CD", "9\u{1F600}"] {
+        assert!(Payload::unwrap_data(data).is_err(), "{:?}", data);
+    }
+}
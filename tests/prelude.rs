@@ -0,0 +1,38 @@
+use channels_lite::prelude::*;
+use channels_lite::testing::{TestChannel, TestTangle};
+
+#[test]
+fn prelude_is_enough_for_a_round_trip() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let author: &mut AuthorChannel = &mut author;
+    let subscriber: &mut SubscriberChannel = &mut subscribers[0];
+
+    let tag = author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+    let msgs: Vec<ChannelMessage> = subscriber.poll_new().unwrap();
+    let msg = msgs.iter().find(|msg| msg.tag == tag).unwrap();
+    assert_eq!(msg.kind, MessageKind::SignedPacket);
+    assert_eq!(msg.public.as_deref(), Some("1"));
+}
+
+#[test]
+fn send_options_are_crate_types() {
+    let options: SendOptions = Network::Devnet.send_options();
+    assert_eq!(options.min_weight_magnitude, 9);
+    assert!(!options.local_pow);
+    assert!(Network::Local.send_options().local_pow);
+}
+
+#[test]
+fn psk_ids_are_hex() {
+    let psk: Psk = new_psk(b"fleet secret");
+    let id = channels_lite::utils::psk::psk_id(&psk);
+    assert_eq!(id.to_hex().len(), id.as_bytes().len() * 2);
+    assert_eq!(format!("{:?}", psk), "[redacted]");
+}
@@ -0,0 +1,27 @@
+use channels_lite::error::{ChannelError, ConfigError};
+use channels_lite::utils::random_seed::{self, DEFAULT_SEED_ALPHABET, DEFAULT_SEED_LEN};
+
+#[test]
+fn seeds_are_drawn_from_the_alphabet() {
+    assert!(random_seed::validate(&random_seed::new()).is_ok());
+
+    let seed = random_seed::new_with(16, "AB").unwrap();
+    assert!(random_seed::validate_with(&seed, 16, "AB").is_ok());
+}
+
+#[test]
+fn an_empty_alphabet_is_refused() {
+    assert!(matches!(
+        random_seed::new_with(DEFAULT_SEED_LEN, ""),
+        Err(ChannelError::Config(ConfigError::InvalidSeed(_)))
+    ));
+}
+
+#[test]
+fn invalid_seeds_dont_leak_into_the_error() {
+    let seed = format!("{}x", &random_seed::new()[1..]);
+    let error = random_seed::validate(&seed).unwrap_err().to_string();
+    assert!(error.contains(DEFAULT_SEED_ALPHABET));
+    assert!(!error.contains('x'));
+    assert!(!error.contains(&seed[..8]));
+}
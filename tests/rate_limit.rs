@@ -0,0 +1,90 @@
+use channels_lite::channels::{channel_author::ChannelBuilder, Network};
+use channels_lite::error::ChannelError;
+use channels_lite::testing::{fixed_seed, TestTangle};
+use channels_lite::transport::rate_limit::{RateLimit, RateLimiter};
+use channels_lite::utils::clock::{Clock, MockClock};
+use channels_lite::utils::payload::json::PayloadBuilder;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn the_bucket_allows_a_burst_then_one_send_per_interval() {
+    let clock = MockClock::new(SystemTime::now());
+    let mut limiter = RateLimiter::new(RateLimit::new(2, Duration::from_secs(1)));
+
+    assert!(limiter.try_acquire(clock.now()).is_ok());
+    assert!(limiter.try_acquire(clock.now()).is_ok());
+    assert_eq!(
+        limiter.try_acquire(clock.now()),
+        Err(Duration::from_millis(500))
+    );
+
+    clock.advance(Duration::from_millis(200));
+    assert_eq!(
+        limiter.try_acquire(clock.now()),
+        Err(Duration::from_millis(300))
+    );
+
+    clock.advance(Duration::from_millis(300));
+    assert!(limiter.try_acquire(clock.now()).is_ok());
+    assert!(limiter.try_acquire(clock.now()).is_err());
+}
+
+#[test]
+fn the_bucket_refills_up_to_the_burst() {
+    let clock = MockClock::new(SystemTime::now());
+    let mut limiter = RateLimiter::new(RateLimit::new(2, Duration::from_secs(1)));
+    assert!(limiter.try_acquire(clock.now()).is_ok());
+    assert!(limiter.try_acquire(clock.now()).is_ok());
+
+    clock.advance(Duration::from_secs(60));
+    assert!(limiter.ready(clock.now()).is_ok());
+    assert!(limiter.try_acquire(clock.now()).is_ok());
+    assert!(limiter.try_acquire(clock.now()).is_ok());
+    assert!(limiter.try_acquire(clock.now()).is_err());
+}
+
+#[test]
+fn ready_doesnt_take_a_token() {
+    let clock = MockClock::new(SystemTime::now());
+    let mut limiter = RateLimiter::new(RateLimit::new(1, Duration::from_secs(1)));
+
+    assert!(limiter.ready(clock.now()).is_ok());
+    assert!(limiter.ready(clock.now()).is_ok());
+    assert!(limiter.try_acquire(clock.now()).is_ok());
+    assert_eq!(limiter.ready(clock.now()), Err(Duration::from_secs(1)));
+}
+
+#[test]
+fn try_write_signed_fails_instead_of_waiting() {
+    let tangle = TestTangle::new();
+    let mut author = ChannelBuilder::new(Network::Devnet)
+        .seed(fixed_seed(0))
+        .transport(tangle.mock().clone())
+        .clock(Rc::new(tangle.clock().clone()))
+        .auto_keyload(true)
+        .rate_limit(RateLimit::new(1, Duration::from_secs(10)))
+        .build()
+        .unwrap();
+    author.open().unwrap();
+    author
+        .write_signed(PayloadBuilder::new().public(&1).unwrap().build())
+        .unwrap();
+
+    let start = tangle.clock().elapsed();
+    match author.try_write_signed(PayloadBuilder::new().public(&2).unwrap().build()) {
+        Err(ChannelError::RateLimited { retry_after }) => {
+            assert!(retry_after > Duration::from_secs(0));
+            assert!(retry_after <= Duration::from_secs(10));
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    // Nothing waited, and the refused write didn't take a token
+    assert_eq!(tangle.clock().elapsed(), start);
+    assert!(author.transport_stats().rate_limited > 0);
+
+    tangle.advance(Duration::from_secs(10));
+    assert!(author
+        .try_write_signed(PayloadBuilder::new().public(&2).unwrap().build())
+        .is_ok());
+}
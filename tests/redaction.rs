@@ -0,0 +1,84 @@
+mod common;
+
+use anyhow::{anyhow, Context};
+use channels_lite::channels::{
+    channel_author, channel_subscriber, config::HttpClientConfig, store::MemoryStore, Network,
+};
+use channels_lite::error::ChannelError;
+use channels_lite::testing::{fixed_seed, TestChannel, TestTangle};
+use channels_lite::utils::psk::new_psk;
+use common::every_error;
+
+const PASSWORD: &str = "correct horse battery staple";
+
+fn assert_redacted(output: &str, seeds: &[String]) {
+    for seed in seeds {
+        // Any third of the seed is a fragment worth hiding
+        for fragment in seed.as_bytes().chunks(27) {
+            let fragment = std::str::from_utf8(fragment).unwrap();
+            assert!(!output.contains(fragment), "{} leaks {}", output, fragment);
+        }
+    }
+}
+
+#[test]
+fn errors_never_print_the_seed() {
+    let seed = fixed_seed(0);
+    let mut errors: Vec<ChannelError> = every_error().into_iter().map(|(e, _, _)| e).collect();
+    // Streams errors, as they reach the channels
+    errors.push(ChannelError::from(anyhow!(
+        "Failed to derive key from {}",
+        seed
+    )));
+    errors.push(ChannelError::from(
+        Err::<(), _>(anyhow!("seed {}", &seed[..40]))
+            .context("Failed to unwrap keyload")
+            .unwrap_err(),
+    ));
+
+    for error in errors {
+        let debug = format!("{:?}", error);
+        let display = error.to_string();
+        let json = error.as_json().to_string();
+        for output in &[debug, display, json] {
+            assert_redacted(output, &[seed.clone()]);
+        }
+    }
+}
+
+#[test]
+fn channels_never_print_the_seed() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        author,
+        subscribers,
+        channel_address,
+        announcement_tag,
+        ..
+    } = tangle.channel(1).unwrap();
+    let seeds = vec![fixed_seed(0), fixed_seed(1)];
+
+    assert_redacted(&format!("{:?}", author), &seeds);
+    assert_redacted(&format!("{:?}", subscribers[0]), &seeds);
+    assert!(format!("{:?}", author).contains(&channel_address));
+
+    let store = MemoryStore::new();
+    author.persist_to(&store, "author", PASSWORD).unwrap();
+    assert_redacted(&format!("{:?}", store), &seeds);
+
+    let mut author_builder = channel_author::ChannelBuilder::new(Network::Devnet);
+    author_builder.seed(fixed_seed(0));
+    let mut subscriber_builder =
+        channel_subscriber::ChannelBuilder::new(Network::Devnet, channel_address, announcement_tag);
+    subscriber_builder
+        .seed(fixed_seed(1))
+        .psk(new_psk(b"shared secret"))
+        .http_client(HttpClientConfig {
+            proxy: Some(format!("http://user:{}@proxy:3128", PASSWORD)),
+            ..HttpClientConfig::default()
+        });
+    assert_redacted(&format!("{:?}", author_builder), &seeds);
+    let subscriber_debug = format!("{:?}", subscriber_builder);
+    assert_redacted(&subscriber_debug, &seeds);
+    assert!(!subscriber_debug.contains(PASSWORD));
+}
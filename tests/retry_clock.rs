@@ -0,0 +1,288 @@
+use anyhow::{bail, Result};
+use channels_lite::error::ChannelError;
+use channels_lite::testing::TestTangle;
+use channels_lite::transport::mock::MockTransport;
+use channels_lite::transport::{
+    retry::{RetryConfig, RetryingTransport},
+    Address, Message, Transport,
+};
+use channels_lite::utils::clock::{Clock, MockClock};
+use instant::Instant;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+struct FlakyTransport {
+    failures_left: u32,
+}
+
+impl Transport<Address, Message> for FlakyTransport {
+    type SendOptions = ();
+
+    fn send_message_with_options(&mut self, _msg: &Message, _opt: ()) -> Result<()> {
+        Ok(())
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options(&mut self, _link: &Address, _opt: ()) -> Result<Vec<Message>> {
+        if self.failures_left > 0 {
+            self.failures_left -= 1;
+            bail!("node unavailable");
+        }
+        Ok(Vec::new())
+    }
+}
+
+struct CorruptingTransport {
+    attempts: u32,
+}
+
+impl Transport<Address, Message> for CorruptingTransport {
+    type SendOptions = ();
+
+    fn send_message_with_options(&mut self, _msg: &Message, _opt: ()) -> Result<()> {
+        Ok(())
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options(&mut self, _link: &Address, _opt: ()) -> Result<Vec<Message>> {
+        self.attempts += 1;
+        Err(ChannelError::PayloadEncoding("corrupted message".to_string()).into())
+    }
+}
+
+///
+/// Mock tangle failing the next operations with the scripted errors
+///
+struct ScriptedTransport {
+    mock: MockTransport,
+    failures: VecDeque<ChannelError>,
+    attempts: u32,
+}
+
+impl ScriptedTransport {
+    fn new(mock: &MockTransport, failures: Vec<ChannelError>) -> Self {
+        ScriptedTransport {
+            mock: mock.clone(),
+            failures: failures.into_iter().collect(),
+            attempts: 0,
+        }
+    }
+
+    fn next_failure(&mut self) -> Result<()> {
+        self.attempts += 1;
+        match self.failures.pop_front() {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Transport<Address, Message> for ScriptedTransport {
+    type SendOptions = ();
+
+    fn send_message_with_options(&mut self, msg: &Message, _opt: ()) -> Result<()> {
+        self.next_failure()?;
+        self.mock.send_message(msg)
+    }
+
+    type RecvOptions = ();
+
+    fn recv_messages_with_options(&mut self, link: &Address, _opt: ()) -> Result<Vec<Message>> {
+        self.next_failure()?;
+        self.mock.recv_messages(link)
+    }
+}
+
+fn node_unavailable() -> ChannelError {
+    ChannelError::Transport(anyhow::anyhow!("node unavailable"))
+}
+
+fn node_timeout() -> ChannelError {
+    ChannelError::Timeout {
+        node: "https://node".to_string(),
+        after: Duration::from_secs(30),
+    }
+}
+
+///
+/// Mock tangle holding the announcement of a channel, and the link to it
+///
+fn announced_channel() -> (TestTangle, Address) {
+    let tangle = TestTangle::new();
+    let (channel_address, announcement_tag) = tangle.author().open().unwrap();
+    let link = Address::from_str(&channel_address, &announcement_tag).unwrap();
+    (tangle, link)
+}
+
+///
+/// Clock that can't wait, as on the browser main thread
+///
+#[derive(Debug)]
+struct NonBlockingClock(MockClock);
+
+impl Clock for NonBlockingClock {
+    fn now(&self) -> Instant {
+        self.0.now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.0.system_time()
+    }
+
+    fn sleep(&self, _duration: Duration) {}
+
+    fn can_sleep(&self) -> bool {
+        false
+    }
+}
+
+fn retry_config() -> RetryConfig {
+    RetryConfig {
+        max_fetch_attempts: 4,
+        base_delay: Duration::from_millis(500),
+        max_delay: Duration::from_secs(10),
+        jitter: Duration::from_secs(0),
+        ..RetryConfig::default()
+    }
+}
+
+#[test]
+fn backoff_advances_the_clock() {
+    let clock = MockClock::new(SystemTime::now());
+    let mut transport = RetryingTransport::new(FlakyTransport { failures_left: 3 }, retry_config())
+        .with_clock(Rc::new(clock.clone()));
+
+    assert!(transport.recv_messages(&Address::default()).is_ok());
+    assert_eq!(clock.elapsed(), Duration::from_millis(500 + 1000 + 2000));
+    assert_eq!(transport.stats().recv.count, 4);
+    assert_eq!(transport.stats().recv.errors, 3);
+}
+
+#[test]
+fn gives_up_after_max_attempts() {
+    let clock = MockClock::new(SystemTime::now());
+    let mut transport =
+        RetryingTransport::new(FlakyTransport { failures_left: 10 }, retry_config())
+            .with_clock(Rc::new(clock.clone()));
+
+    assert!(transport.recv_messages(&Address::default()).is_err());
+    assert_eq!(clock.elapsed(), Duration::from_millis(500 + 1000 + 2000));
+}
+
+#[test]
+fn permanent_errors_are_not_retried() {
+    let clock = MockClock::new(SystemTime::now());
+    let mut transport = RetryingTransport::new(CorruptingTransport { attempts: 0 }, retry_config())
+        .with_clock(Rc::new(clock.clone()));
+
+    let error = transport.recv_messages(&Address::default()).unwrap_err();
+    match error.downcast_ref::<ChannelError>() {
+        Some(ChannelError::PayloadEncoding(_)) => (),
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert_eq!(transport.inner().attempts, 1);
+    assert_eq!(clock.elapsed(), Duration::from_millis(0));
+}
+
+#[test]
+fn custom_predicate_decides_the_retries() {
+    let clock = MockClock::new(SystemTime::now());
+    let config = RetryConfig {
+        is_retryable: |_| true,
+        ..retry_config()
+    };
+    let mut transport = RetryingTransport::new(CorruptingTransport { attempts: 0 }, config)
+        .with_clock(Rc::new(clock.clone()));
+
+    assert!(transport.recv_messages(&Address::default()).is_err());
+    assert_eq!(transport.inner().attempts, 4);
+}
+
+#[test]
+fn no_retry_on_a_clock_that_cant_sleep() {
+    let clock = MockClock::new(SystemTime::now());
+    let mut transport = RetryingTransport::new(FlakyTransport { failures_left: 1 }, retry_config())
+        .with_clock(Rc::new(NonBlockingClock(clock.clone())));
+
+    assert!(transport.recv_messages(&Address::default()).is_err());
+    assert_eq!(transport.stats().recv.count, 1);
+    assert_eq!(clock.elapsed(), Duration::from_secs(0));
+}
+
+#[test]
+fn transient_failures_are_retried_until_the_mock_answers() {
+    let (tangle, link) = announced_channel();
+    let clock = MockClock::new(SystemTime::now());
+    let scripted = ScriptedTransport::new(tangle.mock(), vec![node_unavailable(), node_timeout()]);
+    let mut transport =
+        RetryingTransport::new(scripted, retry_config()).with_clock(Rc::new(clock.clone()));
+
+    assert_eq!(transport.recv_messages(&link).unwrap().len(), 1);
+    assert_eq!(transport.inner().attempts, 3);
+    assert_eq!(transport.stats().recv.errors, 2);
+    assert_eq!(clock.elapsed(), Duration::from_millis(500 + 1000));
+}
+
+#[test]
+fn permanent_failures_of_the_mock_are_not_retried() {
+    let (tangle, link) = announced_channel();
+    let clock = MockClock::new(SystemTime::now());
+    let scripted = ScriptedTransport::new(
+        tangle.mock(),
+        vec![ChannelError::PayloadEncoding(
+            "corrupted message".to_string(),
+        )],
+    );
+    let mut transport =
+        RetryingTransport::new(scripted, retry_config()).with_clock(Rc::new(clock.clone()));
+
+    let error = transport.recv_messages(&link).unwrap_err();
+    match error.downcast_ref::<ChannelError>() {
+        Some(ChannelError::PayloadEncoding(_)) => (),
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert_eq!(transport.inner().attempts, 1);
+    assert_eq!(clock.elapsed(), Duration::from_millis(0));
+
+    // The failure was a single one, the message is still on the mock
+    assert_eq!(transport.recv_messages(&link).unwrap().len(), 1);
+}
+
+#[test]
+fn sends_to_the_mock_are_retried_only_on_transient_failures() {
+    let (tangle, link) = announced_channel();
+    let announcement = tangle
+        .mock()
+        .clone()
+        .recv_messages(&link)
+        .unwrap()
+        .remove(0);
+    let clock = MockClock::new(SystemTime::now());
+    let config = RetryConfig {
+        max_send_attempts: 2,
+        ..retry_config()
+    };
+
+    let scripted = ScriptedTransport::new(tangle.mock(), vec![node_unavailable()]);
+    let mut transport =
+        RetryingTransport::new(scripted, config.clone()).with_clock(Rc::new(clock.clone()));
+    assert!(transport.send_message(&announcement).is_ok());
+    assert_eq!(transport.inner().attempts, 2);
+    assert_eq!(clock.elapsed(), Duration::from_millis(500));
+    assert_eq!(tangle.mock().clone().recv_messages(&link).unwrap().len(), 2);
+
+    let scripted = ScriptedTransport::new(
+        tangle.mock(),
+        vec![ChannelError::PayloadEncoding(
+            "corrupted message".to_string(),
+        )],
+    );
+    let mut transport = RetryingTransport::new(scripted, config).with_clock(Rc::new(clock.clone()));
+    assert!(transport.send_message(&announcement).is_err());
+    assert_eq!(transport.inner().attempts, 1);
+    assert_eq!(clock.elapsed(), Duration::from_millis(500));
+    assert_eq!(tangle.mock().clone().recv_messages(&link).unwrap().len(), 2);
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "json-schema")]
+
+use channels_lite::error::{ChannelError, ConfigError};
+use channels_lite::utils::schema::JsonSchema;
+use serde_json::json;
+
+#[test]
+fn invalid_schemas_are_refused() {
+    assert!(matches!(
+        JsonSchema::new(json!({ "type": 1 })),
+        Err(ChannelError::Config(ConfigError::InvalidSchema(_)))
+    ));
+    assert!(matches!(
+        JsonSchema::from_str("{"),
+        Err(ChannelError::Config(ConfigError::InvalidSchema(_)))
+    ));
+}
+
+#[test]
+fn the_compiled_schema_validates_every_payload() {
+    let schema = JsonSchema::new(json!({
+        "type": "object",
+        "properties": { "presure": { "type": "number" } },
+        "required": ["presure"]
+    }))
+    .unwrap();
+    let shared = schema.clone();
+
+    assert!(schema.violations(&json!({ "presure": 1.5 })).is_empty());
+    assert_eq!(schema.violations(&json!({ "presure": "high" })).len(), 1);
+    assert_eq!(shared.violations(&json!({})).len(), 1);
+    assert!(shared.violations(&json!({ "presure": 2 })).is_empty());
+}
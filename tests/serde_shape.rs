@@ -0,0 +1,197 @@
+//! Pins the JSON shape of the serializable types, a failure here means the serialized
+//! form changed and stored or transmitted data may no longer be read back
+
+use channels_lite::channels::{
+    channel_subscriber::{ChannelInfo, Gap, GapPolicy, KeyloadInfo},
+    config::PowMode,
+    message::{AccessLevel, ChannelMessage, MessageKind, MessageSource},
+    multi_subscriber::SourcedMessage,
+    node::{NodeHealth, NodeInfo, SendOptionsSource},
+};
+use channels_lite::transport::{
+    rate_limit::RateLimit,
+    stats::{OperationStats, TransportStats},
+    tangle::SentRecord,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+use std::fmt::Debug;
+use std::time::{Duration, UNIX_EPOCH};
+
+fn assert_shape<T>(value: T, expected: serde_json::Value)
+where
+    T: Serialize + DeserializeOwned + Debug + PartialEq,
+{
+    assert_eq!(serde_json::to_value(&value).unwrap(), expected);
+    assert_eq!(serde_json::from_value::<T>(expected).unwrap(), value);
+}
+
+fn message() -> ChannelMessage {
+    ChannelMessage {
+        tag: "TAG".to_string(),
+        kind: MessageKind::SignedPacket,
+        public: Some("{\"a\":1}".to_string()),
+        masked: None,
+        decode_error: None,
+        raw: None,
+        source: MessageSource::Archive,
+        attached_at: None,
+        expires_at: Some(UNIX_EPOCH + Duration::from_secs(60)),
+        expired: true,
+        seq: None,
+        ack_request: None,
+        content_type: None,
+        digest: None,
+        content_digest: None,
+        access: AccessLevel::Full,
+        signer: None,
+    }
+}
+
+fn message_json() -> serde_json::Value {
+    json!({
+        "tag": "TAG",
+        "kind": "signed_packet",
+        "public": "{\"a\":1}",
+        "masked": null,
+        "decode_error": null,
+        "raw": null,
+        "source": "archive",
+        "expires_at": { "secs_since_epoch": 60, "nanos_since_epoch": 0 },
+        "expired": true,
+    })
+}
+
+#[test]
+fn channel_message_shape() {
+    assert_shape(message(), message_json());
+    assert_shape(
+        SourcedMessage {
+            channel_address: "ADDRESS".to_string(),
+            message: message(),
+        },
+        json!({ "channel_address": "ADDRESS", "message": message_json() }),
+    );
+}
+
+#[test]
+fn channel_metadata_shape() {
+    assert_shape(
+        ChannelInfo {
+            channel_address: "ADDRESS".to_string(),
+            announcement_tag: "ANNOUNCE".to_string(),
+            keyloads: vec![KeyloadInfo {
+                tag: "KEYLOAD".to_string(),
+                authorized: true,
+            }],
+        },
+        json!({
+            "channel_address": "ADDRESS",
+            "announcement_tag": "ANNOUNCE",
+            "keyloads": [{ "tag": "KEYLOAD", "authorized": true }],
+        }),
+    );
+    assert_shape(
+        Gap {
+            after_tag: None,
+            resumed_at: "TAG".to_string(),
+        },
+        json!({ "after_tag": null, "resumed_at": "TAG" }),
+    );
+    assert_shape(
+        GapPolicy {
+            max_attempts: 3,
+            retry_delay: Duration::from_millis(1500),
+            skip_to_keyload: true,
+        },
+        json!({
+            "max_attempts": 3,
+            "retry_delay": { "secs": 1, "nanos": 500_000_000 },
+            "skip_to_keyload": true,
+        }),
+    );
+}
+
+#[test]
+fn transport_records_shape() {
+    assert_shape(
+        SentRecord {
+            tag: "TAG".to_string(),
+            pow: PowMode::PreferRemote,
+        },
+        json!({ "tag": "TAG", "pow": "prefer_remote" }),
+    );
+    assert_shape(
+        TransportStats {
+            send: OperationStats {
+                count: 2,
+                errors: 1,
+                bytes: 100,
+                total_time: Duration::from_secs(3),
+                max_time: Duration::from_secs(2),
+            },
+            recv: OperationStats::default(),
+            rate_limit: Some(RateLimit::new(10, Duration::from_secs(60))),
+            rate_limited: 1,
+            rate_limit_wait: Duration::from_secs(0),
+        },
+        json!({
+            "send": {
+                "count": 2,
+                "errors": 1,
+                "bytes": 100,
+                "total_time": { "secs": 3, "nanos": 0 },
+                "max_time": { "secs": 2, "nanos": 0 },
+            },
+            "recv": {
+                "count": 0,
+                "errors": 0,
+                "bytes": 0,
+                "total_time": { "secs": 0, "nanos": 0 },
+                "max_time": { "secs": 0, "nanos": 0 },
+            },
+            "rate_limit": { "max_sends": 10, "per": { "secs": 60, "nanos": 0 } },
+            "rate_limited": 1,
+            "rate_limit_wait": { "secs": 0, "nanos": 0 },
+        }),
+    );
+}
+
+#[test]
+fn node_reports_shape() {
+    assert_shape(
+        NodeHealth {
+            node: "https://node".to_string(),
+            app_name: "IRI".to_string(),
+            app_version: "1.8.6".to_string(),
+            latest_milestone_index: 10,
+            latest_solid_milestone_index: 9,
+        },
+        json!({
+            "node": "https://node",
+            "app_name": "IRI",
+            "app_version": "1.8.6",
+            "latest_milestone_index": 10,
+            "latest_solid_milestone_index": 9,
+        }),
+    );
+    assert_shape(
+        NodeInfo {
+            node: "https://node".to_string(),
+            app_name: "HORNET".to_string(),
+            app_version: "0.4.0".to_string(),
+            network_id: "COO".to_string(),
+        },
+        json!({
+            "node": "https://node",
+            "app_name": "HORNET",
+            "app_version": "0.4.0",
+            "network_id": "COO",
+        }),
+    );
+    assert_shape(SendOptionsSource::Static, json!("static"));
+    assert_shape(
+        SendOptionsSource::Fallback("no node info".to_string()),
+        json!({ "fallback": "no node info" }),
+    );
+}
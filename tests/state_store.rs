@@ -0,0 +1,287 @@
+use channels_lite::channels::{
+    channel_author, channel_subscriber,
+    policy::ChannelPolicy,
+    snapshot::ChannelSnapshot,
+    store::{FileStore, MemoryStore, StateStore},
+    Network,
+};
+use channels_lite::testing::{TestChannel, TestTangle};
+use channels_lite::utils::payload::json::PayloadBuilder;
+use channels_lite::utils::psk::new_psk;
+use channels_lite::utils::sequence::SequenceEvent;
+use std::fs;
+use std::rc::Rc;
+use std::time::Duration;
+
+fn open_channel(
+    tangle: &TestTangle,
+) -> (channel_author::Channel, channel_subscriber::Channel, String) {
+    let TestChannel {
+        author,
+        mut subscribers,
+        channel_address,
+        ..
+    } = tangle.channel(1).unwrap();
+    (author, subscribers.remove(0), channel_address)
+}
+
+fn write(author: &mut channel_author::Channel, value: u32) -> String {
+    author
+        .write_signed(PayloadBuilder::new().public(&value).unwrap().build())
+        .unwrap()
+}
+
+#[test]
+fn subscriber_resumes_from_a_memory_store() {
+    let tangle = TestTangle::new();
+    let store = MemoryStore::new();
+    let (mut author, mut subscriber, _) = open_channel(&tangle);
+
+    let first_tag = write(&mut author, 1);
+    let tags: Vec<String> = subscriber
+        .poll_new()
+        .unwrap()
+        .into_iter()
+        .map(|msg| msg.tag)
+        .collect();
+    assert!(tags.contains(&first_tag));
+    subscriber.persist_to(&store, "reader", "secret").unwrap();
+    drop(subscriber);
+
+    let second_tag = write(&mut author, 2);
+    let mut restored = channel_subscriber::Channel::restore_from_with_transport(
+        &store,
+        "reader",
+        "secret",
+        Network::Devnet,
+        tangle.transport(),
+    )
+    .unwrap();
+    let tags: Vec<String> = restored
+        .poll_new()
+        .unwrap()
+        .into_iter()
+        .map(|msg| msg.tag)
+        .collect();
+    assert_eq!(tags, vec![second_tag]);
+
+    assert!(channel_subscriber::Channel::restore_from_with_transport(
+        &store,
+        "reader",
+        "wrong",
+        Network::Devnet,
+        tangle.transport(),
+    )
+    .is_err());
+}
+
+#[test]
+fn author_resumes_from_a_memory_store() {
+    let tangle = TestTangle::new();
+    let store = MemoryStore::new();
+    let (author, _, channel_address) = open_channel(&tangle);
+
+    author.persist_to(&store, "author", "secret").unwrap();
+    let restored = channel_author::Channel::restore_from_with_transport(
+        &store,
+        "author",
+        "secret",
+        Network::Devnet,
+        tangle.transport(),
+    )
+    .unwrap();
+    assert_eq!(restored.export_state(), author.export_state());
+    assert!(restored.export_state().len() > channel_address.len());
+
+    store.delete("author").unwrap();
+    assert!(store.load("author").unwrap().is_none());
+}
+
+#[test]
+fn restore_defers_the_subscriptions_replay() {
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(3).unwrap();
+    for value in 0..1_000 {
+        write(&mut author, value);
+    }
+    let store = MemoryStore::new();
+    author.persist_to(&store, "author", "secret").unwrap();
+    let restore = || {
+        channel_author::Channel::restore_from_with_transport(
+            &store,
+            "author",
+            "secret",
+            Network::Devnet,
+            tangle.transport(),
+        )
+        .unwrap()
+    };
+
+    // Nothing fetched by the restore, whatever the length of the chain
+    let mut restored = restore();
+    assert_eq!(restored.transport_stats().recv.count, 0);
+    assert!(!restored.is_warm());
+    restored.warm_up().unwrap();
+    assert!(restored.is_warm());
+    assert_eq!(restored.transport_stats().recv.count, 3);
+    restored.warm_up().unwrap();
+    assert_eq!(restored.transport_stats().recv.count, 3);
+
+    // Warmed up by the next keyload, the subscribers keep their access
+    let mut restored = restore();
+    let keyload_tag = restored.add_psk(new_psk(b"dashboard")).unwrap();
+    assert!(restored.is_warm());
+    for subscriber in subscribers.iter_mut() {
+        subscriber.update_keyload(keyload_tag.clone()).unwrap();
+    }
+}
+
+#[test]
+fn snapshot_hands_the_authorship_over() {
+    let tangle = TestTangle::new();
+    let (mut author, mut subscriber, channel_address) = open_channel(&tangle);
+    let policy = ChannelPolicy {
+        max_messages_per_epoch: Some(100),
+        max_payload_bytes: None,
+    };
+    author.set_policy(policy.clone());
+    author.set_sequence_numbers(true);
+    subscriber.set_sequence_guard(true);
+    write(&mut author, 1);
+    subscriber.poll_new().unwrap();
+
+    let snapshot = author.snapshot("secret").unwrap();
+    assert_eq!(snapshot.channel_address, channel_address);
+    assert_eq!(snapshot.generation, 1);
+    let bytes = snapshot.to_bytes();
+    assert_eq!(ChannelSnapshot::from_bytes(&bytes).unwrap(), snapshot);
+    assert!(channel_author::Channel::from_snapshot_with_transport(
+        Network::Devnet,
+        tangle.transport(),
+        &bytes,
+        "wrong",
+    )
+    .is_err());
+
+    let mut restored = channel_author::Channel::from_snapshot_with_transport(
+        Network::Devnet,
+        tangle.transport(),
+        &bytes,
+        "secret",
+    )
+    .unwrap();
+    assert_eq!(restored.generation(), 1);
+    assert_eq!(restored.stats(), author.stats());
+    assert_eq!(restored.stats().policy, policy);
+    assert_eq!(
+        restored.processed_subscriptions(),
+        author.processed_subscriptions()
+    );
+
+    let tag = write(&mut restored, 2);
+    let msgs = subscriber.poll_new().unwrap();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].tag, tag);
+    assert_eq!(msgs[0].public.as_deref(), Some("2"));
+    assert!(subscriber.sequence_events().is_empty());
+
+    // The former author writing after the handover is detected
+    let stale_tag = write(&mut author, 3);
+    subscriber.poll_new().unwrap();
+    assert_eq!(
+        subscriber.sequence_events(),
+        &[SequenceEvent::StaleGeneration {
+            sender: channel_address,
+            generation: 0,
+            current: 1,
+            tag: stale_tag,
+        }]
+    );
+}
+
+#[test]
+fn partial_write_keeps_the_previous_state() {
+    let dir = std::env::temp_dir().join(format!("channels_lite_store_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = FileStore::new(&dir).unwrap();
+    let tangle = TestTangle::new();
+    let (mut author, mut subscriber, _) = open_channel(&tangle);
+
+    write(&mut author, 1);
+    subscriber.poll_new().unwrap();
+    subscriber.persist_to(&store, "reader", "secret").unwrap();
+    let saved = fs::read(store.path("reader").unwrap()).unwrap();
+
+    // Crash in the middle of the next save: the temporary file is left half written
+    let tmp = store.path("reader").unwrap().with_extension("state.tmp");
+    fs::write(&tmp, &saved[..saved.len() / 2]).unwrap();
+    assert_eq!(store.load("reader").unwrap(), Some(saved.clone()));
+    assert!(channel_subscriber::Channel::restore_from_with_transport(
+        &store,
+        "reader",
+        "secret",
+        Network::Devnet,
+        tangle.transport(),
+    )
+    .is_ok());
+
+    // The next save replaces the leftover
+    subscriber.persist_to(&store, "reader", "secret").unwrap();
+    assert!(!tmp.exists());
+
+    // A state truncated by other means is refused rather than restored
+    fs::write(store.path("reader").unwrap(), &saved[..saved.len() / 2]).unwrap();
+    assert!(channel_subscriber::Channel::restore_from_with_transport(
+        &store,
+        "reader",
+        "secret",
+        Network::Devnet,
+        tangle.transport(),
+    )
+    .is_err());
+
+    assert!(store.path("../escape").is_err());
+    store.delete("reader").unwrap();
+    assert!(store.load("reader").unwrap().is_none());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn shutdown_persists_to_the_attached_store() {
+    let tangle = TestTangle::new();
+    let store = MemoryStore::new();
+    let (mut author, mut subscriber, _) = open_channel(&tangle);
+    author.attach_store(Rc::new(store.clone()), "author", "secret");
+    subscriber.attach_store(Rc::new(store.clone()), "reader", "secret");
+
+    write(&mut author, 1);
+    let state = author.export_state();
+    let report = author.shutdown().unwrap();
+    assert_eq!(report.persisted_as.as_deref(), Some("author"));
+    assert_eq!(report.final_state, state);
+    let restored = channel_author::Channel::restore_from_with_transport(
+        &store,
+        "author",
+        "secret",
+        Network::Devnet,
+        tangle.transport(),
+    )
+    .unwrap();
+    assert_eq!(restored.export_state(), state);
+
+    // The cancelled wait returns right away, whatever the timeout
+    subscriber.cancel_token().cancel();
+    assert!(subscriber
+        .next_message_blocking(Duration::from_secs(3600))
+        .unwrap()
+        .is_none());
+    let cursor = subscriber.cursor();
+    let report = subscriber.shutdown().unwrap();
+    assert_eq!(report.persisted_as.as_deref(), Some("reader"));
+    assert_eq!(report.final_state, cursor);
+    assert!(store.load("reader").unwrap().is_some());
+}
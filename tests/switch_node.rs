@@ -0,0 +1,67 @@
+use channels_lite::channels::{channel_author, channel_subscriber, config::NetworkConfig, Network};
+use channels_lite::testing::{fixed_seed, TestChannel, TestTangle};
+use channels_lite::transport::tangle::TangleTransport;
+use channels_lite::utils::payload::json::{Payload, PayloadBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SensorData {
+    presure: f32,
+}
+
+fn payload(presure: f32) -> Payload {
+    PayloadBuilder::new()
+        .public(&SensorData { presure: presure })
+        .unwrap()
+        .masked(&SensorData { presure: -presure })
+        .unwrap()
+        .build()
+}
+
+#[test]
+fn channel_state_survives_transport_swap() {
+    // Both nodes see the same tangle, as two synced nodes would
+    let tangle = TestTangle::new();
+    let TestChannel {
+        mut author,
+        mut subscribers,
+        ..
+    } = tangle.channel(1).unwrap();
+    let mut subscriber = subscribers.remove(0);
+    let first_tag = author.write_signed(payload(1.0)).unwrap();
+
+    author.set_transport(tangle.transport());
+    subscriber.set_transport(tangle.transport());
+
+    // The chain continues from the previous message and the keyload still applies
+    let second_tag = author.write_signed(payload(2.0)).unwrap();
+    for (tag, presure) in vec![(first_tag, 1.0), (second_tag, 2.0)] {
+        let signed = subscriber.read_signed(tag).unwrap();
+        assert_eq!(signed.len(), 1);
+        let public: SensorData = serde_json::from_str(signed[0].0.as_ref().unwrap()).unwrap();
+        let masked: SensorData = serde_json::from_str(signed[0].1.as_ref().unwrap()).unwrap();
+        assert_eq!(public, SensorData { presure: presure });
+        assert_eq!(masked, SensorData { presure: -presure });
+    }
+    assert_eq!(author.node(), Network::Devnet.as_string());
+}
+
+#[test]
+fn channels_on_two_networks_keep_their_own_node() {
+    let main = TangleTransport::new(&Network::Main, &NetworkConfig::default()).unwrap();
+    let devnet = TangleTransport::new(&Network::Devnet, &NetworkConfig::default()).unwrap();
+    assert_eq!(main.node(), Network::Main.as_string());
+    assert_eq!(devnet.node(), Network::Devnet.as_string());
+
+    let (channel_address, announcement_tag) = TestTangle::new().author().open().unwrap();
+    let author = channel_author::Channel::new(Network::Main, Some(fixed_seed(0))).unwrap();
+    let subscriber = channel_subscriber::Channel::new(
+        Network::Devnet,
+        channel_address,
+        announcement_tag,
+        Some(fixed_seed(1)),
+    )
+    .unwrap();
+    assert_eq!(author.node(), Network::Main.as_string());
+    assert_eq!(subscriber.node(), Network::Devnet.as_string());
+}
@@ -0,0 +1,54 @@
+#![cfg(target_arch = "wasm32")]
+//! Run with `wasm-pack test --headless --firefox -- --features "wasm test-transport"`
+
+use channels_lite::channels::{channel_author, channel_subscriber, Network};
+use channels_lite::transport::{mock::MockTransport, ChannelTransport};
+use channels_lite::utils::payload::json::PayloadBuilder;
+use channels_lite::wasm::WasmSubscriber;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+fn subscriber_reads_in_the_browser() {
+    let tangle = MockTransport::new();
+
+    let mut author = channel_author::Channel::new_with_transport(
+        Network::Devnet,
+        ChannelTransport::Mock(tangle.clone()),
+        None,
+    )
+    .unwrap();
+    let (channel_address, announcement_tag) = author.open().unwrap();
+
+    let mut subscriber = WasmSubscriber::from_channel(
+        channel_subscriber::Channel::new_with_transport(
+            Network::Devnet,
+            ChannelTransport::Mock(tangle.clone()),
+            channel_address,
+            announcement_tag,
+            None,
+        )
+        .unwrap(),
+    );
+    let subscription_tag = subscriber.connect().unwrap();
+    let keyload_tag = author.add_subscriber(subscription_tag).unwrap();
+    subscriber.update_keyload(keyload_tag).unwrap();
+
+    let signed_tag = author
+        .write_signed(PayloadBuilder::new().public(&"browser").unwrap().build())
+        .unwrap();
+
+    let msgs: Vec<serde_json::Value> =
+        serde_wasm_bindgen::from_value(subscriber.fetch_all().unwrap()).unwrap();
+    let signed = msgs
+        .iter()
+        .find(|msg| msg["tag"] == signed_tag.as_str())
+        .unwrap();
+    assert_eq!(signed["kind"], "signed_packet");
+    assert!(signed["public"].as_str().unwrap().contains("browser"));
+
+    let msgs: Vec<serde_json::Value> =
+        serde_wasm_bindgen::from_value(subscriber.fetch_all().unwrap()).unwrap();
+    assert!(msgs.is_empty());
+}